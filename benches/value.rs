@@ -0,0 +1,68 @@
+//! Parsing and serialization of typical documents: `cargo bench --bench value`
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use nota::Value;
+use std::collections::HashMap;
+use std::hint::black_box;
+
+/// Array of small records, like rows of a table
+fn records() -> Value {
+    Value::Array((0..10_000).map(|i| {
+        let mut row = HashMap::new();
+        row.insert("id".to_string(), Value::Integer(i));
+        row.insert("name".to_string(), Value::Text(format!("user {i}")));
+        row.insert("active".to_string(), Value::Bool(i % 3 == 0));
+        row.insert("tags".to_string(), Value::Array(vec![Value::Text("a".into()), Value::Null]));
+        Value::Record(Box::new(row))
+    }).collect())
+}
+
+/// Array of integers of all sizes
+fn integers() -> Value {
+    Value::Array((0..100_000).map(|i: i128| Value::Integer((i * 0x9E37_79B9_7F4A_7C15) >> (i % 64))).collect())
+}
+
+/// Array of integers that take 8 bytes or more, where every one takes the 8-bytes-at-once path of the slice parser
+fn big_integers() -> Value {
+    Value::Array((0..100_000).map(|i: i128| Value::Integer((1 << 50) + i * 12345)).collect())
+}
+
+fn documents() -> [(&'static str, Value); 3] {
+    [("records", records()), ("integers", integers()), ("big_integers", big_integers())]
+}
+
+fn parse(c: &mut Criterion) {
+    for (name, value) in documents() {
+        let mut bytes = Vec::new();
+        value.serialize_into(&mut bytes);
+        let mut group = c.benchmark_group(format!("parse/{name}"));
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function("parse_slice", |b| b.iter(|| Value::parse_slice(&mut black_box(&bytes[..])).unwrap()));
+        group.bench_function("parse_from", |b| b.iter(|| Value::parse_from(&mut black_box(&bytes[..])).unwrap()));
+        group.finish();
+    }
+}
+
+fn serialize(c: &mut Criterion) {
+    for (name, value) in documents() {
+        let mut group = c.benchmark_group(format!("serialize/{name}"));
+        group.throughput(Throughput::Bytes(value.serialized_size() as u64));
+        let mut out = Vec::new();
+        group.bench_function("serialize_into", |b| b.iter(|| {
+            out.clear();
+            black_box(&value).serialize_into(&mut out);
+        }));
+        group.finish();
+    }
+}
+
+/// Walking a large array touches every element, so it depends on the size of `Value`
+fn traverse(c: &mut Criterion) {
+    let value = integers();
+    c.bench_function("traverse/integers", |b| b.iter(|| {
+        let Value::Array(items) = black_box(&value) else { unreachable!() };
+        items.iter().filter(|v| matches!(v, Value::Integer(i) if i % 2 == 0)).count()
+    }));
+}
+
+criterion_group!(benches, parse, serialize, traverse);
+criterion_main!(benches);
@@ -0,0 +1,270 @@
+#ifndef NOTA_H
+#define NOTA_H
+
+/* Generated by cbindgen from src/capi.rs. Do not edit. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// Opaque handle to a Nota value
+typedef struct NotaValue NotaValue;
+
+typedef enum NotaStatus {
+  NOTA_STATUS_OK = 0,
+  // A `NULL` pointer or invalid UTF-8 was passed in
+  NOTA_STATUS_INVALID_ARGUMENT = 1,
+  // The value is of a different kind than the function expects, or the index is out of range
+  NOTA_STATUS_WRONG_KIND = 2,
+  // The input is not valid Nota
+  NOTA_STATUS_INVALID_DATA = 3,
+  // The input ended in the middle of a value
+  NOTA_STATUS_UNEXPECTED_EOF = 4,
+  // The input uses a Nota feature that this library doesn't support
+  NOTA_STATUS_UNSUPPORTED = 5,
+  // The integer doesn't fit in the output type
+  NOTA_STATUS_OVERFLOW = 6,
+} NotaStatus;
+
+typedef enum NotaKind {
+  NOTA_KIND_BLOB = 0,
+  NOTA_KIND_TEXT = 1,
+  NOTA_KIND_ARRAY = 2,
+  NOTA_KIND_RECORD = 3,
+  NOTA_KIND_INTEGER = 4,
+  NOTA_KIND_DECIMAL_FLOAT = 5,
+  NOTA_KIND_BOOL = 6,
+  NOTA_KIND_NULL = 7,
+  NOTA_KIND_UNKNOWN = 8,
+  // `NULL` was passed in instead of a value
+  NOTA_KIND_INVALID = 9,
+} NotaKind;
+
+// Stable numbers for kinds of errors, for bindings and logs that can't match on [`Error`].
+//
+// The numbers never change, and new kinds get new numbers. [`ErrorCode::from_io`] classifies any error returned by the parsers.
+enum NotaErrorCode
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint32_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  // An I/O error from the reader or writer, not related to the data
+  NOTA_ERROR_CODE_IO = 1,
+  // The data ended in the middle of a value
+  NOTA_ERROR_CODE_UNEXPECTED_EOF = 2,
+  // The data isn't valid Nota, and there's no more specific code
+  NOTA_ERROR_CODE_INVALID_DATA = 3,
+  // The data uses a part of Nota that isn't supported, such as DEC64
+  NOTA_ERROR_CODE_UNSUPPORTED = 4,
+  NOTA_ERROR_CODE_CANCELLED = 5,
+  NOTA_ERROR_CODE_CORRUPTED = 6,
+  NOTA_ERROR_CODE_FRAME_TOO_LARGE = 7,
+  NOTA_ERROR_CODE_INTEGER_TOO_LARGE = 8,
+  NOTA_ERROR_CODE_INVALID_CHAR = 9,
+  NOTA_ERROR_CODE_INVALID_SIGNATURE = 10,
+  NOTA_ERROR_CODE_LENGTH_TOO_LARGE = 11,
+  NOTA_ERROR_CODE_NOT_MINIMAL = 12,
+  NOTA_ERROR_CODE_TOO_LARGE = 13,
+  NOTA_ERROR_CODE_TOO_DEEP = 14,
+  NOTA_ERROR_CODE_TOO_LONG = 15,
+  NOTA_ERROR_CODE_UNREPRESENTABLE = 16,
+  NOTA_ERROR_CODE_SERDE = 17,
+  NOTA_ERROR_CODE_UNEXPECTED_VALUE = 18,
+  NOTA_ERROR_CODE_UNSUPPORTED_VERSION = 19,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum NotaErrorCode NotaErrorCode;
+#else
+typedef uint32_t NotaErrorCode;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+// Iterator over fields of a record, in order of their keys, from `nota_value_record_iter`
+typedef struct NotaRecordIter NotaRecordIter;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses one value from `len` bytes at `data`. On success, `*out` is set to a new handle.
+//
+// The data is treated as untrusted: nesting is limited to 64 levels, a single value to a million elements, chars, or bits,
+// and encodings must be the shortest possible. Use `nota_decode_with_limits` to choose the limits.
+//
+// # Safety
+//
+// `data` must point to `len` readable bytes. `out` must be a valid pointer.
+enum NotaStatus nota_decode(const uint8_t *data,
+                            uintptr_t len,
+                            NotaValue **out);
+
+// Like `nota_decode`, but with the deepest allowed nesting, and the largest number of elements, fields, chars, or bits
+// in a single value. 0 means no limit. Encodings don't have to be the shortest possible.
+//
+// # Safety
+//
+// `data` must point to `len` readable bytes. `out` must be a valid pointer.
+enum NotaStatus nota_decode_with_limits(const uint8_t *data,
+                                        uintptr_t len,
+                                        uintptr_t max_depth,
+                                        uintptr_t max_len,
+                                        NotaValue **out);
+
+// Serializes the value. On success, `*out_data` and `*out_len` are set to a new buffer that must be freed with `nota_bytes_free`.
+//
+// # Safety
+//
+// `value` must be a valid handle. `out_data` and `out_len` must be valid pointers.
+enum NotaStatus nota_encode(const NotaValue *value,
+                            uint8_t **out_data,
+                            uintptr_t *out_len);
+
+// The reason why the last call to `nota_decode`, `nota_decode_with_limits`, or `nota_encode` on this thread has failed, as a `NotaErrorCode`.
+// 0 if it has succeeded, or there were no calls.
+uint32_t nota_last_error_code(void);
+
+// Frees a buffer returned by `nota_encode`
+//
+// # Safety
+//
+// `data` and `len` must be exactly as returned by `nota_encode`, or `data` must be `NULL`.
+void nota_bytes_free(uint8_t *data, uintptr_t len);
+
+// Frees an owned handle and everything in it. `NULL` is ignored.
+//
+// # Safety
+//
+// `value` must be an owned handle that hasn't been freed or given away.
+void nota_value_free(NotaValue *value);
+
+// `NOTA_KIND_INVALID` if `value` is `NULL`
+//
+// # Safety
+//
+// `value` must be a valid handle or `NULL`.
+enum NotaKind nota_value_kind(const NotaValue *value);
+
+// # Safety
+//
+// `value` must be a valid handle, and `out` a valid pointer.
+enum NotaStatus nota_value_get_integer(const NotaValue *value, int64_t *out);
+
+// # Safety
+//
+// `value` must be a valid handle, and `out` a valid pointer.
+enum NotaStatus nota_value_get_bool(const NotaValue *value, bool *out);
+
+// Gives UTF-8 bytes of the text, borrowed from the handle.
+//
+// # Safety
+//
+// `value` must be a valid handle, and `out_data` and `out_len` valid pointers.
+enum NotaStatus nota_value_get_text(const NotaValue *value,
+                                    const uint8_t **out_data,
+                                    uintptr_t *out_len);
+
+// Gives bytes of the blob, borrowed from the handle, and its length in *bits*.
+//
+// # Safety
+//
+// `value` must be a valid handle, and `out_data` and `out_bits` valid pointers.
+enum NotaStatus nota_value_get_blob(const NotaValue *value,
+                                    const uint8_t **out_data,
+                                    uintptr_t *out_bits);
+
+// Number of elements of an array or fields of a record. 0 for other kinds.
+//
+// # Safety
+//
+// `value` must be a valid handle.
+uintptr_t nota_value_len(const NotaValue *value);
+
+// Borrowed handle to the array element, or `NULL` if it's not an array or the index is out of range.
+//
+// # Safety
+//
+// `value` must be a valid handle.
+const NotaValue *nota_value_array_get(const NotaValue *value,
+                                      uintptr_t index);
+
+// Borrowed handle to the record field, or `NULL` if it's not a record or there's no such field.
+//
+// # Safety
+//
+// `value` must be a valid handle, and `key` must point to `key_len` bytes.
+const NotaValue *nota_value_record_get(const NotaValue *value,
+                                       const uint8_t *key,
+                                       uintptr_t key_len);
+
+// New iterator over fields of the record, sorted by key, or `NULL` if it's not a record.
+// It borrows the record, and must be freed with `nota_record_iter_free` before the record is modified or freed.
+//
+// # Safety
+//
+// `value` must be a valid handle.
+struct NotaRecordIter *nota_value_record_iter(const NotaValue *value);
+
+// Sets `*out_key` and `*out_key_len` to the next field's key, as borrowed UTF-8 bytes,
+// and returns a borrowed handle to its value. Returns `NULL` after the last field.
+//
+// # Safety
+//
+// `iter` must be a valid iterator whose record hasn't been modified or freed, and `out_key` and `out_key_len` valid pointers.
+const NotaValue *nota_record_iter_next(struct NotaRecordIter *iter,
+                                       const uint8_t **out_key,
+                                       uintptr_t *out_key_len);
+
+// Frees an iterator. `NULL` is ignored.
+//
+// # Safety
+//
+// `iter` must be returned by `nota_value_record_iter`, and not freed already.
+void nota_record_iter_free(struct NotaRecordIter *iter);
+
+NotaValue *nota_value_new_null(void);
+
+NotaValue *nota_value_new_bool(bool b);
+
+NotaValue *nota_value_new_integer(int64_t i);
+
+// Copies UTF-8 text. Returns `NULL` if the text isn't valid UTF-8.
+//
+// # Safety
+//
+// `data` must point to `len` bytes.
+NotaValue *nota_value_new_text(const uint8_t *data, uintptr_t len);
+
+// Copies a blob that is `bits` long.
+//
+// # Safety
+//
+// `data` must point to at least `(bits + 7) / 8` bytes.
+NotaValue *nota_value_new_blob(const uint8_t *data, uintptr_t bits);
+
+NotaValue *nota_value_new_array(void);
+
+NotaValue *nota_value_new_record(void);
+
+// Appends `element` to the array, taking ownership of it (even on failure).
+//
+// # Safety
+//
+// `array` must be a valid handle, and `element` an owned handle.
+enum NotaStatus nota_value_array_push(NotaValue *array, NotaValue *element);
+
+// Sets the record's field, taking ownership of `field` (even on failure). Replaces any previous value of the field.
+//
+// # Safety
+//
+// `record` must be a valid handle, `key` must point to `key_len` bytes, and `field` must be an owned handle.
+enum NotaStatus nota_value_record_insert(NotaValue *record,
+                                         const uint8_t *key,
+                                         uintptr_t key_len,
+                                         NotaValue *field);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NOTA_H */
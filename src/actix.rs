@@ -0,0 +1,162 @@
+//! [`Nota`] extractor and responder for actix-web, like `actix_web::web::Json`
+//!
+//! Requests must have an `application/nota` content type (or `application/*+nota`), unless disabled in [`NotaConfig`].
+//! Responses are sent with `application/nota`.
+use crate::mime::is_nota_content_type;
+use crate::{Error, ParseOptions};
+use actix_web::body::BoxBody;
+use actix_web::dev::Payload;
+use actix_web::error::PayloadError;
+use actix_web::http::{header, StatusCode};
+use actix_web::web::BytesMut;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, Responder, ResponseError};
+use futures_util::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::{fmt, io};
+
+pub use crate::mime::CONTENT_TYPE;
+
+/// Decodes request bodies and encodes responses as Nota, using the [serde integration](crate::serde)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nota<T>(pub T);
+
+/// Settings for the [`Nota`] extractor. Add it with `App::app_data`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct NotaConfig {
+    /// Maximum size of the request body in bytes. 2MiB by default.
+    pub limit: usize,
+    /// Reject requests that don't have a Nota content type
+    pub content_type_required: bool,
+    /// Limits on the parsed body, such as its nesting depth. [`ParseOptions::untrusted`] by default.
+    pub parse: ParseOptions,
+}
+
+impl Default for NotaConfig {
+    fn default() -> Self {
+        Self { limit: 2 << 20, content_type_required: true, parse: ParseOptions::untrusted() }
+    }
+}
+
+/// Why [`Nota`] couldn't be extracted from a request
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum NotaPayloadError {
+    /// The content type isn't Nota. Responds with 415.
+    ContentType,
+    /// The body is larger than [`NotaConfig::limit`]. Responds with 413.
+    Overflow { limit: usize },
+    /// Reading the body has failed
+    Payload(PayloadError),
+    /// The body isn't valid Nota. Responds with 400.
+    Syntax(io::Error),
+    /// The body doesn't match the expected type. Responds with 422.
+    Data(io::Error),
+}
+
+impl fmt::Display for NotaPayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ContentType => write!(f, "expected request with `Content-Type: {CONTENT_TYPE}`"),
+            Self::Overflow { limit } => write!(f, "Nota body is larger than {limit} bytes"),
+            Self::Payload(err) => err.fmt(f),
+            Self::Syntax(err) => write!(f, "invalid Nota body: {err}"),
+            Self::Data(err) => write!(f, "unexpected Nota body: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for NotaPayloadError {}
+
+impl ResponseError for NotaPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Payload(err) => err.status_code(),
+            Self::Syntax(_) => StatusCode::BAD_REQUEST,
+            Self::Data(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Nota<T> {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, actix_web::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = req.app_data::<NotaConfig>().cloned().unwrap_or_default();
+        let content_type = req.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+        let content_type_ok = !config.content_type_required || content_type.is_some_and(is_nota_content_type);
+        let content_length = req.headers().get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok()?.parse::<usize>().ok());
+        let mut payload = payload.take();
+
+        Box::pin(async move {
+            if !content_type_ok {
+                return Err(NotaPayloadError::ContentType.into());
+            }
+            let limit = config.limit;
+            if content_length.is_some_and(|len| len > limit) {
+                return Err(NotaPayloadError::Overflow { limit }.into());
+            }
+            let mut body = BytesMut::with_capacity(content_length.unwrap_or(0));
+            while let Some(chunk) = payload.next().await {
+                let chunk = chunk.map_err(NotaPayloadError::Payload)?;
+                if body.len() + chunk.len() > limit {
+                    return Err(NotaPayloadError::Overflow { limit }.into());
+                }
+                body.extend_from_slice(&chunk);
+            }
+            crate::serde::from_slice_with(&body, &config.parse).map(Self).map_err(|err| match Error::from_io(&err) {
+                Some(Error::Serde(_) | Error::Unrepresentable(_)) => NotaPayloadError::Data(err).into(),
+                _ => NotaPayloadError::Syntax(err).into(),
+            })
+        })
+    }
+}
+
+impl<T: Serialize> Responder for Nota<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        match crate::serde::to_vec(&self.0) {
+            Ok(bytes) => HttpResponse::Ok().content_type(CONTENT_TYPE).body(bytes),
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        }
+    }
+}
+
+#[test]
+fn extract_and_respond() {
+    use crate::block_on;
+    use actix_web::test::TestRequest;
+
+    let req = TestRequest::default().to_http_request();
+    let response = Nota(vec![1u8, 2]).respond_to(&req);
+    assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), CONTENT_TYPE);
+    let body = block_on(actix_web::body::to_bytes(response.into_body())).unwrap();
+
+    let extract = |req: TestRequest| {
+        let (req, mut payload) = req.to_http_parts();
+        block_on(Nota::<Vec<u8>>::from_request(&req, &mut payload))
+    };
+    let nota = || TestRequest::default().insert_header((header::CONTENT_TYPE, "application/nota; v=1")).set_payload(body.clone());
+
+    assert_eq!(extract(nota()).unwrap().0, [1, 2]);
+    let err = extract(TestRequest::default().set_payload(body.clone())).unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let err = extract(nota().app_data(NotaConfig { limit: 2, ..Default::default() })).unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(extract(TestRequest::default().app_data(NotaConfig { content_type_required: false, ..Default::default() }).set_payload(body.clone())).is_ok());
+    let err = extract(nota().set_payload(&b"\x21a"[..])).unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let mut nested = vec![0x41; 100_000];
+    nested.push(0xC2);
+    let err = extract(nota().set_payload(nested)).unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::BAD_REQUEST);
+}
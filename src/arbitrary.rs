@@ -0,0 +1,106 @@
+//! Random documents for fuzzing, with [`arbitrary`]
+//!
+//! `Value` implements [`Arbitrary`], so it can be the input of a `cargo fuzz` target directly:
+//!
+//! ```rust,ignore
+//! fuzz_target!(|value: nota::Value| {
+//!     let mut bytes = Vec::new();
+//!     value.serialize_into(&mut bytes);
+//!     assert_eq!(nota::Value::parse_slice(&mut &bytes[..]).unwrap(), value);
+//! });
+//! ```
+//!
+//! The generated values can always be serialized: there's no DEC64.
+//! Use [`value`] to limit their depth and the lengths of arrays, records, texts, and blobs.
+use crate::Value;
+use arbitrary::{Arbitrary, Result, Unstructured};
+#[cfg(feature = "blobs")]
+use bitvec::vec::BitVec;
+#[cfg(feature = "records")]
+use std::collections::HashMap;
+
+/// Nesting depth of values from [`Value::arbitrary`]. A scalar at the top level has depth 1.
+pub const DEFAULT_MAX_DEPTH: usize = 8;
+
+/// A value nested at most `max_depth` levels deep, with at most `max_len` elements, fields, chars, or bytes in each value
+pub fn value(u: &mut Unstructured<'_>, max_depth: usize, max_len: usize) -> Result<Value> {
+    let kinds = if max_depth > 1 { 7 } else { 5 };
+    Ok(match u.choose_index(kinds)? {
+        #[cfg(feature = "blobs")]
+        0 => {
+            let len = u.arbitrary_len::<u8>()?.min(max_len);
+            let mut bits = BitVec::from_vec(u.bytes(len)?.to_vec());
+            // not only whole bytes
+            let unused = u.int_in_range(0..=7)?;
+            bits.truncate(bits.len().saturating_sub(unused));
+            Value::Blob(bits)
+        },
+        1 => Value::Text(text(u, max_len)?),
+        2 => {
+            let n = i128::arbitrary(u)?;
+            // shorter numbers are more common
+            Value::Integer(n >> u.int_in_range(0..=127)?)
+        },
+        3 => Value::Bool(u.arbitrary()?),
+        5 => {
+            let len = u.arbitrary_len::<Value>()?.min(max_len);
+            Value::Array((0..len).map(|_| value(u, max_depth - 1, max_len)).collect::<Result<_>>()?)
+        },
+        #[cfg(feature = "records")]
+        6 => {
+            let len = u.arbitrary_len::<(String, Value)>()?.min(max_len);
+            let mut fields = HashMap::with_capacity(len);
+            for _ in 0..len {
+                fields.insert(text(u, max_len)?, value(u, max_depth - 1, max_len)?);
+            }
+            Value::Record(Box::new(fields))
+        },
+        _ => Value::Null,
+    })
+}
+
+fn text(u: &mut Unstructured<'_>, max_len: usize) -> Result<String> {
+    let len = u.arbitrary_len::<char>()?.min(max_len);
+    (0..len).map(|_| char::arbitrary(u)).collect()
+}
+
+impl<'a> Arbitrary<'a> for Value {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        value(u, DEFAULT_MAX_DEPTH, usize::MAX)
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (1, None)
+    }
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn round_trip() {
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let data: Vec<u8> = (0..100_000).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 56) as u8
+    }).collect();
+    let mut u = Unstructured::new(&data);
+    let mut kinds = [false; 7];
+    while !u.is_empty() {
+        let value = value(&mut u, 3, 10).unwrap();
+        kinds[match &value {
+            Value::Blob(_) => 0,
+            Value::Text(_) => 1,
+            Value::Integer(_) => 2,
+            Value::Bool(_) => 3,
+            Value::Array(_) => 5,
+            Value::Record(_) => 6,
+            _ => 4,
+        }] = true;
+        let mut bytes = Vec::new();
+        value.serialize_into(&mut bytes);
+        let options = crate::ParseOptions { max_depth: Some(3), max_len: Some(80), ..Default::default() };
+        assert_eq!(Value::parse_slice_with(&mut &bytes[..], &options).unwrap(), value);
+    }
+    assert!(kinds.iter().all(|&seen| seen));
+}
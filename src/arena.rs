@@ -0,0 +1,339 @@
+//! Values allocated in a [`bumpalo`] arena
+//!
+//! [`ArenaValue::parse_in`] puts all texts, arrays, and records of a document in one [`Bump`].
+//! They're freed all at once when the arena is dropped or [reset](Bump::reset), instead of one `String` or `Vec` at a time.
+//!
+//! ```rust
+//! use nota::arena::{ArenaValue, Bump};
+//!
+//! # let message = [0x42, 0x23, b'c', b'a', b't', 0xC2];
+//! let mut bump = Bump::new();
+//! for _ in 0..3 {
+//!     let value = ArenaValue::parse_in(&mut &message[..], &bump)?;
+//!     assert!(matches!(value, ArenaValue::Array([ArenaValue::Text("cat"), ArenaValue::Null])));
+//!     bump.reset();
+//! }
+//! # Ok::<_, std::io::Error>(())
+//! ```
+use crate::progress::Tracker;
+#[cfg(feature = "blobs")]
+use crate::pull::Bits;
+use crate::{ascii_prefix, kim_char, kim_group, varint, ParseOptions, Value};
+pub use bumpalo::Bump;
+use bumpalo::collections::{String as BumpString, Vec as BumpVec};
+use std::io;
+
+/// Like [`Value`], but borrowed from a [`Bump`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArenaValue<'b> {
+    #[cfg(feature = "blobs")]
+    Blob(Bits<'b>),
+    Text(&'b str),
+    Array(&'b [ArenaValue<'b>]),
+    /// Fields in the order they were in the data, including duplicate keys
+    #[cfg(feature = "records")]
+    Record(&'b [(&'b str, ArenaValue<'b>)]),
+    Integer(i128),
+    Bool(bool),
+    Null,
+    /// Same as [`Value::Unknown`]
+    Unknown { tag: u8, raw_bytes: &'b [u8] },
+}
+
+impl<'b> ArenaValue<'b> {
+    /// Parses one value from the start of `data`, allocating in `bump`.
+    ///
+    /// On success, `data` is advanced past the value.
+    pub fn parse_in(data: &mut &[u8], bump: &'b Bump) -> Result<Self, io::Error> {
+        Self::parse_in_with(data, bump, &ParseOptions::default())
+    }
+
+    pub fn parse_in_with(data: &mut &[u8], bump: &'b Bump, options: &ParseOptions) -> Result<Self, io::Error> {
+        let mut parser = ArenaParser { data, pos: 0, options, depth: 0, progress: Tracker::new(options.progress.as_ref()), bump, strings: Vec::new() };
+        let value = parser.value()?;
+        options.check_size(parser.pos as u64)?;
+        parser.progress.finish(parser.pos as u64);
+        *data = &data[parser.pos..];
+        Ok(value)
+    }
+
+    /// Value of the last field with this key, like in [`Value::Record`]
+    #[cfg(feature = "records")]
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&ArenaValue<'b>> {
+        match self {
+            Self::Record(fields) => fields.iter().rev().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Copies out of the arena
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        match *self {
+            #[cfg(feature = "blobs")]
+            Self::Blob(bits) => {
+                let mut out = bitvec::vec::BitVec::from_slice(bits.bytes);
+                out.truncate(bits.bit_len);
+                Value::Blob(out)
+            },
+            Self::Text(text) => Value::Text(text.into()),
+            Self::Array(items) => Value::Array(items.iter().map(Self::to_value).collect()),
+            #[cfg(feature = "records")]
+            Self::Record(fields) => Value::Record(Box::new(fields.iter().map(|(k, v)| ((*k).into(), v.to_value())).collect())),
+            Self::Integer(n) => Value::Integer(n),
+            Self::Bool(b) => Value::Bool(b),
+            Self::Null => Value::Null,
+            Self::Unknown { tag, raw_bytes } => Value::Unknown { tag, raw_bytes: raw_bytes.into() },
+        }
+    }
+}
+
+struct ArenaParser<'a, 'b> {
+    data: &'a [u8],
+    pos: usize,
+    options: &'a ParseOptions,
+    depth: usize,
+    progress: Tracker<'a>,
+    bump: &'b Bump,
+    /// Texts that back-references point to
+    strings: Vec<&'b str>,
+}
+
+impl<'b> ArenaParser<'_, 'b> {
+    #[inline]
+    fn byte(&mut self) -> Result<u8, io::Error> {
+        let &byte = self.data.get(self.pos).ok_or(io::ErrorKind::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn value(&mut self) -> Result<ArenaValue<'b>, io::Error> {
+        self.depth += 1;
+        let res = self.value_inner();
+        self.depth -= 1;
+        res
+    }
+
+    fn value_inner(&mut self) -> Result<ArenaValue<'b>, io::Error> {
+        self.options.check_cancelled()?;
+        self.options.check_depth(self.depth)?;
+        self.options.check_size(self.pos as u64)?;
+        self.progress.value(self.pos as u64);
+        let preamble = self.byte()?;
+        Ok(match preamble & 0b1110_0000 {
+            #[cfg(feature = "blobs")]
+            0b0000_0000 => {
+                let bit_len = self.len(preamble)?;
+                let len = bit_len.div_ceil(8);
+                self.options.check_size(self.pos.saturating_add(len) as u64)?;
+                if len > self.remaining() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                self.options.check_padding(&self.data[self.pos..self.pos + len], bit_len)?;
+                let bytes = self.bump.alloc_slice_copy(&self.data[self.pos..self.pos + len]);
+                self.pos += len;
+                ArenaValue::Blob(Bits { bytes, bit_len })
+            },
+            0b0010_0000 => {
+                let len = self.len(preamble)?;
+                // every char takes at least a byte
+                self.options.check_size(self.pos.saturating_add(len) as u64)?;
+                let text = self.text(len)?;
+                if self.options.backrefs {
+                    self.strings.push(text);
+                }
+                ArenaValue::Text(text)
+            },
+            0b0100_0000 => {
+                let len = self.len(preamble)?;
+                // every element takes at least a byte
+                let mut out = BumpVec::with_capacity_in(len.min(self.remaining()), self.bump);
+                for _ in 0..len {
+                    out.push(self.value()?);
+                }
+                ArenaValue::Array(out.into_bump_slice())
+            },
+            #[cfg(feature = "records")]
+            0b0110_0000 => {
+                let len = self.len(preamble)?;
+                let mut out = BumpVec::with_capacity_in(len.min(self.remaining() / 2), self.bump);
+                for _ in 0..len {
+                    let ArenaValue::Text(k) = self.value()? else {
+                        return Err(io::ErrorKind::InvalidData.into());
+                    };
+                    out.push((k, self.value()?));
+                }
+                ArenaValue::Record(out.into_bump_slice())
+            },
+            0b1000_0000 => {
+                let mut val = u128::from(preamble & 0b000_0111);
+                if preamble & 0b0001_0000 != 0 {
+                    if let Some((groups, bytes)) = varint::decode8(&self.data[self.pos..]) {
+                        self.pos += bytes;
+                        val = val << (7 * bytes) | u128::from(groups);
+                        self.options.check_minimal(val >> (7 * (bytes - 1)) >= 8)?;
+                    } else {
+                        loop {
+                            let next = self.byte()?;
+                            val = varint::push_int(val, next)?;
+                            self.options.check_minimal(val >= 8)?;
+                            if next & 0b1000_0000 == 0 {
+                                break;
+                            }
+                        }
+                    }
+                }
+                self.options.check_minimal(preamble & 0b000_1000 == 0 || val != 0)?;
+                ArenaValue::Integer(varint::signed(preamble & 0b000_1000 != 0, val)?)
+            },
+            0b1100_0000 => match preamble & 0b0001_1111 {
+                0 => ArenaValue::Bool(false),
+                1 => ArenaValue::Bool(true),
+                2 => ArenaValue::Null,
+                _ => self.alloc(&self.options.constant(preamble)?)?,
+            },
+            0b1110_0000 if self.options.backrefs => {
+                let index = self.unsigned(preamble)?;
+                ArenaValue::Text(match self.options.backref(index) {
+                    Ok(text) => self.bump.alloc_str(text),
+                    Err(index) => self.strings.get(index).copied().ok_or(io::ErrorKind::InvalidData)?,
+                })
+            },
+            0b1110_0000 if self.options.unknown => {
+                let start = self.pos - 1;
+                self.unsigned(preamble)?;
+                ArenaValue::Unknown { tag: 0b111, raw_bytes: self.bump.alloc_slice_copy(&self.data[start..self.pos]) }
+            },
+            #[cfg(not(feature = "blobs"))]
+            0b0000_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            #[cfg(not(feature = "records"))]
+            0b0110_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            // DEC64 has no arena representation
+            0b1010_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            _ => return Err(io::ErrorKind::InvalidData.into()),
+        })
+    }
+
+    /// Copies a value from [`ParseOptions::constants`] into the arena
+    fn alloc(&self, value: &Value) -> Result<ArenaValue<'b>, io::Error> {
+        Ok(match value {
+            #[cfg(feature = "blobs")]
+            Value::Blob(bits) => ArenaValue::Blob(Bits { bytes: self.bump.alloc_slice_copy(bits.as_raw_slice()), bit_len: bits.len() }),
+            Value::Text(text) => ArenaValue::Text(self.bump.alloc_str(text)),
+            Value::Array(items) => {
+                let items = items.iter().map(|v| self.alloc(v)).collect::<Result<Vec<_>, _>>()?;
+                ArenaValue::Array(self.bump.alloc_slice_copy(&items))
+            },
+            #[cfg(feature = "records")]
+            Value::Record(fields) => {
+                let fields = fields.iter().map(|(k, v)| Ok((&*self.bump.alloc_str(k), self.alloc(v)?))).collect::<Result<Vec<_>, io::Error>>()?;
+                ArenaValue::Record(self.bump.alloc_slice_copy(&fields))
+            },
+            Value::Integer(n) => ArenaValue::Integer(*n),
+            #[cfg(feature = "dec64")]
+            Value::DecimalFloat(_) => return Err(io::ErrorKind::Unsupported.into()),
+            Value::Bool(b) => ArenaValue::Bool(*b),
+            Value::Null => ArenaValue::Null,
+            Value::Unknown { tag, raw_bytes } => ArenaValue::Unknown { tag: *tag, raw_bytes: self.bump.alloc_slice_copy(raw_bytes) },
+        })
+    }
+
+    /// `len` is in chars, which take at least a byte each
+    fn text(&mut self, len: usize) -> Result<&'b str, io::Error> {
+        let ascii = ascii_prefix(&self.data[self.pos..], len);
+        if ascii.len() == len {
+            self.pos += len;
+            return Ok(self.bump.alloc_str(ascii));
+        }
+        let mut out = BumpString::with_capacity_in(len.min(self.remaining()), self.bump);
+        let mut remaining = len;
+        while remaining > 0 {
+            let ascii = ascii_prefix(&self.data[self.pos..], remaining);
+            if !ascii.is_empty() {
+                out.push_str(ascii);
+                self.pos += ascii.len();
+                remaining -= ascii.len();
+                continue;
+            }
+            let start = self.pos as u64;
+            let mut val = 0u32;
+            loop {
+                let next = self.byte()?;
+                self.options.check_minimal(val != 0 || next != 0b1000_0000)?;
+                val = kim_group(val, next, start)?;
+                if next & 0b1000_0000 == 0 {
+                    break;
+                }
+            }
+            out.push(kim_char(val, start)?);
+            remaining -= 1;
+        }
+        Ok(out.into_bump_str())
+    }
+
+    fn len(&mut self, preamble: u8) -> Result<usize, io::Error> {
+        let len = self.unsigned(preamble)?;
+        self.options.check_len(len)?;
+        Ok(len)
+    }
+
+    fn unsigned(&mut self, preamble: u8) -> Result<usize, io::Error> {
+        let mut len = usize::from(preamble & 0b000_1111);
+        if preamble & 0b0001_0000 != 0 {
+            if let Some((groups, bytes)) = varint::decode8(&self.data[self.pos..]) {
+                self.pos += bytes;
+                len = varint::join_len(len, groups, bytes)?;
+                self.options.check_minimal(len >> (7 * (bytes - 1)) >= 16)?;
+            } else {
+                loop {
+                    let next = self.byte()?;
+                    len = varint::push_len(len, next)?;
+                    self.options.check_minimal(len >= 16)?;
+                    if next & 0b1000_0000 == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn same_as_value() {
+    let value = Value::Array(vec![
+        Value::Record(Box::new([
+            ("ascii".to_string(), Value::Text("hello".into())),
+            ("☃".to_string(), Value::Text("snow ☃ 𓂀".into())),
+            ("bits".to_string(), Value::Blob([true, false, true].into_iter().collect())),
+        ].into_iter().collect())),
+        Value::Integer(-1234567890123),
+        Value::Array(vec![Value::Null, Value::Bool(true), Value::Text(String::new())]),
+    ]);
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+    bytes.push(0xC2);
+
+    let bump = Bump::new();
+    let mut rest = &bytes[..];
+    let parsed = ArenaValue::parse_in(&mut rest, &bump).unwrap();
+    assert_eq!(rest, [0xC2]);
+    assert_eq!(parsed.to_value(), value);
+    let ArenaValue::Array([record, ..]) = parsed else { panic!() };
+    assert_eq!(record.get("☃"), Some(&ArenaValue::Text("snow ☃ 𓂀")));
+
+    for len in 0..bytes.len() - 1 {
+        let mut data = &bytes[..len];
+        assert_eq!(ArenaValue::parse_in(&mut data, &bump).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(data.len(), len);
+    }
+    let options = ParseOptions { max_depth: Some(2), ..Default::default() };
+    assert!(ArenaValue::parse_in_with(&mut &bytes[..], &bump, &options).is_err());
+}
@@ -0,0 +1,150 @@
+//! Conversions between arrays of records and Arrow's [`RecordBatch`]
+//!
+//! Every record becomes a row, and every field a column. Columns are sorted by name.
+//! Values of a field must have the same type in every record, but they can be [`Value::Null`] or missing, which makes the column nullable.
+//!
+//! | Nota | Arrow |
+//! |------|-------|
+//! | Integer | `Int64` (converts back from any integer type) |
+//! | Text | `Utf8` (and `LargeUtf8`) |
+//! | Bool | `Boolean` |
+//! | Blob (whole bytes only) | `Binary` (and `LargeBinary`) |
+//! | Null | `Null` |
+//!
+//! `Float16`, `Float32`, and `Float64` columns can't be converted. Use Arrow's `cast` kernel to turn them into `Utf8`
+//! or integers first, depending on what the data means. Other Arrow types can't be converted either.
+//! Null entries in Arrow columns become [`Value::Null`] fields.
+use crate::{Error, Value};
+use arrow_array::cast::AsArray;
+use arrow_array::types::*;
+use arrow_array::{ArrayRef, BinaryArray, BooleanArray, Int64Array, NullArray, RecordBatch, RecordBatchOptions, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use bitvec::vec::BitVec;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Converts an array of records with consistently-typed fields
+pub fn to_record_batch(value: &Value) -> Result<RecordBatch, Error> {
+    let Value::Array(rows) = value else {
+        return Err(Error::Unrepresentable("value that isn't an array"));
+    };
+    let records = rows.iter().map(|row| match row {
+        Value::Record(r) => Ok(r),
+        _ => Err(Error::Unrepresentable("array element that isn't a record")),
+    }).collect::<Result<Vec<_>, _>>()?;
+
+    let mut columns = BTreeMap::<&str, (DataType, bool)>::new();
+    for record in &records {
+        for (name, v) in record.iter() {
+            let col = columns.entry(name).or_insert((DataType::Null, false));
+            let data_type = match v {
+                Value::Null => {
+                    col.1 = true;
+                    continue;
+                },
+                Value::Integer(_) => DataType::Int64,
+                Value::Text(_) => DataType::Utf8,
+                Value::Bool(_) => DataType::Boolean,
+                Value::Blob(_) => DataType::Binary,
+                _ => return Err(Error::Unrepresentable("field that isn't a scalar")),
+            };
+            if col.0 == DataType::Null {
+                col.0 = data_type;
+            } else if col.0 != data_type {
+                return Err(Error::Unrepresentable("field with inconsistent types"));
+            }
+        }
+    }
+
+    let mut fields = Vec::with_capacity(columns.len());
+    let mut arrays = Vec::<ArrayRef>::with_capacity(columns.len());
+    for (name, (data_type, mut nullable)) in columns {
+        let cells = records.iter().map(|r| r.get(name).filter(|v| **v != Value::Null)).collect::<Vec<_>>();
+        nullable |= cells.iter().any(Option::is_none);
+        arrays.push(match data_type {
+            DataType::Int64 => Arc::new(cells.iter().map(|v| match v {
+                Some(Value::Integer(i)) => i64::try_from(*i).map(Some).map_err(|_| Error::Unrepresentable("integer larger than 64 bits")),
+                _ => Ok(None),
+            }).collect::<Result<Int64Array, _>>()?),
+            DataType::Utf8 => Arc::new(cells.iter().map(|v| match v {
+                Some(Value::Text(s)) => Some(s.as_str()),
+                _ => None,
+            }).collect::<StringArray>()),
+            DataType::Boolean => Arc::new(cells.iter().map(|v| match v {
+                Some(Value::Bool(b)) => Some(*b),
+                _ => None,
+            }).collect::<BooleanArray>()),
+            DataType::Binary => Arc::new(BinaryArray::from(cells.iter().map(|v| match v {
+                Some(Value::Blob(bits)) if bits.len() % 8 == 0 => Ok(Some(bits.as_raw_slice())),
+                Some(_) => Err(Error::Unrepresentable("blob with a partial byte")),
+                None => Ok(None),
+            }).collect::<Result<Vec<_>, _>>()?)),
+            _ => Arc::new(NullArray::new(records.len())),
+        });
+        fields.push(Field::new(name, data_type, nullable));
+    }
+
+    let options = RecordBatchOptions::new().with_row_count(Some(records.len()));
+    RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), arrays, &options)
+        .map_err(|_| Error::Unrepresentable("invalid record batch"))
+}
+
+/// Converts every row to a record
+pub fn from_record_batch(batch: &RecordBatch) -> Result<Value, Error> {
+    let schema = batch.schema();
+    let mut rows = vec![HashMap::with_capacity(schema.fields().len()); batch.num_rows()];
+    for (field, col) in schema.fields().iter().zip(batch.columns()) {
+        for (i, row) in rows.iter_mut().enumerate() {
+            let v = if col.is_null(i) {
+                Value::Null
+            } else {
+                match col.data_type() {
+                    DataType::Null => Value::Null,
+                    DataType::Boolean => Value::Bool(col.as_boolean().value(i)),
+                    DataType::Int8 => Value::Integer(col.as_primitive::<Int8Type>().value(i).into()),
+                    DataType::Int16 => Value::Integer(col.as_primitive::<Int16Type>().value(i).into()),
+                    DataType::Int32 => Value::Integer(col.as_primitive::<Int32Type>().value(i).into()),
+                    DataType::Int64 => Value::Integer(col.as_primitive::<Int64Type>().value(i).into()),
+                    DataType::UInt8 => Value::Integer(col.as_primitive::<UInt8Type>().value(i).into()),
+                    DataType::UInt16 => Value::Integer(col.as_primitive::<UInt16Type>().value(i).into()),
+                    DataType::UInt32 => Value::Integer(col.as_primitive::<UInt32Type>().value(i).into()),
+                    DataType::UInt64 => Value::Integer(col.as_primitive::<UInt64Type>().value(i).into()),
+                    DataType::Float16 | DataType::Float32 | DataType::Float64 => return Err(Error::Unrepresentable("float")),
+                    DataType::Utf8 => Value::Text(col.as_string::<i32>().value(i).into()),
+                    DataType::LargeUtf8 => Value::Text(col.as_string::<i64>().value(i).into()),
+                    DataType::Binary => Value::Blob(BitVec::from_vec(col.as_binary::<i32>().value(i).to_vec())),
+                    DataType::LargeBinary => Value::Blob(BitVec::from_vec(col.as_binary::<i64>().value(i).to_vec())),
+                    _ => return Err(Error::Unrepresentable("Arrow data type")),
+                }
+            };
+            row.insert(field.name().clone(), v);
+        }
+    }
+    Ok(Value::Array(rows.into_iter().map(|r| Value::Record(Box::new(r))).collect()))
+}
+
+#[test]
+fn roundtrip() {
+    let row = |id: i128, name: Option<&str>, ok: bool| {
+        let mut r = HashMap::new();
+        r.insert("id".to_string(), Value::Integer(id));
+        r.insert("name".to_string(), name.map_or(Value::Null, |n| Value::Text(n.into())));
+        r.insert("ok".to_string(), Value::Bool(ok));
+        r.insert("raw".to_string(), Value::Blob(BitVec::from_vec(vec![id as u8])));
+        Value::Record(Box::new(r))
+    };
+    let value = Value::Array(vec![row(1, Some("a"), true), row(2, None, false), row(3, Some("c"), true)]);
+    let batch = to_record_batch(&value).unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    let schema = batch.schema();
+    assert_eq!(schema.fields().iter().map(|f| f.name().as_str()).collect::<Vec<_>>(), ["id", "name", "ok", "raw"]);
+    assert!(schema.field(1).is_nullable());
+    assert!(!schema.field(0).is_nullable());
+    assert_eq!(from_record_batch(&batch).unwrap(), value);
+
+    let mixed = Value::Array(vec![row(1, None, true), Value::Record(Box::new([("id".to_string(), Value::Text("x".into()))].into_iter().collect()))]);
+    assert!(to_record_batch(&mixed).is_err());
+
+    let floats = RecordBatch::try_from_iter([("f", Arc::new(arrow_array::Float64Array::from(vec![0.5])) as ArrayRef)]).unwrap();
+    assert_eq!(from_record_batch(&floats), Err(Error::Unrepresentable("float")));
+}
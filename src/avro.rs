@@ -0,0 +1,94 @@
+//! Conversions to and from [`apache_avro::types::Value`]
+//!
+//! Without a schema, records become Avro maps and integers become longs. Use [`to_avro_with_schema`]
+//! to get values that match a schema, including records, ints, enums, and unions.
+//!
+//! From Avro, enums become their symbol text, unions are unwrapped, fixed, bytes, and UUIDs become blobs,
+//! and dates, times, and timestamps become integers in their Avro units. Avro floats, doubles, decimals, big decimals,
+//! and durations can't be converted. Integers can still be written to `float` and `double` fields of a schema,
+//! since Avro's schema resolution promotes them.
+use crate::{Error, Value};
+use apache_avro::types::Value as Avro;
+use apache_avro::Schema;
+use bitvec::vec::BitVec;
+
+impl TryFrom<Avro> for Value {
+    type Error = Error;
+
+    fn try_from(avro: Avro) -> Result<Self, Error> {
+        Ok(match avro {
+            Avro::Null => Self::Null,
+            Avro::Boolean(b) => Self::Bool(b),
+            Avro::Int(i) | Avro::Date(i) | Avro::TimeMillis(i) => Self::Integer(i.into()),
+            Avro::Long(i) | Avro::TimeMicros(i) |
+            Avro::TimestampMillis(i) | Avro::TimestampMicros(i) | Avro::TimestampNanos(i) |
+            Avro::LocalTimestampMillis(i) | Avro::LocalTimestampMicros(i) | Avro::LocalTimestampNanos(i) => Self::Integer(i.into()),
+            Avro::Float(_) | Avro::Double(_) => return Err(Error::Unrepresentable("float")),
+            Avro::BigDecimal(_) => return Err(Error::Unrepresentable("Avro big decimal")),
+            Avro::Bytes(b) | Avro::Fixed(_, b) => Self::Blob(BitVec::from_vec(b)),
+            Avro::Uuid(u) => Self::Blob(BitVec::from_vec(u.as_bytes().to_vec())),
+            Avro::String(s) | Avro::Enum(_, s) => Self::Text(s),
+            Avro::Union(_, v) => Self::try_from(*v)?,
+            Avro::Array(a) => Self::Array(a.into_iter().map(Self::try_from).collect::<Result<_, _>>()?),
+            Avro::Map(m) => Self::Record(Box::new(m.into_iter().map(|(k, v)| Ok((k, Self::try_from(v)?))).collect::<Result<_, Error>>()?)),
+            Avro::Record(r) => Self::Record(Box::new(r.into_iter().map(|(k, v)| Ok((k, Self::try_from(v)?))).collect::<Result<_, Error>>()?)),
+            Avro::Decimal(_) => return Err(Error::Unrepresentable("Avro decimal")),
+            Avro::Duration(_) => return Err(Error::Unrepresentable("Avro duration")),
+        })
+    }
+}
+
+impl TryFrom<Value> for Avro {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Ok(match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Boolean(b),
+            Value::Integer(i) => Self::Long(i64::try_from(i).map_err(|_| Error::Unrepresentable("integer larger than 64 bits"))?),
+            Value::Text(s) => Self::String(s),
+            Value::Blob(bits) if bits.len() % 8 == 0 => Self::Bytes(bits.into_vec()),
+            Value::Blob(_) => return Err(Error::Unrepresentable("blob with a partial byte")),
+            Value::Array(a) => Self::Array(a.into_iter().map(Self::try_from).collect::<Result<_, _>>()?),
+            Value::Record(r) => Self::Map(r.into_iter().map(|(k, v)| Ok((k, Self::try_from(v)?))).collect::<Result<_, Error>>()?),
+            Value::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float")),
+            Value::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag")),
+        })
+    }
+}
+
+/// Converts and resolves the value against the schema
+pub fn to_avro_with_schema(value: Value, schema: &Schema) -> Result<Avro, Error> {
+    Avro::try_from(value)?.resolve(schema).map_err(|_| Error::Unrepresentable("value that doesn't match the Avro schema"))
+}
+
+#[test]
+fn with_schema() {
+    let schema = Schema::parse_str(r#"{
+        "type": "record", "name": "Event",
+        "fields": [
+            {"name": "id", "type": "int"},
+            {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A", "B"]}},
+            {"name": "note", "type": ["null", "string"]},
+            {"name": "data", "type": "bytes"}
+        ]
+    }"#).unwrap();
+    let value = Value::Record(Box::new([
+        ("id".to_string(), Value::Integer(7)),
+        ("kind".to_string(), Value::Text("B".into())),
+        ("note".to_string(), Value::Null),
+        ("data".to_string(), Value::Blob(BitVec::from_vec(vec![1, 2]))),
+    ].into_iter().collect()));
+
+    let avro = to_avro_with_schema(value.clone(), &schema).unwrap();
+    let Avro::Record(fields) = &avro else { panic!() };
+    assert_eq!(fields[0], ("id".to_string(), Avro::Int(7)));
+    assert_eq!(fields[1], ("kind".to_string(), Avro::Enum(1, "B".into())));
+    assert_eq!(Value::try_from(avro).unwrap(), value);
+
+    assert!(to_avro_with_schema(Value::Integer(1), &schema).is_err());
+
+    let double = Schema::parse_str(r#""double""#).unwrap();
+    assert_eq!(to_avro_with_schema(Value::Integer(3), &double).unwrap(), Avro::Double(3.));
+    assert_eq!(Value::try_from(Avro::Double(3.)), Err(Error::Unrepresentable("float")));
+}
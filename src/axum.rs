@@ -0,0 +1,109 @@
+//! [`Nota`] extractor and response for axum, like `axum::Json`
+//!
+//! Requests must have an `application/nota` content type (or `application/*+nota`).
+//! Bodies are parsed with [`ParseOptions::untrusted`] limits. Responses are sent with `application/nota`.
+use crate::mime::is_nota_content_type;
+use crate::{Error, ParseOptions};
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::{fmt, io};
+
+pub use crate::mime::CONTENT_TYPE;
+
+/// Decodes request bodies and encodes responses as Nota, using the [serde integration](crate::serde)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nota<T>(pub T);
+
+/// Why [`Nota`] couldn't be extracted from a request
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum NotaRejection {
+    /// The content type isn't Nota. Responds with 415.
+    MissingContentType,
+    /// Reading the body has failed
+    Body(BytesRejection),
+    /// The body isn't valid Nota. Responds with 400.
+    Syntax(io::Error),
+    /// The body doesn't match the expected type. Responds with 422.
+    Data(io::Error),
+}
+
+impl fmt::Display for NotaRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingContentType => write!(f, "expected request with `Content-Type: {CONTENT_TYPE}`"),
+            Self::Body(err) => err.fmt(f),
+            Self::Syntax(err) => write!(f, "invalid Nota body: {err}"),
+            Self::Data(err) => write!(f, "unexpected Nota body: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for NotaRejection {}
+
+impl IntoResponse for NotaRejection {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            Self::MissingContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Body(err) => err.status(),
+            Self::Syntax(_) => StatusCode::BAD_REQUEST,
+            Self::Data(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for Nota<T> {
+    type Rejection = NotaRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, NotaRejection> {
+        let content_type = req.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+        if !content_type.is_some_and(is_nota_content_type) {
+            return Err(NotaRejection::MissingContentType);
+        }
+        let bytes = Bytes::from_request(req, state).await.map_err(NotaRejection::Body)?;
+        crate::serde::from_slice_with(&bytes, &ParseOptions::untrusted()).map(Self).map_err(|err| match Error::from_io(&err) {
+            Some(Error::Serde(_) | Error::Unrepresentable(_)) => NotaRejection::Data(err),
+            _ => NotaRejection::Syntax(err),
+        })
+    }
+}
+
+impl<T: Serialize> IntoResponse for Nota<T> {
+    fn into_response(self) -> Response {
+        match crate::serde::to_vec(&self.0) {
+            Ok(bytes) => ([(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE))], bytes).into_response(),
+            Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+        }
+    }
+}
+
+#[test]
+fn extract_and_respond() {
+    use crate::{block_on, Value};
+
+    let response = Nota(vec![1u8, 2]).into_response();
+    assert_eq!(response.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+    let body = block_on(axum::body::to_bytes(response.into_body(), usize::MAX)).unwrap();
+    assert_eq!(crate::serde::from_slice::<Value>(&body).unwrap(), Value::Array(vec![Value::Integer(1), Value::Integer(2)]));
+
+    let request = |content_type: &str, body: &Bytes| Request::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .body(axum::body::Body::from(body.clone())).unwrap();
+
+    let Nota(list) = block_on(Nota::<Vec<u8>>::from_request(request("application/nota; v=1", &body), &())).unwrap();
+    assert_eq!(list, [1, 2]);
+    assert!(block_on(Nota::<Vec<u8>>::from_request(request("application/vnd.x+nota", &body), &())).is_ok());
+    assert!(matches!(block_on(Nota::<Vec<u8>>::from_request(request("application/json", &body), &())), Err(NotaRejection::MissingContentType)));
+    assert!(matches!(block_on(Nota::<String>::from_request(request(CONTENT_TYPE, &body), &())), Err(NotaRejection::Data(_))));
+    assert!(matches!(block_on(Nota::<String>::from_request(request(CONTENT_TYPE, &Bytes::new()), &())), Err(NotaRejection::Syntax(_))));
+
+    let mut nested = vec![0x41; 100_000];
+    nested.push(0xC2);
+    assert!(matches!(block_on(Nota::<Value>::from_request(request(CONTENT_TYPE, &nested.into()), &())), Err(NotaRejection::Syntax(_))));
+}
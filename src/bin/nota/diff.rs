@@ -0,0 +1,89 @@
+//! Structural differences between two values
+use nota::path::{Path, Segment};
+use nota::Value;
+use std::io::{self, Write};
+
+#[derive(Debug, PartialEq)]
+pub enum Change<'a> {
+    Removed(Path, &'a Value),
+    Added(Path, &'a Value),
+    Changed(Path, &'a Value, &'a Value),
+}
+
+/// Records are compared by key, arrays by index. Values of different kinds are changed as a whole.
+pub fn diff<'a>(a: &'a Value, b: &'a Value) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    diff_at(&mut Path::default(), a, b, &mut changes);
+    changes
+}
+
+fn diff_at<'a>(path: &mut Path, a: &'a Value, b: &'a Value, changes: &mut Vec<Change<'a>>) {
+    match (a, b) {
+        (Value::Record(a), Value::Record(b)) => {
+            let mut keys: Vec<_> = a.keys().chain(b.keys().filter(|k| !a.contains_key(*k))).collect();
+            keys.sort_unstable();
+            for key in keys {
+                path.push(Segment::Key(key.clone()));
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => diff_at(path, a, b, changes),
+                    (Some(a), None) => changes.push(Change::Removed(path.clone(), a)),
+                    (None, Some(b)) => changes.push(Change::Added(path.clone(), b)),
+                    (None, None) => unreachable!(),
+                }
+                path.pop();
+            }
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                path.push(Segment::Index(i));
+                match (a.get(i), b.get(i)) {
+                    (Some(a), Some(b)) => diff_at(path, a, b, changes),
+                    (Some(a), None) => changes.push(Change::Removed(path.clone(), a)),
+                    (None, Some(b)) => changes.push(Change::Added(path.clone(), b)),
+                    (None, None) => unreachable!(),
+                }
+                path.pop();
+            }
+        },
+        (a, b) if a != b => changes.push(Change::Changed(path.clone(), a, b)),
+        _ => {},
+    }
+}
+
+/// `-` removed, `+` added, `~` changed, one per line, in the [diagnostic notation](nota::diag)
+pub fn print(w: &mut dyn Write, changes: &[Change<'_>]) -> io::Result<()> {
+    for change in changes {
+        match change {
+            Change::Removed(path, a) => writeln!(w, "- {path}: {a}")?,
+            Change::Added(path, b) => writeln!(w, "+ {path}: {b}")?,
+            Change::Changed(path, a, b) => writeln!(w, "~ {path}: {a} -> {b}")?,
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn changes() {
+    let doc = |name: &str, tags: &[&str]| Value::Record(Box::new([
+        ("name".to_string(), Value::Text(name.into())),
+        ("tags".to_string(), Value::Array(tags.iter().map(|&t| Value::Text(t.into())).collect())),
+    ].into_iter().collect()));
+
+    let a = doc("x", &["a", "b"]);
+    assert!(diff(&a, &a).is_empty());
+
+    let mut b = doc("y", &["a"]);
+    if let Value::Record(fields) = &mut b {
+        fields.insert("first name".into(), Value::Null);
+    }
+    let changes = diff(&a, &b);
+    assert_eq!(changes, [
+        Change::Added(r#"["first name"]"#.parse().unwrap(), &Value::Null),
+        Change::Changed("name".parse().unwrap(), &Value::Text("x".into()), &Value::Text("y".into())),
+        Change::Removed("tags[1]".parse().unwrap(), &Value::Text("b".into())),
+    ]);
+
+    let mut out = Vec::new();
+    print(&mut out, &diff(&Value::Integer(1), &Value::Null)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "~ .: 1 -> null\n");
+}
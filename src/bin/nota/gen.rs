@@ -0,0 +1,113 @@
+//! Pseudo-random documents for testing other implementations
+use nota::Value;
+use std::collections::HashMap;
+
+/// Characters for texts, from each length class of the kim encoding
+const CHARS: &[char] = &['a', 'Z', '0', ' ', '"', '\n', 'é', 'ß', '☃', '★', '𓂀', '\u{10FFFF}'];
+
+/// SplitMix64, so that the same seed makes the same documents on every platform
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 { 0 } else { (self.next() % n as u64) as usize }
+    }
+}
+
+/// A tree at most `depth` levels deep, with at most `width` elements in each array, record, or text
+pub fn value(rng: &mut Rng, depth: usize, width: usize) -> Value {
+    let kinds = if depth > 1 { 7 } else { 5 };
+    match rng.below(kinds) {
+        0 => Value::Blob((0..rng.below(width * 8 + 1)).map(|_| rng.next() & 1 != 0).collect()),
+        1 => Value::Text((0..rng.below(width + 1)).map(|_| CHARS[rng.below(CHARS.len())]).collect()),
+        2 => {
+            // all sizes of integers, including the largest ones
+            let bits = rng.below(128);
+            Value::Integer((i128::from(rng.next()) << 64 | i128::from(rng.next())) >> bits)
+        },
+        3 => Value::Bool(rng.next() & 1 != 0),
+        4 => Value::Null,
+        5 => Value::Array((0..rng.below(width + 1)).map(|_| value(rng, depth - 1, width)).collect()),
+        _ => {
+            let mut fields = HashMap::new();
+            for _ in 0..rng.below(width + 1) {
+                let key = (0..rng.below(width + 1)).map(|_| CHARS[rng.below(CHARS.len())]).collect();
+                fields.insert(key, value(rng, depth - 1, width));
+            }
+            Value::Record(Box::new(fields))
+        },
+    }
+}
+
+/// Arrays nested `depth` levels deep, written directly, since it may be too deep for [`Value`]
+pub fn deep(depth: usize) -> Vec<u8> {
+    let mut out = vec![0b0100_0001; depth.saturating_sub(1)];
+    out.push(0b1100_0010);
+    out
+}
+
+/// A header claiming a length far larger than the data after it, or an integer with too many digits
+pub fn huge_length(rng: &mut Rng) -> Vec<u8> {
+    // headers of blob, text, array, and record, with a length of 2^60 - 1
+    let headers = [0b0000_0000, 0b0010_0000, 0b0100_0000, 0b0110_0000];
+    let mut out = vec![];
+    match rng.below(headers.len() + 1) {
+        i @ 0..4 => {
+            out.push(headers[i] | 0b0001_1111);
+            out.extend([0xFF; 7]);
+            out.push(0x7F);
+            value(rng, 2, 4).serialize_into(&mut out);
+        },
+        _ => {
+            out.push(0b1001_0111);
+            out.extend(std::iter::repeat_n(0xFF, 40));
+            out.push(0x7F);
+        },
+    }
+    out
+}
+
+#[test]
+fn repeatable() {
+    let make = |seed| {
+        let mut rng = Rng::new(seed);
+        (0..20).map(|_| value(&mut rng, 4, 5)).collect::<Vec<_>>()
+    };
+    assert_eq!(make(1), make(1));
+    assert_ne!(make(1), make(2));
+
+    for value in make(3) {
+        let mut bytes = Vec::new();
+        value.serialize_into(&mut bytes);
+        let (parsed, stats) = Value::parse_with_stats(&mut &bytes[..], &Default::default()).unwrap();
+        assert_eq!(parsed, value);
+        assert!(stats.max_depth <= 4);
+    }
+
+    let mut options = nota::ParseOptions::default();
+    options.max_depth = Some(100);
+    assert!(Value::parse_with(&mut &deep(101)[..], &options).is_err());
+    assert!(Value::parse_with(&mut &deep(100)[..], &options).is_ok());
+    let mut rng = Rng::new(0);
+    for _ in 0..10 {
+        let doc = huge_length(&mut rng);
+        let res = Value::parse_from(&mut &doc[..]);
+        // overlong integers aren't detected yet
+        if doc[0] & 0b1110_0000 != 0b1000_0000 {
+            assert!(res.is_err());
+        }
+    }
+}
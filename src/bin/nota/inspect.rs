@@ -0,0 +1,162 @@
+//! Tree of values with their byte offsets and encoded lengths
+use nota::Value;
+use std::io::{self, Write};
+
+/// Texts longer than this are cut short in the summary
+const PREVIEW_CHARS: usize = 40;
+
+#[derive(Debug)]
+pub struct Node {
+    pub offset: usize,
+    /// Encoded length in bytes, including the header and all children
+    pub len: usize,
+    pub kind: Kind,
+}
+
+#[derive(Debug)]
+pub enum Kind {
+    Array(Vec<Node>),
+    /// Keys with offsets of their own encoding
+    Record(Vec<(usize, String, Node)>),
+    Scalar(Value),
+}
+
+fn invalid(offset: usize, msg: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("at byte {offset}: {msg}"))
+}
+
+/// Headers of arrays and records are decoded here to know where the children are.
+/// Scalars are left to the library.
+pub fn walk(data: &[u8], offset: usize) -> Result<Node, io::Error> {
+    let Some(&preamble) = data.get(offset) else {
+        return Err(invalid(offset, "unexpected end of data"));
+    };
+    let is_array = match preamble & 0b1110_0000 {
+        0b0100_0000 => true,
+        0b0110_0000 => false,
+        _ => {
+            let mut rest = &data[offset..];
+            let value = Value::parse_from(&mut rest).map_err(|e| invalid(offset, e))?;
+            let len = data.len() - offset - rest.len();
+            return Ok(Node { offset, len, kind: Kind::Scalar(value) });
+        },
+    };
+
+    let mut pos = offset + 1;
+    let mut count = usize::from(preamble & 0b1111);
+    let mut more = preamble & 0b0001_0000 != 0;
+    while more {
+        let &next = data.get(pos).ok_or_else(|| invalid(pos, "unexpected end of data"))?;
+        count = count.checked_mul(128).ok_or_else(|| invalid(offset, "length overflows"))? | usize::from(next & 0b0111_1111);
+        more = next & 0b1000_0000 != 0;
+        pos += 1;
+    }
+    // the count is untrusted, and each element takes at least one byte
+    let capacity = count.min(data.len() - pos);
+    let kind = if is_array {
+        let mut items = Vec::with_capacity(capacity);
+        for _ in 0..count {
+            let item = walk(data, pos)?;
+            pos += item.len;
+            items.push(item);
+        }
+        Kind::Array(items)
+    } else {
+        let mut fields = Vec::with_capacity(capacity / 2);
+        for _ in 0..count {
+            let key = walk(data, pos)?;
+            let Kind::Scalar(Value::Text(name)) = key.kind else {
+                return Err(invalid(pos, "record key isn't text"));
+            };
+            let key_offset = pos;
+            pos += key.len;
+            let value = walk(data, pos)?;
+            pos += value.len;
+            fields.push((key_offset, name, value));
+        }
+        Kind::Record(fields)
+    };
+    Ok(Node { offset, len: pos - offset, kind })
+}
+
+/// One line per value: offset, length, then the kind indented by depth.
+/// Children deeper than `max_depth` are summarized by their parent only.
+pub fn print(w: &mut dyn Write, node: &Node, max_depth: Option<usize>) -> io::Result<()> {
+    writeln!(w, "{:>10} {:>10}  value", "offset", "length")?;
+    print_node(w, node, "", 0, max_depth)
+}
+
+fn print_node(w: &mut dyn Write, node: &Node, label: &str, depth: usize, max_depth: Option<usize>) -> io::Result<()> {
+    let indent = depth * 2;
+    write!(w, "{:>10} {:>10}  {:indent$}{label}", node.offset, node.len, "")?;
+    let expand = max_depth.is_none_or(|max| depth < max);
+    match &node.kind {
+        Kind::Array(items) => {
+            writeln!(w, "array, {} items", items.len())?;
+            if expand {
+                for (i, item) in items.iter().enumerate() {
+                    print_node(w, item, &format!("[{i}]: "), depth + 1, max_depth)?;
+                }
+            }
+        },
+        Kind::Record(fields) => {
+            writeln!(w, "record, {} fields", fields.len())?;
+            if expand {
+                for (_, name, value) in fields {
+                    print_node(w, value, &format!("{name:?}: "), depth + 1, max_depth)?;
+                }
+            }
+        },
+        Kind::Scalar(value) => writeln!(w, "{}", describe(value))?,
+    }
+    Ok(())
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Blob(bits) => format!("blob, {} bits", bits.len()),
+        Value::Text(text) => {
+            let chars = text.chars().count();
+            if chars > PREVIEW_CHARS {
+                let preview: String = text.chars().take(PREVIEW_CHARS).collect();
+                format!("text, {chars} chars {preview:?}…")
+            } else {
+                format!("text, {chars} chars {text:?}")
+            }
+        },
+        Value::Integer(n) => format!("integer {n}"),
+        Value::Bool(b) => format!("bool {b}"),
+        Value::Null => "null".into(),
+        other => format!("{other:?}"),
+    }
+}
+
+#[test]
+fn offsets() {
+    let value = Value::Array(vec![
+        Value::Integer(1),
+        Value::Record(Box::new([("a".to_string(), Value::Text("hi".into()))].into_iter().collect())),
+        Value::Null,
+    ]);
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+
+    let node = walk(&bytes, 0).unwrap();
+    assert_eq!(node.len, bytes.len());
+    let Kind::Array(items) = &node.kind else { panic!() };
+    assert_eq!((items[0].offset, items[0].len), (1, 1));
+    assert_eq!(items[1].offset, 2);
+    let Kind::Record(fields) = &items[1].kind else { panic!() };
+    assert_eq!(fields[0].0, 3);
+    assert_eq!(fields[0].2.offset, 3 + 2);
+    assert_eq!(items[2].offset, bytes.len() - 1);
+
+    let mut out = Vec::new();
+    print(&mut out, &node, None).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(r#""a": text, 2 chars "hi""#), "{out}");
+    assert_eq!(out.lines().count(), 6);
+
+    let err = walk(&bytes[..bytes.len() - 2], 0).unwrap_err();
+    assert!(err.to_string().starts_with("at byte 5"), "{err}");
+}
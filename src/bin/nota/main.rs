@@ -0,0 +1,513 @@
+//! Command-line tool for converting and examining Nota files
+use clap::{Parser, Subcommand, ValueEnum};
+use nota::json::{JsonBigIntegers, JsonBlobs, JsonOptions};
+use nota::query::Query;
+use nota::schema::Schema;
+use nota::validate::validate;
+use nota::{ParseOptions, SerializeOptions, Value};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+mod diff;
+mod gen;
+mod inspect;
+mod pretty;
+mod stats;
+mod xxd;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Converts JSON (or another format) to Nota
+    Encode {
+        /// File to read. Reads stdin if not set or `-`.
+        input: Option<PathBuf>,
+        /// Where to write Nota. Writes to stdout if not set or `-`.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Format of the input
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        from: Format,
+        /// Convert every value in the input, such as every line of NDJSON, into a sequence of Nota values
+        #[arg(long)]
+        stream: bool,
+    },
+    /// Converts Nota to JSON (or another format)
+    Decode {
+        /// Nota file to read. Reads stdin if not set or `-`.
+        input: Option<PathBuf>,
+        /// Where to write the output. Writes to stdout if not set or `-`.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Format of the output
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        to: Format,
+        /// Indent JSON or diagnostic notation
+        #[arg(short, long)]
+        pretty: bool,
+        /// Convert every value in a sequence of Nota values. JSON and diagnostic notation are written one value per line.
+        #[arg(long, conflicts_with = "pretty")]
+        stream: bool,
+        /// How to write blobs in JSON, which doesn't have them
+        #[arg(long, value_enum, default_value_t = Blobs::Bytes)]
+        blobs: Blobs,
+        /// How to write integers that don't fit in 64 bits in JSON
+        #[arg(long, value_enum, default_value_t = BigIntegers::String)]
+        big_integers: BigIntegers,
+    },
+    /// Checks that files are well-formed and within limits. Reports the location of the first error.
+    Validate {
+        /// Nota files to check. `-` is stdin.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Deepest allowed nesting. A scalar at the top level has depth 1.
+        #[arg(long)]
+        max_depth: Option<usize>,
+        /// Largest allowed number of elements, fields, characters, or bits in a single value
+        #[arg(long)]
+        max_len: Option<usize>,
+        /// Also check that documents match this schema, in the diagnostic notation. See the `nota::schema` docs for its format.
+        #[arg(long, conflicts_with = "json_schema")]
+        schema: Option<PathBuf>,
+        /// Also check that documents match this JSON Schema
+        #[arg(long)]
+        json_schema: Option<PathBuf>,
+    },
+    /// Prints a tree of all values with their byte offsets, encoded lengths, and kinds
+    Inspect {
+        /// Nota file to read. Reads stdin if `-`.
+        file: PathBuf,
+        /// Don't list children of values nested deeper than this
+        #[arg(long)]
+        depth: Option<usize>,
+    },
+    /// Lists values that have been added (`+`), removed (`-`), or changed (`~`) between two files
+    Diff {
+        /// Nota file to compare from. `-` is stdin.
+        old: PathBuf,
+        /// Nota file to compare to. `-` is stdin.
+        new: PathBuf,
+    },
+    /// Prints values from the file, like `users[3].name` or `.users[] | select(.age > 30)`, in the diagnostic notation, one per line
+    Get {
+        /// Nota file to read. `-` is stdin.
+        file: PathBuf,
+        /// A jq-like query. Record fields are `.name` or `.["any key"]`, array elements `.[3]`, slices `.[1:3]`, all elements `.[]`,
+        /// everything `..`, and filters `select(.field == "x")`, joined with `|`. `.` is the whole document.
+        query: Query,
+        /// Print JSON instead
+        #[arg(long)]
+        json: bool,
+        /// Indent the output
+        #[arg(short, long)]
+        pretty: bool,
+    },
+    /// Re-encodes a document in canonical form, with sorted keys and shortest encodings, so that equal documents have equal bytes
+    Canon {
+        /// Nota file to read. Reads stdin if not set or `-`.
+        input: Option<PathBuf>,
+        /// Where to write Nota. Writes to stdout if not set or `-`.
+        #[arg(short, long, conflicts_with = "check")]
+        output: Option<PathBuf>,
+        /// Don't write anything, only fail if the input isn't canonical
+        #[arg(long)]
+        check: bool,
+    },
+    /// Writes pseudo-random documents, for testing other implementations
+    Gen {
+        /// Where to write Nota. Writes to stdout if not set or `-`.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Deepest nesting. A scalar at the top level has depth 1.
+        #[arg(long, default_value_t = 4)]
+        depth: usize,
+        /// Most elements in each array, fields in each record, and characters in each text
+        #[arg(long, default_value_t = 8)]
+        width: usize,
+        /// Makes the same documents every time. Random if not set, and printed to stderr.
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Number of documents, written one after another
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+        /// Write documents that are hard to parse instead
+        #[arg(long, value_enum)]
+        adversarial: Option<Adversarial>,
+    },
+    /// Prints the document in the diagnostic notation, indented and colored
+    Pretty {
+        /// Nota file to read. Reads stdin if not set or `-`.
+        input: Option<PathBuf>,
+        /// Spaces per level of nesting
+        #[arg(long, default_value_t = 2)]
+        indent: usize,
+        /// Use ANSI colors. `auto` colors only terminals, unless `NO_COLOR` is set.
+        #[arg(long, value_enum, default_value_t = Color::Auto)]
+        color: Color,
+    },
+    /// Dumps every byte in binary, with the meaning of its tag, continuation, sign, and value bits
+    Xxd {
+        /// Nota file to read. Reads stdin if not set or `-`.
+        input: Option<PathBuf>,
+    },
+    /// Counts values of each kind, keys, and the bytes they take, and lists the largest arrays and records
+    Stats {
+        /// Nota file to read. `-` is stdin.
+        file: PathBuf,
+        /// How many keys and largest values to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Writes Rust structs with serde derives for the types in a schema. See the `nota::schema` docs for its format.
+    Codegen {
+        /// Schema file to read. Reads stdin if `-`.
+        schema: PathBuf,
+        /// Where to write the Rust code. Writes to stdout if not set or `-`.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Format of the schema file
+        #[arg(long, value_enum, default_value_t = Format::Diag)]
+        from: Format,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Cbor,
+    #[value(name = "msgpack")]
+    MessagePack,
+    /// Diagnostic notation, human-readable text
+    Diag,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Adversarial {
+    /// Arrays nested `--depth` levels deep
+    Deep,
+    /// Lengths far larger than the data, and integers larger than 128 bits
+    HugeLength,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Blobs {
+    /// Fail
+    Error,
+    /// Arrays of byte values
+    Bytes,
+    /// Strings of `0` and `1`
+    Bits,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum BigIntegers {
+    /// Fail
+    Error,
+    /// Strings of decimal digits
+    String,
+    /// Imprecise floats
+    Float,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("nota: {err}");
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(cli: Cli) -> Result<(), io::Error> {
+    match cli.command {
+        Command::Encode { input, output, from, stream } => {
+            let mut r = open(input.as_deref())?;
+            let mut w = create(output.as_deref())?;
+            let mut out = Vec::new();
+            let mut write = |value: Value| {
+                out.clear();
+                value.serialize_into(&mut out);
+                w.write_all(&out)
+            };
+            if stream {
+                read_stream(from, &mut r, write)?;
+            } else {
+                write(read_as(from, &mut r)?)?;
+            }
+            w.flush()
+        },
+        Command::Decode { input, output, to, pretty, stream, blobs, big_integers } => {
+            let mut r = open(input.as_deref())?;
+            let mut w = create(output.as_deref())?;
+            let mut options = JsonOptions::default();
+            options.blobs = match blobs {
+                Blobs::Error => JsonBlobs::Error,
+                Blobs::Bytes => JsonBlobs::Bytes,
+                Blobs::Bits => JsonBlobs::BitString,
+            };
+            options.big_integers = match big_integers {
+                BigIntegers::Error => JsonBigIntegers::Error,
+                BigIntegers::String => JsonBigIntegers::String,
+                BigIntegers::Float => JsonBigIntegers::Float,
+            };
+            if stream {
+                while !r.fill_buf()?.is_empty() {
+                    write_as(to, Value::parse_from(&mut r)?, &mut w, false, &options)?;
+                }
+            } else {
+                write_as(to, Value::parse_from(&mut r)?, &mut w, pretty, &options)?;
+            }
+            w.flush()
+        },
+        Command::Validate { files, max_depth, max_len, schema, json_schema } => {
+            let mut options = ParseOptions::default();
+            options.max_depth = max_depth;
+            options.max_len = max_len;
+            let invalid_schema = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+            let schema = match (schema, json_schema) {
+                (Some(path), _) => Some(Schema::from_value(&read_as(Format::Diag, &mut open(Some(&path))?)?).map_err(invalid_schema)?),
+                (_, Some(path)) => Some(nota::json_schema::compile(&serde_json::from_reader(open(Some(&path))?)?).map_err(invalid_schema)?),
+                (None, None) => None,
+            };
+            let mut failed = 0;
+            for file in &files {
+                let res = match &schema {
+                    None => validate(&mut open(Some(file))?, &options).map_err(|e| e.to_string()),
+                    Some(schema) => {
+                        let mut data = Vec::new();
+                        open(Some(file))?.read_to_end(&mut data)?;
+                        validate(&mut &data[..], &options).map_err(|e| e.to_string()).and_then(|stats| {
+                            let value = Value::parse_slice_with(&mut &data[..], &options).map_err(|e| e.to_string())?;
+                            schema.validate(&value).map_err(|e| e.to_string())?;
+                            Ok(stats)
+                        })
+                    },
+                };
+                match res {
+                    Ok(stats) => println!("{}: ok, {} values, depth {}", file.display(), stats.values(), stats.max_depth),
+                    Err(err) => {
+                        failed += 1;
+                        eprintln!("{}: {err}", file.display());
+                    },
+                }
+            }
+            if failed > 0 {
+                return Err(io::Error::other(format!("{failed} of {} files are invalid", files.len())));
+            }
+            Ok(())
+        },
+        Command::Inspect { file, depth } => {
+            let mut data = Vec::new();
+            open(Some(&file))?.read_to_end(&mut data)?;
+            let node = inspect::walk(&data, 0)?;
+            let mut w = create(None)?;
+            inspect::print(&mut w, &node, depth)?;
+            if node.len < data.len() {
+                writeln!(w, "{:>10} {:>10}  trailing bytes", node.len, data.len() - node.len)?;
+            }
+            w.flush()
+        },
+        Command::Diff { old, new } => {
+            let old = Value::parse_from(&mut open(Some(&old))?)?;
+            let new = Value::parse_from(&mut open(Some(&new))?)?;
+            let changes = diff::diff(&old, &new);
+            let mut w = create(None)?;
+            diff::print(&mut w, &changes)?;
+            w.flush()?;
+            if !changes.is_empty() {
+                return Err(io::Error::other(format!("{} differences", changes.len())));
+            }
+            Ok(())
+        },
+        Command::Get { file, query, json, pretty } => {
+            let value = Value::parse_from(&mut open(Some(&file))?)?;
+            let results = query.run(&value);
+            if results.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, format!("nothing matches {query}")));
+            }
+            let mut w = create(None)?;
+            for found in results {
+                match (json, pretty) {
+                    (true, _) => {
+                        let json = found.to_json(&JsonOptions::default())?;
+                        if pretty { serde_json::to_writer_pretty(&mut w, &json)?; } else { serde_json::to_writer(&mut w, &json)?; }
+                    },
+                    (false, true) => write!(w, "{found:#}")?,
+                    (false, false) => write!(w, "{found}")?,
+                }
+                writeln!(w)?;
+            }
+            w.flush()
+        },
+        Command::Canon { input, output, check } => {
+            let mut data = Vec::new();
+            open(input.as_deref())?.read_to_end(&mut data)?;
+            let mut rest = &data[..];
+            let value = Value::parse_from(&mut rest)?;
+            if !rest.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "trailing bytes after the value"));
+            }
+            let mut options = SerializeOptions::default();
+            options.canonical = true;
+            let mut out = Vec::new();
+            value.serialize_with(&mut out, &options);
+            if check {
+                if out != data {
+                    return Err(io::Error::other("not in canonical form"));
+                }
+                return Ok(());
+            }
+            let mut w = create(output.as_deref())?;
+            w.write_all(&out)?;
+            w.flush()
+        },
+        Command::Gen { output, depth, width, seed, count, adversarial } => {
+            let seed = seed.unwrap_or_else(|| {
+                let seed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos() as u64;
+                eprintln!("seed: {seed}");
+                seed
+            });
+            let mut rng = gen::Rng::new(seed);
+            let mut w = create(output.as_deref())?;
+            let mut out = Vec::new();
+            for _ in 0..count {
+                out.clear();
+                match adversarial {
+                    None => gen::value(&mut rng, depth.max(1), width).serialize_into(&mut out),
+                    Some(Adversarial::Deep) => out = gen::deep(depth),
+                    Some(Adversarial::HugeLength) => out = gen::huge_length(&mut rng),
+                }
+                w.write_all(&out)?;
+            }
+            w.flush()
+        },
+        Command::Pretty { input, indent, color } => {
+            let value = Value::parse_from(&mut open(input.as_deref())?)?;
+            let color = match color {
+                Color::Always => true,
+                Color::Never => false,
+                Color::Auto => io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+            };
+            let mut w = create(None)?;
+            pretty::Pretty { indent, color }.write(&mut w, &value)?;
+            w.flush()
+        },
+        Command::Xxd { input } => {
+            let mut data = Vec::new();
+            open(input.as_deref())?.read_to_end(&mut data)?;
+            let (lines, res) = xxd::dump(&data);
+            let mut w = create(None)?;
+            xxd::print(&mut w, &lines)?;
+            w.flush()?;
+            let end = res.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            if end < data.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} trailing bytes after the value", data.len() - end)));
+            }
+            Ok(())
+        },
+        Command::Stats { file, top } => {
+            let mut data = Vec::new();
+            open(Some(&file))?.read_to_end(&mut data)?;
+            let report = stats::Report::new(&inspect::walk(&data, 0)?, top);
+            let mut w = create(None)?;
+            report.print(&mut w, top)?;
+            w.flush()
+        },
+        Command::Codegen { schema, output, from } => {
+            let schema = read_as(from, &mut open(Some(&schema))?)?;
+            let schema = Schema::from_value(&schema).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let mut w = create(output.as_deref())?;
+            w.write_all(nota::codegen::rust(&schema).as_bytes())?;
+            w.flush()
+        },
+    }
+}
+
+/// Calls `each` for every value until the end of input. JSON values can be separated by any whitespace, diagnostic notation values by newlines.
+fn read_stream(format: Format, reader: &mut dyn BufRead, mut each: impl FnMut(Value) -> Result<(), io::Error>) -> Result<(), io::Error> {
+    match format {
+        Format::Json => {
+            for json in serde_json::Deserializer::from_reader(reader).into_iter::<serde_json::Value>() {
+                each(json?.try_into()?)?;
+            }
+        },
+        Format::Diag => {
+            for (n, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let value = line.parse().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {e}", n + 1)))?;
+                each(value)?;
+            }
+        },
+        Format::Cbor | Format::MessagePack => while !reader.fill_buf()?.is_empty() {
+            each(read_as(format, reader)?)?;
+        },
+    }
+    Ok(())
+}
+
+fn write_as(format: Format, value: Value, w: &mut dyn Write, pretty: bool, options: &JsonOptions) -> Result<(), io::Error> {
+    match format {
+        Format::Json => {
+            let json = value.to_json(options)?;
+            if pretty {
+                serde_json::to_writer_pretty(&mut *w, &json)?;
+            } else {
+                serde_json::to_writer(&mut *w, &json)?;
+            }
+            writeln!(w)
+        },
+        Format::Cbor => ciborium::into_writer(&ciborium::Value::try_from(value)?, w).map_err(io::Error::other),
+        Format::MessagePack => Ok(rmpv::encode::write_value(&mut &mut *w, &rmpv::Value::try_from(value)?)?),
+        Format::Diag if pretty => writeln!(w, "{value:#}"),
+        Format::Diag => writeln!(w, "{value}"),
+    }
+}
+
+fn read_as(format: Format, reader: &mut dyn Read) -> Result<Value, io::Error> {
+    let invalid = |err: String| io::Error::new(io::ErrorKind::InvalidData, err);
+    Ok(match format {
+        Format::Json => serde_json::from_reader::<_, serde_json::Value>(reader)?.try_into()?,
+        Format::Cbor => ciborium::from_reader::<ciborium::Value, _>(reader).map_err(|e| invalid(e.to_string()))?.try_into()?,
+        Format::MessagePack => rmpv::decode::read_value(&mut &mut *reader).map_err(|e| invalid(e.to_string()))?.try_into()?,
+        Format::Diag => {
+            let mut text = String::new();
+            reader.read_to_string(&mut text)?;
+            text.parse().map_err(|e: nota::diag::DiagError| invalid(e.to_string()))?
+        },
+    })
+}
+
+/// `None` or `-` is stdin
+fn open(path: Option<&Path>) -> Result<Box<dyn BufRead>, io::Error> {
+    Ok(match path {
+        Some(path) if path != Path::new("-") => Box::new(BufReader::new(File::open(path)?)),
+        _ => Box::new(io::stdin().lock()),
+    })
+}
+
+/// `None` or `-` is stdout
+fn create(path: Option<&Path>) -> Result<Box<dyn Write>, io::Error> {
+    Ok(match path {
+        Some(path) if path != Path::new("-") => Box::new(BufWriter::new(File::create(path)?)),
+        _ => Box::new(BufWriter::new(io::stdout().lock())),
+    })
+}
@@ -0,0 +1,87 @@
+//! Indented diagnostic notation with ANSI colors
+use nota::Value;
+use std::io::{self, Write};
+
+const RESET: &str = "\x1b[0m";
+const KEY: &str = "\x1b[34m";
+const TEXT: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[36m";
+const BLOB: &str = "\x1b[35m";
+const CONSTANT: &str = "\x1b[33m";
+
+pub struct Pretty {
+    pub indent: usize,
+    pub color: bool,
+}
+
+impl Pretty {
+    /// Same as `{:#}` formatting of [`Value`], but with the given indentation and colors
+    pub fn write(&self, w: &mut dyn Write, value: &Value) -> io::Result<()> {
+        self.value(w, value, 0)?;
+        writeln!(w)
+    }
+
+    fn value(&self, w: &mut dyn Write, value: &Value, level: usize) -> io::Result<()> {
+        match value {
+            Value::Array(items) => self.container(w, ('[', ']'), items.iter().map(|v| (None, v)), level),
+            Value::Record(fields) => {
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_unstable_by_key(|&(k, _)| k);
+                self.container(w, ('{', '}'), fields.into_iter().map(|(k, v)| (Some(k), v)), level)
+            },
+            scalar => {
+                let color = match scalar {
+                    Value::Text(_) => TEXT,
+                    Value::Blob(_) => BLOB,
+                    Value::Bool(_) | Value::Null => CONSTANT,
+                    _ => NUMBER,
+                };
+                self.styled(w, color, format_args!("{scalar}"))
+            },
+        }
+    }
+
+    fn container<'a>(&self, w: &mut dyn Write, (open, close): (char, char), items: impl ExactSizeIterator<Item = (Option<&'a String>, &'a Value)>, level: usize) -> io::Result<()> {
+        if items.len() == 0 {
+            return write!(w, "{open}{close}");
+        }
+        write!(w, "{open}")?;
+        let inner = (level + 1) * self.indent;
+        for (n, (key, value)) in items.enumerate() {
+            if n > 0 {
+                write!(w, ",")?;
+            }
+            write!(w, "\n{:inner$}", "")?;
+            if let Some(key) = key {
+                self.styled(w, KEY, format_args!("{key:?}"))?;
+                write!(w, ": ")?;
+            }
+            self.value(w, value, level + 1)?;
+        }
+        write!(w, "\n{:outer$}{close}", "", outer = level * self.indent)
+    }
+
+    fn styled(&self, w: &mut dyn Write, color: &str, text: std::fmt::Arguments<'_>) -> io::Result<()> {
+        if self.color {
+            write!(w, "{color}{text}{RESET}")
+        } else {
+            w.write_fmt(text)
+        }
+    }
+}
+
+#[test]
+fn same_as_display() {
+    let value = Value::Record(Box::new([
+        ("list".to_string(), Value::Array(vec![Value::Integer(1), Value::Record(Box::default()), Value::Text("a\"".into())])),
+        ("blob".to_string(), Value::Blob(vec![1u8, 2].try_into().unwrap())),
+        ("ok".to_string(), Value::Null),
+    ].into_iter().collect()));
+    let mut out = Vec::new();
+    Pretty { indent: 2, color: false }.write(&mut out, &value).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), format!("{value:#}\n"));
+
+    let mut out = Vec::new();
+    Pretty { indent: 0, color: true }.write(&mut out, &Value::Array(vec![Value::Bool(true)])).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "[\n\x1b[33mtrue\x1b[0m\n]\n");
+}
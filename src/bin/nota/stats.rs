@@ -0,0 +1,134 @@
+//! Where the bytes of a document go
+use crate::inspect::{Kind, Node};
+use nota::path::{Path, Segment};
+use nota::Value;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub count: u64,
+    /// Encoded size. For arrays and records only their headers, since the elements are counted separately.
+    pub bytes: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub total_bytes: u64,
+    pub max_depth: usize,
+    pub kinds: HashMap<&'static str, Usage>,
+    /// Bytes are the encoded keys
+    pub keys: HashMap<String, Usage>,
+    /// Arrays and records by encoded size, largest first
+    pub largest: Vec<(u64, Path)>,
+}
+
+impl Report {
+    /// Keeps only `top` of the largest arrays and records
+    pub fn new(root: &Node, top: usize) -> Self {
+        let mut report = Self { total_bytes: root.len as u64, ..Self::default() };
+        report.add(root, &mut Path::default(), 1);
+        report.largest.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.segments().len().cmp(&b.1.segments().len())));
+        report.largest.truncate(top);
+        report
+    }
+
+    fn add(&mut self, node: &Node, path: &mut Path, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+        let (kind, own_bytes) = match &node.kind {
+            Kind::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    path.push(Segment::Index(i));
+                    self.add(item, path, depth + 1);
+                    path.pop();
+                }
+                ("array", node.len - items.iter().map(|n| n.len).sum::<usize>())
+            },
+            Kind::Record(fields) => {
+                let mut children = 0;
+                for (key_offset, name, value) in fields {
+                    let key_len = value.offset - key_offset;
+                    children += key_len + value.len;
+                    let key = self.keys.entry(name.clone()).or_default();
+                    key.count += 1;
+                    key.bytes += key_len as u64;
+                    path.push(Segment::Key(name.clone()));
+                    self.add(value, path, depth + 1);
+                    path.pop();
+                }
+                ("record", node.len - children)
+            },
+            Kind::Scalar(value) => (match value {
+                Value::Blob(_) => "blob",
+                Value::Text(_) => "text",
+                Value::Integer(_) => "integer",
+                Value::Bool(_) => "bool",
+                Value::Null => "null",
+                _ => "other",
+            }, node.len),
+        };
+        let usage = self.kinds.entry(kind).or_default();
+        usage.count += 1;
+        usage.bytes += own_bytes as u64;
+        if matches!(node.kind, Kind::Array(_) | Kind::Record(_)) {
+            self.largest.push((node.len as u64, path.clone()));
+        }
+    }
+
+    pub fn print(&self, w: &mut dyn Write, top: usize) -> io::Result<()> {
+        let percent = |bytes: u64| bytes as f64 * 100. / self.total_bytes.max(1) as f64;
+        let values: u64 = self.kinds.values().map(|u| u.count).sum();
+        writeln!(w, "{} bytes, {values} values, depth {}", self.total_bytes, self.max_depth)?;
+
+        writeln!(w, "\n{:<10} {:>10} {:>12} {:>7}", "kind", "count", "bytes", "%")?;
+        let mut kinds: Vec<_> = self.kinds.iter().map(|(&k, &u)| (k.to_string(), u)).collect();
+        if !self.keys.is_empty() {
+            let count = self.keys.values().map(|u| u.count).sum();
+            let bytes = self.keys.values().map(|u| u.bytes).sum();
+            kinds.push(("keys".into(), Usage { count, bytes }));
+        }
+        kinds.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+        for (kind, usage) in kinds {
+            writeln!(w, "{kind:<10} {:>10} {:>12} {:>6.1}%", usage.count, usage.bytes, percent(usage.bytes))?;
+        }
+
+        if !self.keys.is_empty() {
+            writeln!(w, "\n{:<30} {:>10} {:>12}", "key", "count", "bytes")?;
+            let mut keys: Vec<_> = self.keys.iter().collect();
+            keys.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(b.0)));
+            for (key, usage) in keys.into_iter().take(top) {
+                writeln!(w, "{:<30} {:>10} {:>12}", format!("{key:?}"), usage.count, usage.bytes)?;
+            }
+        }
+
+        if !self.largest.is_empty() {
+            writeln!(w, "\n{:>12} {:>7}  path", "bytes", "%")?;
+            for (bytes, path) in &self.largest {
+                writeln!(w, "{bytes:>12} {:>6.1}%  {path}", percent(*bytes))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn breakdown() {
+    let value = Value::Record(Box::new([
+        ("users".to_string(), Value::Array((0..3).map(|i| Value::Record(Box::new([
+            ("id".to_string(), Value::Integer(i)),
+            ("name".to_string(), Value::Text("x".repeat(100))),
+        ].into_iter().collect()))).collect())),
+    ].into_iter().collect()));
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+    let report = Report::new(&crate::inspect::walk(&bytes, 0).unwrap(), 2);
+
+    assert_eq!(report.max_depth, 4);
+    assert_eq!(report.kinds["text"].count, 3);
+    assert_eq!(report.keys["id"], Usage { count: 3, bytes: 3 * 3 });
+    let all_bytes: u64 = report.kinds.values().chain(report.keys.values()).map(|u| u.bytes).sum();
+    assert_eq!(all_bytes, bytes.len() as u64);
+    assert_eq!(report.largest.len(), 2);
+    assert_eq!(report.largest[0].1, Path::default());
+    assert_eq!(report.largest[1].1.to_string(), "users");
+}
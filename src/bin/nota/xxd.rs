@@ -0,0 +1,196 @@
+//! Byte-by-byte dump with the meaning of every bit
+use std::io::{self, Write};
+
+pub struct Line {
+    pub offset: usize,
+    pub byte: u8,
+    /// Binary digits of the byte, split into fields with spaces
+    pub bits: String,
+    pub note: String,
+}
+
+struct Dump<'a> {
+    data: &'a [u8],
+    pos: usize,
+    lines: Vec<Line>,
+}
+
+/// One line per byte. On error, the lines up to the error are returned with it.
+pub fn dump(data: &[u8]) -> (Vec<Line>, Result<usize, String>) {
+    let mut dump = Dump { data, pos: 0, lines: Vec::new() };
+    let res = dump.value(0, false).map(|()| dump.pos);
+    (dump.lines, res)
+}
+
+/// Binary of `byte`, with a space after each of the given field widths
+fn fields(byte: u8, widths: &[usize]) -> String {
+    let all = format!("{byte:08b}");
+    let mut out = String::new();
+    let mut at = 0;
+    for &w in widths {
+        out.push_str(&all[at..at + w]);
+        out.push(' ');
+        at += w;
+    }
+    out.push_str(&all[at..]);
+    out
+}
+
+impl Dump<'_> {
+    fn next(&mut self, bits: String, depth: usize, note: impl Into<String>) -> Result<u8, String> {
+        let &byte = self.data.get(self.pos).ok_or_else(|| format!("unexpected end of data at byte {}", self.pos))?;
+        let note = note.into();
+        let note = if note.is_empty() { note } else { format!("{:indent$}{note}", "", indent = depth * 2) };
+        self.lines.push(Line { offset: self.pos, byte, bits, note });
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn peek(&self) -> Result<u8, String> {
+        self.data.get(self.pos).copied().ok_or_else(|| format!("unexpected end of data at byte {}", self.pos))
+    }
+
+    fn annotate(&mut self, note: String, depth: usize) {
+        if let Some(line) = self.lines.last_mut() {
+            let sep = if line.note.is_empty() { format!("{:indent$}", "", indent = depth * 2) } else { ", ".into() };
+            line.note = format!("{}{sep}{note}", line.note);
+        }
+    }
+
+    /// Tag, continuation bit, and 4 bits of the length, then 7 bits per byte
+    fn length(&mut self, name: &str, depth: usize) -> Result<usize, String> {
+        let first = self.peek()?;
+        let tag_note = format!("{name}{}", if first & 0x10 != 0 { " (continued)" } else { "" });
+        self.next(fields(first, &[3, 1]), depth, tag_note)?;
+        let mut len = usize::from(first & 0x0F);
+        let mut more = first & 0x10 != 0;
+        while more {
+            let byte = self.peek()?;
+            self.next(fields(byte, &[1]), depth, "")?;
+            len = len.checked_mul(128).ok_or("length overflows")? | usize::from(byte & 0x7F);
+            more = byte & 0x80 != 0;
+        }
+        Ok(len)
+    }
+
+    fn value(&mut self, depth: usize, is_key: bool) -> Result<(), String> {
+        let first = self.peek()?;
+        match first >> 5 {
+            0b000 => {
+                let len = self.length("blob", depth)?;
+                self.annotate(format!("{len} bits"), depth);
+                for i in 0..len.div_ceil(8) {
+                    let bits = len - i * 8;
+                    let byte = self.peek()?;
+                    if bits < 8 {
+                        self.next(fields(byte, &[bits]), depth + 1, format!("{bits} bits, {} bits of padding", 8 - bits))?;
+                    } else {
+                        self.next(fields(byte, &[]), depth + 1, "")?;
+                    }
+                }
+            },
+            0b001 => {
+                let len = self.length(if is_key { "key" } else { "text" }, depth)?;
+                self.annotate(format!("{len} chars"), depth);
+                for _ in 0..len {
+                    let mut code = 0u32;
+                    loop {
+                        let byte = self.peek()?;
+                        self.next(fields(byte, &[1]), depth + 1, "")?;
+                        code = code.checked_mul(128).ok_or("character overflows")? | u32::from(byte & 0x7F);
+                        if byte & 0x80 == 0 {
+                            break;
+                        }
+                    }
+                    let c = char::from_u32(code).ok_or_else(|| format!("invalid character U+{code:04X}"))?;
+                    self.annotate(format!("{c:?} U+{code:04X}"), depth + 1);
+                }
+            },
+            0b010 => {
+                let len = self.length("array", depth)?;
+                self.annotate(format!("{len} items"), depth);
+                for _ in 0..len {
+                    self.value(depth + 1, false)?;
+                }
+            },
+            0b011 => {
+                let len = self.length("record", depth)?;
+                self.annotate(format!("{len} fields"), depth);
+                for _ in 0..len {
+                    if self.peek()? >> 5 != 0b001 {
+                        return Err(format!("record key at byte {} isn't text", self.pos));
+                    }
+                    self.value(depth + 1, true)?;
+                    self.value(depth + 1, false)?;
+                }
+            },
+            0b100 => {
+                let sign = if first & 0x08 != 0 { "negative" } else { "positive" };
+                let more = first & 0x10 != 0;
+                self.next(fields(first, &[3, 1, 1]), depth, format!("integer, {sign}{}", if more { " (continued)" } else { "" }))?;
+                let mut n = u128::from(first & 0x07);
+                let mut more = more;
+                while more {
+                    let byte = self.peek()?;
+                    self.next(fields(byte, &[1]), depth, "")?;
+                    n = n.checked_mul(128).ok_or("integer is larger than 128 bits")? | u128::from(byte & 0x7F);
+                    more = byte & 0x80 != 0;
+                }
+                self.annotate(if first & 0x08 != 0 { format!("-{n}") } else { n.to_string() }, depth);
+            },
+            0b110 => {
+                let note = match first & 0x1F {
+                    0 => "false",
+                    1 => "true",
+                    2 => "null",
+                    _ => "unknown constant",
+                };
+                self.next(fields(first, &[3]), depth, note)?;
+            },
+            _ => {
+                self.next(fields(first, &[3]), depth, "decimal float (unsupported)")?;
+                return Err(format!("can't decode decimal floats at byte {}", self.pos - 1));
+            },
+        }
+        Ok(())
+    }
+}
+
+pub fn print(w: &mut dyn Write, lines: &[Line]) -> io::Result<()> {
+    for line in lines {
+        writeln!(w, "{:>8}  {:02x}  {:<11}  {}", line.offset, line.byte, line.bits, line.note)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn annotations() {
+    let value = nota::Value::Record(Box::new([("a".to_string(), nota::Value::Array(vec![
+        nota::Value::Integer(-300),
+        nota::Value::Blob(bitvec::bitvec![u8, bitvec::order::Msb0; 1, 0, 1]),
+        nota::Value::Null,
+    ]))].into_iter().collect()));
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+
+    let (lines, res) = dump(&bytes);
+    assert_eq!(res, Ok(bytes.len()));
+    assert_eq!(lines.len(), bytes.len());
+    let notes: Vec<_> = lines.iter().map(|l| l.note.as_str()).collect();
+    assert_eq!(notes, [
+        "record, 1 fields",
+        "  key, 1 chars",
+        "    'a' U+0061",
+        "  array, 3 items",
+        "    integer, negative (continued)",
+        "    -300",
+        "    blob, 3 bits",
+        "      3 bits, 5 bits of padding",
+        "    null",
+    ]);
+    assert_eq!(lines[4].bits, "100 1 1 010");
+
+    let (lines, res) = dump(&bytes[..5]);
+    assert_eq!(lines.len(), 5);
+    assert!(res.unwrap_err().contains("byte 5"));
+}
@@ -0,0 +1,113 @@
+//! Conversions to and from [`bson::Bson`]
+//!
+//! Types that Nota doesn't have are mapped to records with a single field, named like in MongoDB's Extended JSON:
+//!
+//! * `ObjectId` is `{"$oid": <96-bit blob>}`
+//! * `DateTime` is `{"$date": <milliseconds since the Unix epoch>}`
+//! * `Double` is `{"$numberDouble": <text>}`, with the shortest text that parses back to the same double,
+//!   or `Infinity`, `-Infinity`, `NaN`
+//!
+//! Binary data becomes a blob, and its subtype is lost. Other BSON types (regular expressions, JavaScript code,
+//! timestamps, decimal128, etc.) can't be converted.
+use crate::{Error, Value};
+use bitvec::vec::BitVec;
+use bson::Bson;
+use bson::spec::BinarySubtype;
+
+pub const OBJECT_ID_KEY: &str = "$oid";
+pub const DATE_KEY: &str = "$date";
+pub const DOUBLE_KEY: &str = "$numberDouble";
+
+impl TryFrom<Bson> for Value {
+    type Error = Error;
+
+    fn try_from(bson: Bson) -> Result<Self, Error> {
+        Ok(match bson {
+            Bson::Double(f) => tagged(DOUBLE_KEY, Self::Text(match f {
+                f64::INFINITY => "Infinity".into(),
+                f64::NEG_INFINITY => "-Infinity".into(),
+                f if f.is_nan() => "NaN".into(),
+                f => f.to_string(),
+            })),
+            Bson::String(s) => Self::Text(s),
+            Bson::Array(a) => Self::Array(a.into_iter().map(Self::try_from).collect::<Result<_, _>>()?),
+            Bson::Document(d) => Self::Record(Box::new(d.into_iter().map(|(k, v)| Ok((k, Self::try_from(v)?))).collect::<Result<_, Error>>()?)),
+            Bson::Boolean(b) => Self::Bool(b),
+            Bson::Null => Self::Null,
+            Bson::Int32(i) => Self::Integer(i.into()),
+            Bson::Int64(i) => Self::Integer(i.into()),
+            Bson::Binary(b) => Self::Blob(BitVec::from_vec(b.bytes)),
+            Bson::ObjectId(oid) => tagged(OBJECT_ID_KEY, Self::Blob(BitVec::from_vec(oid.bytes().to_vec()))),
+            Bson::DateTime(d) => tagged(DATE_KEY, Self::Integer(d.timestamp_millis().into())),
+            _ => return Err(Error::Unrepresentable("BSON type without a Nota equivalent")),
+        })
+    }
+}
+
+fn tagged(key: &str, value: Value) -> Value {
+    Value::Record(Box::new([(key.into(), value)].into_iter().collect()))
+}
+
+impl TryFrom<Value> for Bson {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Ok(match value {
+            Value::Text(s) => Self::String(s),
+            Value::Array(a) => Self::Array(a.into_iter().map(Self::try_from).collect::<Result<_, _>>()?),
+            Value::Record(r) if r.len() == 1 => {
+                let (k, v) = r.into_iter().next().unwrap();
+                match (k.as_str(), v) {
+                    (OBJECT_ID_KEY, Value::Blob(bits)) => {
+                        let bytes = <[u8; 12]>::try_from(bits.as_raw_slice()).ok().filter(|_| bits.len() == 96)
+                            .ok_or(Error::Unrepresentable("ObjectId that isn't 96 bits"))?;
+                        Self::ObjectId(bson::oid::ObjectId::from_bytes(bytes))
+                    },
+                    (DATE_KEY, Value::Integer(ms)) => {
+                        Self::DateTime(bson::DateTime::from_millis(i64::try_from(ms).map_err(|_| Error::Unrepresentable("date out of range"))?))
+                    },
+                    (DOUBLE_KEY, Value::Text(f)) => Self::Double(f.parse().map_err(|_| Error::Unrepresentable("invalid double"))?),
+                    (_, v) => Self::Document([(k, Self::try_from(v)?)].into_iter().collect()),
+                }
+            },
+            Value::Record(r) => Self::Document(r.into_iter().map(|(k, v)| Ok((k, Self::try_from(v)?))).collect::<Result<_, Error>>()?),
+            Value::Integer(i) => {
+                if let Ok(i) = i32::try_from(i) {
+                    Self::Int32(i)
+                } else {
+                    Self::Int64(i64::try_from(i).map_err(|_| Error::Unrepresentable("integer larger than 64 bits"))?)
+                }
+            },
+            Value::Blob(bits) if bits.len() % 8 == 0 => Self::Binary(bson::Binary { subtype: BinarySubtype::Generic, bytes: bits.into_vec() }),
+            Value::Blob(_) => return Err(Error::Unrepresentable("blob with a partial byte")),
+            Value::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float")),
+            Value::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag")),
+            Value::Bool(b) => Self::Boolean(b),
+            Value::Null => Self::Null,
+        })
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let oid = bson::oid::ObjectId::new();
+    let doc = bson::doc! {
+        "_id": oid,
+        "at": bson::DateTime::from_millis(1_700_000_000_123),
+        "n": 5_000_000_000i64,
+        "small": 3,
+        "ratio": 0.1,
+        "limit": f64::NEG_INFINITY,
+        "tags": ["a", "b"],
+        "nested": { "x": true, "y": Bson::Null },
+        "data": bson::Binary { subtype: BinarySubtype::Generic, bytes: vec![1, 2, 3] },
+    };
+    let value = Value::try_from(Bson::Document(doc.clone())).unwrap();
+    let Value::Record(r) = &value else { panic!() };
+    assert_eq!(r["at"], tagged(DATE_KEY, Value::Integer(1_700_000_000_123)));
+    assert_eq!(r["ratio"], tagged(DOUBLE_KEY, Value::Text("0.1".into())));
+    assert_eq!(r["limit"], tagged(DOUBLE_KEY, Value::Text("-Infinity".into())));
+    assert_eq!(Bson::try_from(value).unwrap(), Bson::Document(doc));
+
+    assert!(Value::try_from(Bson::MaxKey).is_err());
+}
@@ -0,0 +1,523 @@
+//! C API. The header is in `include/nota.h`, regenerated with `cbindgen --config cbindgen.toml --output include/nota.h`.
+//!
+//! Values are opaque `NotaValue` handles. Handles returned by `nota_decode` and `nota_value_new_*` are owned by the caller,
+//! and must be freed with `nota_value_free`, unless ownership is passed to a container (`nota_value_array_push`, `nota_value_record_insert`).
+//! Handles returned by getters are borrowed from their parent, and are valid until the parent is modified or freed.
+//! Record iterators borrow the record the same way, and are freed with `nota_record_iter_free`.
+//!
+//! Text is passed as UTF-8 bytes with explicit lengths, and is not NUL-terminated.
+//!
+//! When `nota_decode`, `nota_decode_with_limits`, or `nota_encode` fails, `nota_last_error_code` tells more precisely why.
+use crate::{ErrorCode, ParseOptions, Value};
+use bitvec::vec::BitVec;
+use std::cell::Cell;
+use std::{io, ptr, slice};
+
+thread_local! {
+    static LAST_ERROR_CODE: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Opaque handle to a [`Value`]
+#[repr(transparent)]
+pub struct NotaValue(Value);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotaStatus {
+    Ok = 0,
+    /// A `NULL` pointer or invalid UTF-8 was passed in
+    InvalidArgument = 1,
+    /// The value is of a different kind than the function expects, or the index is out of range
+    WrongKind = 2,
+    /// The input is not valid Nota
+    InvalidData = 3,
+    /// The input ended in the middle of a value
+    UnexpectedEof = 4,
+    /// The input uses a Nota feature that this library doesn't support
+    Unsupported = 5,
+    /// The integer doesn't fit in the output type
+    Overflow = 6,
+}
+
+impl From<io::Error> for NotaStatus {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => Self::UnexpectedEof,
+            io::ErrorKind::Unsupported => Self::Unsupported,
+            _ => Self::InvalidData,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotaKind {
+    Blob = 0,
+    Text = 1,
+    Array = 2,
+    Record = 3,
+    Integer = 4,
+    DecimalFloat = 5,
+    Bool = 6,
+    Null = 7,
+    Unknown = 8,
+    /// `NULL` was passed in instead of a value
+    Invalid = 9,
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(data, len))
+    }
+}
+
+unsafe fn text<'a>(data: *const u8, len: usize) -> Option<&'a str> {
+    std::str::from_utf8(bytes(data, len)?).ok()
+}
+
+fn set_error_code(code: Option<ErrorCode>) {
+    LAST_ERROR_CODE.with(|c| c.set(code.map_or(0, |code| code as u32)));
+}
+
+fn new(value: Value) -> *mut NotaValue {
+    Box::into_raw(Box::new(NotaValue(value)))
+}
+
+/// Parses one value from `len` bytes at `data`. On success, `*out` is set to a new handle.
+///
+/// The data is treated as untrusted: nesting is limited to 64 levels, a single value to a million elements, chars, or bits,
+/// and encodings must be the shortest possible. Use `nota_decode_with_limits` to choose the limits.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes. `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nota_decode(data: *const u8, len: usize, out: *mut *mut NotaValue) -> NotaStatus {
+    decode(data, len, &ParseOptions::untrusted(), out)
+}
+
+/// Like `nota_decode`, but with the deepest allowed nesting, and the largest number of elements, fields, chars, or bits
+/// in a single value. 0 means no limit. Encodings don't have to be the shortest possible.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes. `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nota_decode_with_limits(data: *const u8, len: usize, max_depth: usize, max_len: usize, out: *mut *mut NotaValue) -> NotaStatus {
+    let options = ParseOptions { max_depth: Some(max_depth).filter(|&n| n > 0), max_len: Some(max_len).filter(|&n| n > 0), ..ParseOptions::default() };
+    decode(data, len, &options, out)
+}
+
+unsafe fn decode(data: *const u8, len: usize, options: &ParseOptions, out: *mut *mut NotaValue) -> NotaStatus {
+    let (Some(mut data), false) = (bytes(data, len), out.is_null()) else {
+        return NotaStatus::InvalidArgument;
+    };
+    match Value::parse_slice_with(&mut data, options) {
+        Ok(v) => {
+            set_error_code(None);
+            *out = new(v);
+            NotaStatus::Ok
+        },
+        Err(e) => {
+            set_error_code(Some(ErrorCode::from_io(&e)));
+            e.into()
+        },
+    }
+}
+
+/// Serializes the value. On success, `*out_data` and `*out_len` are set to a new buffer that must be freed with `nota_bytes_free`.
+///
+/// # Safety
+///
+/// `value` must be a valid handle. `out_data` and `out_len` must be valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nota_encode(value: *const NotaValue, out_data: *mut *mut u8, out_len: *mut usize) -> NotaStatus {
+    let Some(value) = value.as_ref() else { return NotaStatus::InvalidArgument };
+    if out_data.is_null() || out_len.is_null() {
+        return NotaStatus::InvalidArgument;
+    }
+    let mut out = Vec::new();
+    if let Err(e) = value.0.try_serialize_into(&mut out) {
+        set_error_code(Some(e.code()));
+        return NotaStatus::Unsupported;
+    }
+    set_error_code(None);
+    let out = Box::into_raw(out.into_boxed_slice());
+    *out_len = out.len();
+    *out_data = out.cast();
+    NotaStatus::Ok
+}
+
+/// The reason why the last call to `nota_decode`, `nota_decode_with_limits`, or `nota_encode` on this thread has failed, as a `NotaErrorCode`.
+/// 0 if it has succeeded, or there were no calls.
+#[no_mangle]
+pub extern "C" fn nota_last_error_code() -> u32 {
+    LAST_ERROR_CODE.with(Cell::get)
+}
+
+/// Frees a buffer returned by `nota_encode`
+///
+/// # Safety
+///
+/// `data` and `len` must be exactly as returned by `nota_encode`, or `data` must be `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn nota_bytes_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+/// Frees an owned handle and everything in it. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `value` must be an owned handle that hasn't been freed or given away.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_free(value: *mut NotaValue) {
+    if !value.is_null() {
+        drop(Box::from_raw(value));
+    }
+}
+
+/// `NOTA_KIND_INVALID` if `value` is `NULL`
+///
+/// # Safety
+///
+/// `value` must be a valid handle or `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_kind(value: *const NotaValue) -> NotaKind {
+    let Some(value) = value.as_ref() else { return NotaKind::Invalid };
+    match &value.0 {
+        Value::Blob(_) => NotaKind::Blob,
+        Value::Text(_) => NotaKind::Text,
+        Value::Array(_) => NotaKind::Array,
+        Value::Record(_) => NotaKind::Record,
+        Value::Integer(_) => NotaKind::Integer,
+        Value::DecimalFloat(_) => NotaKind::DecimalFloat,
+        Value::Bool(_) => NotaKind::Bool,
+        Value::Null => NotaKind::Null,
+        Value::Unknown { .. } => NotaKind::Unknown,
+    }
+}
+
+/// # Safety
+///
+/// `value` must be a valid handle, and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_get_integer(value: *const NotaValue, out: *mut i64) -> NotaStatus {
+    match value.as_ref().map(|v| &v.0) {
+        Some(Value::Integer(i)) if !out.is_null() => match i64::try_from(*i) {
+            Ok(i) => {
+                *out = i;
+                NotaStatus::Ok
+            },
+            Err(_) => NotaStatus::Overflow,
+        },
+        Some(_) if !out.is_null() => NotaStatus::WrongKind,
+        _ => NotaStatus::InvalidArgument,
+    }
+}
+
+/// # Safety
+///
+/// `value` must be a valid handle, and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_get_bool(value: *const NotaValue, out: *mut bool) -> NotaStatus {
+    match value.as_ref().map(|v| &v.0) {
+        Some(Value::Bool(b)) if !out.is_null() => {
+            *out = *b;
+            NotaStatus::Ok
+        },
+        Some(_) if !out.is_null() => NotaStatus::WrongKind,
+        _ => NotaStatus::InvalidArgument,
+    }
+}
+
+/// Gives UTF-8 bytes of the text, borrowed from the handle.
+///
+/// # Safety
+///
+/// `value` must be a valid handle, and `out_data` and `out_len` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_get_text(value: *const NotaValue, out_data: *mut *const u8, out_len: *mut usize) -> NotaStatus {
+    if out_data.is_null() || out_len.is_null() {
+        return NotaStatus::InvalidArgument;
+    }
+    match value.as_ref().map(|v| &v.0) {
+        Some(Value::Text(s)) => {
+            *out_data = s.as_ptr();
+            *out_len = s.len();
+            NotaStatus::Ok
+        },
+        Some(_) => NotaStatus::WrongKind,
+        None => NotaStatus::InvalidArgument,
+    }
+}
+
+/// Gives bytes of the blob, borrowed from the handle, and its length in *bits*.
+///
+/// # Safety
+///
+/// `value` must be a valid handle, and `out_data` and `out_bits` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_get_blob(value: *const NotaValue, out_data: *mut *const u8, out_bits: *mut usize) -> NotaStatus {
+    if out_data.is_null() || out_bits.is_null() {
+        return NotaStatus::InvalidArgument;
+    }
+    match value.as_ref().map(|v| &v.0) {
+        Some(Value::Blob(bits)) => {
+            *out_data = bits.as_raw_slice().as_ptr();
+            *out_bits = bits.len();
+            NotaStatus::Ok
+        },
+        Some(_) => NotaStatus::WrongKind,
+        None => NotaStatus::InvalidArgument,
+    }
+}
+
+/// Number of elements of an array or fields of a record. 0 for other kinds.
+///
+/// # Safety
+///
+/// `value` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_len(value: *const NotaValue) -> usize {
+    match value.as_ref().map(|v| &v.0) {
+        Some(Value::Array(a)) => a.len(),
+        Some(Value::Record(r)) => r.len(),
+        _ => 0,
+    }
+}
+
+/// Borrowed handle to the array element, or `NULL` if it's not an array or the index is out of range.
+///
+/// # Safety
+///
+/// `value` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_array_get(value: *const NotaValue, index: usize) -> *const NotaValue {
+    match value.as_ref().map(|v| &v.0) {
+        Some(Value::Array(a)) => a.get(index).map_or(ptr::null(), |v| ptr::from_ref(v).cast()),
+        _ => ptr::null(),
+    }
+}
+
+/// Borrowed handle to the record field, or `NULL` if it's not a record or there's no such field.
+///
+/// # Safety
+///
+/// `value` must be a valid handle, and `key` must point to `key_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_record_get(value: *const NotaValue, key: *const u8, key_len: usize) -> *const NotaValue {
+    match (value.as_ref().map(|v| &v.0), text(key, key_len)) {
+        (Some(Value::Record(r)), Some(key)) => r.get(key).map_or(ptr::null(), |v| ptr::from_ref(v).cast()),
+        _ => ptr::null(),
+    }
+}
+
+/// Iterator over fields of a record, in order of their keys, from `nota_value_record_iter`
+pub struct NotaRecordIter {
+    fields: std::vec::IntoIter<(*const str, *const Value)>,
+}
+
+/// New iterator over fields of the record, sorted by key, or `NULL` if it's not a record.
+/// It borrows the record, and must be freed with `nota_record_iter_free` before the record is modified or freed.
+///
+/// # Safety
+///
+/// `value` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_record_iter(value: *const NotaValue) -> *mut NotaRecordIter {
+    match value.as_ref().map(|v| &v.0) {
+        Some(Value::Record(r)) => {
+            let mut fields: Vec<_> = r.iter().collect();
+            fields.sort_unstable_by_key(|&(k, _)| k);
+            let fields: Vec<_> = fields.into_iter().map(|(k, v)| (ptr::from_ref(k.as_str()), ptr::from_ref(v))).collect();
+            Box::into_raw(Box::new(NotaRecordIter { fields: fields.into_iter() }))
+        },
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Sets `*out_key` and `*out_key_len` to the next field's key, as borrowed UTF-8 bytes,
+/// and returns a borrowed handle to its value. Returns `NULL` after the last field.
+///
+/// # Safety
+///
+/// `iter` must be a valid iterator whose record hasn't been modified or freed, and `out_key` and `out_key_len` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nota_record_iter_next(iter: *mut NotaRecordIter, out_key: *mut *const u8, out_key_len: *mut usize) -> *const NotaValue {
+    if out_key.is_null() || out_key_len.is_null() {
+        return ptr::null();
+    }
+    match iter.as_mut().and_then(|iter| iter.fields.next()) {
+        Some((k, v)) => {
+            let k = &*k;
+            *out_key = k.as_ptr();
+            *out_key_len = k.len();
+            v.cast()
+        },
+        None => ptr::null(),
+    }
+}
+
+/// Frees an iterator. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `iter` must be returned by `nota_value_record_iter`, and not freed already.
+#[no_mangle]
+pub unsafe extern "C" fn nota_record_iter_free(iter: *mut NotaRecordIter) {
+    if !iter.is_null() {
+        drop(Box::from_raw(iter));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn nota_value_new_null() -> *mut NotaValue {
+    new(Value::Null)
+}
+
+#[no_mangle]
+pub extern "C" fn nota_value_new_bool(b: bool) -> *mut NotaValue {
+    new(Value::Bool(b))
+}
+
+#[no_mangle]
+pub extern "C" fn nota_value_new_integer(i: i64) -> *mut NotaValue {
+    new(Value::Integer(i.into()))
+}
+
+/// Copies UTF-8 text. Returns `NULL` if the text isn't valid UTF-8.
+///
+/// # Safety
+///
+/// `data` must point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_new_text(data: *const u8, len: usize) -> *mut NotaValue {
+    text(data, len).map_or(ptr::null_mut(), |s| new(Value::Text(s.into())))
+}
+
+/// Copies a blob that is `bits` long.
+///
+/// # Safety
+///
+/// `data` must point to at least `(bits + 7) / 8` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_new_blob(data: *const u8, bits: usize) -> *mut NotaValue {
+    let Some(data) = bytes(data, bits.div_ceil(8)) else { return ptr::null_mut() };
+    let mut blob = BitVec::from_vec(data.to_vec());
+    blob.truncate(bits);
+    new(Value::Blob(blob))
+}
+
+#[no_mangle]
+pub extern "C" fn nota_value_new_array() -> *mut NotaValue {
+    new(Value::Array(Vec::new()))
+}
+
+#[no_mangle]
+pub extern "C" fn nota_value_new_record() -> *mut NotaValue {
+    new(Value::Record(Box::default()))
+}
+
+/// Appends `element` to the array, taking ownership of it (even on failure).
+///
+/// # Safety
+///
+/// `array` must be a valid handle, and `element` an owned handle.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_array_push(array: *mut NotaValue, element: *mut NotaValue) -> NotaStatus {
+    if element.is_null() {
+        return NotaStatus::InvalidArgument;
+    }
+    let element = Box::from_raw(element).0;
+    match array.as_mut().map(|v| &mut v.0) {
+        Some(Value::Array(a)) => {
+            a.push(element);
+            NotaStatus::Ok
+        },
+        Some(_) => NotaStatus::WrongKind,
+        None => NotaStatus::InvalidArgument,
+    }
+}
+
+/// Sets the record's field, taking ownership of `field` (even on failure). Replaces any previous value of the field.
+///
+/// # Safety
+///
+/// `record` must be a valid handle, `key` must point to `key_len` bytes, and `field` must be an owned handle.
+#[no_mangle]
+pub unsafe extern "C" fn nota_value_record_insert(record: *mut NotaValue, key: *const u8, key_len: usize, field: *mut NotaValue) -> NotaStatus {
+    if field.is_null() {
+        return NotaStatus::InvalidArgument;
+    }
+    let field = Box::from_raw(field).0;
+    let Some(key) = text(key, key_len) else { return NotaStatus::InvalidArgument };
+    match record.as_mut().map(|v| &mut v.0) {
+        Some(Value::Record(r)) => {
+            r.insert(key.into(), field);
+            NotaStatus::Ok
+        },
+        Some(_) => NotaStatus::WrongKind,
+        None => NotaStatus::InvalidArgument,
+    }
+}
+
+#[test]
+fn build_encode_decode() {
+    unsafe {
+        let rec = nota_value_new_record();
+        let arr = nota_value_new_array();
+        assert_eq!(nota_value_array_push(arr, nota_value_new_integer(-5)), NotaStatus::Ok);
+        assert_eq!(nota_value_array_push(arr, nota_value_new_text("☃".as_ptr(), 3)), NotaStatus::Ok);
+        assert_eq!(nota_value_record_insert(rec, "list".as_ptr(), 4, arr), NotaStatus::Ok);
+        assert_eq!(nota_value_record_insert(rec, "blob".as_ptr(), 4, nota_value_new_blob([0xFF].as_ptr(), 3)), NotaStatus::Ok);
+
+        let (mut data, mut len) = (ptr::null_mut(), 0);
+        assert_eq!(nota_encode(rec, &mut data, &mut len), NotaStatus::Ok);
+        let mut decoded = ptr::null_mut();
+        assert_eq!(nota_decode(data, len, &mut decoded), NotaStatus::Ok);
+        assert_eq!((*decoded).0, (*rec).0);
+        assert_eq!(nota_decode(data, len - 1, &mut ptr::null_mut()), NotaStatus::UnexpectedEof);
+        assert_eq!(nota_last_error_code(), ErrorCode::UnexpectedEof as u32);
+        nota_bytes_free(data, len);
+
+        let nested = [0x41, 0x41, 0x41, 0xC2];
+        assert_eq!(nota_decode_with_limits(nested.as_ptr(), nested.len(), 3, 0, &mut ptr::null_mut()), NotaStatus::InvalidData);
+        assert_eq!(nota_last_error_code(), ErrorCode::TooDeep as u32);
+        let mut array = ptr::null_mut();
+        assert_eq!(nota_decode_with_limits(nested.as_ptr(), nested.len(), 4, 1, &mut array), NotaStatus::Ok);
+        nota_value_free(array);
+
+        assert_eq!(nota_value_kind(decoded), NotaKind::Record);
+        assert_eq!(nota_value_kind(ptr::null()), NotaKind::Invalid);
+        assert_eq!(nota_value_len(decoded), 2);
+        let list = nota_value_record_get(decoded, "list".as_ptr(), 4);
+        let mut i = 0;
+        assert_eq!(nota_value_get_integer(nota_value_array_get(list, 0), &mut i), NotaStatus::Ok);
+        assert_eq!(i, -5);
+        assert_eq!(nota_value_get_integer(nota_value_array_get(list, 1), &mut i), NotaStatus::WrongKind);
+        assert!(nota_value_array_get(list, 2).is_null());
+        let (mut blob, mut bits) = (ptr::null(), 0);
+        assert_eq!(nota_value_get_blob(nota_value_record_get(decoded, "blob".as_ptr(), 4), &mut blob, &mut bits), NotaStatus::Ok);
+        assert_eq!(bits, 3);
+
+        let iter = nota_value_record_iter(decoded);
+        let (mut key, mut key_len) = (ptr::null(), 0);
+        let mut keys = Vec::new();
+        while !nota_record_iter_next(iter, &mut key, &mut key_len).is_null() {
+            keys.push(slice::from_raw_parts(key, key_len));
+        }
+        assert_eq!(keys, [&b"blob"[..], b"list"]);
+        nota_record_iter_free(iter);
+        assert!(nota_value_record_iter(list).is_null());
+
+        nota_value_free(decoded);
+        nota_value_free(rec);
+    }
+}
@@ -0,0 +1,140 @@
+//! Conversions to and from [`ciborium::Value`]
+//!
+//! Blobs that are a whole number of bytes become CBOR byte strings. Blobs with a partial byte
+//! are wrapped in a [`BIT_BLOB_TAG`] tag containing `[bit length, bytes]`.
+//! Integers that don't fit in CBOR's 65-bit range use the standard bignum tags 2 and 3.
+//!
+//! CBOR floats, simple values, and maps with non-text keys can't be converted.
+//! Neither can other tags, such as decimal fractions, because the value without its tag would mean something else.
+//! Only the self-described CBOR tag 55799 is skipped.
+use crate::{Error, Value};
+use bitvec::vec::BitVec;
+use ciborium::value::Integer;
+
+/// CBOR tag for `[bit length, bytes]` arrays representing blobs that aren't whole bytes
+pub const BIT_BLOB_TAG: u64 = 0x6e6f_7461;
+
+impl TryFrom<Value> for ciborium::Value {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Ok(match value {
+            Value::Blob(bits) => {
+                let len = bits.len();
+                let mut bytes = bits.into_vec();
+                // BitVec doesn't clear bits past its length
+                if let Some(last) = bytes.last_mut().filter(|_| len % 8 != 0) {
+                    *last &= !(0xFF >> (len % 8));
+                }
+                let bytes = Self::Bytes(bytes);
+                if len % 8 == 0 {
+                    bytes
+                } else {
+                    Self::Tag(BIT_BLOB_TAG, Box::new(Self::Array(vec![Self::Integer(len.into()), bytes])))
+                }
+            },
+            Value::Text(s) => Self::Text(s),
+            Value::Array(a) => Self::Array(a.into_iter().map(Self::try_from).collect::<Result<_, _>>()?),
+            Value::Record(r) => Self::Map(r.into_iter().map(|(k, v)| Ok((Self::Text(k), Self::try_from(v)?))).collect::<Result<_, Error>>()?),
+            Value::Integer(i) => match Integer::try_from(i) {
+                Ok(i) => Self::Integer(i),
+                Err(_) if i < 0 => Self::Tag(3, Box::new(bignum_bytes(!i as u128))),
+                Err(_) => Self::Tag(2, Box::new(bignum_bytes(i as u128))),
+            },
+            Value::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float")),
+            Value::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag")),
+            Value::Bool(b) => Self::Bool(b),
+            Value::Null => Self::Null,
+        })
+    }
+}
+
+impl TryFrom<ciborium::Value> for Value {
+    type Error = Error;
+
+    fn try_from(cbor: ciborium::Value) -> Result<Self, Error> {
+        use ciborium::Value as C;
+        Ok(match cbor {
+            C::Integer(i) => Self::Integer(i.into()),
+            C::Bytes(b) => Self::Blob(BitVec::from_vec(b)),
+            C::Float(_) => return Err(Error::Unrepresentable("float")),
+            C::Text(s) => Self::Text(s),
+            C::Bool(b) => Self::Bool(b),
+            C::Null => Self::Null,
+            C::Tag(2, inner) => Self::Integer(i128::try_from(bignum(*inner)?).map_err(|_| Error::Unrepresentable("bignum"))?),
+            C::Tag(3, inner) => Self::Integer(!i128::try_from(bignum(*inner)?).map_err(|_| Error::Unrepresentable("bignum"))?),
+            C::Tag(BIT_BLOB_TAG, inner) => match *inner {
+                C::Array(a) => match <[C; 2]>::try_from(a) {
+                    Ok([C::Integer(len), C::Bytes(bytes)]) => {
+                        let len = usize::try_from(len).map_err(|_| Error::Unrepresentable("bit blob length"))?;
+                        if len > bytes.len() * 8 {
+                            return Err(Error::Unrepresentable("bit blob length"));
+                        }
+                        let mut bits = BitVec::from_vec(bytes);
+                        bits.truncate(len);
+                        Self::Blob(bits)
+                    },
+                    _ => return Err(Error::Unrepresentable("malformed bit blob")),
+                },
+                _ => return Err(Error::Unrepresentable("malformed bit blob")),
+            },
+            C::Tag(55799, inner) => Self::try_from(*inner)?,
+            C::Tag(..) => return Err(Error::Unrepresentable("CBOR tag")),
+            C::Array(a) => Self::Array(a.into_iter().map(Self::try_from).collect::<Result<_, _>>()?),
+            C::Map(m) => Self::Record(Box::new(m.into_iter().map(|(k, v)| match k {
+                C::Text(k) => Ok((k, Self::try_from(v)?)),
+                _ => Err(Error::Unrepresentable("map key that isn't text")),
+            }).collect::<Result<_, _>>()?)),
+            _ => return Err(Error::Unrepresentable("CBOR simple value")),
+        })
+    }
+}
+
+fn bignum_bytes(n: u128) -> ciborium::Value {
+    let bytes = n.to_be_bytes();
+    let skip = (n.leading_zeros() / 8) as usize;
+    ciborium::Value::Bytes(bytes[skip..].to_vec())
+}
+
+fn bignum(bytes: ciborium::Value) -> Result<u128, Error> {
+    match bytes {
+        ciborium::Value::Bytes(b) if b.len() <= 16 => Ok(b.iter().fold(0, |n, &b| (n << 8) | u128::from(b))),
+        _ => Err(Error::Unrepresentable("bignum")),
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let mut bits = BitVec::from_vec(vec![0xAA]);
+    bits.push(true);
+    let value = Value::Array(vec![
+        Value::Blob(bits),
+        Value::Blob(BitVec::from_vec(vec![1, 2])),
+        Value::Integer(i128::MAX),
+        Value::Integer(i128::MIN + 1),
+        Value::Integer(-(1 << 64)),
+        Value::Record(Box::new([("k".to_string(), Value::Null)].into_iter().collect())),
+        Value::Text("☃".into()),
+        Value::Bool(false),
+    ]);
+    let cbor = ciborium::Value::try_from(value.clone()).unwrap();
+    let ciborium::Value::Array(items) = &cbor else { panic!() };
+    assert!(matches!(items[0], ciborium::Value::Tag(BIT_BLOB_TAG, _)));
+    assert_eq!(items[1], ciborium::Value::Bytes(vec![1, 2]));
+    assert!(matches!(items[2], ciborium::Value::Tag(2, _)));
+    assert!(matches!(items[3], ciborium::Value::Tag(3, _)));
+    assert!(matches!(items[4], ciborium::Value::Integer(_)));
+    assert_eq!(Value::try_from(cbor).unwrap(), value);
+
+    assert!(Value::try_from(ciborium::Value::Float(1.5)).is_err());
+
+    let mut bits = BitVec::from_vec(vec![0xFF]);
+    bits.truncate(3);
+    let ciborium::Value::Tag(_, inner) = ciborium::Value::try_from(Value::Blob(bits)).unwrap() else { panic!() };
+    assert_eq!(*inner, ciborium::Value::Array(vec![ciborium::Value::Integer(3.into()), ciborium::Value::Bytes(vec![0xE0])]));
+
+    let fraction = ciborium::Value::Array(vec![ciborium::Value::Integer((-1).into()), ciborium::Value::Integer(15.into())]);
+    assert_eq!(Value::try_from(ciborium::Value::Tag(4, Box::new(fraction))), Err(Error::Unrepresentable("CBOR tag")));
+    let described = ciborium::Value::Tag(55799, Box::new(ciborium::Value::Null));
+    assert_eq!(Value::try_from(described).unwrap(), Value::Null);
+}
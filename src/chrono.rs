@@ -0,0 +1,238 @@
+//! Datetimes from [`chrono`]
+//!
+//! Nota doesn't have a datetime type. This crate writes them as an [`Integer`](Value::Integer) of nanoseconds
+//! since 1970-01-01T00:00:00Z (ignoring leap seconds), which is compact and sorts in time order, but loses the time zone.
+//!
+//! The [tagged](crate::tagged) form, `{"$datetime": "2024-02-29T12:00:00+01:00"}`, has RFC 3339 text instead, like
+//! tagged TOML datetimes, and keeps the offset.
+//!
+//! Conversions from values accept the integers, RFC 3339 text, and the tagged form.
+//! For serde, use `#[serde(with = "nota::chrono::epoch_nanos")]` on `DateTime<Utc>` fields to store them as integers.
+//!
+//! [`TimeDelta`] is stored like [`std::time::Duration`](crate::duration), as integer nanoseconds or text like `"-1h30m"`,
+//! and can use `#[serde(with = "nota::chrono::duration_nanos")]`.
+//!
+//! ```rust
+//! use chrono::{DateTime, Utc};
+//! use nota::Value;
+//!
+//! let time = DateTime::from_timestamp(1_700_000_000, 5).unwrap();
+//! let value = Value::from(time);
+//! assert_eq!(value, Value::Integer(1_700_000_000_000_000_005));
+//! assert_eq!(DateTime::<Utc>::try_from(&value)?, time);
+//! assert_eq!(DateTime::<Utc>::try_from(&Value::Text("2023-11-14T22:13:20.000000005Z".into()))?, time);
+//! # Ok::<_, nota::Error>(())
+//! ```
+use crate::{Error, Value};
+use crate::duration::nanos_of;
+use chrono::{DateTime, FixedOffset, TimeDelta, TimeZone, Utc};
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+const EXPECTED: Error = Error::UnexpectedValue { expected: "a datetime" };
+
+impl<Tz: TimeZone> From<DateTime<Tz>> for Value {
+    fn from(time: DateTime<Tz>) -> Self {
+        Self::Integer(nanos(&time))
+    }
+}
+
+fn nanos<Tz: TimeZone>(time: &DateTime<Tz>) -> i128 {
+    i128::from(time.timestamp()) * NANOS_PER_SEC + i128::from(time.timestamp_subsec_nanos())
+}
+
+fn from_nanos(nanos: i128) -> Result<DateTime<Utc>, Error> {
+    let secs = nanos.div_euclid(NANOS_PER_SEC).try_into().map_err(|_| EXPECTED)?;
+    DateTime::from_timestamp(secs, nanos.rem_euclid(NANOS_PER_SEC) as u32).ok_or(EXPECTED)
+}
+
+impl TryFrom<&Value> for DateTime<FixedOffset> {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        match value {
+            &Value::Integer(nanos) => Ok(from_nanos(nanos)?.fixed_offset()),
+            Value::Text(text) => Self::parse_from_rfc3339(text).map_err(|_| EXPECTED),
+            #[cfg(feature = "records")]
+            value => match value.as_tagged() {
+                Some(("datetime", inner)) => Self::try_from(inner),
+                _ => Err(EXPECTED),
+            },
+            #[cfg(not(feature = "records"))]
+            _ => Err(EXPECTED),
+        }
+    }
+}
+
+impl TryFrom<&Value> for DateTime<Utc> {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        DateTime::<FixedOffset>::try_from(value).map(|time| time.to_utc())
+    }
+}
+
+impl TryFrom<Value> for DateTime<FixedOffset> {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Self::try_from(&value)
+    }
+}
+
+impl TryFrom<Value> for DateTime<Utc> {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Self::try_from(&value)
+    }
+}
+
+#[cfg(feature = "records")]
+impl crate::tagged::Extension for DateTime<FixedOffset> {
+    const TAG: &'static str = "datetime";
+
+    fn to_value(&self) -> Value {
+        Value::Text(self.to_rfc3339())
+    }
+
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        Self::try_from(value)
+    }
+}
+
+#[cfg(feature = "records")]
+impl crate::tagged::Extension for DateTime<Utc> {
+    const TAG: &'static str = "datetime";
+
+    fn to_value(&self) -> Value {
+        Value::Text(self.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+    }
+
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        Self::try_from(value)
+    }
+}
+
+impl From<TimeDelta> for Value {
+    fn from(delta: TimeDelta) -> Self {
+        Self::Integer(delta_nanos(delta))
+    }
+}
+
+fn delta_nanos(delta: TimeDelta) -> i128 {
+    i128::from(delta.num_seconds()) * NANOS_PER_SEC + i128::from(delta.subsec_nanos())
+}
+
+fn delta_from_nanos(nanos: i128) -> Result<TimeDelta, Error> {
+    let secs = nanos.div_euclid(NANOS_PER_SEC).try_into().map_err(|_| crate::duration::EXPECTED)?;
+    TimeDelta::new(secs, nanos.rem_euclid(NANOS_PER_SEC) as u32).ok_or(crate::duration::EXPECTED)
+}
+
+impl TryFrom<&Value> for TimeDelta {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        delta_from_nanos(nanos_of(value)?)
+    }
+}
+
+impl TryFrom<Value> for TimeDelta {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Self::try_from(&value)
+    }
+}
+
+#[cfg(feature = "records")]
+impl crate::tagged::Extension for TimeDelta {
+    const TAG: &'static str = "duration";
+
+    fn to_value(&self) -> Value {
+        Value::Text(crate::duration::format_nanos(delta_nanos(*self)))
+    }
+
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        Self::try_from(value)
+    }
+}
+
+/// Serde `with` module that writes `DateTime<Utc>` as integer nanoseconds since the epoch
+#[cfg(feature = "serde")]
+pub mod epoch_nanos {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i128(super::nanos(time))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        super::from_nanos(i128::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serde `with` module that writes `TimeDelta` as integer nanoseconds, and reads integers or text with units
+#[cfg(feature = "serde")]
+pub mod duration_nanos {
+    use chrono::TimeDelta;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(delta: &TimeDelta, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i128(super::delta_nanos(*delta))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TimeDelta, D::Error> {
+        let nanos = deserializer.deserialize_any(crate::duration::nanos::NanosVisitor)?;
+        super::delta_from_nanos(nanos).map_err(serde::de::Error::custom)
+    }
+}
+
+#[test]
+fn conversions() {
+    let time = DateTime::parse_from_rfc3339("1969-12-31T23:59:59.5-02:00").unwrap();
+    let value = Value::from(time);
+    assert_eq!(value, Value::Integer(7_199_500_000_000));
+    assert_eq!(DateTime::<Utc>::try_from(&value).unwrap(), time);
+    assert_eq!(DateTime::<Utc>::try_from(Value::Integer(-1)).unwrap().to_rfc3339(), "1969-12-31T23:59:59.999999999+00:00");
+    assert_eq!(DateTime::<Utc>::try_from(Value::Integer(i128::MAX)), Err(EXPECTED));
+    assert_eq!(DateTime::<Utc>::try_from(Value::Null), Err(EXPECTED));
+
+    #[cfg(feature = "records")]
+    {
+        use crate::tagged::{decode, encode};
+        let tagged = encode(&time);
+        assert_eq!(tagged.to_string(), r#"{"$datetime": "1969-12-31T23:59:59.500-02:00"}"#);
+        assert_eq!(decode::<DateTime<FixedOffset>>(&tagged).unwrap().offset(), time.offset());
+        assert_eq!(encode(&time.to_utc()).to_string(), r#"{"$datetime": "1970-01-01T01:59:59.500Z"}"#);
+        assert_eq!(DateTime::<Utc>::try_from(&tagged).unwrap(), time);
+        assert_eq!(DateTime::<Utc>::try_from(&Value::tagged("datetime", value)).unwrap(), time);
+        assert_eq!(encode(&TimeDelta::milliseconds(-1500)).to_string(), r#"{"$duration": "-1.5s"}"#);
+    }
+
+    let delta = TimeDelta::nanoseconds(-1);
+    assert_eq!(Value::from(delta), Value::Integer(-1));
+    assert_eq!(TimeDelta::try_from(Value::Integer(-1)), Ok(delta));
+    assert_eq!(TimeDelta::try_from(Value::Text("-1h30m".into())), Ok(TimeDelta::minutes(-90)));
+    assert_eq!(TimeDelta::try_from(Value::Integer(i128::MAX)), Err(crate::duration::EXPECTED));
+
+    #[cfg(feature = "serde")]
+    {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Event {
+            #[serde(with = "epoch_nanos")]
+            at: DateTime<Utc>,
+        }
+        let event = Event { at: time.to_utc() };
+        #[derive(serde::Deserialize)]
+        struct Config {
+            #[serde(with = "duration_nanos")]
+            retry: TimeDelta,
+        }
+        let config: Config = crate::serde::from_value(r#"{"retry": "2m"}"#.parse().unwrap()).unwrap();
+        assert_eq!(config.retry, TimeDelta::minutes(2));
+        let value = crate::serde::to_value(&event).unwrap();
+        assert_eq!(value.to_string(), r#"{"at": 7199500000000}"#);
+        assert_eq!(crate::serde::from_value::<Event>(value).unwrap(), event);
+    }
+}
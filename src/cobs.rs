@@ -0,0 +1,254 @@
+//! [COBS](https://en.wikipedia.org/wiki/Consistent_Overhead_Byte_Stuffing) framing, for serial links like UART or RS-485.
+//!
+//! Each frame is the serialized value with zero bytes removed by COBS, followed by a single `0` delimiter.
+//! A receiver that starts listening mid-stream, or loses bytes, resynchronizes at the next delimiter.
+//!
+//! [`encode`], [`decode_in_place`], and [`Deframer`] don't allocate. A [checksum](crate::framing::Checksum)
+//! is appended to the data before encoding, so frames from a [`Deframer`] can be checked with [`Checksum::verify`](crate::framing::Checksum::verify).
+use crate::framing::FrameOptions;
+use crate::{BufferTooSmall, Error, Value};
+use std::io::{self, BufRead, Read, Write};
+use std::fmt;
+
+/// Ends every frame
+pub const DELIMITER: u8 = 0;
+
+/// Why a COBS frame couldn't be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CobsError {
+    /// The frame contains a zero byte, or a length points past its end
+    Invalid,
+    /// The frame doesn't fit in the [`Deframer`]'s buffer
+    TooLong,
+}
+
+impl fmt::Display for CobsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Invalid => "invalid COBS frame",
+            Self::TooLong => "COBS frame is too long for the buffer",
+        })
+    }
+}
+
+impl std::error::Error for CobsError {}
+
+impl From<CobsError> for io::Error {
+    fn from(e: CobsError) -> Self {
+        Self::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Size of the buffer needed by [`encode`] for `len` bytes of data, including the delimiter
+#[must_use]
+pub const fn max_encoded_len(len: usize) -> usize {
+    len + len / 254 + 2
+}
+
+/// Writes `data` as one frame, including the delimiter. Returns the number of bytes written.
+pub fn encode(data: &[u8], out: &mut [u8]) -> Result<usize, BufferTooSmall> {
+    let mut code_pos = 0;
+    let mut pos = 1;
+    let mut code = 1u8;
+    for &byte in data {
+        if byte != 0 {
+            *out.get_mut(pos).ok_or(BufferTooSmall)? = byte;
+            pos += 1;
+            code += 1;
+        }
+        if byte == 0 || code == 0xFF {
+            *out.get_mut(code_pos).ok_or(BufferTooSmall)? = code;
+            code_pos = pos;
+            pos += 1;
+            code = 1;
+        }
+    }
+    *out.get_mut(code_pos).ok_or(BufferTooSmall)? = code;
+    *out.get_mut(pos).ok_or(BufferTooSmall)? = DELIMITER;
+    Ok(pos + 1)
+}
+
+/// Decodes a frame without its delimiter. The data is moved to the start of `frame`, and its length is returned.
+pub fn decode_in_place(frame: &mut [u8]) -> Result<usize, CobsError> {
+    let mut read = 0;
+    let mut write = 0;
+    while read < frame.len() {
+        let code = usize::from(frame[read]);
+        if code == 0 || read + code > frame.len() || frame[read + 1..read + code].contains(&0) {
+            return Err(CobsError::Invalid);
+        }
+        frame.copy_within(read + 1..read + code, write);
+        write += code - 1;
+        read += code;
+        if code < 0xFF && read < frame.len() {
+            frame[write] = 0;
+            write += 1;
+        }
+    }
+    Ok(write)
+}
+
+/// Collects bytes received one at a time, and decodes each frame when its delimiter arrives.
+///
+/// ```rust
+/// let mut deframer = nota::cobs::Deframer::<64>::new();
+/// for &byte in &[0x03, 0xC1, 0xC2, 0x00] {
+///     if let Some(frame) = deframer.push(byte) {
+///         assert_eq!(frame.unwrap(), [0xC1, 0xC2]);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Deframer<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    overflow: bool,
+}
+
+impl<const N: usize> Deframer<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { buf: [0; N], len: 0, overflow: false }
+    }
+
+    /// Returns the decoded data when `byte` ends a frame. Empty frames are skipped.
+    ///
+    /// After an error the next frame is decoded normally.
+    pub fn push(&mut self, byte: u8) -> Option<Result<&[u8], CobsError>> {
+        if byte != DELIMITER {
+            match self.buf.get_mut(self.len) {
+                Some(slot) => { *slot = byte; self.len += 1; },
+                None => self.overflow = true,
+            }
+            return None;
+        }
+        let len = std::mem::take(&mut self.len);
+        if std::mem::take(&mut self.overflow) {
+            return Some(Err(CobsError::TooLong));
+        }
+        if len == 0 {
+            return None;
+        }
+        Some(decode_in_place(&mut self.buf[..len]).map(|len| &self.buf[..len]))
+    }
+}
+
+impl<const N: usize> Default for Deframer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes the value as one COBS frame
+pub fn write_cobs<W: Write>(writer: &mut W, value: &Value) -> Result<(), io::Error> {
+    write_cobs_with(writer, value, &FrameOptions::default())
+}
+
+/// Writes the value as one COBS frame, with the checksum if one is set
+pub fn write_cobs_with<W: Write>(writer: &mut W, value: &Value, options: &FrameOptions) -> Result<(), io::Error> {
+    let mut data = Vec::new();
+    value.try_serialize_into(&mut data)?;
+    options.checksum.append(&mut data);
+    let mut frame = vec![0; max_encoded_len(data.len())];
+    let len = encode(&data, &mut frame).map_err(io::Error::other)?;
+    writer.write_all(&frame[..len])
+}
+
+/// Reads one frame written by [`write_cobs`].
+///
+/// Returns `Ok(None)` at the end of input. Empty frames are skipped.
+/// Frames longer than `max_frame_size` are rejected with [`Error::FrameTooLarge`].
+pub fn read_cobs<R: BufRead>(reader: &mut R, max_frame_size: usize) -> Result<Option<Value>, io::Error> {
+    read_cobs_with(reader, &FrameOptions { max_frame_size, ..FrameOptions::default() })
+}
+
+/// Reads one frame written by [`write_cobs_with`] with the same options
+pub fn read_cobs_with<R: BufRead>(reader: &mut R, options: &FrameOptions) -> Result<Option<Value>, io::Error> {
+    let max_frame_size = options.max_frame_size;
+    let mut frame = Vec::new();
+    while frame.is_empty() {
+        let limit = max_encoded_len(max_frame_size) as u64;
+        let read = (&mut *reader).take(limit).read_until(DELIMITER, &mut frame)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if frame.pop() != Some(DELIMITER) {
+            if read as u64 == limit {
+                return Err(Error::FrameTooLarge { len: limit, max: max_frame_size }.into());
+            }
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+    }
+    let len = decode_in_place(&mut frame)?;
+    if len > max_frame_size {
+        return Err(Error::FrameTooLarge { len: len as u64, max: max_frame_size }.into());
+    }
+    let mut body = options.checksum.verify(&frame[..len])?;
+    let value = Value::parse_slice_with(&mut body, &options.parse)?;
+    if !body.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "trailing bytes in frame"));
+    }
+    Ok(Some(value))
+}
+
+#[test]
+fn round_trip() {
+    let long: Vec<u8> = (0..600).map(|n| (n % 7) as u8 * 40).collect();
+    let long_nonzero: Vec<u8> = (0..600).map(|n| (n % 255) as u8 + 1).collect();
+    for data in [&[][..], &[0], &[0, 0], &[1, 2, 0, 3], &[0x11; 254], &[0x11; 255], &long, &long_nonzero] {
+        let mut out = vec![0xAA; max_encoded_len(data.len())];
+        let len = encode(data, &mut out).unwrap();
+        assert_eq!(out[len - 1], DELIMITER);
+        assert!(!out[..len - 1].contains(&DELIMITER));
+        assert_eq!(encode(data, &mut out[..len - 1]), Err(BufferTooSmall));
+
+        let mut deframer = Deframer::<700>::new();
+        let frames: Vec<_> = out[..len].iter().filter_map(|&b| deframer.push(b).map(|f| f.unwrap().to_vec())).collect();
+        assert_eq!(frames, [data]);
+    }
+    assert_eq!(encode(&[0x11, 0, 0x22], &mut [0; 5]), Ok(5));
+    assert_eq!(decode_in_place(&mut [3, 1, 0]), Err(CobsError::Invalid));
+    assert_eq!(decode_in_place(&mut [2, 1, 4, 1]), Err(CobsError::Invalid));
+
+    let mut deframer = Deframer::<2>::new();
+    for byte in [4, 1, 2, 3, 2, 9] {
+        assert_eq!(deframer.push(byte), None);
+    }
+    assert_eq!(deframer.push(0), Some(Err(CobsError::TooLong)));
+    for byte in [2, 9] {
+        assert_eq!(deframer.push(byte), None);
+    }
+    assert_eq!(deframer.push(0), Some(Ok(&[9][..])));
+}
+
+#[test]
+fn values() {
+    let mut stream = vec![0, 0];
+    write_cobs(&mut stream, &Value::Array(vec![Value::Integer(0), Value::Null])).unwrap();
+    write_cobs(&mut stream, &Value::Text("x".repeat(200))).unwrap();
+
+    let mut r = &stream[..];
+    assert_eq!(read_cobs(&mut r, 1000).unwrap(), Some(Value::Array(vec![Value::Integer(0), Value::Null])));
+    assert_eq!(read_cobs(&mut r, 1000).unwrap(), Some(Value::Text("x".repeat(200))));
+    assert_eq!(read_cobs(&mut r, 1000).unwrap(), None);
+
+    let mut r = &stream[..];
+    read_cobs(&mut r, 10).unwrap();
+    let err = read_cobs(&mut r, 10).unwrap_err();
+    assert!(matches!(Error::from_io(&err), Some(Error::FrameTooLarge { max: 10, .. })));
+
+    use crate::framing::Checksum;
+
+    let options = FrameOptions { checksum: Checksum::Crc16, ..Default::default() };
+    let mut stream = Vec::new();
+    write_cobs_with(&mut stream, &Value::Integer(1), &options).unwrap();
+    assert_eq!(read_cobs_with(&mut &stream[..], &options).unwrap(), Some(Value::Integer(1)));
+    let mut deframer = Deframer::<8>::new();
+    let frame = stream.iter().find_map(|&b| deframer.push(b).map(|f| f.unwrap().to_vec())).unwrap();
+    assert_eq!(Checksum::Crc16.verify(&frame), Ok(&[0x81][..]));
+
+    stream[1] = 0x82;
+    let err = read_cobs_with(&mut &stream[..], &options).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::Corrupted));
+}
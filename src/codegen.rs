@@ -0,0 +1,284 @@
+//! Rust types generated from a [schema](crate::schema), so that one schema file can be shared between services
+//!
+//! The types implement serde's `Serialize` and `Deserialize`, and are read and written with [`nota::serde`](crate::serde)
+//! (or any other serde format). The generated code needs the `serde` crate with the `derive` feature,
+//! and `serde_bytes` if the schema has blobs.
+//!
+//! | Schema | Rust |
+//! |--------|------|
+//! | `any` | `nota::Value` |
+//! | `null` | `()` |
+//! | `bool` | `bool` |
+//! | `integer` | the smallest integer type that fits `min` and `max`, or `i128` if they're not set |
+//! | `text` | `String` |
+//! | `blob` | `serde_bytes::ByteBuf` |
+//! | `array` | `Vec` |
+//! | `record` | `struct`, with `Option` for optional fields, or `HashMap` if it has only `extra` fields |
+//! | `enum` of texts | `enum` |
+//! | `one_of` null and another type | `Option` |
+//! | other `enum` and `one_of` | `nota::Value` |
+//!
+//! Named types get the name converted to `UpperCamelCase`, and the root type is named `Root`, unless it refers to a named type.
+//! Records and enums nested in other types are named after the path to them, e.g. `UserAddress`.
+//! Limits, like `min` and `max`, aren't checked by the generated code. Use [`Schema::validate`] for that.
+//! Fields of types that contain the record they're in are `Box`ed.
+//!
+//! In `build.rs`:
+//!
+//! ```rust,no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! println!("cargo::rerun-if-changed=schema.nota");
+//! let schema: nota::schema::Schema = std::fs::read_to_string("schema.nota")?.parse()?;
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR")?).join("schema.rs");
+//! std::fs::write(out, nota::codegen::rust(&schema))?;
+//! # Ok(()) }
+//! ```
+//!
+//! and then `include!(concat!(env!("OUT_DIR"), "/schema.rs"));` in the crate.
+//! The CLI can also write the code with `nota codegen schema.nota -o src/schema.rs`.
+use crate::schema::{Record, Schema, Type};
+use crate::Value;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Source code of Rust types for all types in the schema
+#[must_use]
+pub fn rust(schema: &Schema) -> String {
+    let mut gen = Generator { schema, current: None, items: Vec::new() };
+    for (name, ty) in &schema.types {
+        gen.current = Some(name);
+        gen.named(&camel_case(name), ty);
+    }
+    gen.current = None;
+    if !matches!(schema.root, Type::Ref(_)) {
+        gen.named("Root", &schema.root);
+    }
+    let mut out = String::from("// Generated by nota from a schema. Do not edit.\n");
+    for item in gen.items {
+        out.push('\n');
+        out.push_str(&item);
+    }
+    out
+}
+
+struct Generator<'a> {
+    schema: &'a Schema,
+    /// Named type being generated
+    current: Option<&'a String>,
+    items: Vec<String>,
+}
+
+impl Generator<'_> {
+    fn named(&mut self, name: &str, ty: &Type) {
+        let item = match ty {
+            Type::Record(record) => self.record(name, record),
+            Type::Enum(values) if text_values(values).is_some() => enumeration(name, values),
+            ty => format!("pub type {name} = {};\n", self.type_name(ty, name, false)),
+        };
+        self.items.push(item);
+    }
+
+    /// `context` is the name for nested records and enums. `direct` is true if the type is stored inline in a struct.
+    fn type_name(&mut self, ty: &Type, context: &str, direct: bool) -> String {
+        match ty {
+            Type::Any => "nota::Value".into(),
+            Type::Null => "()".into(),
+            Type::Bool => "bool".into(),
+            Type::Integer { min, max } => integer_type(*min, *max).into(),
+            Type::Text { .. } => "String".into(),
+            #[cfg(feature = "blobs")]
+            Type::Blob { .. } => "serde_bytes::ByteBuf".into(),
+            Type::Array { items, .. } => format!("Vec<{}>", self.type_name(items, &format!("{context}Item"), false)),
+            Type::Record(Record { fields, extra: Some(extra), .. }) if fields.is_empty() => {
+                format!("std::collections::HashMap<String, {}>", self.type_name(extra, &format!("{context}Value"), false))
+            },
+            Type::Record(_) => {
+                self.named(context, ty);
+                context.into()
+            },
+            Type::Enum(values) if text_values(values).is_some() => {
+                self.named(context, ty);
+                context.into()
+            },
+            Type::OneOf(types) if types.len() == 2 && types.contains(&Type::Null) => {
+                let other = types.iter().find(|&ty| *ty != Type::Null).unwrap_or(&Type::Null);
+                format!("Option<{}>", self.type_name(other, context, direct))
+            },
+            Type::Ref(name) if direct && self.current.is_some_and(|current| contains(self.schema, ty, current, &mut HashSet::new())) => {
+                format!("Box<{}>", camel_case(name))
+            },
+            Type::Ref(name) => camel_case(name),
+            _ => "nota::Value".into(),
+        }
+    }
+
+    fn record(&mut self, name: &str, record: &Record) -> String {
+        let mut out = String::new();
+        doc_comment(&mut out, "", record.doc.as_deref());
+        out.push_str("#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n");
+        if record.extra.is_none() {
+            out.push_str("#[serde(deny_unknown_fields)]\n");
+        }
+        let _ = writeln!(out, "pub struct {name} {{");
+        for field in &record.fields {
+            let ident = snake_case(&field.name);
+            let ty = self.type_name(&field.ty, &format!("{name}{}", camel_case(&field.name)), true);
+            doc_comment(&mut out, "    ", field.doc.as_deref());
+            if ident.trim_start_matches("r#") != field.name {
+                let _ = writeln!(out, "    #[serde(rename = {:?})]", field.name);
+            }
+            if field.optional && !ty.starts_with("Option<") {
+                let _ = writeln!(out, "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub {ident}: Option<{ty}>,");
+            } else {
+                if field.optional {
+                    out.push_str("    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n");
+                }
+                let _ = writeln!(out, "    pub {ident}: {ty},");
+            }
+        }
+        if let Some(extra) = &record.extra {
+            let ty = self.type_name(extra, &format!("{name}Extra"), false);
+            let _ = writeln!(out, "    /// Fields not listed above\n    #[serde(flatten)]\n    pub extra: std::collections::HashMap<String, {ty}>,");
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn enumeration(name: &str, values: &[Value]) -> String {
+    let mut out = String::from("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]\n");
+    let _ = writeln!(out, "pub enum {name} {{");
+    for value in text_values(values).into_iter().flatten() {
+        let variant = camel_case(value);
+        if variant != value {
+            let _ = writeln!(out, "    #[serde(rename = {value:?})]");
+        }
+        let _ = writeln!(out, "    {variant},");
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// The type, stored inline, contains the named type
+fn contains<'a>(schema: &'a Schema, ty: &'a Type, name: &str, seen: &mut HashSet<&'a str>) -> bool {
+    match ty {
+        Type::Ref(other) if other == name => true,
+        Type::Ref(other) => seen.insert(other) && schema.types.get(other).is_some_and(|ty| contains(schema, ty, name, seen)),
+        Type::Record(record) => record.fields.iter().any(|f| contains(schema, &f.ty, name, seen)),
+        Type::OneOf(types) if types.len() == 2 && types.contains(&Type::Null) => types.iter().any(|ty| contains(schema, ty, name, seen)),
+        _ => false,
+    }
+}
+
+/// `Some` if all values are texts
+fn text_values(values: &[Value]) -> Option<Vec<&str>> {
+    values.iter().map(|v| match v {
+        Value::Text(text) => Some(text.as_str()),
+        _ => None,
+    }).collect()
+}
+
+fn doc_comment(out: &mut String, indent: &str, doc: Option<&str>) {
+    for line in doc.into_iter().flat_map(str::lines) {
+        let _ = writeln!(out, "{indent}/// {line}");
+    }
+}
+
+fn integer_type(min: Option<i128>, max: Option<i128>) -> &'static str {
+    let (Some(min), Some(max)) = (min, max) else { return "i128" };
+    let fits = |lo: i128, hi: i128| lo <= min && max <= hi;
+    if min >= 0 {
+        [("u8", u8::MAX.into()), ("u16", u16::MAX.into()), ("u32", u32::MAX.into()), ("u64", u64::MAX.into())]
+            .into_iter().find(|&(_, hi)| fits(0, hi)).map_or("u128", |(ty, _)| ty)
+    } else {
+        [("i8", i8::MIN.into(), i8::MAX.into()), ("i16", i16::MIN.into(), i16::MAX.into()), ("i32", i32::MIN.into(), i32::MAX.into()), ("i64", i64::MIN.into(), i64::MAX.into())]
+            .into_iter().find(|&(_, lo, hi)| fits(lo, hi)).map_or("i128", |(ty, _, _)| ty)
+    }
+}
+
+/// `user_id` and `user-id` become `UserId`
+fn camel_case(name: &str) -> String {
+    let mut out: String = name.split(|c: char| !c.is_alphanumeric()).flat_map(|word| {
+        let mut chars = word.chars();
+        chars.next().into_iter().flat_map(char::to_uppercase).chain(chars)
+    }).collect();
+    if !out.starts_with(char::is_alphabetic) {
+        out.insert(0, 'T');
+    }
+    out
+}
+
+/// `userId` and `user-id` become `user_id`
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_uppercase() && prev_lower {
+            out.push('_');
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        if c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        } else {
+            out.push('_');
+        }
+    }
+    if !out.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        out.insert(0, '_');
+    }
+    match out.as_str() {
+        "self" | "super" | "crate" => out + "_",
+        "as" | "async" | "await" | "break" | "const" | "continue" | "dyn" | "else" | "enum" | "extern" | "false" | "fn" | "for" | "if"
+        | "impl" | "in" | "let" | "loop" | "match" | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static" | "struct" | "trait"
+        | "true" | "type" | "unsafe" | "use" | "where" | "while" | "abstract" | "become" | "box" | "do" | "final" | "gen" | "macro"
+        | "override" | "priv" | "try" | "typeof" | "unsized" | "virtual" | "yield" => format!("r#{out}"),
+        _ => out,
+    }
+}
+
+#[test]
+fn generates_structs() {
+    let schema: Schema = r#"{"root": {"type": "array", "items": "user"}, "types": {
+        "user": {"type": "record", "doc": "An account", "fields": {
+            "userId": {"type": "integer", "min": 0, "max": 65535},
+            "type": {"type": "enum", "values": ["admin", "read-only"], "doc": "What they can do"},
+            "address": {"type": "record", "fields": {"city": "text"}, "extra": "text"},
+            "manager": {"type": "one_of", "types": ["null", "user"], "optional": true},
+            "tags": {"type": "record", "extra": {"type": "array", "items": "user"}}
+        }}
+    }}"#.parse().unwrap();
+    assert_eq!(rust(&schema), r#"// Generated by nota from a schema. Do not edit.
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UserAddress {
+    pub city: String,
+    /// Fields not listed above
+    #[serde(flatten)]
+    pub extra: std::collections::HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum UserType {
+    #[serde(rename = "admin")]
+    Admin,
+    #[serde(rename = "read-only")]
+    ReadOnly,
+}
+
+/// An account
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct User {
+    pub address: UserAddress,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manager: Option<Box<User>>,
+    pub tags: std::collections::HashMap<String, Vec<User>>,
+    /// What they can do
+    pub r#type: UserType,
+    #[serde(rename = "userId")]
+    pub user_id: u16,
+}
+
+pub type Root = Vec<User>;
+"#);
+}
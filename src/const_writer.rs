@@ -0,0 +1,148 @@
+/// Serializes simple values at compile time, into an array of exactly `N` bytes.
+///
+/// Supports constants, integers, ASCII text, and headers of arrays and records. Mistakes, such as a wrong `N`
+/// or non-ASCII text, are compilation errors when used in a `const` or `static`.
+///
+/// ```rust
+/// static HELLO: [u8; 12] = nota::ConstWriter::new()
+///     .record(2)
+///     .key("temp").integer(-12)
+///     .key("ok").bool(true)
+///     .finish();
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ConstWriter<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> ConstWriter<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { buf: [0; N], len: 0 }
+    }
+
+    /// Number of bytes written so far
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    const fn byte(mut self, byte: u8) -> Self {
+        assert!(self.len < N, "ConstWriter's size is too small for the message");
+        self.buf[self.len] = byte;
+        self.len += 1;
+        self
+    }
+
+    #[must_use]
+    pub const fn null(self) -> Self {
+        self.byte(0b1100_0010)
+    }
+
+    #[must_use]
+    pub const fn bool(self, value: bool) -> Self {
+        self.byte(0b1100_0000 | value as u8)
+    }
+
+    #[must_use]
+    pub const fn integer(self, value: i128) -> Self {
+        let sign_bit = if value < 0 { 0b1000 } else { 0 };
+        self.preamble(0b1000_0000 | sign_bit, 3, value.unsigned_abs())
+    }
+
+    /// # Panics
+    ///
+    /// If the text isn't ASCII
+    #[must_use]
+    pub const fn text(self, value: &str) -> Self {
+        assert!(value.is_ascii(), "ConstWriter only supports ASCII text");
+        let bytes = value.as_bytes();
+        let mut this = self.preamble(0b0010_0000, 4, bytes.len() as u128);
+        let mut i = 0;
+        while i < bytes.len() {
+            this = this.byte(bytes[i]);
+            i += 1;
+        }
+        this
+    }
+
+    /// Same as [`text`](Self::text)
+    #[must_use]
+    pub const fn key(self, key: &str) -> Self {
+        self.text(key)
+    }
+
+    /// Header of an array. Write exactly `len` values after it.
+    #[must_use]
+    pub const fn array(self, len: usize) -> Self {
+        self.preamble(0b0100_0000, 4, len as u128)
+    }
+
+    /// Header of a record. Write exactly `len` pairs of [`key`](Self::key) and a value after it.
+    #[must_use]
+    pub const fn record(self, len: usize) -> Self {
+        self.preamble(0b0110_0000, 4, len as u128)
+    }
+
+    /// First byte has `first_bits` of the value after the continuation bit, then 7 bits per byte
+    const fn preamble(self, header: u8, first_bits: u32, value: u128) -> Self {
+        let minimum_bit_len = 128 - value.leading_zeros();
+        let mut bit_len = first_bits + minimum_bit_len.saturating_sub(first_bits).div_ceil(7) * 7;
+        bit_len -= first_bits;
+        let first = (value >> bit_len) as u8 & ((1 << first_bits) - 1);
+        let mut this = self.byte(header | first | if bit_len > 0 { 0b0001_0000 } else { 0 });
+        while bit_len > 0 {
+            bit_len -= 7;
+            let c = if bit_len > 0 { 0b1000_0000 } else { 0 };
+            this = this.byte((value >> bit_len) as u8 & 0b111_1111 | c);
+        }
+        this
+    }
+
+    /// # Panics
+    ///
+    /// If fewer than `N` bytes have been written
+    #[must_use]
+    pub const fn finish(self) -> [u8; N] {
+        assert!(self.len == N, "ConstWriter's size is larger than the message");
+        self.buf
+    }
+}
+
+impl<const N: usize> Default for ConstWriter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn same_as_value() {
+    use crate::Value;
+
+    const MESSAGE: [u8; 20] = ConstWriter::new()
+        .record(2)
+        .key("id").text("abc")
+        .key("list").array(3).integer(-1234567).null().bool(false)
+        .finish();
+    let value = Value::Record(Box::new([
+        ("list".to_string(), Value::Array(vec![Value::Integer(-1234567), Value::Null, Value::Bool(false)])),
+        ("id".to_string(), Value::Text("abc".into())),
+    ].into_iter().collect()));
+    let mut expected = Vec::new();
+    value.serialize_with(&mut expected, &crate::SerializeOptions { canonical: true, ..Default::default() });
+    assert_eq!(MESSAGE[..], expected);
+
+    for n in [0, 7, 8, -8, 1000, -1000, i128::MAX, i128::MIN + 1, i128::MIN] {
+        let mut expected = Vec::new();
+        Value::Integer(n).serialize_into(&mut expected);
+        let w = ConstWriter::<20>::new().integer(n);
+        assert_eq!(w.buf[..w.len()], expected);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Meanings of constants other than `false`, `true`, and `null`, for [`ParseOptions::constants`](crate::ParseOptions::constants).
+///
+/// Nota has room for 32 constants, and defines only the first 3. When another one is parsed, it's given to this handler,
+/// and it's replaced with the value the handler returns. If the handler returns `None`, the constant is an error,
+/// or a [`Value::Unknown`] with [`ParseOptions::unknown`](crate::ParseOptions::unknown).
+///
+/// ```rust
+/// use nota::{Constants, ParseOptions, Value};
+///
+/// // an "undefined" extension, read as null
+/// let mut options = ParseOptions::default();
+/// options.constants = Some(Constants::from_values([(3, Value::Null)]));
+/// assert_eq!(Value::parse_with(&mut &[0x42, 0xC3, 0xC1][..], &options)?, Value::Array(vec![Value::Null, Value::Bool(true)]));
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct Constants(Arc<dyn Fn(u8) -> Option<Value> + Send + Sync>);
+
+impl Constants {
+    /// The handler gets the constant's number, from 3 to 31
+    pub fn new(handler: impl Fn(u8) -> Option<Value> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+
+    /// Values of specific constants. Others aren't handled.
+    pub fn from_values(values: impl IntoIterator<Item = (u8, Value)>) -> Self {
+        let values: HashMap<u8, Value> = values.into_iter().collect();
+        Self::new(move |constant| values.get(&constant).cloned())
+    }
+
+    #[must_use]
+    pub fn get(&self, constant: u8) -> Option<Value> {
+        (self.0)(constant)
+    }
+}
+
+impl fmt::Debug for Constants {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Constants")
+    }
+}
@@ -0,0 +1,135 @@
+//! CSV import and export of arrays of flat records
+//!
+//! The header row has names of all fields that appear in any record, sorted alphabetically.
+//! Missing fields and [`Value::Null`] are written as empty cells. Fields must be text, integers, booleans, or null.
+//!
+//! CSV cells don't have types. By default, [`read_csv`] reads every cell as text, so CSV read and written back is unchanged,
+//! but integers and booleans written by [`write_csv`] come back as text. [`CsvOptions::infer_types`] turns cells that look
+//! like integers or booleans back into them, and empty cells into nulls. It still writes back the same CSV,
+//! but then texts like `"1"`, `"true"`, or `""` come back as other types.
+use crate::{Error, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{Read, Write};
+use std::io;
+
+/// How cells are interpreted by [`read_csv`]. By default every cell is text.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct CsvOptions {
+    /// Cells that look like decimal integers become [`Value::Integer`]
+    pub infer_integers: bool,
+    /// `true` and `false` become [`Value::Bool`]
+    pub infer_bools: bool,
+    /// Empty cells become [`Value::Null`] instead of empty text
+    pub empty_as_null: bool,
+}
+
+impl CsvOptions {
+    /// Integers, booleans, and nulls for cells that look like them
+    #[must_use]
+    pub fn infer_types() -> Self {
+        Self { infer_integers: true, infer_bools: true, empty_as_null: true }
+    }
+
+    fn cell(&self, cell: &str) -> Value {
+        if cell.is_empty() && self.empty_as_null {
+            return Value::Null;
+        }
+        if self.infer_bools {
+            match cell {
+                "true" => return Value::Bool(true),
+                "false" => return Value::Bool(false),
+                _ => {},
+            }
+        }
+        if self.infer_integers && looks_like_integer(cell) {
+            if let Ok(i) = cell.parse() {
+                return Value::Integer(i);
+            }
+        }
+        Value::Text(cell.into())
+    }
+}
+
+/// Rejects forms like `+1` or `007` that wouldn't be written back the same way
+fn looks_like_integer(cell: &str) -> bool {
+    let digits = cell.strip_prefix('-').unwrap_or(cell);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) && (digits == "0" || !digits.starts_with('0')) && cell != "-0"
+}
+
+/// Writes an array of records as CSV with a header row
+pub fn write_csv<W: Write>(value: &Value, writer: W) -> Result<(), io::Error> {
+    let Value::Array(rows) = value else {
+        return Err(Error::Unrepresentable("value that isn't an array").into());
+    };
+    let records = rows.iter().map(|row| match row {
+        Value::Record(r) => Ok(r),
+        _ => Err(Error::Unrepresentable("array element that isn't a record")),
+    }).collect::<Result<Vec<_>, _>>()?;
+    let header = records.iter().flat_map(|r| r.keys().map(String::as_str)).collect::<BTreeSet<_>>();
+
+    let mut w = csv::Writer::from_writer(writer);
+    w.write_record(&header)?;
+    let mut cells = Vec::with_capacity(header.len());
+    for record in records {
+        cells.clear();
+        for name in &header {
+            cells.push(match record.get(*name) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::Text(s)) => s.clone(),
+                Some(Value::Integer(i)) => i.to_string(),
+                Some(Value::Bool(b)) => b.to_string(),
+                Some(_) => return Err(Error::Unrepresentable("field that isn't text, integer, or bool").into()),
+            });
+        }
+        w.write_record(&cells)?;
+    }
+    w.flush()
+}
+
+/// Reads CSV with a header row into an array of records
+pub fn read_csv<R: Read>(reader: R, options: &CsvOptions) -> Result<Value, io::Error> {
+    let mut r = csv::Reader::from_reader(reader);
+    let header = r.headers()?.clone();
+    let mut rows = Vec::new();
+    for row in r.records() {
+        let row = row?;
+        rows.push(Value::Record(Box::new(header.iter().zip(row.iter())
+            .map(|(name, cell)| (name.to_string(), options.cell(cell)))
+            .collect::<HashMap<_, _>>())));
+    }
+    Ok(Value::Array(rows))
+}
+
+#[test]
+fn roundtrip() {
+    let data = "id,name,ok\n1,\"a, b\",true\n-20,,false\n007,x,maybe\n";
+    let value = read_csv(data.as_bytes(), &CsvOptions::infer_types()).unwrap();
+    let Value::Array(rows) = &value else { panic!() };
+    let Value::Record(r) = &rows[1] else { panic!() };
+    assert_eq!(r["id"], Value::Integer(-20));
+    assert_eq!(r["name"], Value::Null);
+    assert_eq!(r["ok"], Value::Bool(false));
+    let Value::Record(r) = &rows[2] else { panic!() };
+    assert_eq!(r["id"], Value::Text("007".into()));
+
+    let mut out = Vec::new();
+    write_csv(&value, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), data);
+
+    let text = read_csv(data.as_bytes(), &CsvOptions::default()).unwrap();
+    let Value::Array(rows) = &text else { panic!() };
+    let Value::Record(r) = &rows[1] else { panic!() };
+    assert_eq!(r["id"], Value::Text("-20".into()));
+    assert_eq!(r["name"], Value::Text(String::new()));
+    let mut out = Vec::new();
+    write_csv(&text, &mut out).unwrap();
+    assert_eq!(read_csv(&out[..], &CsvOptions::default()).unwrap(), text);
+
+    // inferred types are lossy for texts that look like other types
+    let texts = Value::Array(vec![Value::Record(Box::new([("a".to_string(), Value::Text("1".into()))].into_iter().collect()))]);
+    let mut out = Vec::new();
+    write_csv(&texts, &mut out).unwrap();
+    assert_eq!(read_csv(&out[..], &CsvOptions::default()).unwrap(), texts);
+    assert_ne!(read_csv(&out[..], &CsvOptions::infer_types()).unwrap(), texts);
+}
@@ -0,0 +1,475 @@
+use crate::{ascii_prefix, kim_char, kim_group, varint, ParseOptions, Value};
+#[cfg(feature = "blobs")]
+use bitvec::vec::BitVec;
+#[cfg(feature = "records")]
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io;
+
+/// Incremental parser that keeps its state between calls.
+///
+/// Unlike [`Value::parse_from`], it doesn't need the whole message to be available at once,
+/// so it can be fed from nonblocking sockets. When the data runs out (or the reader returns `WouldBlock`),
+/// call it again with more data, and it will continue where it left off.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    stack: Vec<Frame>,
+    state: State,
+    options: ParseOptions,
+    /// Texts that back-references point to
+    strings: Vec<String>,
+    /// Bytes of the current top-level value read so far
+    consumed: u64,
+    /// The last push has stopped because of `max_values_per_push`
+    paused: bool,
+}
+
+#[derive(Debug)]
+enum Frame {
+    Array { items: Vec<Value>, remaining: usize },
+    #[cfg(feature = "records")]
+    Record { map: HashMap<String, Value>, remaining: usize, key: Option<String> },
+}
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Preamble,
+    Len { preamble: u8, len: usize },
+    Integer { negative: bool, val: u128 },
+    #[cfg(feature = "blobs")]
+    Blob { bits: usize, bytes: Vec<u8> },
+    /// `ch` is the unfinished char, and the offset of its first byte
+    Text { remaining: usize, out: String, ch: Option<(u32, u64)> },
+    /// The number after a reserved tag, see [`ParseOptions::unknown`]
+    Unknown { len: usize, raw_bytes: Vec<u8> },
+}
+
+impl Decoder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self { options, ..Self::default() }
+    }
+
+    /// `true` if some bytes of an unfinished value have been consumed
+    #[must_use]
+    pub fn in_progress(&self) -> bool {
+        !self.stack.is_empty() || !matches!(self.state, State::Preamble)
+    }
+
+    /// `true` if the last [`push`](Self::push) has stopped before the end of the data,
+    /// because it has reached [`ParseOptions::max_values_per_push`]. Push the rest of the data to continue.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Forgets any partially-decoded value
+    pub fn reset(&mut self) {
+        self.stack.clear();
+        self.state = State::Preamble;
+        self.strings.clear();
+        self.consumed = 0;
+    }
+
+    /// Consumes bytes from `data` until a complete value is decoded, or the data runs out.
+    ///
+    /// Returns number of bytes consumed. Bytes after the end of the value are not consumed.
+    /// After an error the decoder is reset.
+    ///
+    /// With [`ParseOptions::max_values_per_push`] it may also stop early, and then [`is_paused`](Self::is_paused) is `true`.
+    pub fn push(&mut self, data: &[u8]) -> Result<(usize, Option<Value>), io::Error> {
+        let mut rest = data;
+        let mut values = 0;
+        self.paused = false;
+        while !rest.is_empty() {
+            if matches!(self.state, State::Preamble) {
+                if self.options.max_values_per_push.is_some_and(|max| values >= max.max(1)) {
+                    self.paused = true;
+                    return Ok((data.len() - rest.len(), None));
+                }
+                values += 1;
+            }
+            let before = rest.len();
+            let res = self.step(&mut rest);
+            self.consumed += (before - rest.len()) as u64;
+            match res.and_then(|value| self.options.check_size(self.consumed).map(|()| value)) {
+                Ok(Some(value)) => {
+                    self.consumed = 0;
+                    return Ok((data.len() - rest.len(), Some(value)));
+                },
+                Ok(None) => {},
+                Err(e) => {
+                    self.reset();
+                    return Err(e);
+                },
+            }
+        }
+        Ok((data.len(), None))
+    }
+
+    /// Reads exactly one value, without consuming any bytes after it.
+    ///
+    /// Errors from the reader, such as `WouldBlock` and `Interrupted`, are returned as-is,
+    /// and the call can be retried later without losing any data.
+    /// End of input in the middle of a value is reported as `UnexpectedEof`, and also can be retried.
+    pub fn read_from<R: BufRead>(&mut self, reader: &mut R) -> Result<Value, io::Error> {
+        loop {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let (used, value) = self.push(buf)?;
+            reader.consume(used);
+            if let Some(value) = value {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn step(&mut self, data: &mut &[u8]) -> Result<Option<Value>, io::Error> {
+        let step_len = data.len();
+        let value = match &mut self.state {
+            State::Preamble => {
+                self.options.check_cancelled()?;
+                self.options.check_depth(self.stack.len() + 1)?;
+                self.options.check_size(self.consumed)?;
+                let preamble = next_byte(data);
+                match preamble & 0b1110_0000 {
+                    0b0000_0000 | 0b0010_0000 | 0b0100_0000 | 0b0110_0000 => {
+                        let len = preamble as usize & 0b000_1111;
+                        if preamble & 0b0001_0000 != 0 {
+                            self.state = State::Len { preamble, len };
+                            return Ok(None);
+                        }
+                        self.start(preamble, len)?
+                    },
+                    0b1000_0000 => {
+                        let negative = preamble & 0b000_1000 != 0;
+                        let val = (preamble & 0b000_0111) as u128;
+                        if preamble & 0b0001_0000 != 0 {
+                            self.state = State::Integer { negative, val };
+                            return Ok(None);
+                        }
+                        self.options.check_minimal(!negative || val != 0)?;
+                        Some(Value::Integer(varint::signed(negative, val)?))
+                    },
+                    0b1110_0000 if self.options.backrefs => {
+                        let index = preamble as usize & 0b000_1111;
+                        if preamble & 0b0001_0000 != 0 {
+                            self.state = State::Len { preamble, len: index };
+                            return Ok(None);
+                        }
+                        self.start(preamble, index)?
+                    },
+                    0b1110_0000 if self.options.unknown => {
+                        if preamble & 0b0001_0000 != 0 {
+                            self.state = State::Unknown { len: usize::from(preamble & 0b000_1111), raw_bytes: vec![preamble] };
+                            return Ok(None);
+                        }
+                        Some(Value::Unknown { tag: 0b111, raw_bytes: Box::new([preamble]) })
+                    },
+                    0b1010_0000 => return Err(io::ErrorKind::Unsupported.into()),
+                    0b1100_0000 => match preamble & 0b0001_1111 {
+                        0 => Some(Value::Bool(false)),
+                        1 => Some(Value::Bool(true)),
+                        2 => Some(Value::Null),
+                        _ => Some(self.options.constant(preamble)?),
+                    },
+                    _ => return Err(io::ErrorKind::InvalidData.into()),
+                }
+            },
+            State::Len { preamble, len } => {
+                let next = next_byte(data);
+                *len = varint::push_len(*len, next)?;
+                self.options.check_minimal(*len >= 16)?;
+                if next & 0b1000_0000 != 0 {
+                    return Ok(None);
+                }
+                let (preamble, len) = (*preamble, *len);
+                self.start(preamble, len)?
+            },
+            State::Unknown { len, raw_bytes } => {
+                let next = next_byte(data);
+                // the number is skipped, but it must fit like a length
+                *len = varint::push_len(*len, next)?;
+                raw_bytes.push(next);
+                if next & 0b1000_0000 != 0 {
+                    return Ok(None);
+                }
+                Some(Value::Unknown { tag: 0b111, raw_bytes: std::mem::take(raw_bytes).into() })
+            },
+            State::Integer { negative, val } => {
+                let next = next_byte(data);
+                *val = varint::push_int(*val, next)?;
+                self.options.check_minimal(*val >= 8)?;
+                if next & 0b1000_0000 != 0 {
+                    return Ok(None);
+                }
+                self.options.check_minimal(!*negative || *val != 0)?;
+                Some(Value::Integer(varint::signed(*negative, *val)?))
+            },
+            #[cfg(feature = "blobs")]
+            State::Blob { bits, bytes } => {
+                let missing = bits.div_ceil(8) - bytes.len();
+                let (chunk, rest) = data.split_at(missing.min(data.len()));
+                bytes.extend_from_slice(chunk);
+                *data = rest;
+                if chunk.len() < missing {
+                    return Ok(None);
+                }
+                let bits = *bits;
+                self.options.check_padding(bytes, bits)?;
+                let mut out = BitVec::from_vec(std::mem::take(bytes));
+                if bits & 7 != 0 {
+                    out.truncate(bits);
+                }
+                Some(Value::Blob(out))
+            },
+            State::Text { remaining, out, ch } => {
+                if ch.is_none() {
+                    // all but the last char, which completes the value below
+                    let ascii = ascii_prefix(data, *remaining - 1);
+                    out.push_str(ascii);
+                    *remaining -= ascii.len();
+                    *data = &data[ascii.len()..];
+                    if data.is_empty() {
+                        return Ok(None);
+                    }
+                }
+                let offset = self.consumed + (step_len - data.len()) as u64;
+                let next = next_byte(data);
+                let (code_point, start) = ch.take().unwrap_or((0, offset));
+                self.options.check_minimal(code_point != 0 || next != 0b1000_0000)?;
+                let code_point = kim_group(code_point, next, start)?;
+                if next & 0b1000_0000 != 0 {
+                    *ch = Some((code_point, start));
+                    return Ok(None);
+                }
+                out.push(kim_char(code_point, start)?);
+                *remaining -= 1;
+                if *remaining > 0 {
+                    return Ok(None);
+                }
+                let text = std::mem::take(out);
+                if self.options.backrefs {
+                    self.strings.push(text.clone());
+                }
+                Some(Value::Text(text))
+            },
+        };
+        match value {
+            Some(value) => {
+                self.state = State::Preamble;
+                self.complete(value)
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Begins a length-prefixed value. Returns it if it's already complete.
+    fn start(&mut self, preamble: u8, len: usize) -> Result<Option<Value>, io::Error> {
+        if preamble & 0b1110_0000 == 0b1110_0000 {
+            let text = match self.options.backref(len) {
+                Ok(text) => text.to_owned(),
+                Err(index) => self.strings.get(index).ok_or(io::ErrorKind::InvalidData)?.clone(),
+            };
+            return Ok(Some(Value::Text(text)));
+        }
+        self.options.check_len(len)?;
+        self.state = State::Preamble;
+        // blobs take len / 8 bytes, and chars at least a byte each
+        match preamble & 0b1110_0000 {
+            0b0000_0000 => self.options.check_size(self.consumed.saturating_add(len.div_ceil(8) as u64))?,
+            0b0010_0000 => self.options.check_size(self.consumed.saturating_add(len as u64))?,
+            _ => {},
+        }
+        Ok(match preamble & 0b1110_0000 {
+            #[cfg(feature = "blobs")]
+            0b0000_0000 if len > 0 => {
+                self.state = State::Blob { bits: len, bytes: Vec::with_capacity(len.div_ceil(8).min(1 << 20)) };
+                None
+            },
+            #[cfg(feature = "blobs")]
+            0b0000_0000 => Some(Value::Blob(BitVec::new())),
+            #[cfg(not(feature = "blobs"))]
+            0b0000_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            0b0010_0000 if len > 0 => {
+                self.state = State::Text { remaining: len, out: String::with_capacity(len.min(1 << 20)), ch: None };
+                None
+            },
+            0b0010_0000 => {
+                if self.options.backrefs {
+                    self.strings.push(String::new());
+                }
+                Some(Value::Text(String::new()))
+            },
+            0b0100_0000 if len > 0 => {
+                self.stack.push(Frame::Array { items: Vec::with_capacity(len.min(1 << 18)), remaining: len });
+                None
+            },
+            0b0100_0000 => Some(Value::Array(Vec::new())),
+            #[cfg(feature = "records")]
+            _ if len > 0 => {
+                self.stack.push(Frame::Record { map: HashMap::with_capacity(len.min(1 << 16)), remaining: len, key: None });
+                None
+            },
+            #[cfg(feature = "records")]
+            _ => Some(Value::Record(Box::default())),
+            #[cfg(not(feature = "records"))]
+            _ => return Err(io::ErrorKind::Unsupported.into()),
+        })
+    }
+
+    /// Adds a finished value to its parent container. Returns the top-level value if it's done.
+    fn complete(&mut self, mut value: Value) -> Result<Option<Value>, io::Error> {
+        loop {
+            match self.stack.last_mut() {
+                None => {
+                    self.strings.clear();
+                    return Ok(Some(value));
+                },
+                Some(Frame::Array { items, remaining }) => {
+                    items.push(value);
+                    *remaining -= 1;
+                    if *remaining > 0 {
+                        return Ok(None);
+                    }
+                },
+                #[cfg(feature = "records")]
+                Some(Frame::Record { map, remaining, key }) => {
+                    let Some(k) = key.take() else {
+                        let Value::Text(k) = value else {
+                            return Err(io::ErrorKind::InvalidData.into());
+                        };
+                        *key = Some(self.options.field_name(k));
+                        return Ok(None);
+                    };
+                    map.insert(k, value);
+                    *remaining -= 1;
+                    if *remaining > 0 {
+                        return Ok(None);
+                    }
+                },
+            }
+            value = match self.stack.pop() {
+                Some(Frame::Array { items, .. }) => Value::Array(items),
+                #[cfg(feature = "records")]
+                Some(Frame::Record { map, .. }) => Value::Record(Box::new(map)),
+                None => unreachable!(),
+            };
+        }
+    }
+}
+
+fn next_byte(data: &mut &[u8]) -> u8 {
+    let (&first, rest) = data.split_first().expect("checked by the caller");
+    *data = rest;
+    first
+}
+
+#[cfg(all(test, feature = "blobs", feature = "records"))]
+fn sample() -> (Value, Vec<u8>) {
+    let mut record = HashMap::new();
+    record.insert("Hello".into(), Value::Integer(123456789));
+    record.insert("☃".into(), Value::Blob(vec![1u8, 2, 3].try_into().unwrap()));
+    let value = Value::Array(vec![Value::Record(Box::new(record)), Value::Text("cat".into()), Value::Array(vec![]), Value::Bool(true)]);
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+    (value, bytes)
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn byte_at_a_time() {
+    let (value, bytes) = sample();
+    let mut dec = Decoder::new();
+    for (i, b) in bytes.iter().enumerate() {
+        let (used, res) = dec.push(std::slice::from_ref(b)).unwrap();
+        assert_eq!(used, 1);
+        if i + 1 < bytes.len() {
+            assert!(res.is_none());
+            assert!(dec.in_progress());
+        } else {
+            assert_eq!(res.unwrap(), value);
+            assert!(!dec.in_progress());
+        }
+    }
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn would_block() {
+    struct Trickle<'a> { data: &'a [u8], blocked: bool }
+    impl io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.blocked = !self.blocked;
+            if self.blocked {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let n = buf.len().min(self.data.len()).min(3);
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    let (value, mut bytes) = sample();
+    bytes.push(0xC0);
+    let mut reader = io::BufReader::with_capacity(2, Trickle { data: &bytes, blocked: false });
+    let mut dec = Decoder::new();
+    let mut retries = 0;
+    let res = loop {
+        match dec.read_from(&mut reader) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => retries += 1,
+            res => break res.unwrap(),
+        }
+    };
+    assert!(retries > 1);
+    assert_eq!(res, value);
+    assert_eq!(dec.read_from(&mut reader).unwrap(), Value::Bool(false));
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn limits() {
+    use crate::Error;
+
+    let (_, bytes) = sample();
+    let mut dec = Decoder::with_options(ParseOptions { max_depth: Some(2), ..Default::default() });
+    let err = dec.push(&bytes).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::TooDeep { max: 2 }));
+
+    let mut dec = Decoder::with_options(ParseOptions { max_len: Some(6), ..Default::default() });
+    let err = dec.push(&bytes).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::TooLong { len: 24, max: 6 }));
+}
+
+#[test]
+fn paused() {
+    let value = Value::Array((0..100).map(|n| Value::Array(vec![Value::Integer(n), Value::Text(n.to_string())])).collect());
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+
+    let options = ParseOptions { max_values_per_push: Some(10), ..Default::default() };
+    let mut decoder = Decoder::with_options(options);
+    let mut rest = &bytes[..];
+    let mut pushes = 0;
+    let decoded = loop {
+        let (used, decoded) = decoder.push(rest).unwrap();
+        rest = &rest[used..];
+        pushes += 1;
+        if let Some(decoded) = decoded {
+            break decoded;
+        }
+        assert!(decoder.is_paused());
+    };
+    assert_eq!(decoded, value);
+    assert!(rest.is_empty() && !decoder.is_paused());
+    assert_eq!(pushes, 31);
+}
@@ -0,0 +1,99 @@
+//! [`defmt::Format`] for values, [pull parser](crate::pull) events, and errors, for logging with defmt
+//!
+//! Values are written in a notation similar to the [diagnostic notation](crate::diag), except that record fields aren't sorted,
+//! and blobs are written as their bit length and bytes.
+//!
+//! defmt needs its linker script and a global logger, so this feature only links in builds set up for it.
+//! The crate requires `std`, so such a target must have it too.
+use crate::pull::{Bits, Event, KimStr, PullError};
+use crate::{BufferTooSmall, SliceWriteError, Value};
+use defmt::{write, Format, Formatter};
+
+impl Format for Value {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            #[cfg(feature = "blobs")]
+            Self::Blob(bits) => write!(f, "blob({=usize}, {=[u8]:02x})", bits.len(), bits.as_raw_slice()),
+            Self::Text(text) => write!(f, "{=str:?}", text.as_str()),
+            Self::Array(items) => {
+                write!(f, "[");
+                for (n, item) in items.iter().enumerate() {
+                    if n > 0 {
+                        write!(f, ", ");
+                    }
+                    item.format(f);
+                }
+                write!(f, "]");
+            },
+            #[cfg(feature = "records")]
+            Self::Record(fields) => {
+                write!(f, "{{");
+                for (n, (key, value)) in fields.iter().enumerate() {
+                    if n > 0 {
+                        write!(f, ", ");
+                    }
+                    write!(f, "{=str:?}: {}", key.as_str(), value);
+                }
+                write!(f, "}}");
+            },
+            Self::Integer(n) => write!(f, "{=i128}", n),
+            #[cfg(feature = "dec64")]
+            #[allow(deprecated)]
+            Self::DecimalFloat(d) => write!(f, "{=i64}e{=i32}", d.coefficient, d.exponent),
+            Self::Bool(b) => write!(f, "{=bool}", b),
+            Self::Null => write!(f, "null"),
+            Self::Unknown { raw_bytes, .. } => write!(f, "unknown({=[u8]:02x})", &raw_bytes[..]),
+        }
+    }
+}
+
+impl Format for KimStr<'_> {
+    fn format(&self, f: Formatter<'_>) {
+        write!(f, "\"");
+        for c in self.chars() {
+            write!(f, "{=char}", c);
+        }
+        write!(f, "\"");
+    }
+}
+
+impl Format for Bits<'_> {
+    fn format(&self, f: Formatter<'_>) {
+        write!(f, "blob({=usize}, {=[u8]:02x})", self.bit_len, self.bytes);
+    }
+}
+
+impl Format for Event<'_> {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Self::Blob(bits) => bits.format(f),
+            Self::Text(text) => text.format(f),
+            Self::Array(len) => write!(f, "array({=usize})", len),
+            Self::Record(len) => write!(f, "record({=usize})", len),
+            Self::Integer(n) => write!(f, "{=i128}", n),
+            Self::Bool(b) => write!(f, "{=bool}", b),
+            Self::Null => write!(f, "null"),
+        }
+    }
+}
+
+impl Format for PullError {
+    fn format(&self, f: Formatter<'_>) {
+        write!(f, "{=str}", self.reason());
+    }
+}
+
+impl Format for BufferTooSmall {
+    fn format(&self, f: Formatter<'_>) {
+        write!(f, "buffer is too small for the serialized value");
+    }
+}
+
+impl Format for SliceWriteError {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Self::BufferTooSmall => BufferTooSmall.format(f),
+            Self::Unrepresentable(what) => write!(f, "{=str} can't be serialized", what),
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Descriptions of Rust types as [schemas](crate::schema), for tools that work with any document
+//!
+//! Types implement [`Describe`] to say what their values look like: record fields, their types, and their docs.
+//! [`Schema::of`] collects that into a [`Schema`], which can validate documents, [generate code](crate::codegen)
+//! for other services, or be sent as a Nota document itself with [`Schema::to_value`].
+//!
+//! Generic editors, like admin panels and form generators, can find out what's expected anywhere in a document
+//! with [`Schema::type_at`] and [`Schema::field_at`], and create new values with [`Schema::default_value`].
+//!
+//! ```rust
+//! use nota::describe::Describe;
+//! use nota::schema::{Field, Record, Schema, Type};
+//!
+//! struct User {
+//!     name: String,
+//!     age: Option<u8>,
+//! }
+//!
+//! impl Describe for User {
+//!     fn describe(schema: &mut Schema) -> Type {
+//!         schema.define("User", |schema| Type::Record(Record::new([
+//!             Field::new("name", String::describe(schema)).doc("Full name"),
+//!             Field::new("age", u8::describe(schema)).optional(),
+//!         ])))
+//!     }
+//! }
+//!
+//! let schema = Schema::of::<Vec<User>>();
+//! let field = schema.field_at(&"[0].name".parse()?).unwrap();
+//! assert_eq!(field.doc.as_deref(), Some("Full name"));
+//! assert_eq!(schema.type_at(&"[0].age".parse()?), Some(&Type::Integer { min: Some(0), max: Some(255) }));
+//! assert!(schema.validate(&r#"[{"name": "Ann", "age": 300}]"#.parse()?).is_err());
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+use crate::path::{Path, Segment};
+use crate::schema::{Field, Record, Schema, Type};
+use crate::Value;
+#[cfg(feature = "blobs")]
+use bitvec::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A Rust type that can describe its values with a schema [`Type`]
+pub trait Describe {
+    /// Type of this Rust type's values.
+    ///
+    /// Records should be [defined](Schema::define) as named types in the `schema`, so that they're shared, and can contain themselves.
+    fn describe(schema: &mut Schema) -> Type;
+}
+
+impl Schema {
+    /// Schema with `T` as the root type
+    #[must_use]
+    pub fn of<T: Describe + ?Sized>() -> Self {
+        let mut schema = Self::new(Type::Any);
+        schema.root = T::describe(&mut schema);
+        schema
+    }
+
+    /// Adds a named type, if it's not defined yet, and returns a [`Type::Ref`] to it.
+    ///
+    /// While `define` runs, the name already refers to a placeholder, so the type can refer to itself.
+    pub fn define(&mut self, name: &str, define: impl FnOnce(&mut Self) -> Type) -> Type {
+        if !self.types.contains_key(name) {
+            self.types.insert(name.into(), Type::Any);
+            let ty = define(self);
+            self.types.insert(name.into(), ty);
+        }
+        Type::Ref(name.into())
+    }
+
+    /// The type expected at the path in documents of this schema, with refs resolved.
+    ///
+    /// `None` if the path can't exist in a valid document. For [`Type::OneOf`], the first type that has the path is used.
+    #[must_use]
+    pub fn type_at(&self, path: &Path) -> Option<&Type> {
+        path.segments().iter().try_fold(self.resolve(&self.root)?, |ty, segment| self.child(ty, segment))
+    }
+
+    /// Description of the record field the path points to, including its doc.
+    ///
+    /// `None` if the path doesn't end with a key, or the field isn't in the record's `fields`.
+    #[must_use]
+    pub fn field_at(&self, path: &Path) -> Option<&Field> {
+        let (Segment::Key(name), parent) = path.segments().split_last()? else {
+            return None;
+        };
+        self.field_of(self.type_at(&Path::from(parent.to_vec()))?, name)
+    }
+
+    fn field_of<'a>(&'a self, ty: &'a Type, name: &str) -> Option<&'a Field> {
+        match ty {
+            Type::Record(record) => record.field(name),
+            Type::OneOf(types) => types.iter().find_map(|ty| self.field_of(self.resolve(ty)?, name)),
+            _ => None,
+        }
+    }
+
+    fn child<'a>(&'a self, ty: &'a Type, segment: &Segment) -> Option<&'a Type> {
+        let child = match (ty, segment) {
+            (Type::Any, _) => return Some(ty),
+            (Type::Array { items, .. }, Segment::Index(_)) => items,
+            (Type::Record(record), Segment::Key(key)) => record.field(key).map(|f| &f.ty).or(record.extra.as_deref())?,
+            (Type::OneOf(types), segment) => return types.iter().find_map(|ty| self.child(self.resolve(ty)?, segment)),
+            _ => return None,
+        };
+        self.resolve(child)
+    }
+
+    /// The simplest valid value of the type, for adding new items in editors.
+    ///
+    /// It's the smallest number in range, an empty text, array, or blob (even if they're too short), a record with only
+    /// its required fields, the first value of an enum, and null where it's allowed.
+    #[must_use]
+    pub fn default_value(&self, ty: &Type) -> Value {
+        self.default_at_depth(ty, 0)
+    }
+
+    fn default_at_depth(&self, ty: &Type, depth: usize) -> Value {
+        // required fields that contain their own record can't have a finite value
+        if depth > 2 * self.types.len() + 16 {
+            return Value::Null;
+        }
+        match ty {
+            Type::Any | Type::Null => Value::Null,
+            Type::Bool => Value::Bool(false),
+            Type::Integer { min, max } => Value::Integer(0.clamp(min.unwrap_or(i128::MIN), max.unwrap_or(i128::MAX).max(min.unwrap_or(i128::MIN)))),
+            Type::Text { .. } => Value::Text(String::new()),
+            #[cfg(feature = "blobs")]
+            Type::Blob { .. } => Value::Blob(BitVec::new()),
+            Type::Array { .. } => Value::Array(Vec::new()),
+            Type::Record(record) => Value::Record(Box::new(record.fields.iter()
+                .filter(|f| !f.optional)
+                .map(|f| (f.name.clone(), self.default_at_depth(&f.ty, depth + 1)))
+                .collect())),
+            Type::Enum(values) => values.first().cloned().unwrap_or(Value::Null),
+            Type::OneOf(types) if types.contains(&Type::Null) => Value::Null,
+            Type::OneOf(types) => types.first().map_or(Value::Null, |ty| self.default_at_depth(ty, depth + 1)),
+            Type::Ref(name) => self.types.get(name).map_or(Value::Null, |ty| self.default_at_depth(ty, depth + 1)),
+        }
+    }
+}
+
+impl Record {
+    /// Record with exactly these fields
+    #[must_use]
+    pub fn new(fields: impl IntoIterator<Item = Field>) -> Self {
+        let mut fields: Vec<_> = fields.into_iter().collect();
+        fields.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { fields, ..Self::default() }
+    }
+}
+
+impl Field {
+    /// The field can be missing
+    #[must_use]
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    #[must_use]
+    pub fn doc(mut self, doc: impl Into<String>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
+}
+
+impl Describe for () {
+    fn describe(_: &mut Schema) -> Type {
+        Type::Null
+    }
+}
+
+impl Describe for bool {
+    fn describe(_: &mut Schema) -> Type {
+        Type::Bool
+    }
+}
+
+macro_rules! describe_int {
+    ($($int:ty),*) => {$(
+        impl Describe for $int {
+            fn describe(_: &mut Schema) -> Type {
+                Type::Integer { min: Some(<$int>::MIN as i128), max: Some(<$int>::MAX as i128) }
+            }
+        }
+    )*};
+}
+
+describe_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl Describe for i128 {
+    fn describe(_: &mut Schema) -> Type {
+        Type::Integer { min: None, max: None }
+    }
+}
+
+impl Describe for u128 {
+    fn describe(_: &mut Schema) -> Type {
+        Type::Integer { min: Some(0), max: None }
+    }
+}
+
+impl Describe for str {
+    fn describe(_: &mut Schema) -> Type {
+        Type::Text { min_len: None, max_len: None }
+    }
+}
+
+impl Describe for String {
+    fn describe(_: &mut Schema) -> Type {
+        Type::Text { min_len: None, max_len: None }
+    }
+}
+
+impl Describe for char {
+    fn describe(_: &mut Schema) -> Type {
+        Type::Text { min_len: Some(1), max_len: Some(1) }
+    }
+}
+
+impl Describe for Value {
+    fn describe(_: &mut Schema) -> Type {
+        Type::Any
+    }
+}
+
+#[cfg(feature = "blobs")]
+impl Describe for BitVec<u8, Msb0> {
+    fn describe(_: &mut Schema) -> Type {
+        Type::Blob { min_len: None, max_len: None }
+    }
+}
+
+impl<T: Describe> Describe for [T] {
+    fn describe(schema: &mut Schema) -> Type {
+        Type::Array { items: Box::new(T::describe(schema)), min_len: None, max_len: None }
+    }
+}
+
+impl<T: Describe> Describe for Vec<T> {
+    fn describe(schema: &mut Schema) -> Type {
+        <[T]>::describe(schema)
+    }
+}
+
+impl<T: Describe, const N: usize> Describe for [T; N] {
+    fn describe(schema: &mut Schema) -> Type {
+        Type::Array { items: Box::new(T::describe(schema)), min_len: Some(N), max_len: Some(N) }
+    }
+}
+
+impl<T: Describe> Describe for Option<T> {
+    fn describe(schema: &mut Schema) -> Type {
+        Type::OneOf(vec![T::describe(schema), Type::Null])
+    }
+}
+
+impl<T: Describe, S> Describe for HashMap<String, T, S> {
+    fn describe(schema: &mut Schema) -> Type {
+        Type::Record(Record { extra: Some(Box::new(T::describe(schema))), ..Record::default() })
+    }
+}
+
+impl<T: Describe> Describe for BTreeMap<String, T> {
+    fn describe(schema: &mut Schema) -> Type {
+        HashMap::<String, T>::describe(schema)
+    }
+}
+
+macro_rules! describe_pointer {
+    ($($ptr:ident),*) => {$(
+        impl<T: Describe + ?Sized> Describe for $ptr<T> {
+            fn describe(schema: &mut Schema) -> Type {
+                T::describe(schema)
+            }
+        }
+    )*};
+}
+
+describe_pointer!(Box, Rc, Arc);
+
+impl<T: Describe + ?Sized> Describe for &T {
+    fn describe(schema: &mut Schema) -> Type {
+        T::describe(schema)
+    }
+}
+
+#[test]
+fn descriptors() {
+    struct Node {
+        _label: String,
+        _children: Vec<Node>,
+        _meta: HashMap<String, Option<i32>>,
+    }
+
+    impl Describe for Node {
+        fn describe(schema: &mut Schema) -> Type {
+            schema.define("Node", |schema| {
+                let mut record = Record::new([
+                    Field::new("label", String::describe(schema)).doc("Shown in the tree"),
+                    Field::new("children", Vec::<Node>::describe(schema)).optional(),
+                    Field::new("meta", HashMap::<String, Option<i32>>::describe(schema)),
+                    Field::new("kind", Type::Enum(vec![Value::Text("leaf".into())])),
+                ]);
+                record.doc = Some("A node of a tree".into());
+                Type::Record(record)
+            })
+        }
+    }
+
+    let schema = Schema::of::<Box<Node>>();
+    assert_eq!(schema.root, Type::Ref("Node".into()));
+    let path = |p: &str| p.parse::<Path>().unwrap();
+    assert_eq!(schema.field_at(&path("children[3].children[0].label")).and_then(|f| f.doc.as_deref()), Some("Shown in the tree"));
+    assert_eq!(schema.type_at(&path("meta.anything")), Some(&Type::OneOf(vec![Type::Integer { min: Some(i32::MIN.into()), max: Some(i32::MAX.into()) }, Type::Null])));
+    assert_eq!(schema.type_at(&path("meta.x.y")), None);
+    assert_eq!(schema.type_at(&path("[0]")), None);
+    assert!(schema.field_at(&path("meta.x")).is_none());
+
+    let new_node = schema.default_value(&schema.root);
+    assert_eq!(new_node.to_string(), r#"{"kind": "leaf", "label": "", "meta": {}}"#);
+    assert_eq!(schema.validate(&new_node), Ok(()));
+    assert_eq!(schema.default_value(&Type::Integer { min: Some(5), max: None }), Value::Integer(5));
+    assert_eq!(schema.default_value(&Type::Integer { min: None, max: Some(-5) }), Value::Integer(-5));
+
+    let definition = schema.to_value();
+    assert_eq!(Schema::from_value(&definition), Ok(schema.clone()));
+    assert!(crate::codegen::rust(&Schema::from_value(&definition).unwrap()).contains("pub struct Node"));
+    assert_eq!(Schema::of::<[Option<char>; 2]>().to_value().to_string(), r#"{"root": {"items": {"type": "one_of", "types": [{"max_len": 1, "min_len": 1, "type": "text"}, "null"]}, "max_len": 2, "min_len": 2, "type": "array"}}"#);
+}
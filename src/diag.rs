@@ -0,0 +1,291 @@
+//! Diagnostic notation: human-readable text form of values, for debugging and tests
+//!
+//! [`Value`] implements [`Display`](fmt::Display) with it. The alternate form (`{:#}`) is indented.
+//!
+//! ```text
+//! {"blob": x"ff00", "bits": b"101", "list": [1, -2, null], "ok": true, "text": "hi\n"}
+//! ```
+//!
+//! Texts and keys are quoted with Rust's string escapes. Blobs that are a whole number of bytes are written in hex as `x"…"`,
+//! other blobs as bits in `b"…"`. Record fields are sorted by key. [Unknown](Value::Unknown) values are their bytes in hex, as `unknown(x"…")`.
+//!
+//! The text can be parsed back with [`str::parse`], which also accepts any whitespace between tokens.
+use crate::path::unquote;
+use crate::Value;
+#[cfg(feature = "blobs")]
+use bitvec::prelude::*;
+#[cfg(feature = "records")]
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// Why the diagnostic notation couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DiagError {
+    /// Byte offset in the text
+    pub offset: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for DiagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid diagnostic notation at {}: {}", self.offset, self.reason)
+    }
+}
+
+impl std::error::Error for DiagError {}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indent = f.alternate().then_some(0);
+        write_value(f, self, indent)
+    }
+}
+
+fn write_value(f: &mut dyn Write, value: &Value, indent: Option<usize>) -> fmt::Result {
+    match value {
+        #[cfg(feature = "blobs")]
+        Value::Blob(bits) if bits.len() % 8 == 0 => {
+            f.write_str("x\"")?;
+            for byte in bits.as_raw_slice() {
+                write!(f, "{byte:02x}")?;
+            }
+            f.write_char('"')
+        },
+        #[cfg(feature = "blobs")]
+        Value::Blob(bits) => {
+            f.write_str("b\"")?;
+            for bit in bits.iter().by_vals() {
+                f.write_char(if bit { '1' } else { '0' })?;
+            }
+            f.write_char('"')
+        },
+        Value::Text(text) => write!(f, "{text:?}"),
+        Value::Array(items) => write_container(f, '[', ']', items.iter().map(|item| (None, item)), indent),
+        #[cfg(feature = "records")]
+        Value::Record(fields) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_unstable_by_key(|&(k, _)| k);
+            write_container(f, '{', '}', fields.into_iter().map(|(k, v)| (Some(k), v)), indent)
+        },
+        Value::Integer(n) => write!(f, "{n}"),
+        #[cfg(feature = "dec64")]
+        #[allow(deprecated)]
+        Value::DecimalFloat(d) => write!(f, "{}e{}", d.coefficient, d.exponent),
+        Value::Bool(b) => write!(f, "{b}"),
+        Value::Null => f.write_str("null"),
+        Value::Unknown { raw_bytes, .. } => {
+            f.write_str("unknown(x\"")?;
+            for byte in raw_bytes {
+                write!(f, "{byte:02x}")?;
+            }
+            f.write_str("\")")
+        },
+    }
+}
+
+fn write_container<'a>(f: &mut dyn Write, open: char, close: char, items: impl ExactSizeIterator<Item = (Option<&'a String>, &'a Value)>, indent: Option<usize>) -> fmt::Result {
+    f.write_char(open)?;
+    let empty = items.len() == 0;
+    let inner = indent.map(|i| i + 2);
+    for (n, (key, value)) in items.enumerate() {
+        if n > 0 {
+            f.write_char(',')?;
+        }
+        match inner {
+            Some(i) => write!(f, "\n{:i$}", "")?,
+            None if n > 0 => f.write_char(' ')?,
+            None => {},
+        }
+        if let Some(key) = key {
+            write!(f, "{key:?}: ")?;
+        }
+        write_value(f, value, inner)?;
+    }
+    if let (Some(i), false) = (indent, empty) {
+        write!(f, "\n{:i$}", "")?;
+    }
+    f.write_char(close)
+}
+
+impl FromStr for Value {
+    type Err = DiagError;
+
+    fn from_str(s: &str) -> Result<Self, DiagError> {
+        let mut reader = Reader { text: s, rest: s };
+        let value = reader.value()?;
+        reader.skip_whitespace();
+        if !reader.rest.is_empty() {
+            return Err(reader.error("unexpected text after the value"));
+        }
+        Ok(value)
+    }
+}
+
+struct Reader<'a> {
+    text: &'a str,
+    rest: &'a str,
+}
+
+impl Reader<'_> {
+    fn error(&self, reason: &'static str) -> DiagError {
+        DiagError { offset: self.text.len() - self.rest.len(), reason }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if let Some(rest) = self.rest.strip_prefix(token) {
+            self.rest = rest;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn string(&mut self) -> Result<String, DiagError> {
+        let (string, rest) = unquote(self.rest).map_err(|reason| self.error(reason))?;
+        self.rest = rest;
+        Ok(string)
+    }
+
+    /// Comma-separated items until `close`
+    fn items(&mut self, close: &str, mut item: impl FnMut(&mut Self) -> Result<(), DiagError>) -> Result<(), DiagError> {
+        if self.eat(close) {
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            if self.eat(close) {
+                return Ok(());
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected `,`"));
+            }
+        }
+    }
+
+    /// Hex digits after `x"`
+    fn hex(&mut self) -> Result<Vec<u8>, DiagError> {
+        let start = self.error("");
+        let hex = self.string()?;
+        if hex.len() % 2 != 0 {
+            return Err(DiagError { reason: "odd number of hex digits", ..start });
+        }
+        (0..hex.len()).step_by(2)
+            .map(|i| hex.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(DiagError { reason: "invalid hex digit", ..start })
+    }
+
+    fn value(&mut self) -> Result<Value, DiagError> {
+        self.skip_whitespace();
+        for (word, value) in [("null", Value::Null), ("true", Value::Bool(true)), ("false", Value::Bool(false))] {
+            if self.eat(word) {
+                return Ok(value);
+            }
+        }
+        if self.eat("\"") {
+            return self.string().map(Value::Text);
+        }
+        #[cfg(feature = "blobs")]
+        if self.eat("x\"") {
+            return Ok(Value::Blob(BitVec::from_vec(self.hex()?)));
+        }
+        if self.eat("unknown(") {
+            if !self.eat("x\"") {
+                return Err(self.error("expected `x\"`"));
+            }
+            let start = self.error("");
+            let raw_bytes = self.hex()?;
+            let &first = raw_bytes.first().ok_or(DiagError { reason: "expected at least one byte", ..start })?;
+            if !self.eat(")") {
+                return Err(self.error("expected `)`"));
+            }
+            return Ok(Value::Unknown { tag: first >> 5, raw_bytes: raw_bytes.into() });
+        }
+        #[cfg(feature = "blobs")]
+        if self.eat("b\"") {
+            let start = self.error("");
+            let bits = self.string()?.chars().map(|c| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => Err(DiagError { reason: "expected only `0` and `1` bits", ..start.clone() }),
+            }).collect::<Result<BitVec<u8, Msb0>, _>>()?;
+            return Ok(Value::Blob(bits));
+        }
+        if self.eat("[") {
+            let mut items = Vec::new();
+            self.items("]", |r| { items.push(r.value()?); Ok(()) })?;
+            return Ok(Value::Array(items));
+        }
+        #[cfg(feature = "records")]
+        if self.eat("{") {
+            let mut fields = HashMap::new();
+            self.items("}", |r| {
+                if !r.eat("\"") {
+                    return Err(r.error("expected a quoted key"));
+                }
+                let key_error = r.error("duplicate key");
+                let key = r.string()?;
+                if !r.eat(":") {
+                    return Err(r.error("expected `:`"));
+                }
+                if fields.insert(key, r.value()?).is_some() {
+                    return Err(key_error);
+                }
+                Ok(())
+            })?;
+            return Ok(Value::Record(Box::new(fields)));
+        }
+        let end = self.rest.char_indices().skip(1).find(|&(_, c)| !c.is_ascii_digit()).map_or(self.rest.len(), |(i, _)| i);
+        let n = self.rest[..end].parse().map_err(|_| self.error("expected a value"))?;
+        self.rest = &self.rest[end..];
+        if self.rest.starts_with(['e', 'E', '.']) {
+            return Err(self.error("decimal floats aren't supported"));
+        }
+        Ok(Value::Integer(n))
+    }
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn display() {
+
+    let value = Value::Record(Box::new([
+        ("list".to_string(), Value::Array(vec![Value::Integer(1), Value::Integer(-2), Value::Null])),
+        ("text".to_string(), Value::Text("hi\n".into())),
+        ("blob".to_string(), Value::Blob(bitvec![u8, Msb0; 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0])),
+        ("bits".to_string(), Value::Blob(bitvec![u8, Msb0; 1, 0, 1])),
+        ("ok".to_string(), Value::Bool(true)),
+        ("empty".to_string(), Value::Array(vec![])),
+    ].into_iter().collect()));
+    assert_eq!(value.to_string(), r#"{"bits": b"101", "blob": x"ff00", "empty": [], "list": [1, -2, null], "ok": true, "text": "hi\n"}"#);
+    assert_eq!(format!("{:#}", Value::Array(vec![Value::Array(vec![]), Value::Record(Box::new([("a".to_string(), Value::Integer(1))].into_iter().collect()))])),
+        "[\n  [],\n  {\n    \"a\": 1\n  }\n]");
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn parse() {
+    let value = Value::Record(Box::new([
+        ("list".to_string(), Value::Array(vec![Value::Integer(i128::MIN), Value::Null, Value::Text("\"\u{1}é".into())])),
+        ("blobs".to_string(), Value::Array(vec![Value::Blob(bitvec![u8, Msb0; 1, 0, 1]), Value::Blob(BitVec::from_vec(vec![0xab, 1])), Value::Blob(BitVec::new())])),
+        ("empty".to_string(), Value::Record(Box::default())),
+        ("ok".to_string(), Value::Bool(false)),
+    ].into_iter().collect()));
+    assert_eq!(value.to_string().parse::<Value>().unwrap(), value);
+    assert_eq!(format!("{value:#}").parse::<Value>().unwrap(), value);
+
+    assert_eq!(" [ 1 ,-2 ] ".parse::<Value>().unwrap(), Value::Array(vec![Value::Integer(1), Value::Integer(-2)]));
+    assert_eq!("[1 2]".parse::<Value>().unwrap_err().offset, 3);
+    assert_eq!(r#"{"a": 1, "a": 2}"#.parse::<Value>().unwrap_err().reason, "duplicate key");
+    assert_eq!(r#"x"abc""#.parse::<Value>().unwrap_err().offset, 2);
+    assert!("1.5".parse::<Value>().is_err());
+    assert!("nul".parse::<Value>().is_err());
+    assert!("1 2".parse::<Value>().is_err());
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Texts agreed on in advance by the writer and the reader, so that messages can refer to them instead of including them.
+///
+/// Set it in both [`SerializeOptions::dictionary`](crate::SerializeOptions::dictionary) and [`ParseOptions::dictionary`](crate::ParseOptions::dictionary),
+/// together with `backrefs`. The texts are referred to by their position, so both sides need exactly the same list in the same order.
+///
+/// ```rust
+/// use nota::{Dictionary, ParseOptions, SerializeOptions, Value};
+///
+/// let dictionary = Dictionary::new(["temperature", "humidity", "sensor-0042"]);
+/// let value = Value::Array(vec![Value::Text("sensor-0042".into()), Value::Text("humidity".into())]);
+///
+/// let mut options = SerializeOptions::default();
+/// options.backrefs = true;
+/// options.dictionary = Some(dictionary.clone());
+/// let mut message = Vec::new();
+/// value.serialize_with(&mut message, &options);
+/// assert_eq!(message.len(), 3);
+///
+/// let mut options = ParseOptions::default();
+/// options.backrefs = true;
+/// options.dictionary = Some(dictionary);
+/// assert_eq!(Value::parse_with(&mut &message[..], &options)?, value);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct Dictionary(Arc<Inner>);
+
+struct Inner {
+    texts: Vec<String>,
+    index: HashMap<String, usize>,
+}
+
+impl Dictionary {
+    pub fn new(texts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let texts: Vec<String> = texts.into_iter().map(Into::into).collect();
+        let mut index = HashMap::with_capacity(texts.len());
+        for (i, text) in texts.iter().enumerate() {
+            index.entry(text.clone()).or_insert(i);
+        }
+        Self(Arc::new(Inner { texts, index }))
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.texts.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.texts.is_empty()
+    }
+
+    #[must_use]
+    pub fn texts(&self) -> &[String] {
+        &self.0.texts
+    }
+
+    pub(crate) fn find(&self, text: &str) -> Option<usize> {
+        self.0.index.get(text).copied()
+    }
+}
+
+impl fmt::Debug for Dictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Dictionary").field(&self.0.texts).finish()
+    }
+}
+
+#[test]
+fn with_document_texts() {
+    use crate::{Decoder, ParseOptions, SerializeOptions, Value};
+
+    let dictionary = Dictionary::new(["status", "active", "status"]);
+    let value = Value::Array(vec![Value::Text("active".into()), Value::Text("inactive".into()), Value::Text("inactive".into())]);
+    let mut message = Vec::new();
+    value.serialize_with(&mut message, &SerializeOptions { backrefs: true, dictionary: Some(dictionary.clone()), ..Default::default() });
+    assert_eq!(message, [0x43, 0xE1, 0x28, b'i', b'n', b'a', b'c', b't', b'i', b'v', b'e', 0xE3]);
+
+    let options = ParseOptions { backrefs: true, dictionary: Some(dictionary), ..Default::default() };
+    assert_eq!(Value::parse_slice_with(&mut &message[..], &options).unwrap(), value);
+    let mut decoder = Decoder::with_options(options);
+    assert_eq!(decoder.push(&message).unwrap(), (message.len(), Some(value)));
+
+    let options = ParseOptions { backrefs: true, ..Default::default() };
+    assert!(Value::parse_with(&mut &message[..], &options).is_err());
+}
@@ -0,0 +1,248 @@
+//! Content hashes computed while writing
+//!
+//! Wrap the writer in [`HashingWriter`], and every byte written through it is also fed to a [`Digest`],
+//! such as `sha2::Sha256` or `blake3::Hasher` (with its `traits-preview` feature).
+//! Large outputs don't need to be read again to hash them.
+//!
+//! ```rust
+//! use sha2::{Digest, Sha256};
+//!
+//! let value = nota::Value::Text("hello".into());
+//! let mut file = Vec::new();
+//! let hash = nota::digest::write_hashed::<Sha256, _>(&mut file, &value)?;
+//! assert_eq!(hash, Sha256::digest(&file));
+//! # Ok::<_, std::io::Error>(())
+//! ```
+//!
+//! [`Value::content_hash`] hashes the canonical encoding, so equal values have equal hashes regardless of the order of record fields.
+//! [`MerkleTree`] also has a hash of every nested value, which finds shared subtrees, and where two documents differ
+//! without comparing them in full.
+use crate::path::{Path, Segment};
+#[cfg(feature = "records")]
+use crate::serialize_string;
+use crate::{serialize_unsigned_preamble, Error, SerializeOptions, Value};
+pub use digest::{Digest, Output};
+use std::cmp::Ordering;
+use std::io::{self, Write};
+
+/// Passes writes through to the inner writer, and hashes what has been written
+#[derive(Debug, Clone, Default)]
+pub struct HashingWriter<W, D> {
+    inner: W,
+    digest: D,
+}
+
+impl<W: Write, D: Digest> HashingWriter<W, D> {
+    pub fn new(inner: W) -> Self {
+        Self::with_digest(inner, D::new())
+    }
+
+    /// Continues from a digest that may have already hashed a prefix, such as a header
+    pub fn with_digest(inner: W, digest: D) -> Self {
+        Self { inner, digest }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Hash of everything written so far
+    pub fn finalize(self) -> (W, Output<D>) {
+        (self.inner, self.digest.finalize())
+    }
+
+    pub fn into_parts(self) -> (W, D) {
+        (self.inner, self.digest)
+    }
+}
+
+impl<W: Write, D: Digest> Write for HashingWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.digest.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes the serialized value, and returns its hash
+pub fn write_hashed<D: Digest, W: Write>(writer: &mut W, value: &Value) -> Result<Output<D>, io::Error> {
+    let mut data = Vec::new();
+    value.try_serialize_into(&mut data)?;
+    let mut writer = HashingWriter::<_, D>::new(writer);
+    writer.write_all(&data)?;
+    Ok(writer.finalize().1)
+}
+
+impl Value {
+    /// Hash of the [canonical](crate::SerializeOptions::canonical) encoding of the value.
+    ///
+    /// Fails if the value can't be serialized, like [`try_serialize_into`](Self::try_serialize_into).
+    pub fn content_hash<D: Digest>(&self) -> Result<Output<D>, Error> {
+        let mut data = Vec::new();
+        self.try_serialize_with(&mut data, &SerializeOptions { canonical: true, ..SerializeOptions::default() })?;
+        Ok(D::digest(&data))
+    }
+}
+
+/// Hashes of a value and of all values nested in it
+///
+/// Scalars are hashed as their encoding. Arrays and records hash their header followed by the hashes of their elements,
+/// and for records, each hash is preceded by the encoded key. Fields are in canonical order, so the hash doesn't depend on it.
+///
+/// The top-level hash is not the same as [`Value::content_hash`], but it's equally unique.
+#[derive(Debug, Clone)]
+pub struct MerkleTree<D: Digest> {
+    pub hash: Output<D>,
+    /// Elements of an array, or fields of a record sorted by key. Empty for other values.
+    pub children: Vec<(Segment, MerkleTree<D>)>,
+}
+
+impl<D: Digest> MerkleTree<D> {
+    /// Fails like [`Value::try_serialize_into`]
+    pub fn new(value: &Value) -> Result<Self, Error> {
+        let mut header = Vec::new();
+        let mut digest = D::new();
+        let children = match value {
+            Value::Array(items) => {
+                serialize_unsigned_preamble(0b0100_0000, items.len() as u128, &mut header);
+                digest.update(&header);
+                items.iter().enumerate().map(|(i, item)| {
+                    let child = Self::new(item)?;
+                    digest.update(&child.hash);
+                    Ok((Segment::Index(i), child))
+                }).collect::<Result<_, Error>>()?
+            },
+            #[cfg(feature = "records")]
+            Value::Record(fields) => {
+                serialize_unsigned_preamble(0b0110_0000, fields.len() as u128, &mut header);
+                digest.update(&header);
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_unstable_by_key(|&(k, _)| k);
+                fields.into_iter().map(|(k, v)| {
+                    let child = Self::new(v)?;
+                    header.clear();
+                    serialize_string(k, &mut header);
+                    digest.update(&header);
+                    digest.update(&child.hash);
+                    Ok((Segment::Key(k.clone()), child))
+                }).collect::<Result<_, Error>>()?
+            },
+            scalar => {
+                scalar.try_serialize_into(&mut header)?;
+                digest.update(&header);
+                Vec::new()
+            },
+        };
+        Ok(Self { hash: digest.finalize(), children })
+    }
+
+    /// Paths of the smallest parts that differ: changed scalars, elements and fields that are only in one of the trees,
+    /// and values that have changed type. Subtrees with equal hashes are skipped without looking inside.
+    #[must_use]
+    pub fn changes(&self, other: &Self) -> Vec<Path> {
+        let mut changes = Vec::new();
+        self.changes_at(other, &mut Path::default(), &mut changes);
+        changes
+    }
+
+    fn changes_at(&self, other: &Self, path: &mut Path, changes: &mut Vec<Path>) {
+        if self.hash == other.hash {
+            return;
+        }
+        let same_kind = match (self.children.first(), other.children.first()) {
+            (Some((a, _)), Some((b, _))) => std::mem::discriminant(a) == std::mem::discriminant(b),
+            _ => false,
+        };
+        if !same_kind {
+            changes.push(path.clone());
+            return;
+        }
+        // both are sorted by the segment
+        let (mut a, mut b) = (self.children.iter().peekable(), other.children.iter().peekable());
+        loop {
+            let order = match (a.peek(), b.peek()) {
+                (Some((sa, _)), Some((sb, _))) => segment_order(sa, sb),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            let (segment, children) = match order {
+                Ordering::Less => (&a.next().unwrap().0, None),
+                Ordering::Greater => (&b.next().unwrap().0, None),
+                Ordering::Equal => {
+                    let ((segment, ca), (_, cb)) = (a.next().unwrap(), b.next().unwrap());
+                    (segment, Some((ca, cb)))
+                },
+            };
+            path.push(segment.clone());
+            match children {
+                Some((ca, cb)) => ca.changes_at(cb, path, changes),
+                None => changes.push(path.clone()),
+            }
+            path.pop();
+        }
+    }
+}
+
+fn segment_order(a: &Segment, b: &Segment) -> Ordering {
+    match (a, b) {
+        (Segment::Index(a), Segment::Index(b)) => a.cmp(b),
+        (Segment::Key(a), Segment::Key(b)) => a.cmp(b),
+        (Segment::Index(_), Segment::Key(_)) => Ordering::Less,
+        (Segment::Key(_), Segment::Index(_)) => Ordering::Greater,
+    }
+}
+
+#[test]
+fn same_as_hashing_after() {
+    use sha2::Sha256;
+
+    let value = Value::Array((0..1000).map(Value::Integer).collect());
+    let mut out = Vec::new();
+    let hash = write_hashed::<Sha256, _>(&mut out, &value).unwrap();
+    assert_eq!(hash, Sha256::digest(&out));
+
+    let mut prefixed = Sha256::new();
+    prefixed.update(b"header");
+    let mut writer = HashingWriter::with_digest(Vec::new(), prefixed);
+    writer.write_all(&out[..10]).unwrap();
+    writer.write_all(&out[10..]).unwrap();
+    let (written, hash) = writer.finalize();
+    assert_eq!(written, out);
+    assert_eq!(hash, Sha256::digest([&b"header"[..], &out].concat()));
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn merkle() {
+    use sha2::Sha256;
+
+    let user = |name: &str, age| Value::Record(Box::new([
+        ("name".to_string(), Value::Text(name.into())),
+        ("age".to_string(), Value::Integer(age)),
+    ].into_iter().collect()));
+    let doc = |users| Value::Record(Box::new([("users".to_string(), Value::Array(users))].into_iter().collect()));
+    let before = doc(vec![user("a", 1), user("b", 2), user("a", 1)]);
+    let after = doc(vec![user("a", 1), user("b", 3), user("a", 1), Value::Null]);
+
+    let mut canonical = Vec::new();
+    before.serialize_with(&mut canonical, &SerializeOptions { canonical: true, ..SerializeOptions::default() });
+    assert_eq!(before.content_hash::<Sha256>().unwrap(), Sha256::digest(&canonical));
+    assert_eq!(before.clone().content_hash::<Sha256>().unwrap(), before.content_hash::<Sha256>().unwrap());
+    assert_ne!(after.content_hash::<Sha256>().unwrap(), before.content_hash::<Sha256>().unwrap());
+
+    let a = MerkleTree::<Sha256>::new(&before).unwrap();
+    let b = MerkleTree::<Sha256>::new(&after).unwrap();
+    assert_eq!(MerkleTree::<Sha256>::new(&before.clone()).unwrap().hash, a.hash);
+    let users = &a.children[0].1.children;
+    assert_eq!(users[0].1.hash, users[2].1.hash);
+    assert_ne!(users[0].1.hash, users[1].1.hash);
+    let changes: Vec<_> = b.changes(&a).iter().map(Path::to_string).collect();
+    assert_eq!(changes, ["users[1].age", "users[3]"]);
+    assert!(a.changes(&a).is_empty());
+    assert_eq!(a.changes(&MerkleTree::new(&Value::Null).unwrap()), [Path::default()]);
+}
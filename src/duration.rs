@@ -0,0 +1,243 @@
+//! Durations, such as timeouts and intervals
+//!
+//! A [`Duration`] is stored as an [`Integer`](Value::Integer) of nanoseconds. Conversions from values also accept text
+//! with units, like `"1h30m"`, `"2.5s"` or `"250ms"`, which is easier to write in config files.
+//! The units are `d`, `h`, `m`, `s`, `ms`, `us` (or `µs`), and `ns`.
+//!
+//! The [tagged](crate::tagged) form, `{"$duration": "1m30s"}`, has the text.
+//! For serde, use `#[serde(with = "nota::duration::nanos")]`, which reads integers or text, and writes integers.
+//!
+//! ```rust
+//! use nota::Value;
+//! use std::time::Duration;
+//!
+//! let timeout = Duration::try_from(Value::Text("1m30s".into()))?;
+//! assert_eq!(timeout, Duration::from_secs(90));
+//! assert_eq!(Value::from(timeout), Value::Integer(90_000_000_000));
+//! # Ok::<_, nota::Error>(())
+//! ```
+use crate::{Error, Value};
+use std::time::Duration;
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+pub(crate) const EXPECTED: Error = Error::UnexpectedValue { expected: "a duration" };
+
+const UNITS: [(&str, i128); 8] = [
+    ("d", 86400 * NANOS_PER_SEC),
+    ("h", 3600 * NANOS_PER_SEC),
+    ("ms", 1_000_000),
+    ("m", 60 * NANOS_PER_SEC),
+    ("s", NANOS_PER_SEC),
+    ("us", 1000),
+    ("µs", 1000),
+    ("ns", 1),
+];
+
+/// Nanoseconds from text like `-1h2.5s`
+pub(crate) fn parse_nanos(text: &str) -> Option<i128> {
+    let (negative, mut rest) = match text.trim().strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.trim()),
+    };
+    if rest.is_empty() {
+        return None;
+    }
+    let mut total = 0i128;
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_len);
+        let &(unit, unit_nanos) = UNITS.iter().find(|(unit, _)| after.starts_with(unit))?;
+        rest = &after[unit.len()..];
+
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        let mut nanos = if whole.is_empty() { 0 } else { whole.parse::<i128>().ok()?.checked_mul(unit_nanos)? };
+        let mut scale = unit_nanos;
+        for digit in fraction.bytes() {
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            scale /= 10;
+            nanos += i128::from(digit - b'0') * scale;
+        }
+        total = total.checked_add(nanos)?;
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// Text like `1h30m0.5s`, which [`parse_nanos`] reads back
+#[cfg(feature = "records")]
+pub(crate) fn format_nanos(nanos: i128) -> String {
+    use std::fmt::Write;
+
+    let mut text = String::new();
+    if nanos < 0 {
+        text.push('-');
+    }
+    let nanos = nanos.unsigned_abs();
+    let (hours, rest) = (nanos / 3_600_000_000_000, nanos % 3_600_000_000_000);
+    let (minutes, rest) = (rest / 60_000_000_000, rest % 60_000_000_000);
+    let (seconds, fraction) = (rest / 1_000_000_000, rest % 1_000_000_000);
+    if hours > 0 {
+        let _ = write!(text, "{hours}h");
+    }
+    if minutes > 0 {
+        let _ = write!(text, "{minutes}m");
+    }
+    if seconds > 0 || fraction > 0 || nanos == 0 {
+        let _ = write!(text, "{seconds}");
+        if fraction > 0 {
+            let _ = write!(text, ".{}", format!("{fraction:09}").trim_end_matches('0'));
+        }
+        text.push('s');
+    }
+    text
+}
+
+/// Integer nanoseconds, text with units, or the tagged form
+pub(crate) fn nanos_of(value: &Value) -> Result<i128, Error> {
+    match value {
+        &Value::Integer(nanos) => Ok(nanos),
+        Value::Text(text) => parse_nanos(text).ok_or(EXPECTED),
+        #[cfg(feature = "records")]
+        value => match value.as_tagged() {
+            Some(("duration", inner)) => nanos_of(inner),
+            _ => Err(EXPECTED),
+        },
+        #[cfg(not(feature = "records"))]
+        _ => Err(EXPECTED),
+    }
+}
+
+fn from_nanos(nanos: i128) -> Result<Duration, Error> {
+    if nanos < 0 {
+        return Err(EXPECTED);
+    }
+    let secs = (nanos / NANOS_PER_SEC).try_into().map_err(|_| EXPECTED)?;
+    Ok(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+}
+
+impl From<Duration> for Value {
+    fn from(duration: Duration) -> Self {
+        Self::Integer(duration.as_nanos() as i128)
+    }
+}
+
+impl TryFrom<&Value> for Duration {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        from_nanos(nanos_of(value)?)
+    }
+}
+
+impl TryFrom<Value> for Duration {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Self::try_from(&value)
+    }
+}
+
+#[cfg(feature = "records")]
+impl crate::tagged::Extension for Duration {
+    const TAG: &'static str = "duration";
+
+    fn to_value(&self) -> Value {
+        Value::Text(format_nanos(self.as_nanos() as i128))
+    }
+
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        Self::try_from(value)
+    }
+}
+
+/// Serde `with` module that writes `Duration` as integer nanoseconds, and reads integers or text with units
+#[cfg(feature = "serde")]
+pub mod nanos {
+    use serde::de::{Error, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u128(duration.as_nanos())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        super::from_nanos(deserializer.deserialize_any(NanosVisitor)?).map_err(D::Error::custom)
+    }
+
+    /// Nanoseconds from integers or text
+    pub(crate) struct NanosVisitor;
+
+    impl Visitor<'_> for NanosVisitor {
+        type Value = i128;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("nanoseconds or a duration like \"1m30s\"")
+        }
+
+        fn visit_i64<E: Error>(self, nanos: i64) -> Result<i128, E> {
+            Ok(nanos.into())
+        }
+
+        fn visit_u64<E: Error>(self, nanos: u64) -> Result<i128, E> {
+            Ok(nanos.into())
+        }
+
+        fn visit_i128<E: Error>(self, nanos: i128) -> Result<i128, E> {
+            Ok(nanos)
+        }
+
+        fn visit_u128<E: Error>(self, nanos: u128) -> Result<i128, E> {
+            nanos.try_into().map_err(E::custom)
+        }
+
+        fn visit_str<E: Error>(self, text: &str) -> Result<i128, E> {
+            super::parse_nanos(text).ok_or_else(|| E::custom(super::EXPECTED))
+        }
+    }
+}
+
+#[test]
+fn conversions() {
+    for (text, secs, nanos) in [("1h30m", 5400, 0), ("2.5s", 2, 500_000_000), ("250ms", 0, 250_000_000), ("1d1us", 86400, 1000), (".5m", 30, 0), ("1.0000000019s", 1, 1)] {
+        assert_eq!(Duration::try_from(Value::Text(text.into())), Ok(Duration::new(secs, nanos)), "{text}");
+    }
+    for text in ["", "-", "1", "s", "1x", "1.2.3s", "-1s", "1s 2s"] {
+        assert_eq!(Duration::try_from(Value::Text(text.into())), Err(EXPECTED), "{text}");
+    }
+    assert_eq!(Duration::try_from(Value::Integer(-1)), Err(EXPECTED));
+    assert_eq!(Value::from(Duration::from_millis(1)), Value::Integer(1_000_000));
+
+    #[cfg(feature = "records")]
+    {
+        use crate::tagged::{decode, encode};
+        for nanos in [0, 1, -1_500_000_000, 3_723_000_000_001, 86_400_000_000_000] {
+            assert_eq!(parse_nanos(&format_nanos(nanos)), Some(nanos));
+        }
+        assert_eq!(format_nanos(3_723_500_000_000), "1h2m3.5s");
+        assert_eq!(format_nanos(60_000_000_000), "1m");
+
+        let tagged = encode(&Duration::from_millis(90_250));
+        assert_eq!(tagged.to_string(), r#"{"$duration": "1m30.25s"}"#);
+        assert_eq!(decode::<Duration>(&tagged), Ok(Duration::from_millis(90_250)));
+    }
+
+    #[cfg(feature = "serde")]
+    {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Config {
+            #[serde(with = "nanos")]
+            timeout: Duration,
+        }
+        let config: Config = crate::serde::from_value(r#"{"timeout": "5s"}"#.parse().unwrap()).unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(5));
+        let value = crate::serde::to_value(&config).unwrap();
+        assert_eq!(value.to_string(), r#"{"timeout": 5000000000}"#);
+        assert_eq!(crate::serde::from_value::<Config>(value).unwrap(), config);
+    }
+}
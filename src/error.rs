@@ -0,0 +1,171 @@
+use std::fmt;
+use std::io;
+
+/// Nota-specific reasons for failure.
+///
+/// The parsing functions return them wrapped in `io::Error`. Use [`Error::from_io`] to get them back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Parsing has been stopped via [`ParseOptions::cancel`](crate::ParseOptions::cancel)
+    Cancelled,
+    /// The frame's [checksum](crate::framing::Checksum) doesn't match its contents
+    Corrupted,
+    /// The frame length prefix is larger than the allowed maximum
+    FrameTooLarge { len: u64, max: usize },
+    /// The encoding isn't the shortest possible, which [`ParseOptions::strict`](crate::ParseOptions::strict) doesn't allow
+    NotMinimal,
+    /// The document is larger than [`ParseOptions::max_size`](crate::ParseOptions::max_size) bytes
+    TooLarge { max: usize },
+    /// Arrays and records are nested deeper than [`ParseOptions::max_depth`](crate::ParseOptions::max_depth)
+    TooDeep { max: usize },
+    /// A kim char isn't a Unicode scalar value: it's a surrogate, or it's above U+10FFFF
+    InvalidChar {
+        /// The char, or as much of it as has been decoded when it went out of range
+        code_point: u32,
+        /// Of the first byte of the char, from the start of the document
+        offset: u64,
+    },
+    /// A [signed envelope](crate::signing) is malformed, or its signature doesn't match the payload and the key
+    InvalidSignature,
+    /// An integer doesn't fit in `i128`
+    IntegerTooLarge,
+    /// A length or back-reference index doesn't fit in `usize`. Smaller limits are set with [`ParseOptions::max_len`](crate::ParseOptions::max_len).
+    LengthTooLarge,
+    /// A value has more elements, fields, characters, or bits than [`ParseOptions::max_len`](crate::ParseOptions::max_len)
+    TooLong { len: usize, max: usize },
+    /// The value has no equivalent in the other format
+    Unrepresentable(&'static str),
+    /// A value doesn't have the shape that a conversion to a Rust type expects
+    UnexpectedValue { expected: &'static str },
+    /// The data doesn't match the Rust type, or a serde implementation has failed
+    Serde(String),
+    /// The [header](crate::header) has a format version newer than this crate understands
+    UnsupportedVersion { version: u8 },
+}
+
+/// Stable numbers for kinds of errors, for bindings and logs that can't match on [`Error`].
+///
+/// The numbers never change, and new kinds get new numbers. [`ErrorCode::from_io`] classifies any error returned by the parsers.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// An I/O error from the reader or writer, not related to the data
+    Io = 1,
+    /// The data ended in the middle of a value
+    UnexpectedEof = 2,
+    /// The data isn't valid Nota, and there's no more specific code
+    InvalidData = 3,
+    /// The data uses a part of Nota that isn't supported, such as DEC64
+    Unsupported = 4,
+    Cancelled = 5,
+    Corrupted = 6,
+    FrameTooLarge = 7,
+    IntegerTooLarge = 8,
+    InvalidChar = 9,
+    InvalidSignature = 10,
+    LengthTooLarge = 11,
+    NotMinimal = 12,
+    TooLarge = 13,
+    TooDeep = 14,
+    TooLong = 15,
+    Unrepresentable = 16,
+    Serde = 17,
+    UnexpectedValue = 18,
+    UnsupportedVersion = 19,
+}
+
+impl ErrorCode {
+    #[must_use]
+    pub fn from_io(err: &io::Error) -> Self {
+        if let Some(err) = Error::from_io(err) {
+            return err.code();
+        }
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => Self::UnexpectedEof,
+            io::ErrorKind::InvalidData => Self::InvalidData,
+            io::ErrorKind::Unsupported => Self::Unsupported,
+            _ => Self::Io,
+        }
+    }
+}
+
+impl Error {
+    /// Finds the nota error inside an I/O error, if there's one
+    #[must_use]
+    pub fn from_io(err: &io::Error) -> Option<&Self> {
+        err.get_ref()?.downcast_ref()
+    }
+
+    #[must_use]
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Cancelled => ErrorCode::Cancelled,
+            Self::Corrupted => ErrorCode::Corrupted,
+            Self::FrameTooLarge { .. } => ErrorCode::FrameTooLarge,
+            Self::NotMinimal => ErrorCode::NotMinimal,
+            Self::TooLarge { .. } => ErrorCode::TooLarge,
+            Self::TooDeep { .. } => ErrorCode::TooDeep,
+            Self::InvalidChar { .. } => ErrorCode::InvalidChar,
+            Self::InvalidSignature => ErrorCode::InvalidSignature,
+            Self::IntegerTooLarge => ErrorCode::IntegerTooLarge,
+            Self::LengthTooLarge => ErrorCode::LengthTooLarge,
+            Self::TooLong { .. } => ErrorCode::TooLong,
+            Self::Unrepresentable(_) => ErrorCode::Unrepresentable,
+            Self::Serde(_) => ErrorCode::Serde,
+            Self::UnexpectedValue { .. } => ErrorCode::UnexpectedValue,
+            Self::UnsupportedVersion { .. } => ErrorCode::UnsupportedVersion,
+        }
+    }
+
+    fn io_kind(&self) -> io::ErrorKind {
+        match self {
+            Self::Cancelled => io::ErrorKind::Other,
+            Self::Corrupted | Self::FrameTooLarge { .. } | Self::IntegerTooLarge | Self::InvalidChar { .. } | Self::InvalidSignature | Self::LengthTooLarge | Self::NotMinimal | Self::TooLarge { .. } | Self::TooDeep { .. } | Self::TooLong { .. } => io::ErrorKind::InvalidData,
+            Self::Unrepresentable(_) => io::ErrorKind::InvalidInput,
+            Self::Serde(_) | Self::UnexpectedValue { .. } => io::ErrorKind::InvalidData,
+            Self::UnsupportedVersion { .. } => io::ErrorKind::Unsupported,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => f.write_str("parsing has been cancelled"),
+            Self::Corrupted => f.write_str("frame checksum mismatch, the data is corrupted"),
+            Self::FrameTooLarge { len, max } => write!(f, "frame of {len} bytes exceeds the limit of {max}"),
+            Self::IntegerTooLarge => f.write_str("integer doesn't fit in i128"),
+            Self::InvalidChar { code_point, offset } => write!(f, "invalid char U+{code_point:X} at byte {offset}"),
+            Self::InvalidSignature => f.write_str("the signature is invalid"),
+            Self::LengthTooLarge => f.write_str("length doesn't fit in the address space"),
+            Self::NotMinimal => f.write_str("a value is not encoded in the shortest form"),
+            Self::TooLarge { max } => write!(f, "document exceeds the size limit of {max} bytes"),
+            Self::TooDeep { max } => write!(f, "nesting is deeper than the limit of {max}"),
+            Self::TooLong { len, max } => write!(f, "length {len} exceeds the limit of {max}"),
+            Self::Unrepresentable(what) => write!(f, "{what} can't be converted"),
+            Self::Serde(msg) => f.write_str(msg),
+            Self::UnexpectedValue { expected } => write!(f, "expected {expected}"),
+            Self::UnsupportedVersion { version } => write!(f, "format version {version} is not supported"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        Self::new(err.io_kind(), err)
+    }
+}
+
+#[test]
+fn codes() {
+    let eof = crate::Value::parse_slice(&mut &[0x42, 0xC0][..]).unwrap_err();
+    assert_eq!(ErrorCode::from_io(&eof), ErrorCode::UnexpectedEof);
+    let deep = crate::Value::parse_slice_with(&mut &[0x41, 0x41, 0x40][..], &crate::ParseOptions { max_depth: Some(2), ..Default::default() }).unwrap_err();
+    assert_eq!(ErrorCode::from_io(&deep), ErrorCode::TooDeep);
+    assert_eq!(ErrorCode::from_io(&io::Error::other("disk")), ErrorCode::Io);
+    assert_eq!(ErrorCode::from_io(&Error::Serde("x".into()).into()) as u32, 17);
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Numbers for record keys, agreed on in advance by the writer and the reader, like field numbers in Protocol Buffers.
+///
+/// Set it in both [`SerializeOptions::field_ids`](crate::SerializeOptions::field_ids) and [`ParseOptions::field_ids`](crate::ParseOptions::field_ids).
+/// Keys in the table are written as a one-char text, whose code point is the number. Numbers below 128 take a single byte,
+/// and below 16384 two, so a key costs 2 or 3 bytes regardless of its length. The documents are still standard Nota.
+///
+/// Other keys are written as they are, and the reader keeps one-char keys with unknown numbers, so fields added by newer writers
+/// aren't lost. Once a number has been used, it must never be given to another field.
+///
+/// ```rust
+/// use nota::{FieldIds, ParseOptions, SerializeOptions, Value};
+///
+/// let ids = FieldIds::new([(1, "temperature"), (2, "humidity")]);
+/// let value: Value = r#"{"temperature": 21, "humidity": 40}"#.parse().unwrap();
+///
+/// let mut options = SerializeOptions::default();
+/// options.field_ids = Some(ids.clone());
+/// let mut message = Vec::new();
+/// value.serialize_with(&mut message, &options);
+/// assert_eq!(message.len(), 9);
+///
+/// let mut options = ParseOptions::default();
+/// options.field_ids = Some(ids);
+/// assert_eq!(Value::parse_with(&mut &message[..], &options)?, value);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct FieldIds(Arc<Inner>);
+
+struct Inner {
+    names: HashMap<char, String>,
+    keys: HashMap<String, String>,
+}
+
+impl FieldIds {
+    /// # Panics
+    ///
+    /// If a number or a name is repeated, or a number is from 0xD800 to 0xDFFF, which aren't valid chars
+    pub fn new(fields: impl IntoIterator<Item = (u16, impl Into<String>)>) -> Self {
+        let mut names = HashMap::new();
+        let mut keys = HashMap::new();
+        for (id, name) in fields {
+            let name = name.into();
+            let key = char::from_u32(id.into()).unwrap_or_else(|| panic!("field number {id} isn't a valid char"));
+            assert!(names.insert(key, name.clone()).is_none(), "field number {id} is repeated");
+            assert!(keys.insert(name, key.to_string()).is_none(), "field name of {id} is repeated");
+        }
+        Self(Arc::new(Inner { names, keys }))
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.names.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.names.is_empty()
+    }
+
+    /// Number of the field
+    #[must_use]
+    pub fn id(&self, name: &str) -> Option<u16> {
+        self.0.keys.get(name).and_then(|key| key.chars().next()).map(|key| key as u16)
+    }
+
+    /// Name of the field with this number
+    #[must_use]
+    pub fn name(&self, id: u16) -> Option<&str> {
+        self.0.names.get(&char::from_u32(id.into())?).map(String::as_str)
+    }
+
+    /// The key to write instead of the name. Fails for one-char names that would be read as another field.
+    pub(crate) fn key<'a>(&'a self, name: &'a str) -> Result<&'a str, crate::Error> {
+        if let Some(key) = self.0.keys.get(name) {
+            return Ok(key);
+        }
+        match one_char(name) {
+            Some(c) if self.0.names.contains_key(&c) => Err(crate::Error::Unrepresentable("key that is the number of another field")),
+            _ => Ok(name),
+        }
+    }
+
+    /// The name of the field, if the key is a number in the table
+    pub(crate) fn name_of_key(&self, key: String) -> String {
+        match one_char(&key).and_then(|c| self.0.names.get(&c)) {
+            Some(name) => name.clone(),
+            None => key,
+        }
+    }
+}
+
+fn one_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    chars.next().filter(|_| chars.next().is_none())
+}
+
+impl fmt::Debug for FieldIds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fields: Vec<_> = self.0.names.iter().map(|(&id, name)| (id as u32, name)).collect();
+        fields.sort_unstable();
+        f.debug_tuple("FieldIds").field(&fields).finish()
+    }
+}
+
+#[test]
+fn with_other_keys() {
+    use crate::{Decoder, ParseOptions, SerializeOptions, Value};
+
+    let ids = FieldIds::new([(0, "id"), (300, "description"), (0x41, "other")]);
+    assert_eq!((ids.id("description"), ids.name(0x41)), (Some(300), Some("other")));
+    let value: Value = r#"[{"id": 1, "description": "x", "extra": {"id": 2}, "z": null}, {"\u{1}": true}]"#.parse().unwrap();
+    let mut message = Vec::new();
+    value.serialize_with(&mut message, &SerializeOptions { field_ids: Some(ids.clone()), backrefs: true, ..Default::default() });
+
+    let options = ParseOptions { field_ids: Some(ids.clone()), backrefs: true, ..Default::default() };
+    assert_eq!(Value::parse_with(&mut &message[..], &options).unwrap(), value);
+    assert_eq!(Value::parse_slice_with(&mut &message[..], &options).unwrap(), value);
+    let mut decoder = Decoder::with_options(options);
+    assert_eq!(decoder.push(&message).unwrap().1, Some(value));
+
+    let clash: Value = r#"{"A": 1}"#.parse().unwrap();
+    assert!(clash.try_serialize_with(&mut Vec::new(), &SerializeOptions { field_ids: Some(ids), ..Default::default() }).is_err());
+}
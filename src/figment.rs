@@ -0,0 +1,148 @@
+//! Loading configuration from Nota files with [`figment`]
+//!
+//! ```rust,no_run
+//! use figment::Figment;
+//! use nota::figment::Nota;
+//!
+//! let figment = Figment::new()
+//!     .merge(Nota::file("app.nota"))
+//!     .merge(Nota::file("app.local.nota"));
+//! ```
+//!
+//! The file must contain a record. Values are converted with the [serde integration](crate::serde).
+use crate::Value;
+use figment::value::{Dict, Map};
+use figment::{Error, Metadata, Profile, Provider};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Figment provider that reads a Nota record from a file or bytes
+#[derive(Debug, Clone)]
+pub struct Nota {
+    source: Source,
+    profile: Option<Profile>,
+    nested: bool,
+    required: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    File(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl Nota {
+    /// Reads the file when the configuration is extracted. A missing file is treated as empty, unless [`required`](Self::required).
+    pub fn file<P: AsRef<Path>>(path: P) -> Self {
+        Self { source: Source::File(path.as_ref().into()), profile: Some(Profile::Default), nested: false, required: false }
+    }
+
+    /// Parses a serialized Nota record
+    #[must_use]
+    pub fn bytes(bytes: Vec<u8>) -> Self {
+        Self { source: Source::Bytes(bytes), profile: Some(Profile::Default), nested: false, required: true }
+    }
+
+    /// Fail if the file doesn't exist
+    #[must_use]
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Top-level fields are profile names, with each profile's settings in them
+    #[must_use]
+    pub fn nested(mut self) -> Self {
+        self.nested = true;
+        self.profile = None;
+        self
+    }
+
+    /// Puts all settings into this profile. Ignored if [`nested`](Self::nested).
+    #[must_use]
+    pub fn profile<P: Into<Profile>>(mut self, profile: P) -> Self {
+        self.profile = Some(profile.into());
+        self.nested = false;
+        self
+    }
+
+    fn read(&self) -> Result<Option<Value>, io::Error> {
+        match &self.source {
+            Source::Bytes(bytes) => Value::parse_slice(&mut &bytes[..]).map(Some),
+            Source::File(path) => match std::fs::read(path) {
+                Ok(bytes) => Value::parse_slice(&mut &bytes[..]).map(Some),
+                Err(e) if e.kind() == io::ErrorKind::NotFound && !self.required => Ok(None),
+                Err(e) => Err(e),
+            },
+        }
+    }
+}
+
+impl Provider for Nota {
+    fn metadata(&self) -> Metadata {
+        match &self.source {
+            Source::File(path) => Metadata::from("Nota file", path.as_path()),
+            Source::Bytes(_) => Metadata::named("Nota bytes"),
+        }
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, Error> {
+        let value = self.read().map_err(|e| match &self.source {
+            Source::File(path) => Error::from(format!("{}: {e}", path.display())),
+            Source::Bytes(_) => Error::from(e.to_string()),
+        })?;
+        let Some(value) = value else {
+            return Ok(Map::new());
+        };
+        if !matches!(value, Value::Record(_)) {
+            return Err(Error::from("Nota configuration must be a record".to_string()));
+        }
+        let to_figment = |e: crate::Error| Error::from(e.to_string());
+        match &self.profile {
+            Some(profile) if !self.nested => {
+                let dict = crate::serde::from_value::<Dict>(value).map_err(to_figment)?;
+                Ok([(profile.clone(), dict)].into_iter().collect())
+            },
+            _ => crate::serde::from_value(value).map_err(to_figment),
+        }
+    }
+
+    fn profile(&self) -> Option<Profile> {
+        self.profile.clone()
+    }
+}
+
+#[test]
+fn merge_with_other_providers() {
+    use figment::providers::Serialized;
+    use figment::Figment;
+
+    #[derive(serde::Deserialize)]
+    struct Config {
+        name: String,
+        port: u16,
+        debug: bool,
+    }
+
+    let file = crate::serde::to_vec(&Value::Record(Box::new([
+        ("name".to_string(), Value::Text("app".into())),
+        ("port".to_string(), Value::Integer(80)),
+    ].into_iter().collect()))).unwrap();
+
+    let config: Config = Figment::new()
+        .merge(Serialized::defaults(Value::Record(Box::new([("debug".to_string(), Value::Bool(true)), ("port".to_string(), Value::Integer(1))].into_iter().collect()))))
+        .merge(Nota::bytes(file))
+        .merge(Nota::file("/nonexistent/app.nota"))
+        .extract().unwrap();
+    assert_eq!(config.name, "app");
+    assert_eq!(config.port, 80);
+    assert!(config.debug);
+
+    let nested = crate::serde::to_vec(&Value::Record(Box::new([
+        ("release".to_string(), Value::Record(Box::new([("port".to_string(), Value::Integer(443))].into_iter().collect()))),
+    ].into_iter().collect()))).unwrap();
+    let port: u16 = Figment::new().merge(Nota::bytes(nested).nested()).select("release").extract_inner("port").unwrap();
+    assert_eq!(port, 443);
+
+    assert!(Figment::new().merge(Nota::file("/nonexistent/app.nota").required(true)).extract::<Dict>().is_err());
+}
@@ -0,0 +1,226 @@
+//! Length-prefixed messages, for transports that need to know message size before parsing it.
+//!
+//! Each frame is the length of the serialized value in bytes (as a big-endian base-128 varint,
+//! with a continuation bit in every byte but the last, like the rest of Nota), followed by the value itself.
+//!
+//! For lossy transports, [`FrameOptions::checksum`] adds a CRC after the value, which is included in the length.
+use crate::{Error, ParseOptions, Value};
+use std::io::{Read, Write};
+use std::io;
+
+/// Frames larger than this are rejected by [`read_framed`]
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 << 20;
+
+/// Checksum appended to the serialized value, big-endian
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Checksum {
+    #[default]
+    None,
+    /// CRC-16/CCITT-FALSE, 2 bytes
+    Crc16,
+    /// CRC-32 as used by zlib and Ethernet, 4 bytes
+    Crc32,
+}
+
+impl Checksum {
+    /// Number of bytes it adds
+    #[must_use]
+    pub const fn size(self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::Crc16 => 2,
+            Self::Crc32 => 4,
+        }
+    }
+
+    /// CRC of the data, 0 for `None`
+    #[must_use]
+    pub fn compute(self, data: &[u8]) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Crc16 => u32::from(data.iter().fold(0xFFFFu16, |crc, &byte| {
+                (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+                    if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 }
+                })
+            })),
+            Self::Crc32 => !data.iter().fold(!0u32, |crc, &byte| {
+                (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+                    if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 }
+                })
+            }),
+        }
+    }
+
+    /// Appends the checksum of everything in `data`
+    pub fn append(self, data: &mut Vec<u8>) {
+        let crc = self.compute(data).to_be_bytes();
+        data.extend_from_slice(&crc[4 - self.size()..]);
+    }
+
+    /// Checks and removes the checksum at the end of the frame
+    pub fn verify(self, frame: &[u8]) -> Result<&[u8], Error> {
+        let (data, crc) = frame.split_at_checked(frame.len().wrapping_sub(self.size())).ok_or(Error::Corrupted)?;
+        if crc != &self.compute(data).to_be_bytes()[4 - self.size()..] {
+            return Err(Error::Corrupted);
+        }
+        Ok(data)
+    }
+}
+
+/// Settings for [`write_framed_with`] and [`read_framed_with`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct FrameOptions {
+    /// Longer frames are rejected with [`Error::FrameTooLarge`] before reading their body
+    pub max_frame_size: usize,
+    /// Mismatches are reported as [`Error::Corrupted`]
+    pub checksum: Checksum,
+    /// Limits and other settings for parsing the value in the frame
+    pub parse: ParseOptions,
+}
+
+impl Default for FrameOptions {
+    fn default() -> Self {
+        Self { max_frame_size: DEFAULT_MAX_FRAME_SIZE, checksum: Checksum::None, parse: ParseOptions::default() }
+    }
+}
+
+/// Writes the value prefixed with its length
+pub fn write_framed<W: Write>(writer: &mut W, value: &Value) -> Result<(), io::Error> {
+    write_framed_with(writer, value, &FrameOptions::default())
+}
+
+/// Writes the value prefixed with its length, and followed by the checksum if one is set
+pub fn write_framed_with<W: Write>(writer: &mut W, value: &Value, options: &FrameOptions) -> Result<(), io::Error> {
+    let mut out = Vec::new();
+    value.try_serialize_into(&mut out)?;
+    options.checksum.append(&mut out);
+    let mut frame = Vec::with_capacity(out.len() + 10);
+    write_varint(out.len() as u64, &mut frame);
+    frame.extend_from_slice(&out);
+    writer.write_all(&frame)
+}
+
+/// Reads one frame written by [`write_framed`].
+///
+/// Returns `Ok(None)` if the reader is at the end of input (but not in the middle of a frame).
+/// Frames longer than `max_frame_size` are rejected with [`Error::FrameTooLarge`] before reading their body.
+pub fn read_framed<R: Read>(reader: &mut R, max_frame_size: usize) -> Result<Option<Value>, io::Error> {
+    read_framed_with(reader, &FrameOptions { max_frame_size, ..FrameOptions::default() })
+}
+
+/// Reads one frame written by [`write_framed_with`] with the same options
+pub fn read_framed_with<R: Read>(reader: &mut R, options: &FrameOptions) -> Result<Option<Value>, io::Error> {
+    let Some(frame) = read_frame_bytes(reader, options.max_frame_size)? else {
+        return Ok(None);
+    };
+    let mut body = options.checksum.verify(&frame)?;
+    let value = Value::parse_slice_with(&mut body, &options.parse)?;
+    if !body.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "trailing bytes in frame"));
+    }
+    Ok(Some(value))
+}
+
+pub(crate) fn read_frame_bytes<R: Read>(reader: &mut R, max_frame_size: usize) -> Result<Option<Vec<u8>>, io::Error> {
+    let mut first = 0;
+    if reader.read(std::slice::from_mut(&mut first))? == 0 {
+        return Ok(None);
+    }
+    let len = read_varint(first, reader)?;
+    if len > max_frame_size as u64 {
+        return Err(Error::FrameTooLarge { len, max: max_frame_size }.into());
+    }
+    let mut frame = Vec::new();
+    reader.take(len).read_to_end(&mut frame)?;
+    if frame.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(Some(frame))
+}
+
+pub(crate) fn write_varint(value: u64, into: &mut Vec<u8>) {
+    let mut shift = (63 - (value | 1).leading_zeros()) / 7 * 7;
+    while shift > 0 {
+        into.push(0b1000_0000 | (value >> shift) as u8 & 0b0111_1111);
+        shift -= 7;
+    }
+    into.push(value as u8 & 0b0111_1111);
+}
+
+fn read_varint<R: Read>(mut next: u8, reader: &mut R) -> Result<u64, io::Error> {
+    let mut value = 0u64;
+    loop {
+        if value.leading_zeros() < 7 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "frame length overflow"));
+        }
+        value = (value << 7) | u64::from(next & 0b0111_1111);
+        if next & 0b1000_0000 == 0 {
+            return Ok(value);
+        }
+        reader.read_exact(std::slice::from_mut(&mut next))?;
+    }
+}
+
+#[test]
+fn varint() {
+    for n in [0, 1, 127, 128, 300, 16383, 16384, u64::from(u32::MAX), u64::MAX] {
+        let mut out = Vec::new();
+        write_varint(n, &mut out);
+        assert_eq!(out.len(), (64 - (n | 1).leading_zeros() as usize).div_ceil(7));
+        assert_eq!(read_varint(out[0], &mut &out[1..]).unwrap(), n);
+    }
+}
+
+#[test]
+fn frames() {
+    let mut stream = Vec::new();
+    write_framed(&mut stream, &Value::Text("x".repeat(200))).unwrap();
+    write_framed(&mut stream, &Value::Bool(true)).unwrap();
+    assert_eq!(&stream[..3], &[0x81, 0x4A, 0x31]);
+
+    let mut r = &stream[..];
+    assert_eq!(read_framed(&mut r, 1000).unwrap(), Some(Value::Text("x".repeat(200))));
+    assert_eq!(read_framed(&mut r, 1000).unwrap(), Some(Value::Bool(true)));
+    assert_eq!(read_framed(&mut r, 1000).unwrap(), None);
+
+    let err = read_framed(&mut &stream[..], 100).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::FrameTooLarge { len: 202, max: 100 }));
+
+    let options = FrameOptions { parse: ParseOptions { max_len: Some(100), ..Default::default() }, ..Default::default() };
+    let err = read_framed_with(&mut &stream[..], &options).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::TooLong { len: 200, max: 100 }));
+}
+
+#[test]
+fn checksums() {
+    assert_eq!(Checksum::Crc16.compute(b"123456789"), 0x29B1);
+    assert_eq!(Checksum::Crc32.compute(b"123456789"), 0xCBF4_3926);
+
+    for checksum in [Checksum::None, Checksum::Crc16, Checksum::Crc32] {
+        let options = FrameOptions { checksum, ..Default::default() };
+        let mut stream = Vec::new();
+        write_framed_with(&mut stream, &Value::Text("hello".into()), &options).unwrap();
+        assert_eq!(stream.len(), 7 + checksum.size());
+        assert_eq!(read_framed_with(&mut &stream[..], &options).unwrap(), Some(Value::Text("hello".into())));
+
+        if checksum != Checksum::None {
+            stream[3] ^= 0x04;
+            let err = read_framed_with(&mut &stream[..], &options).unwrap_err();
+            assert_eq!(Error::from_io(&err), Some(&Error::Corrupted));
+        }
+    }
+    assert_eq!(Checksum::Crc32.verify(&[1, 2]), Err(Error::Corrupted));
+}
+
+#[cfg(feature = "dec64")]
+#[test]
+fn unserializable() {
+    #[allow(deprecated)]
+    let value = Value::Array(vec![Value::DecimalFloat(crate::DecimalFloat { exponent: -1, coefficient: 15 })]);
+    let mut stream = vec![1];
+    let err = write_framed_with(&mut stream, &value, &FrameOptions::default()).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::Unrepresentable("decimal float")));
+    assert_eq!(stream, [1]);
+}
@@ -0,0 +1,73 @@
+//! Files that start with a magic number and a format version
+//!
+//! Nota itself has no header, because it's meant for small messages. Files written with [`write_with_header`] start with
+//! [`MAGIC`] and a version byte, so tools can tell what they are, and future versions of the format can be told apart.
+//! The `file(1)` magic rules for them are `0 string \x8eNOTA Nota document` and `>5 byte x \b, version %d`.
+//!
+//! [`read_with_header`] rejects files without the header, and files from newer versions
+//! with [`Error::UnsupportedVersion`].
+use crate::{Error, ParseOptions, Value};
+use std::io::{self, Read, Write};
+
+/// First bytes of files with a header. The first byte isn't ASCII, so the files aren't mistaken for text.
+pub const MAGIC: [u8; 5] = *b"\x8eNOTA";
+
+/// Version of the format written by this crate, and the newest one it reads
+pub const VERSION: u8 = 1;
+
+/// Length of [`MAGIC`] and the version byte
+pub const HEADER_LEN: usize = MAGIC.len() + 1;
+
+/// Format version from the start of a file, or `None` if it doesn't have the header
+#[must_use]
+pub fn version(data: &[u8]) -> Option<u8> {
+    match data.get(..HEADER_LEN)?.split_last()? {
+        (&version, magic) if magic == MAGIC => Some(version),
+        _ => None,
+    }
+}
+
+/// Writes the header and then the value
+pub fn write_with_header<W: Write>(writer: &mut W, value: &Value) -> Result<(), io::Error> {
+    let mut data = Vec::with_capacity(HEADER_LEN + value.serialized_size());
+    data.extend_from_slice(&MAGIC);
+    data.push(VERSION);
+    value.try_serialize_into(&mut data)?;
+    writer.write_all(&data)
+}
+
+/// Checks the header, and reads the value after it
+pub fn read_with_header<R: Read>(reader: &mut R) -> Result<Value, io::Error> {
+    read_with_header_with(reader, &ParseOptions::default())
+}
+
+pub fn read_with_header_with<R: Read>(reader: &mut R, options: &ParseOptions) -> Result<Value, io::Error> {
+    let mut header = [0; HEADER_LEN];
+    reader.read_exact(&mut header)?;
+    match version(&header) {
+        Some(1..=VERSION) => Value::parse_with(reader, options),
+        Some(version) => Err(Error::UnsupportedVersion { version }.into()),
+        None => Err(Error::UnexpectedValue { expected: "the nota file header" }.into()),
+    }
+}
+
+#[test]
+fn header() {
+    let value = Value::Array(vec![Value::Text("hi".into()), Value::Integer(-1)]);
+    let mut file = Vec::new();
+    write_with_header(&mut file, &value).unwrap();
+    assert!(file.starts_with(b"\x8eNOTA\x01"));
+    assert_eq!(version(&file), Some(VERSION));
+    assert_eq!(read_with_header(&mut &file[..]).unwrap(), value);
+
+    file[5] = 2;
+    let err = read_with_header(&mut &file[..]).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::UnsupportedVersion { version: 2 }));
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+    let mut plain = Vec::new();
+    value.serialize_into(&mut plain);
+    assert_eq!(version(&plain), None);
+    assert!(read_with_header(&mut &plain[..]).is_err());
+    assert!(read_with_header(&mut &b"\x8eNO"[..]).is_err());
+}
@@ -0,0 +1,225 @@
+//! Decoding into [`heapless`] containers, without allocating
+//!
+//! Types implementing [`Decode`] are read from [pull parser](crate::pull) events. Arrays go into [`heapless::Vec`],
+//! records into [`heapless::IndexMap`] (such as [`FnvIndexMap`](heapless::index_map::FnvIndexMap)) with [`heapless::String`] keys.
+//! Data that doesn't fit the capacity is an error.
+//!
+//! Decoding doesn't allocate, but the crate requires `std`, so there are no `no_std` builds to use it in yet.
+//!
+//! ```rust
+//! use heapless::index_map::FnvIndexMap;
+//! use heapless::{String, Vec};
+//!
+//! # let mut buf = [0; 32];
+//! # let mut w = nota::SliceWriter::new(&mut buf);
+//! # w.record(1).unwrap(); w.key("temps").unwrap(); w.array(2).unwrap(); w.integer(21).unwrap(); w.integer(-3).unwrap();
+//! # let message = w.written();
+//! let config: FnvIndexMap<String<8>, Vec<i16, 4>, 4> = nota::heapless::from_slice(message)?;
+//! assert_eq!(config["temps"], [21, -3]);
+//! # Ok::<_, nota::heapless::DecodeError>(())
+//! ```
+use crate::pull::{Bits, Event, KimStr, PullError, PullParser};
+use core::fmt;
+use core::hash::BuildHasher;
+
+/// Deepest nesting supported by [`from_slice`]
+pub const MAX_DEPTH: usize = 16;
+
+/// Why the data couldn't be decoded into the Rust type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    Pull(PullError),
+    /// An array, record, or text has more elements than the container's capacity
+    Capacity,
+    /// The value has a different type, or a number is out of range
+    Type,
+    /// A record has the same key twice
+    DuplicateKey,
+    /// There's data after the end of the value
+    TrailingData,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pull(e) => e.reason(),
+            Self::Capacity => "value doesn't fit in the container's capacity",
+            Self::Type => "value doesn't match the Rust type",
+            Self::DuplicateKey => "duplicate record key",
+            Self::TrailingData => "unexpected data after the value",
+        })
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<PullError> for DecodeError {
+    fn from(e: PullError) -> Self {
+        Self::Pull(e)
+    }
+}
+
+/// Types that can be built from pull parser events
+pub trait Decode<'a>: Sized {
+    /// Builds the value from its first `event`, taking elements of arrays and records from the `parser`
+    fn decode<const DEPTH: usize>(event: Event<'a>, parser: &mut PullParser<'a, DEPTH>) -> Result<Self, DecodeError>;
+}
+
+/// Decodes one value that takes the whole `data`
+pub fn from_slice<'a, T: Decode<'a>>(data: &'a [u8]) -> Result<T, DecodeError> {
+    let mut parser = PullParser::<'a, MAX_DEPTH>::new(data);
+    let value = decode_next(&mut parser)?;
+    if !parser.remaining().is_empty() {
+        return Err(DecodeError::TrailingData);
+    }
+    Ok(value)
+}
+
+fn decode_next<'a, T: Decode<'a>, const DEPTH: usize>(parser: &mut PullParser<'a, DEPTH>) -> Result<T, DecodeError> {
+    let event = parser.next().ok_or(PullError::UnexpectedEnd)??;
+    T::decode(event, parser)
+}
+
+impl<'a> Decode<'a> for bool {
+    fn decode<const DEPTH: usize>(event: Event<'a>, _: &mut PullParser<'a, DEPTH>) -> Result<Self, DecodeError> {
+        match event {
+            Event::Bool(b) => Ok(b),
+            _ => Err(DecodeError::Type),
+        }
+    }
+}
+
+macro_rules! decode_int {
+    ($($t:ty),*) => {$(
+        impl<'a> Decode<'a> for $t {
+            fn decode<const DEPTH: usize>(event: Event<'a>, _: &mut PullParser<'a, DEPTH>) -> Result<Self, DecodeError> {
+                match event {
+                    Event::Integer(n) => n.try_into().map_err(|_| DecodeError::Type),
+                    _ => Err(DecodeError::Type),
+                }
+            }
+        }
+    )*};
+}
+decode_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// `null` is `None`
+impl<'a, T: Decode<'a>> Decode<'a> for Option<T> {
+    fn decode<const DEPTH: usize>(event: Event<'a>, parser: &mut PullParser<'a, DEPTH>) -> Result<Self, DecodeError> {
+        match event {
+            Event::Null => Ok(None),
+            event => T::decode(event, parser).map(Some),
+        }
+    }
+}
+
+/// Borrowed from the input
+impl<'a> Decode<'a> for KimStr<'a> {
+    fn decode<const DEPTH: usize>(event: Event<'a>, _: &mut PullParser<'a, DEPTH>) -> Result<Self, DecodeError> {
+        match event {
+            Event::Text(text) => Ok(text),
+            _ => Err(DecodeError::Type),
+        }
+    }
+}
+
+/// Borrowed from the input
+impl<'a> Decode<'a> for Bits<'a> {
+    fn decode<const DEPTH: usize>(event: Event<'a>, _: &mut PullParser<'a, DEPTH>) -> Result<Self, DecodeError> {
+        match event {
+            Event::Blob(bits) => Ok(bits),
+            _ => Err(DecodeError::Type),
+        }
+    }
+}
+
+impl<'a, const N: usize> Decode<'a> for heapless::String<N> {
+    fn decode<const DEPTH: usize>(event: Event<'a>, _: &mut PullParser<'a, DEPTH>) -> Result<Self, DecodeError> {
+        let Event::Text(text) = event else {
+            return Err(DecodeError::Type);
+        };
+        let mut out = Self::new();
+        for c in text.chars() {
+            out.push(c).map_err(|_| DecodeError::Capacity)?;
+        }
+        Ok(out)
+    }
+}
+
+impl<'a, T: Decode<'a>, const N: usize> Decode<'a> for heapless::Vec<T, N> {
+    fn decode<const DEPTH: usize>(event: Event<'a>, parser: &mut PullParser<'a, DEPTH>) -> Result<Self, DecodeError> {
+        let Event::Array(len) = event else {
+            return Err(DecodeError::Type);
+        };
+        if len > N {
+            return Err(DecodeError::Capacity);
+        }
+        let mut out = Self::new();
+        for _ in 0..len {
+            out.push(decode_next(parser)?).map_err(|_| DecodeError::Capacity)?;
+        }
+        Ok(out)
+    }
+}
+
+impl<'a, V: Decode<'a>, S: BuildHasher + Default, const K: usize, const N: usize> Decode<'a> for heapless::IndexMap<heapless::String<K>, V, S, N> {
+    fn decode<const DEPTH: usize>(event: Event<'a>, parser: &mut PullParser<'a, DEPTH>) -> Result<Self, DecodeError> {
+        let Event::Record(len) = event else {
+            return Err(DecodeError::Type);
+        };
+        if len > N {
+            return Err(DecodeError::Capacity);
+        }
+        let mut out = Self::default();
+        for _ in 0..len {
+            let key = decode_next(parser)?;
+            let value = decode_next(parser)?;
+            match out.insert(key, value) {
+                Ok(None) => {},
+                Ok(Some(_)) => return Err(DecodeError::DuplicateKey),
+                Err(_) => return Err(DecodeError::Capacity),
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn capacity() {
+    use crate::Value;
+    use heapless::index_map::FnvIndexMap;
+    use heapless::{String, Vec};
+
+    let mut buf = [0; 64];
+    let mut buf2 = [0; 64];
+    let value = Value::Array(vec![Value::Text("snow ☃".into()), Value::Text("x".into())]);
+    let len = value.serialize_to_slice(&mut buf).unwrap();
+    let data = &buf[..len];
+    let names: Vec<String<8>, 2> = from_slice(data).unwrap();
+    assert_eq!(names, ["snow ☃", "x"]);
+    assert_eq!(from_slice::<Vec<String<8>, 1>>(data), Err(DecodeError::Capacity));
+    assert_eq!(from_slice::<Vec<String<5>, 2>>(data), Err(DecodeError::Capacity));
+    assert_eq!(from_slice::<Vec<KimStr<'_>, 2>>(data).unwrap()[1], "x");
+
+    let value = Value::Record(Box::new([
+        ("ids".to_string(), Value::Array(vec![Value::Null, Value::Integer(300)])),
+        ("more".to_string(), Value::Array(vec![])),
+        ("x".to_string(), Value::Array(vec![Value::Integer(1)])),
+    ].into_iter().collect()));
+    let len = value.serialize_to_slice(&mut buf2).unwrap();
+    let data = &buf2[..len];
+    let ids: FnvIndexMap<String<4>, Vec<Option<u16>, 2>, 4> = from_slice(data).unwrap();
+    assert_eq!(ids["ids"], [None, Some(300)]);
+    assert!(ids["more"].is_empty());
+    assert_eq!(from_slice::<FnvIndexMap<String<4>, Vec<Option<u16>, 2>, 2>>(data), Err(DecodeError::Capacity));
+    assert_eq!(from_slice::<FnvIndexMap<String<4>, Vec<Option<u8>, 2>, 4>>(data), Err(DecodeError::Type));
+    assert_eq!(from_slice::<FnvIndexMap<String<4>, Vec<u16, 2>, 4>>(data), Err(DecodeError::Type));
+
+    let len = Value::Integer(i128::MIN).serialize_to_slice(&mut buf).unwrap();
+    assert_eq!(from_slice::<i128>(&buf[..len]), Ok(i128::MIN));
+    assert_eq!(from_slice::<u8>(&[0x81, 0xC0]), Err(DecodeError::TrailingData));
+    assert_eq!(from_slice::<bool>(&[0x61, 0x21, 0x61, 0xC0, 0x21, 0x61, 0xC1]), Err(DecodeError::Type));
+    assert_eq!(from_slice::<FnvIndexMap<String<1>, bool, 2>>(&[0x62, 0x21, 0x61, 0xC0, 0x21, 0x61, 0xC1]), Err(DecodeError::DuplicateKey));
+}
@@ -0,0 +1,188 @@
+//! Sending and receiving values as [`http_body::Body`], for hyper and tower services
+//!
+//! [`NotaBody`] serializes the value incrementally, one chunk at a time, instead of producing the whole message up front.
+//! [`from_body`] decodes chunks as they arrive, without collecting the body first.
+use crate::{serialize_string, serialize_unsigned_preamble, Decoder, Error, Value};
+use bytes::{Buf, Bytes};
+use http_body::{Body, Frame, SizeHint};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::hash_map;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::{io, vec};
+
+/// Bodies are sent in chunks of about this many bytes. Large texts and blobs are sent in one chunk.
+pub const DEFAULT_CHUNK_SIZE: usize = 16 << 10;
+
+/// Body that serializes a [`Value`] as it's being sent
+#[derive(Debug)]
+pub struct NotaBody {
+    next: Option<Value>,
+    stack: Vec<Pending>,
+    chunk_size: usize,
+}
+
+#[derive(Debug)]
+enum Pending {
+    Array(vec::IntoIter<Value>),
+    Record(hash_map::IntoIter<String, Value>),
+}
+
+impl NotaBody {
+    #[must_use]
+    pub fn new(value: Value) -> Self {
+        Self { next: Some(value), stack: Vec::new(), chunk_size: DEFAULT_CHUNK_SIZE }
+    }
+
+    /// Converts any serializable type using the [serde integration](crate::serde)
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<Self, Error> {
+        Ok(Self::new(crate::serde::to_value(value)?))
+    }
+
+    #[must_use]
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    fn next_value(&mut self, chunk: &mut Vec<u8>) -> Option<Value> {
+        if let Some(value) = self.next.take() {
+            return Some(value);
+        }
+        loop {
+            match self.stack.last_mut()? {
+                Pending::Array(items) => if let Some(item) = items.next() {
+                    return Some(item);
+                },
+                Pending::Record(fields) => if let Some((k, v)) = fields.next() {
+                    serialize_string(&k, chunk);
+                    return Some(v);
+                },
+            }
+            self.stack.pop();
+        }
+    }
+
+    fn fill(&mut self, chunk: &mut Vec<u8>) -> Result<(), Error> {
+        while chunk.len() < self.chunk_size {
+            let Some(value) = self.next_value(chunk) else { break };
+            match value {
+                Value::Array(items) => {
+                    serialize_unsigned_preamble(0b0100_0000, items.len() as u128, chunk);
+                    self.stack.push(Pending::Array(items.into_iter()));
+                },
+                Value::Record(fields) => {
+                    serialize_unsigned_preamble(0b0110_0000, fields.len() as u128, chunk);
+                    self.stack.push(Pending::Record(fields.into_iter()));
+                },
+                scalar => scalar.try_serialize_into(chunk)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Body for NotaBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, io::Error>>> {
+        let this = self.get_mut();
+        let mut chunk = Vec::new();
+        if let Err(err) = this.fill(&mut chunk) {
+            this.next = None;
+            this.stack.clear();
+            return Poll::Ready(Some(Err(err.into())));
+        }
+        Poll::Ready(if chunk.is_empty() { None } else { Some(Ok(Frame::data(chunk.into()))) })
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.next.is_none() && self.stack.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        if self.is_end_stream() { SizeHint::with_exact(0) } else { SizeHint::default() }
+    }
+}
+
+/// Decodes one value from the body, as the data arrives.
+///
+/// Bodies longer than `limit` bytes are rejected with [`Error::FrameTooLarge`]. Data after the value is an error.
+pub async fn from_body<B>(body: B, limit: usize) -> Result<Value, io::Error>
+where
+    B: Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let mut body = std::pin::pin!(body);
+    let mut decoder = Decoder::new();
+    let mut received = 0;
+    let mut value = None;
+    while let Some(frame) = std::future::poll_fn(|cx| body.as_mut().poll_frame(cx)).await {
+        let Ok(mut data) = frame.map_err(io::Error::other)?.into_data() else {
+            continue; // trailers
+        };
+        while data.has_remaining() {
+            let chunk = data.chunk();
+            received += chunk.len();
+            if received > limit {
+                return Err(Error::FrameTooLarge { len: received as u64, max: limit }.into());
+            }
+            if value.is_some() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "trailing bytes in body"));
+            }
+            let (used, decoded) = decoder.push(chunk)?;
+            if used < chunk.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "trailing bytes in body"));
+            }
+            value = decoded;
+            let len = chunk.len();
+            data.advance(len);
+        }
+    }
+    value.ok_or_else(|| io::ErrorKind::UnexpectedEof.into())
+}
+
+/// Decodes the body into any deserializable type, using the [serde integration](crate::serde). See [`from_body`].
+pub async fn from_body_as<T, B>(body: B, limit: usize) -> Result<T, io::Error>
+where
+    T: DeserializeOwned,
+    B: Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    Ok(crate::serde::from_value(from_body(body, limit).await?)?)
+}
+
+#[test]
+fn stream_roundtrip() {
+    use crate::block_on;
+
+    let value = Value::Record(Box::new([
+        ("list".to_string(), Value::Array((0..1000).map(Value::Integer).collect())),
+        ("text".to_string(), Value::Text("hello".into())),
+        ("empty".to_string(), Value::Array(vec![])),
+    ].into_iter().collect()));
+    let mut expected = Vec::new();
+    value.serialize_into(&mut expected);
+
+    let mut body = NotaBody::new(value.clone()).with_chunk_size(100);
+    let mut chunks = 0;
+    let mut streamed = Vec::new();
+    while let Some(frame) = block_on(std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx))) {
+        streamed.extend_from_slice(&frame.unwrap().into_data().unwrap());
+        chunks += 1;
+    }
+    assert!(body.is_end_stream());
+    assert!(chunks > 10);
+    assert_eq!(streamed.len(), expected.len());
+    assert_eq!(Value::parse_from(&mut &streamed[..]).unwrap(), value);
+
+    let body = || NotaBody::new(value.clone()).with_chunk_size(7);
+    assert_eq!(block_on(from_body(body(), usize::MAX)).unwrap(), value);
+    let err = block_on(from_body(body(), 1000)).unwrap_err();
+    assert!(matches!(Error::from_io(&err), Some(Error::FrameTooLarge { .. })));
+
+    let list: Vec<i32> = block_on(from_body_as(NotaBody::from_serialize(&[1, 2, 3]).unwrap(), 100)).unwrap();
+    assert_eq!(list, [1, 2, 3]);
+}
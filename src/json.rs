@@ -0,0 +1,149 @@
+//! Conversions to and from [`serde_json::Value`]
+use crate::{Error, Value};
+use std::collections::HashMap;
+
+/// How [`Value::to_json`] represents values that JSON doesn't have, and [`Value::from_json`] numbers that Nota doesn't have
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct JsonOptions {
+    pub blobs: JsonBlobs,
+    pub big_integers: JsonBigIntegers,
+    pub floats: JsonFloats,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JsonBlobs {
+    /// Fail the conversion
+    #[default]
+    Error,
+    /// Array of byte values. Fails if the blob's length isn't a whole number of bytes.
+    Bytes,
+    /// String of `0` and `1` characters, one per bit
+    BitString,
+}
+
+/// For integers that don't fit in `i64`/`u64`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JsonBigIntegers {
+    /// Fail the conversion
+    #[default]
+    Error,
+    /// Decimal digits in a string
+    String,
+    /// Nearest `f64`, which loses precision
+    Float,
+}
+
+/// For JSON numbers with a fraction or an exponent, like `1.5` or `1e3`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JsonFloats {
+    /// Fail the conversion
+    #[default]
+    Error,
+    /// [`Value::Text`] with the number as written by `serde_json`, which turns back into a JSON string, not a number
+    Text,
+}
+
+/// Uses the default [`JsonOptions`], so numbers that aren't integers are an error
+impl TryFrom<serde_json::Value> for Value {
+    type Error = Error;
+
+    fn try_from(json: serde_json::Value) -> Result<Self, Error> {
+        Self::from_json(json, &JsonOptions::default())
+    }
+}
+
+impl Value {
+    pub fn from_json(json: serde_json::Value, options: &JsonOptions) -> Result<Self, Error> {
+        Ok(match json {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(b) => Self::Bool(b),
+            serde_json::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    Self::Integer(i.into())
+                } else if let Some(u) = n.as_u64() {
+                    Self::Integer(u.into())
+                } else {
+                    match options.floats {
+                        JsonFloats::Error => return Err(Error::Unrepresentable("float")),
+                        JsonFloats::Text => Self::Text(n.to_string()),
+                    }
+                }
+            },
+            serde_json::Value::String(s) => Self::Text(s),
+            serde_json::Value::Array(a) => Self::Array(a.into_iter().map(|v| Self::from_json(v, options)).collect::<Result<_, _>>()?),
+            serde_json::Value::Object(o) => Self::Record(Box::new(o.into_iter().map(|(k, v)| Ok((k, Self::from_json(v, options)?))).collect::<Result<HashMap<_, _>, Error>>()?)),
+        })
+    }
+
+    pub fn to_json(&self, options: &JsonOptions) -> Result<serde_json::Value, Error> {
+        Ok(match self {
+            Self::Blob(bits) => match options.blobs {
+                JsonBlobs::Error => return Err(Error::Unrepresentable("blob")),
+                JsonBlobs::Bytes if bits.len() % 8 != 0 => return Err(Error::Unrepresentable("blob with a partial byte")),
+                JsonBlobs::Bytes => bits.as_raw_slice().iter().map(|&b| serde_json::Value::from(b)).collect(),
+                JsonBlobs::BitString => bits.iter().map(|b| if *b { '1' } else { '0' }).collect::<String>().into(),
+            },
+            Self::Text(s) => s.as_str().into(),
+            Self::Array(a) => a.iter().map(|v| v.to_json(options)).collect::<Result<_, _>>()?,
+            Self::Record(r) => serde_json::Value::Object(r.iter().map(|(k, v)| Ok((k.clone(), v.to_json(options)?))).collect::<Result<_, Error>>()?),
+            Self::Integer(i) => {
+                if let Ok(i) = i64::try_from(*i) {
+                    i.into()
+                } else if let Ok(u) = u64::try_from(*i) {
+                    u.into()
+                } else {
+                    match options.big_integers {
+                        JsonBigIntegers::Error => return Err(Error::Unrepresentable("integer larger than 64 bits")),
+                        JsonBigIntegers::String => i.to_string().into(),
+                        JsonBigIntegers::Float => (*i as f64).into(),
+                    }
+                }
+            },
+            Self::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float")),
+            Self::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag")),
+            Self::Bool(b) => (*b).into(),
+            Self::Null => serde_json::Value::Null,
+        })
+    }
+}
+
+/// Uses the default [`JsonOptions`]
+impl TryFrom<Value> for serde_json::Value {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        value.to_json(&JsonOptions::default())
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let json = serde_json::json!({"a": [1, -2, true, null, "☃"], "b": {}, "big": 18446744073709551615u64, "f": 1.5});
+    assert_eq!(Value::try_from(json.clone()), Err(Error::Unrepresentable("float")));
+    let value = Value::from_json(json.clone(), &JsonOptions { floats: JsonFloats::Text, ..Default::default() }).unwrap();
+    let Value::Record(r) = &value else { panic!() };
+    assert_eq!(r["f"], Value::Text("1.5".into()));
+
+    let mut expected = json;
+    expected["f"] = "1.5".into();
+    assert_eq!(serde_json::Value::try_from(value).unwrap(), expected.clone());
+    expected.as_object_mut().unwrap().remove("f");
+    assert_eq!(serde_json::Value::try_from(Value::try_from(expected.clone()).unwrap()).unwrap(), expected);
+}
+
+#[test]
+fn options() {
+    let blob = Value::Blob(bitvec::bitvec![u8, bitvec::order::Msb0; 1, 0, 1]);
+    assert_eq!(blob.to_json(&JsonOptions::default()), Err(Error::Unrepresentable("blob")));
+    let opts = JsonOptions { blobs: JsonBlobs::BitString, big_integers: JsonBigIntegers::String, ..Default::default() };
+    assert_eq!(blob.to_json(&opts).unwrap(), serde_json::json!("101"));
+    assert_eq!(Value::Integer(i128::MIN + 1).to_json(&opts).unwrap(), serde_json::json!((i128::MIN + 1).to_string()));
+
+    let opts = JsonOptions { blobs: JsonBlobs::Bytes, ..opts };
+    assert!(blob.to_json(&opts).is_err());
+    assert_eq!(Value::Blob(vec![1u8, 255].try_into().unwrap()).to_json(&opts).unwrap(), serde_json::json!([1, 255]));
+}
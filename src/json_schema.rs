@@ -0,0 +1,273 @@
+//! Compiles JSON Schemas into [schemas](crate::schema), so that existing contracts can validate Nota documents
+//!
+//! A practical subset of JSON Schema is supported:
+//!
+//! * `type` (a name or an array of names), `enum`, `const`
+//! * `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum` (numbers, not the booleans of draft 4)
+//! * `minLength`, `maxLength`, `minItems`, `maxItems`, `items`
+//! * `properties`, `required`, `additionalProperties`
+//! * `anyOf`, `oneOf` (both accept values that match any of the types)
+//! * `$ref` to `#/$defs/…` or `#/definitions/…`
+//! * `description` as docs of records and their fields
+//!
+//! Annotations, like `title`, `format`, and `default`, are ignored. Other keywords, like `pattern`, are errors
+//! instead, so that the schema doesn't accept more than it should.
+//!
+//! Integers are the only numbers a Nota document can have, so `number` is the same as `integer`, and fractional bounds
+//! are rounded towards the allowed range (`"minimum": 0.5` accepts 1 and up). Fractional numbers in `enum` and `const`
+//! could never match, so they're errors.
+//!
+//! ```rust
+//! let json = serde_json::json!({
+//!     "type": "object",
+//!     "properties": {"id": {"type": "integer", "minimum": 1}, "name": {"type": ["string", "null"]}},
+//!     "required": ["id"],
+//!     "additionalProperties": false,
+//! });
+//! let schema = nota::json_schema::compile(&json)?;
+//! assert!(schema.validate(&r#"{"id": 1, "name": null}"#.parse()?).is_ok());
+//! assert!(schema.validate(&r#"{"id": 0}"#.parse()?).is_err());
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+use crate::path::{Path, Segment};
+use crate::schema::{Field, Record, Schema, SchemaError, Type};
+use crate::Value;
+use serde_json::{Map, Value as Json};
+
+const ANNOTATIONS: &[&str] = &[
+    "$schema", "$id", "$comment", "title", "description", "default", "examples", "format", "deprecated", "readOnly", "writeOnly",
+];
+
+/// Converts the JSON Schema, with its `$defs` or `definitions` as [named types](Schema::types)
+pub fn compile(json: &Json) -> Result<Schema, SchemaError> {
+    let mut path = Path::default();
+    let mut schema = Schema::new(Type::Any);
+    let mut defs_key = "$defs";
+    for key in ["$defs", "definitions"] {
+        let Some(defs) = json.get(key) else { continue };
+        path.push(Segment::Key(key.into()));
+        let defs = defs.as_object().ok_or_else(|| err(&path, "expected an object"))?;
+        defs_key = key;
+        for (name, def) in defs {
+            path.push(Segment::Key(name.clone()));
+            schema.types.insert(name.clone(), compile_type(def, &mut path)?);
+            path.pop();
+        }
+        path.pop();
+    }
+    schema.root = compile_type(json, &mut path)?;
+    schema.check_refs(&Path::default(), defs_key)?;
+    Ok(schema)
+}
+
+fn err(path: &Path, reason: impl Into<String>) -> SchemaError {
+    SchemaError { path: path.clone(), reason: reason.into() }
+}
+
+fn enum_value(json: &Json, key: &str, path: &Path) -> Result<Value, SchemaError> {
+    Value::try_from(json.clone()).map_err(|e| err(path, format!("`{key}` value: {e}")))
+}
+
+fn compile_type(json: &Json, path: &mut Path) -> Result<Type, SchemaError> {
+    let object = match json {
+        Json::Bool(true) => return Ok(Type::Any),
+        Json::Bool(false) => return Ok(Type::Enum(Vec::new())),
+        Json::Object(object) => object,
+        _ => return Err(err(path, "expected an object or a boolean")),
+    };
+    if let Some(reference) = object.get("$ref") {
+        if let Some(key) = object.keys().find(|&k| k != "$ref" && !ANNOTATIONS.contains(&k.as_str())) {
+            return Err(err(path, format!("`{key}` next to `$ref` isn't supported")));
+        }
+        return reference.as_str().and_then(ref_name).map(Type::Ref)
+            .ok_or_else(|| err(path, "only `$ref`s to `#/$defs/…` or `#/definitions/…` are supported"));
+    }
+    let mut alternatives = Vec::new();
+    for (key, value) in object {
+        match key.as_str() {
+            "enum" => {
+                let values = value.as_array().ok_or_else(|| err(path, "expected an array of `enum` values"))?;
+                alternatives.push(Type::Enum(values.iter().map(|v| enum_value(v, key, path)).collect::<Result<_, _>>()?));
+            },
+            "const" => alternatives.push(Type::Enum(vec![enum_value(value, key, path)?])),
+            "anyOf" | "oneOf" => {
+                path.push(Segment::Key(key.clone()));
+                let types = value.as_array().ok_or_else(|| err(path, "expected an array"))?;
+                let types = types.iter().enumerate().map(|(i, ty)| {
+                    path.push(Segment::Index(i));
+                    let ty = compile_type(ty, path);
+                    path.pop();
+                    ty
+                }).collect::<Result<_, _>>()?;
+                path.pop();
+                alternatives.push(Type::OneOf(types));
+            },
+            "type" | "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" | "minLength" | "maxLength" | "minItems" | "maxItems"
+            | "items" | "properties" | "required" | "additionalProperties" | "$defs" | "definitions" => {},
+            key if ANNOTATIONS.contains(&key) => {},
+            key => return Err(err(path, format!("`{key}` isn't supported"))),
+        }
+    }
+    let types: Vec<&str> = match object.get("type") {
+        None => infer_type(object).into_iter().collect(),
+        Some(Json::String(name)) => vec![name],
+        Some(Json::Array(names)) => names.iter().map(|n| n.as_str().ok_or_else(|| err(path, "expected names of `type`s"))).collect::<Result<_, _>>()?,
+        Some(_) => return Err(err(path, "expected a name or an array of `type`s")),
+    };
+    if !types.is_empty() || object.contains_key("type") {
+        let types = types.into_iter().map(|name| typed(name, object, path)).collect::<Result<Vec<_>, _>>()?;
+        alternatives.push(if types.len() == 1 { types.into_iter().next().unwrap() } else { Type::OneOf(types) });
+    }
+    // every alternative must match, but they can't be combined in general, so only one is supported
+    match alternatives.len() {
+        0 => Ok(Type::Any),
+        1 => Ok(alternatives.pop().unwrap()),
+        _ => Err(err(path, "only one of `type`, `enum`, `const`, `anyOf`, or `oneOf` is supported")),
+    }
+}
+
+/// Without `type`, keywords only apply to their type, but the type is most likely the one they're for
+fn infer_type(object: &Map<String, Json>) -> Option<&'static str> {
+    let has = |keys: &[&str]| keys.iter().any(|&k| object.contains_key(k));
+    if has(&["properties", "required", "additionalProperties"]) {
+        Some("object")
+    } else if has(&["items", "minItems", "maxItems"]) {
+        Some("array")
+    } else if has(&["minLength", "maxLength"]) {
+        Some("string")
+    } else if has(&["minimum", "maximum", "exclusiveMinimum", "exclusiveMaximum"]) {
+        Some("integer")
+    } else {
+        None
+    }
+}
+
+fn typed(name: &str, object: &Map<String, Json>, path: &mut Path) -> Result<Type, SchemaError> {
+    let len = |key: &str| -> Result<Option<usize>, SchemaError> {
+        object.get(key).map(|v| v.as_u64().and_then(|n| n.try_into().ok()).ok_or_else(|| err(path, format!("invalid `{key}`")))).transpose()
+    };
+    Ok(match name {
+        "null" => Type::Null,
+        "boolean" => Type::Bool,
+        "integer" | "number" => {
+            let bound = |key: &str, round: fn(f64) -> f64, offset: i128| -> Result<Option<i128>, SchemaError> {
+                object.get(key).map(|v| match (v.as_i64(), v.as_u64(), v.as_f64()) {
+                    (Some(n), _, _) => Ok(i128::from(n) + offset),
+                    (_, Some(n), _) => Ok(i128::from(n) + offset),
+                    (_, _, Some(f)) if f.is_finite() => Ok(round(f) as i128 + offset),
+                    _ => Err(err(path, format!("invalid `{key}`"))),
+                }).transpose()
+            };
+            let min = [bound("minimum", f64::ceil, 0)?, bound("exclusiveMinimum", f64::floor, 1)?].into_iter().flatten().max();
+            let max = [bound("maximum", f64::floor, 0)?, bound("exclusiveMaximum", f64::ceil, -1)?].into_iter().flatten().min();
+            Type::Integer { min, max }
+        },
+        "string" => Type::Text { min_len: len("minLength")?, max_len: len("maxLength")? },
+        "array" => {
+            let (min_len, max_len) = (len("minItems")?, len("maxItems")?);
+            let items = match object.get("items") {
+                Some(items) => {
+                    path.push(Segment::Key("items".into()));
+                    let items = compile_type(items, path)?;
+                    path.pop();
+                    items
+                },
+                None => Type::Any,
+            };
+            Type::Array { items: Box::new(items), min_len, max_len }
+        },
+        "object" => Type::Record(record(object, path)?),
+        name => return Err(err(path, format!("unknown type `{name}`"))),
+    })
+}
+
+fn record(object: &Map<String, Json>, path: &mut Path) -> Result<Record, SchemaError> {
+    let required = match object.get("required") {
+        Some(required) => required.as_array().and_then(|r| r.iter().map(Json::as_str).collect::<Option<Vec<_>>>())
+            .ok_or_else(|| err(path, "expected an array of `required` names"))?,
+        None => Vec::new(),
+    };
+    let mut record = Record { doc: object.get("description").and_then(Json::as_str).map(From::from), ..Record::default() };
+    record.extra = match object.get("additionalProperties") {
+        Some(Json::Bool(false)) => None,
+        None | Some(Json::Bool(true)) => Some(Box::new(Type::Any)),
+        Some(extra) => {
+            path.push(Segment::Key("additionalProperties".into()));
+            let extra = compile_type(extra, path)?;
+            path.pop();
+            Some(Box::new(extra))
+        },
+    };
+    if let Some(properties) = object.get("properties") {
+        path.push(Segment::Key("properties".into()));
+        let properties = properties.as_object().ok_or_else(|| err(path, "expected an object"))?;
+        for (name, property) in properties {
+            path.push(Segment::Key(name.clone()));
+            let mut field = Field::new(name.clone(), compile_type(property, path)?);
+            field.optional = !required.contains(&name.as_str());
+            field.doc = property.get("description").and_then(Json::as_str).map(From::from);
+            record.fields.push(field);
+            path.pop();
+        }
+        path.pop();
+    }
+    // required fields without a schema can be anything
+    for name in required {
+        if !record.fields.iter().any(|f| f.name == name) {
+            record.fields.push(Field::new(name, Type::Any));
+        }
+    }
+    record.fields.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    Ok(record)
+}
+
+/// Name from a `#/$defs/name` JSON pointer
+fn ref_name(reference: &str) -> Option<String> {
+    let name = reference.strip_prefix("#/$defs/").or_else(|| reference.strip_prefix("#/definitions/"))?;
+    (!name.contains('/')).then(|| name.replace("~1", "/").replace("~0", "~"))
+}
+
+#[test]
+fn contracts() {
+    use serde_json::json;
+
+    let schema = compile(&json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Order",
+        "description": "An order",
+        "type": "object",
+        "properties": {
+            "id": {"type": "integer", "exclusiveMinimum": 0, "maximum": 1e3},
+            "status": {"enum": ["new", "paid"]},
+            "lines": {"type": "array", "items": {"$ref": "#/$defs/line"}, "minItems": 1},
+            "note": {"type": ["string", "null"], "maxLength": 5, "description": "For the courier"},
+            "meta": {"additionalProperties": {"type": "string"}}
+        },
+        "required": ["id", "status", "lines", "version"],
+        "additionalProperties": false,
+        "$defs": {
+            "line": {"type": "object", "properties": {"sku": {"const": "x"}, "qty": {"type": "integer", "minimum": 1.5}}, "required": ["sku"]}
+        }
+    })).unwrap();
+    let Type::Record(order) = &schema.root else { panic!() };
+    assert_eq!(order.doc.as_deref(), Some("An order"));
+    assert_eq!(order.field("note").unwrap().doc.as_deref(), Some("For the courier"));
+    assert!(order.field("note").unwrap().optional);
+    assert!(!order.field("version").unwrap().optional);
+    assert_eq!(order.field("id").unwrap().ty, Type::Integer { min: Some(1), max: Some(1000) });
+
+    let check = |doc: &str| schema.validate(&doc.parse().unwrap()).map_err(|e| e.to_string());
+    assert_eq!(check(r#"{"id": 1, "status": "new", "version": null, "lines": [{"sku": "x", "qty": 2}], "note": null, "meta": {"a": "b"}}"#), Ok(()));
+    assert_eq!(check(r#"{"id": 0, "status": "new", "version": 1, "lines": [{"sku": "x"}]}"#).unwrap_err(), "at id: 0 is less than 1");
+    assert_eq!(check(r#"{"id": 1, "status": "new", "version": 1, "lines": []}"#).unwrap_err(), "at lines: length 0 is less than 1");
+    assert_eq!(check(r#"{"id": 1, "status": "new", "version": 1, "lines": [{"sku": "x", "qty": 1}]}"#).unwrap_err(), "at lines[0].qty: 1 is less than 2");
+    assert_eq!(check(r#"{"id": 1, "status": "old", "version": 1, "lines": [{"sku": "x"}]}"#).unwrap_err(), r#"at status: "old" is not one of ["new", "paid"]"#);
+    assert_eq!(check(r#"{"id": 1, "status": "new", "version": 1, "lines": [{"sku": "x"}], "meta": {"a": 1}}"#).unwrap_err(), "at meta.a: expected text, found integer");
+    assert_eq!(check(r#"{"id": 1, "status": "new", "version": 1, "lines": [{"sku": "x"}], "x": 1}"#).unwrap_err(), "at .: unexpected field `x`");
+
+    let invalid = |json: Json| compile(&json).unwrap_err().to_string();
+    assert_eq!(invalid(json!({"type": "string", "pattern": "^a"})), "at .: `pattern` isn't supported");
+    assert_eq!(invalid(json!({"enum": [1, 1.5]})), "at .: `enum` value: float can't be converted");
+    assert_eq!(invalid(json!({"items": {"$ref": "#/$defs/nope"}})), "at .: undefined type `nope`");
+    assert_eq!(invalid(json!({"properties": {"a": {"$ref": "other.json"}}})), "at properties.a: only `$ref`s to `#/$defs/…` or `#/definitions/…` are supported");
+}
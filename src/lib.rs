@@ -1,29 +1,199 @@
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "blobs")]
 use bitvec::prelude::Msb0;
+#[cfg(feature = "blobs")]
 use bitvec::vec::BitVec;
+#[cfg(feature = "records")]
 use std::collections::HashMap;
-use std::io::Read;
+use std::io::{BufRead, Read};
 use std::io;
 
+#[cfg(all(
+    not(all(feature = "blobs", feature = "records", feature = "dec64")),
+    any(feature = "arrow", feature = "apache-avro", feature = "bson", feature = "capi", feature = "ciborium", feature = "csv",
+        feature = "napi", feature = "python", feature = "redis", feature = "rmpv", feature = "rusqlite", feature = "serde",
+        feature = "serde_json", feature = "serde_yaml", feature = "sqlx", feature = "toml", feature = "uniffi", feature = "wasm"),
+))]
+compile_error!("conversions to other formats need the `blobs`, `records`, and `dec64` features");
+
+#[cfg(feature = "actix-web")]
+pub mod actix;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "apache-avro")]
+pub mod avro;
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "bson")]
+pub mod bson;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "ciborium")]
+pub mod cbor;
+#[cfg(feature = "chrono")]
+pub mod chrono;
+#[cfg(feature = "cobs")]
+pub mod cobs;
+#[cfg(feature = "records")]
+pub mod codegen;
+mod const_writer;
+mod constants;
+#[cfg(feature = "csv")]
+pub mod csv;
+mod decoder;
+#[cfg(feature = "defmt")]
+pub mod defmt;
+#[cfg(feature = "records")]
+pub mod describe;
+pub mod diag;
+#[cfg(feature = "digest")]
+pub mod digest;
+mod dictionary;
+pub mod duration;
+mod error;
+#[cfg(feature = "records")]
+mod field_ids;
+#[cfg(feature = "figment")]
+pub mod figment;
+pub mod framing;
+pub mod header;
+#[cfg(feature = "heapless")]
+pub mod heapless;
+#[cfg(feature = "http-body")]
+pub mod http_body;
+#[cfg(feature = "serde_json")]
+pub mod json;
+#[cfg(feature = "serde_json")]
+pub mod json_schema;
+pub mod log;
+#[cfg(feature = "rmpv")]
+pub mod msgpack;
+#[cfg(any(feature = "actix-web", feature = "axum", feature = "reqwest"))]
+mod mime;
+#[cfg(feature = "records")]
+pub mod migrate;
+#[cfg(feature = "memmap2")]
+pub mod mmap;
+#[cfg(feature = "blobs")]
+pub mod net;
+#[cfg(feature = "napi")]
+pub mod node;
+mod order;
+#[cfg(feature = "blobs")]
+pub mod packed;
+mod parser;
+pub mod path;
+mod pool;
+mod progress;
+pub mod pull;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod query;
+#[cfg(feature = "rayon")]
+pub mod rayon;
+pub mod recover;
+mod recycle;
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(feature = "reqwest")]
+pub mod reqwest;
+#[cfg(feature = "rusqlite")]
+pub mod rusqlite;
+#[cfg(feature = "records")]
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod serializer;
+#[cfg(feature = "signing")]
+pub mod signing;
+mod slice_parser;
+#[cfg(feature = "sqlx")]
+pub mod sqlx;
+mod stats;
+#[cfg(feature = "records")]
+pub mod tagged;
+#[cfg(all(feature = "blobs", feature = "records"))]
+pub mod test_vectors;
+pub mod testing;
+#[cfg(feature = "time")]
+pub mod time;
+#[cfg(feature = "toml")]
+pub mod toml;
+mod tree;
+#[cfg(feature = "ufmt")]
+pub mod ufmt;
+#[cfg(feature = "uniffi")]
+pub mod uniffi;
+#[cfg(feature = "uuid")]
+pub mod uuid;
+pub mod validate;
+mod varint;
+pub mod visit;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod writer;
+#[cfg(feature = "serde_yaml")]
+pub mod yaml;
+#[cfg(feature = "zstd")]
+pub mod zstd;
+pub use const_writer::ConstWriter;
+pub use constants::Constants;
+pub use decoder::Decoder;
+pub use dictionary::Dictionary;
+pub use error::{Error, ErrorCode};
+#[cfg(feature = "records")]
+pub use field_ids::FieldIds;
+pub use parser::ParseOptions;
+pub use pool::{BufferPool, PooledBuffer};
+pub use progress::{Progress, ProgressCallback};
+pub use recycle::Recycler;
+pub use serializer::SerializeOptions;
+pub use stats::Stats;
+pub use writer::{BufferTooSmall, SliceWriteError, SliceWriter};
+use parser::{Buffered, Parser, Unbuffered};
+use serializer::Serializer;
+
+#[cfg(feature = "uniffi")]
+::uniffi::setup_scaffolding!();
+
 #[derive(Debug, Clone, PartialEq)]
 #[non_exhaustive]
 pub enum Value {
     /// This stores *bits*, not bytes.
+    #[cfg(feature = "blobs")]
     Blob(BitVec<u8, Msb0>),
     Text(String),
     Array(Vec<Value>),
-    Record(HashMap<String, Value>),
+    /// Boxed, because a `HashMap` is twice as large as the other variants, and would make every `Value` larger
+    #[cfg(feature = "records")]
+    Record(Box<HashMap<String, Value>>),
     Integer(i128),
+    #[cfg(feature = "dec64")]
     #[allow(deprecated)]
     DecimalFloat(DecimalFloat),
     Bool(bool),
+    Null,
+    /// A value with a reserved tag, or a constant that isn't defined yet, kept as it was encoded.
+    ///
+    /// Only parsed with [`ParseOptions::unknown`]. It's serialized back unchanged.
+    Unknown {
+        /// The top 3 bits of the first byte
+        tag: u8,
+        /// The whole encoded value, including the tag
+        raw_bytes: Box<[u8]>,
+    },
 }
 
 /// Conversion from `f32`/`f64` is going to be tricky, see the [`ryu`](https://lib.rs/crates/ryu) crate.
 /// ```js
 /// value = coefficient * power(10, exponent)
 /// ```
+#[cfg(feature = "dec64")]
 #[derive(Debug, Clone, PartialEq)]
 #[deprecated(note = "this unimplemented, and likely to be removed")]
 pub struct DecimalFloat {
@@ -31,159 +201,140 @@ pub struct DecimalFloat {
     pub coefficient: i64,
 }
 
-fn serialize_signed_preamble(header: u8, value: i128, into: &mut Vec<u8>) {
-    let (sign_bit, value) = if value < 0 {
-        (1, -value as u128)
-    } else {
-        (0, value as u128)
-    };
+pub(crate) fn serialize_signed_preamble(header: u8, value: i128, into: &mut impl Extend<u8>) {
+    let (sign_bit, value) = (u8::from(value < 0), value.unsigned_abs());
 
     let minimum_bit_len = 128 - value.leading_zeros();
-    let mut bit_len = 3 + ((minimum_bit_len.saturating_sub(3) + 6) / 7) * 7;
+    let mut bit_len = 3 + minimum_bit_len.saturating_sub(3).div_ceil(7) * 7;
 
     let next = (value >> (bit_len as i32 - 3) & 0b111) as u8;
-    into.push(header | (sign_bit << 3) | next | if bit_len > 3 { 0b0001_0000 } else { 0 });
+    into.extend([header | (sign_bit << 3) | next | if bit_len > 3 { 0b0001_0000 } else { 0 }]);
     bit_len -= 3;
     serialize_integer_continuation(value, bit_len, into);
 }
 
-fn serialize_unsigned_preamble(header: u8, value: u128, into: &mut Vec<u8>) {
+pub(crate) fn serialize_unsigned_preamble(header: u8, value: u128, into: &mut impl Extend<u8>) {
     let minimum_bit_len = 128 - value.leading_zeros();
-    let mut bit_len = 4 + ((minimum_bit_len.saturating_sub(4) + 6) / 7) * 7;
+    let mut bit_len = 4 + minimum_bit_len.saturating_sub(4).div_ceil(7) * 7;
 
     let next = (value >> (bit_len as i32 - 4) & 0b1111) as u8;
-    into.push(header | next | if bit_len > 4 { 0b0001_0000 } else { 0 });
+    into.extend([header | next | if bit_len > 4 { 0b0001_0000 } else { 0 }]);
     bit_len -= 4;
     serialize_integer_continuation(value, bit_len, into);
 }
 
-fn serialize_integer_continuation(value: u128, mut bit_len: u32, into: &mut Vec<u8>) {
-    while bit_len > 0 {
-        let next = (value >> (bit_len as i32 - 7)) as u8 & 0b111_1111;
-        let c = if bit_len > 7 { 0b1000_0000 } else { 0 };
-        into.push(next | c);
-        if bit_len <= 7 { break; }
-        bit_len -= 7;
+/// `bit_len` is a multiple of 7
+fn serialize_integer_continuation(value: u128, bit_len: u32, into: &mut impl Extend<u8>) {
+    let mut len = bit_len / 7;
+    // groups that don't fit in the u64 below
+    while len > 8 {
+        len -= 1;
+        into.extend([(value >> (7 * len)) as u8 & 0b0111_1111 | 0b1000_0000]);
     }
+    if len == 0 {
+        return;
+    }
+    // spreads 7-bit groups into bytes, the least significant group in the lowest byte
+    let mut groups = value as u64 & 0x00FF_FFFF_FFFF_FFFF;
+    groups = (groups & 0x0000_0000_0FFF_FFFF) | (groups & 0x00FF_FFFF_F000_0000) << 4;
+    groups = (groups & 0x0000_3FFF_0000_3FFF) | (groups & 0x0FFF_C000_0FFF_C000) << 2;
+    groups = (groups & 0x007F_007F_007F_007F) | (groups & 0x3F80_3F80_3F80_3F80) << 1;
+    let continuation = 0x8080_8080_8080_8000 & (u64::MAX >> (64 - 8 * len));
+    let bytes = (groups | continuation).to_be_bytes();
+    into.extend(bytes[8 - len as usize..].iter().copied());
 }
 
 impl Value {
+    /// # Panics
+    ///
+    /// If the value contains a DEC64 float, which can't be serialized. [`try_serialize_into`](Self::try_serialize_into) returns an error instead.
     pub fn serialize_into(&self, into: &mut Vec<u8>) {
+        self.serialize_with(into, &SerializeOptions::default());
+    }
+
+    /// # Panics
+    ///
+    /// Same as [`serialize_into`](Self::serialize_into)
+    pub fn serialize_with(&self, into: &mut Vec<u8>, options: &SerializeOptions) {
+        if let Err(err) = self.try_serialize_with(into, options) {
+            panic!("{err}");
+        }
+    }
+
+    /// Fails with [`Error::Unrepresentable`] if the value contains a DEC64 float. Nothing is appended then.
+    pub fn try_serialize_into(&self, into: &mut Vec<u8>) -> Result<(), Error> {
+        self.try_serialize_with(into, &SerializeOptions::default())
+    }
+
+    pub fn try_serialize_with(&self, into: &mut Vec<u8>, options: &SerializeOptions) -> Result<(), Error> {
+        into.reserve(self.serialized_size());
+        let start = into.len();
+        let res = Serializer::new(into, options).serialize(self);
+        if res.is_err() {
+            into.truncate(start);
+        }
+        res
+    }
+
+    /// Exact number of bytes [`serialize_into`](Self::serialize_into) will write.
+    ///
+    /// DEC64 floats can't be serialized, and count as 0 bytes.
+    #[must_use]
+    pub fn serialized_size(&self) -> usize {
         match self {
-            Value::Blob(val) => {
-                serialize_unsigned_preamble(0, val.len() as u128, into);
-                debug_assert_eq!((val.len() + 7) / 8, val.as_raw_slice().len());
-                into.extend_from_slice(val.as_raw_slice());
-            },
-            Value::Text(val) => {
-                serialize_string(val, into);
-            },
-            Value::Array(val) => {
-                serialize_unsigned_preamble(0b0100_0000, val.len() as u128, into);
-                for v in val {
-                    v.serialize_into(into);
-                }
-            },
-            Value::Record(val) => {
-                serialize_unsigned_preamble(0b0110_0000, val.len() as u128, into);
-                for (k, v) in val {
-                    serialize_string(k, into);
-                    v.serialize_into(into);
-                }
-            },
-            Value::Integer(val) => {
-                serialize_signed_preamble(0b1000_0000, *val, into);
-            },
-            Value::DecimalFloat(_val) => {
-                unimplemented!("this platform uses IEEE754 floats, not DEC64 floats");
-            },
-            Value::Bool(val) => {
-                into.push(0b1100_0000 | u8::from(*val));
-            },
+            #[cfg(feature = "blobs")]
+            Self::Blob(val) => unsigned_preamble_size(val.len() as u128, 4) + val.len().div_ceil(8),
+            Self::Text(val) => string_size(val),
+            Self::Array(val) => unsigned_preamble_size(val.len() as u128, 4) + val.iter().map(Self::serialized_size).sum::<usize>(),
+            #[cfg(feature = "records")]
+            Self::Record(val) => unsigned_preamble_size(val.len() as u128, 4) + val.iter().map(|(k, v)| string_size(k) + v.serialized_size()).sum::<usize>(),
+            Self::Integer(val) => unsigned_preamble_size(val.unsigned_abs(), 3),
+            #[cfg(feature = "dec64")]
+            Self::DecimalFloat(_) => 0,
+            Self::Bool(_) | Self::Null => 1,
+            Self::Unknown { raw_bytes, .. } => raw_bytes.len(),
         }
     }
 
     pub fn parse_from<R: Read>(reader: &mut R) -> Result<Self, io::Error> {
-        let mut preamble = 0;
-        reader.read_exact(std::slice::from_mut(&mut preamble))?;
-        let kind = preamble & 0b1110_0000;
-        Ok(match kind {
-            0b0000_0000 => {
-                let len = parse_len(preamble, reader)?;
-                let len_bytes = (len + 7) / 8;
-                let mut out = Vec::new();
-                reader.take(len_bytes as u64).read_to_end(&mut out)?;
-                if out.len() != len_bytes {
-                    return Err(io::ErrorKind::UnexpectedEof.into());
-                }
-                let mut out = BitVec::from_vec(out);
-                if len & 7 != 0 {
-                    out.truncate(len);
-                }
-                Self::Blob(out)
-            },
-            0b0010_0000 => {
-                let len = parse_len(preamble, reader)?;
-                let mut out = String::with_capacity(len.min(1 << 20));
-                for _ in 0..len {
-                    out.push(read_kim_char(reader)?);
-                }
-                Self::Text(out)
-            },
-            0b0100_0000 => {
-                let len = parse_len(preamble, reader)?;
-                let mut out = Vec::with_capacity(len.min(1 << 18));
-                for _ in 0..len {
-                    out.push(Self::parse_from(reader)?);
-                }
-                Self::Array(out)
-            },
-            0b0110_0000 => {
-                let len = parse_len(preamble, reader)?;
-                let mut out = HashMap::with_capacity(len.min(1 << 16));
-                for _ in 0..len {
-                    let k = Self::parse_from(reader)?;
-                    let v = Self::parse_from(reader)?;
-                    if let Value::Text(k) = k {
-                        out.insert(k, v);
-                    } else {
-                        return Err(io::Error::from(io::ErrorKind::InvalidData).into());
-                    }
-                }
-                Self::Record(out)
-            },
-            0b1000_0000 => {
-                let sign = preamble & 0b000_1000;
-                let mut val = (preamble & 0b000_0111) as u128;
-                if preamble & 0b0001_0000 != 0 {
-                    loop {
-                        val <<= 7;
-                        let mut next = 0;
-                        reader.read_exact(std::slice::from_mut(&mut next))?;
-                        val |= (next & 0b0111_1111) as u128;
-                        if next & 0b1000_0000 == 0 {
-                            break;
-                        }
-                    }
-                }
-                Self::Integer(if sign == 0 { val as i128 } else { -(val as i128) })
-            },
-            0b1010_0000 => unimplemented!("this platform uses IEEE754 floats, not DEC64 floats"),
-            0b1100_0000 => {
-                let val = preamble & 0b0001_1111;
-                match val {
-                    0 => Self::Bool(false),
-                    1 => Self::Bool(true),
-                    _ => return Err(io::ErrorKind::Unsupported.into()),
-                }
-            },
-            _ => return Err(io::ErrorKind::InvalidData.into()),
-        })
+        Self::parse_with(reader, &ParseOptions::default())
+    }
+
+    pub fn parse_with<R: Read>(reader: &mut R, options: &ParseOptions) -> Result<Self, io::Error> {
+        Parser::new(Unbuffered(reader), options).parse()
+    }
+
+    /// Parses and also counts what's in the document
+    pub fn parse_with_stats<R: Read>(reader: &mut R, options: &ParseOptions) -> Result<(Self, Stats), io::Error> {
+        Parser::new(Unbuffered(reader), options).parse_with_stats()
     }
+
+    /// Takes bytes directly from the reader's buffer, which is faster than [`parse_from`](Self::parse_from) with a `BufReader`.
+    ///
+    /// Bytes after the value are left in the reader.
+    pub fn parse_buf<R: BufRead>(reader: &mut R) -> Result<Self, io::Error> {
+        Self::parse_buf_with(reader, &ParseOptions::default())
+    }
+
+    pub fn parse_buf_with<R: BufRead>(reader: &mut R, options: &ParseOptions) -> Result<Self, io::Error> {
+        Parser::new(Buffered(reader), options).parse()
+    }
+}
+
+/// Preamble with `first_bits` of the value, and 7 bits in each following byte
+pub(crate) fn unsigned_preamble_size(value: u128, first_bits: u32) -> usize {
+    1 + (128 - value.leading_zeros()).saturating_sub(first_bits).div_ceil(7) as usize
+}
+
+pub(crate) fn string_size(val: &str) -> usize {
+    let (chars, bytes) = val.chars().fold((0, 0), |(chars, bytes), c| {
+        (chars + 1, bytes + match c as u32 { 0..0x80 => 1, 0x80..0x4000 => 2, _ => 3 })
+    });
+    unsigned_preamble_size(chars, 4) + bytes
 }
 
 #[inline(never)]
-fn serialize_string(val: &str, into: &mut Vec<u8>) {
+pub(crate) fn serialize_string(val: &str, into: &mut impl Extend<u8>) {
     let char_len = val.chars().count();
     serialize_unsigned_preamble(0b0010_0000, char_len as u128, into);
     for c in val.chars() {
@@ -191,53 +342,67 @@ fn serialize_string(val: &str, into: &mut Vec<u8>) {
     }
 }
 
-fn read_kim_char<R: Read>(reader: &mut R) -> Result<char, io::Error> {
-    let mut val = 0;
-    loop {
-        let mut next = 0;
-        reader.read_exact(std::slice::from_mut(&mut next))?;
-        val |= next as u32 & 0b0111_1111;
-        if next & 0b1000_0000 == 0 {
-            return char::from_u32(val).ok_or(io::Error::from(io::ErrorKind::InvalidData));
-        }
-        val <<= 7;
-    }
-}
-
-fn write_kim_char(code_point: char, into: &mut Vec<u8>) {
+fn write_kim_char(code_point: char, into: &mut impl Extend<u8>) {
     let val = code_point as u32;
+    debug_assert!(val < 1 << 21, "kim has at most 3 bytes per char");
     if val < 0x80 {
-        into.push(val as u8);
+        into.extend([val as u8]);
     } else {
         if val >= 1 << 14 {
-            into.push(0b1000_0000 | (val >> 14) as u8);
+            into.extend([0b1000_0000 | (val >> 14) as u8]);
         }
-        into.push(0b1000_0000 | (val >> 7) as u8);
-        into.push(val as u8 & 0b0111_1111);
+        into.extend([0b1000_0000 | (val >> 7) as u8]);
+        into.extend([val as u8 & 0b0111_1111]);
     }
 }
 
-#[inline(never)]
-fn parse_len<R: Read>(preamble: u8, reader: &mut R) -> Result<usize, io::Error> {
-    let mut len = preamble as usize & 0b000_1111;
-    if preamble & 0b0001_0000 != 0 {
-        loop {
-            len <<= 7;
-            let mut next = 0;
-            reader.read_exact(std::slice::from_mut(&mut next))?;
-            len |= (next & 0b0111_1111) as usize;
-            if next & 0b1000_0000 == 0 {
-                break;
-            }
+/// Appends the 7-bit group of `byte` to a kim char that starts at `offset`, and fails as soon as it's out of Unicode's range
+#[inline]
+pub(crate) fn kim_group(code_point: u32, byte: u8, offset: u64) -> Result<u32, Error> {
+    let code_point = code_point << 7 | u32::from(byte & 0b0111_1111);
+    if code_point > char::MAX as u32 {
+        return Err(Error::InvalidChar { code_point, offset });
+    }
+    Ok(code_point)
+}
+
+/// Completes a kim char that starts at `offset`. Surrogates are not allowed.
+#[inline]
+pub(crate) fn kim_char(code_point: u32, offset: u64) -> Result<char, Error> {
+    char::from_u32(code_point).ok_or(Error::InvalidChar { code_point, offset })
+}
+
+/// The longest run of ASCII at the start of kim-encoded `bytes`, up to `max` chars.
+///
+/// Continuation bytes of kim always have the high bit set, so starting at a char boundary every byte below 0x80 is a whole char.
+pub(crate) fn ascii_prefix(bytes: &[u8], max: usize) -> &str {
+    let bytes = &bytes[..max.min(bytes.len())];
+    let mut len = 0;
+    for chunk in bytes.chunks_exact(8) {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        if u64::from_ne_bytes(word) & 0x8080_8080_8080_8080 != 0 {
+            break;
         }
+        len += 8;
+    }
+    len += bytes[len..].iter().take_while(|b| b.is_ascii()).count();
+    std::str::from_utf8(&bytes[..len]).unwrap_or_default()
+}
+
+/// Runs a future that is expected to be ready immediately
+#[cfg(all(test, any(feature = "actix-web", feature = "axum", feature = "http-body", feature = "reqwest")))]
+fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+    use std::task::{Context, Poll, Waker};
+    match std::pin::pin!(fut).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(out) => out,
+        Poll::Pending => panic!("the future should be ready immediately"),
     }
-    Ok(len)
 }
 
 #[cfg(test)]
 #[track_caller]
 fn assert_serializes(val: Value, nota: &[u8]) {
-    let mut tmp = nota;
     let mut out = Vec::new();
     val.serialize_into(&mut out);
     if out != nota {
@@ -247,7 +412,7 @@ fn assert_serializes(val: Value, nota: &[u8]) {
             out.iter().map(|n| format!("0x{n:02x}, ")).collect::<String>(),
         );
     }
-    assert_eq!(Value::parse_from(&mut tmp).unwrap(), val);
+    assert_eq!(testing::assert_roundtrip(&val), nota);
 }
 
 #[test]
@@ -262,12 +427,29 @@ fn integer() {
     assert_serializes(Value::Integer(0b101110111110111111111), &[0x90, 0xdd, 0xfb, 0x7f]);
     assert_serializes(Value::Integer(0b1001110111110111111111), &[0x91, 0x9d, 0xfb, 0x7f]);
     assert_serializes(Value::Integer(i128::MAX), &[0x91, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]);
+    assert_serializes(Value::Integer(i128::MIN), &[0x9a, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]);
+    for shift in 0..127 {
+        for n in [(1 << shift) - 1, 1 << shift, -(1 << shift)] {
+            let mut out = Vec::new();
+            Value::Integer(n).serialize_into(&mut out);
+            assert_eq!(out.len(), 1 + (128 - n.unsigned_abs().leading_zeros()).saturating_sub(3).div_ceil(7) as usize);
+            assert_eq!(Value::Integer(n).serialized_size(), out.len());
+            assert_eq!(Value::parse_from(&mut &out[..]).unwrap(), Value::Integer(n));
+        }
+    }
+}
+
+#[test]
+fn value_size() {
+    // an i128 and a tag
+    assert!(std::mem::size_of::<Value>() <= 32);
 }
 
 #[test]
 fn bool() {
     assert_serializes(Value::Bool(false), &[0xC0]);
     assert_serializes(Value::Bool(true), &[0xC1]);
+    assert_serializes(Value::Null, &[0xC2]);
 }
 
 #[test]
@@ -278,12 +460,48 @@ fn text() {
     assert_serializes(Value::Text("𓂀𓃠𓅣𓂻𓂺𓁟𓂑𓃻𓇼𓊽𓂭𓎆𓍢𓏢𓐠".into()), &[0x2F, 0x84, 0xE1, 0x00, 0x84, 0xE1, 0x60, 0x84, 0xE2, 0x63, 0x84, 0xE1, 0x3B, 0x84, 0xE1, 0x3A, 0x84, 0xE0, 0x5F, 0x84, 0xE1, 0x11, 0x84, 0xE1, 0x7B, 0x84, 0xE3, 0x7C, 0x84, 0xE5, 0x3D, 0x84, 0xE1, 0x2D, 0x84, 0xE7, 0x06, 0x84, 0xE6, 0x62, 0x84, 0xE7, 0x62, 0x84, 0xE8, 0x20]);
 }
 
+#[test]
+fn ascii_runs() {
+    assert_eq!(ascii_prefix(b"hello world, long enough\xCC\x03!", 100), "hello world, long enough");
+    assert_eq!(ascii_prefix(b"hello world, long enough", 7), "hello w");
+    assert_eq!(ascii_prefix(b"\xCC\x03", 2), "");
+
+    let text = Value::Text("a long line of ascii ☃ then more ascii 𓂀 and the end ★".into());
+    let mut bytes = Vec::new();
+    text.serialize_into(&mut bytes);
+    assert_eq!(Value::parse_slice(&mut &bytes[..]).unwrap(), text);
+    assert_eq!(Value::parse_buf(&mut &bytes[..]).unwrap(), text);
+    let mut decoder = Decoder::new();
+    let mut parsed = None;
+    for chunk in bytes.chunks(5) {
+        let (used, value) = decoder.push(chunk).unwrap();
+        assert_eq!(used, chunk.len());
+        parsed = parsed.or(value);
+    }
+    assert_eq!(parsed, Some(text));
+}
+
+#[test]
+fn invalid_chars() {
+    for (text, code_point) in [(&[0x83, 0xB0, 0x00][..], 0xD800), (&[0xFF, 0xFF, 0x7F], 0x1F_FFFF), (&[0xC4, 0x80, 0x80, 0x80, 0x80, 0x00], 0x11_0000)] {
+        let mut data = vec![0x42, 0x80, 0x21];
+        data.extend_from_slice(text);
+        let expected = Error::InvalidChar { code_point, offset: 3 };
+        let mut decoder = Decoder::new();
+        let byte_at_a_time = data.iter().find_map(|b| decoder.push(std::slice::from_ref(b)).err()).unwrap();
+        for err in [Value::parse_from(&mut &data[..]), Value::parse_buf(&mut &data[..]), Value::parse_slice(&mut &data[..]), Err(byte_at_a_time)] {
+            assert_eq!(Error::from_io(&err.unwrap_err()), Some(&expected));
+        }
+    }
+}
+
 #[test]
 fn array() {
     assert_serializes(Value::Array(vec![Value::Bool(false), Value::Integer(2023)]), &[0b1000010, 0xC0, 0x90, 0x8F, 0x67]);
 }
 
 // likely incorrect, because the spec has no examples to test against
+#[cfg(feature = "blobs")]
 #[test]
 fn blob() {
     let mut bitblob = BitVec::new();
@@ -297,10 +515,102 @@ fn blob() {
 }
 
 // may be incorrect, because the spec has no examples to test against
+#[cfg(feature = "records")]
 #[test]
 fn record() {
     let mut hash = HashMap::new();
     hash.insert("Hello".into(), Value::Integer(123456789));
 
-    assert_serializes(Value::Record(hash), &[0x61, 0x25, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x90, 0xba, 0xef, 0x9a, 0x15]);
+    assert_serializes(Value::Record(Box::new(hash)), &[0x61, 0x25, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x90, 0xba, 0xef, 0x9a, 0x15]);
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn canonical() {
+    let mut bits = BitVec::<u8, Msb0>::new();
+    bits.extend([true; 8]);
+    bits.truncate(3);
+    let mut out = Vec::new();
+    Value::Blob(bits).serialize_into(&mut out);
+    assert_eq!(out, [3, 0b1110_0000]);
+
+    let options = SerializeOptions { canonical: true, ..Default::default() };
+    let record = |keys: &[&str]| Value::Record(Box::new(keys.iter().map(|&k| (k.to_string(), Value::Null)).collect()));
+    let mut a = Vec::new();
+    record(&["b", "a", "é", "c"]).serialize_with(&mut a, &options);
+    let mut b = Vec::new();
+    record(&["c", "é", "a", "b"]).serialize_with(&mut b, &options);
+    assert_eq!(a, b);
+    assert_eq!(a[..4], [0x64, 0x21, b'a', 0xC2]);
+}
+
+/// Parsing must fail with an error, never panic, whatever the input
+#[test]
+fn no_panics() {
+    let mut sample = Vec::new();
+    Value::Array(vec![Value::Text("snow ☃ 𓂀".into()), Value::Integer(-1 << 100), Value::Array(vec![Value::Null; 3]), Value::Bool(true)]).serialize_into(&mut sample);
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 56) as usize
+    };
+    let all_options = [ParseOptions::default(), ParseOptions::untrusted(), ParseOptions { backrefs: true, ..Default::default() }, ParseOptions { unknown: true, ..Default::default() }];
+    for i in 0..20_000 {
+        let data: Vec<u8> = if i % 2 == 0 {
+            let mut data = sample.clone();
+            for _ in 0..=i % 3 {
+                let pos = random() % data.len();
+                data[pos] = random() as u8;
+            }
+            data.truncate(random() % (data.len() + 1));
+            data
+        } else {
+            (0..i % 40).map(|_| random() as u8).collect()
+        };
+        for options in &all_options {
+            let _ = Value::parse_with(&mut &data[..], options);
+            let _ = Value::parse_buf_with(&mut &data[..], options);
+            let _ = Value::parse_slice_with(&mut &data[..], options);
+            let _ = Decoder::with_options(options.clone()).push(&data);
+            #[cfg(feature = "bumpalo")]
+            let _ = arena::ArenaValue::parse_in_with(&mut &data[..], &bumpalo::Bump::new(), options);
+            #[cfg(feature = "rayon")]
+            let _ = Value::par_parse_slice_with(&mut &data[..], options);
+        }
+        pull::PullParser::<8>::new(&data).take(100).for_each(drop);
+    }
+
+    // text and blob of usize::MAX length
+    for data in [&[0x31, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, b'a'], &[0x11, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, b'a']] {
+        for options in &all_options {
+            assert!(Value::parse_with(&mut &data[..], options).is_err());
+            assert!(Value::parse_slice_with(&mut &data[..], options).is_err());
+            let _ = Decoder::with_options(options.clone()).push(data);
+            #[cfg(feature = "bumpalo")]
+            assert!(arena::ArenaValue::parse_in_with(&mut &data[..], &bumpalo::Bump::new(), options).is_err());
+        }
+    }
+
+    // larger than i128, and larger than u128
+    for data in [&[0x92, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00][..], &[0x97, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]] {
+        let mut decoder = Decoder::new();
+        let byte_at_a_time = data.iter().find_map(|b| decoder.push(std::slice::from_ref(b)).err()).unwrap();
+        for err in [Value::parse_from(&mut &data[..]), Value::parse_buf(&mut &data[..]), Value::parse_slice(&mut &data[..]), Err(byte_at_a_time)] {
+            assert_eq!(Error::from_io(&err.unwrap_err()), Some(&Error::IntegerTooLarge));
+        }
+    }
+
+    #[cfg(feature = "dec64")]
+    {
+        assert_eq!(Value::parse_slice(&mut &[0xA0][..]).unwrap_err().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(Value::parse_from(&mut &[0xA0][..]).unwrap_err().kind(), io::ErrorKind::Unsupported);
+        #[allow(deprecated)]
+        let value = Value::Array(vec![Value::Null, Value::DecimalFloat(DecimalFloat { exponent: 1, coefficient: 1 })]);
+        let mut out = vec![1];
+        assert_eq!(value.try_serialize_into(&mut out), Err(Error::Unrepresentable("decimal float")));
+        assert_eq!(out, [1]);
+        assert_eq!(value.serialize_to_slice(&mut [0; 100]), Err(SliceWriteError::Unrepresentable("decimal float")));
+    }
 }
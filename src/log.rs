@@ -0,0 +1,250 @@
+//! Append-only logs of values, for event sourcing and write-ahead logs
+//!
+//! Every value is a [frame](crate::framing) with a CRC-32 checksum. Every few values, and whenever a [`LogWriter`] starts,
+//! there's a [`SYNC_MARKER`], which can't be mistaken for the start of a frame.
+//!
+//! A crash while appending can leave a torn value at the end of the log. [`LogReader`] stops quietly before it,
+//! and tells where it starts with [`truncated_at`](LogReader::truncated_at), so the log can be truncated there.
+//! If a log has been appended to after a torn value, or has been damaged, the reader skips to the next sync marker.
+//!
+//! ```rust
+//! use nota::log::{LogReader, LogWriter};
+//! use nota::Value;
+//!
+//! let mut file = Vec::new();
+//! let mut log = LogWriter::new(&mut file);
+//! log.append(&Value::Text("created".into()))?;
+//! log.append(&Value::Text("renamed".into()))?;
+//! file.extend_from_slice(&[5, 0x2A]); // a crash in the middle of writing
+//!
+//! let mut reader = LogReader::new(&file);
+//! let events: Vec<_> = reader.by_ref().map(|entry| entry.unwrap().1).collect();
+//! assert_eq!(events, [Value::Text("created".into()), Value::Text("renamed".into())]);
+//! assert_eq!(reader.truncated_at(), Some(file.len() - 2));
+//! # Ok::<_, std::io::Error>(())
+//! ```
+use crate::framing::{read_frame_bytes, write_varint, Checksum, DEFAULT_MAX_FRAME_SIZE};
+use crate::recover::Damaged;
+use crate::Value;
+use std::io::{self, Write};
+
+/// Written between values. It starts with a frame length of 0, which a value with a checksum can't have.
+pub const SYNC_MARKER: [u8; 8] = *b"\0\x8eNOTA\xff\0";
+
+/// Settings for [`LogWriter`] and [`LogReader`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LogOptions {
+    /// Number of values between sync markers. Damage loses at most this many values. 0 writes a marker only at the start.
+    pub sync_interval: usize,
+    /// Longer values are treated as damage
+    pub max_frame_size: usize,
+}
+
+impl Default for LogOptions {
+    fn default() -> Self {
+        Self { sync_interval: 64, max_frame_size: DEFAULT_MAX_FRAME_SIZE }
+    }
+}
+
+/// Appends values to a log. Open the file in append mode.
+#[derive(Debug)]
+pub struct LogWriter<W: Write> {
+    writer: W,
+    sync_interval: usize,
+    /// Values written since the last sync marker, `None` before the first one
+    since_sync: Option<usize>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> LogWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_options(writer, &LogOptions::default())
+    }
+
+    pub fn with_options(writer: W, options: &LogOptions) -> Self {
+        Self { writer, sync_interval: options.sync_interval, since_sync: None, buffer: Vec::new() }
+    }
+
+    /// Writes the value, after a sync marker if it's time for one. The value is written with a single `write_all`.
+    pub fn append(&mut self, value: &Value) -> Result<(), io::Error> {
+        self.buffer.clear();
+        if self.since_sync.is_none_or(|n| self.sync_interval > 0 && n >= self.sync_interval) {
+            self.buffer.extend_from_slice(&SYNC_MARKER);
+            self.since_sync = Some(0);
+        }
+        let mut frame = Vec::with_capacity(value.serialized_size() + Checksum::Crc32.size());
+        value.try_serialize_into(&mut frame)?;
+        Checksum::Crc32.append(&mut frame);
+        write_varint(frame.len() as u64, &mut self.buffer);
+        self.buffer.extend_from_slice(&frame);
+        self.writer.write_all(&self.buffer)?;
+        self.since_sync = self.since_sync.map(|n| n + 1);
+        Ok(())
+    }
+
+    /// Writes a sync marker now, e.g. before a batch of related values
+    pub fn sync_marker(&mut self) -> Result<(), io::Error> {
+        self.writer.write_all(&SYNC_MARKER)?;
+        self.since_sync = Some(0);
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), io::Error> {
+        self.writer.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Iterator of values in a log, with their byte offsets, and the damaged parts that have been skipped
+#[derive(Debug)]
+pub struct LogReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    max_frame_size: usize,
+    truncated_at: Option<usize>,
+}
+
+impl<'a> LogReader<'a> {
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::with_options(data, &LogOptions::default())
+    }
+
+    #[must_use]
+    pub fn with_options(data: &'a [u8], options: &LogOptions) -> Self {
+        Self { data, pos: 0, max_frame_size: options.max_frame_size, truncated_at: None }
+    }
+
+    /// Where the torn value at the end of the log starts, including sync markers before it, if the iteration has reached it
+    #[must_use]
+    pub fn truncated_at(&self) -> Option<usize> {
+        self.truncated_at
+    }
+
+    /// Continues from the last sync marker that is followed by a readable value, or the end of the log.
+    /// Returns its offset, or `None` if there's no such marker, and the position hasn't changed.
+    ///
+    /// This reads only the recent values, without going through the whole log.
+    pub fn seek_to_last_sync(&mut self) -> Option<usize> {
+        let mut end = self.data.len();
+        while let Some(pos) = self.data[..end].windows(SYNC_MARKER.len()).rposition(|w| w == SYNC_MARKER) {
+            let after = pos + SYNC_MARKER.len();
+            if after == self.data.len() || self.read_at(after).is_ok() {
+                self.pos = pos;
+                self.truncated_at = None;
+                return Some(pos);
+            }
+            end = pos + SYNC_MARKER.len() - 1;
+        }
+        None
+    }
+
+    /// Reads one value at `pos`, and returns how many bytes it took
+    fn read_at(&self, pos: usize) -> Result<(Value, usize), io::Error> {
+        let mut rest = &self.data[pos..];
+        let frame = read_frame_bytes(&mut rest, self.max_frame_size)?.ok_or(io::ErrorKind::UnexpectedEof)?;
+        let mut body = Checksum::Crc32.verify(&frame)?;
+        let value = Value::parse_slice(&mut body)?;
+        if !body.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "trailing bytes in frame"));
+        }
+        Ok((value, self.data.len() - pos - rest.len()))
+    }
+
+    fn next_sync(&self, after: usize) -> Option<usize> {
+        let start = (after + 1).min(self.data.len());
+        self.data[start..].windows(SYNC_MARKER.len()).position(|w| w == SYNC_MARKER).map(|pos| start + pos)
+    }
+}
+
+impl Iterator for LogReader<'_> {
+    type Item = Result<(usize, Value), Damaged>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let markers_start = self.pos;
+        while self.data[self.pos..].starts_with(&SYNC_MARKER) {
+            self.pos += SYNC_MARKER.len();
+        }
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let offset = self.pos;
+        let error = if SYNC_MARKER.starts_with(&self.data[offset..]) {
+            io::ErrorKind::UnexpectedEof.into()
+        } else {
+            match self.read_at(offset) {
+                Ok((value, len)) => {
+                    self.pos += len;
+                    return Some(Ok((offset, value)));
+                },
+                Err(error) => error,
+            }
+        };
+        match self.next_sync(offset) {
+            Some(next) => {
+                self.pos = next;
+                Some(Err(Damaged { offset, len: next - offset, error }))
+            },
+            None if error.kind() == io::ErrorKind::UnexpectedEof => {
+                self.pos = self.data.len();
+                self.truncated_at = Some(markers_start);
+                None
+            },
+            None => {
+                self.pos = self.data.len();
+                Some(Err(Damaged { offset, len: self.data.len() - offset, error }))
+            },
+        }
+    }
+}
+
+#[test]
+fn torn_and_damaged() {
+    let options = LogOptions { sync_interval: 2, ..Default::default() };
+    let mut log = LogWriter::with_options(Vec::new(), &options);
+    for n in 0..5 {
+        log.append(&Value::Integer(n)).unwrap();
+    }
+    let mut data = log.into_inner();
+    assert_eq!(data.windows(SYNC_MARKER.len()).filter(|&w| w == SYNC_MARKER).count(), 3);
+    let values = |data: &[u8]| LogReader::new(data).filter_map(Result::ok).map(|(_, v)| v).collect::<Vec<_>>();
+    assert_eq!(values(&data), (0..5).map(Value::Integer).collect::<Vec<_>>());
+
+    // torn at every possible length
+    let whole = data.len();
+    let mut torn = data.clone();
+    LogWriter::new(&mut torn).append(&Value::Text("last".into())).unwrap();
+    for len in whole + 1..torn.len() {
+        let mut reader = LogReader::new(&torn[..len]);
+        assert_eq!(reader.by_ref().filter_map(Result::ok).count(), 5, "{len}");
+        assert_eq!(reader.truncated_at(), Some(whole).filter(|_| len != whole + SYNC_MARKER.len()), "{len}");
+    }
+
+    // appended after a torn value
+    data.extend_from_slice(&[9, 1, 2]);
+    let mut log = LogWriter::new(&mut data);
+    log.append(&Value::Bool(true)).unwrap();
+    let entries: Vec<_> = LogReader::new(&data).collect();
+    assert_eq!(entries.len(), 7);
+    assert!(entries[5].is_err());
+    assert_eq!(entries[6].as_ref().unwrap().1, Value::Bool(true));
+
+    // damage in the middle loses values only until the next marker
+    let mut damaged = data.clone();
+    damaged[SYNC_MARKER.len() + 3] ^= 1;
+    assert_eq!(values(&damaged), [2, 3, 4].map(Value::Integer).into_iter().chain([Value::Bool(true)]).collect::<Vec<_>>());
+
+    let mut reader = LogReader::new(&data);
+    let last_sync = reader.seek_to_last_sync().unwrap();
+    assert_eq!(reader.next().unwrap().unwrap(), (last_sync + SYNC_MARKER.len(), Value::Bool(true)));
+    assert!(reader.next().is_none());
+    assert_eq!(LogReader::new(&[]).seek_to_last_sync(), None);
+}
@@ -0,0 +1,242 @@
+//! Upgrading documents written by older versions of a program
+//!
+//! Documents are records with a version number field, `"version"` by default. A document without it is version 0.
+//! [`Migrations`] has a [`Migration`] for every version that changed the format, and applies them in order,
+//! from the document's version to the current one, so old stored data can be read as if it was written today.
+//!
+//! ```rust
+//! use nota::migrate::Migrations;
+//! use nota::Value;
+//!
+//! let mut migrations = Migrations::new(2);
+//! // Version 0 had `name`, version 1 calls it `full_name`
+//! migrations.from_version(0).rename("name".parse()?, "full_name");
+//! // Version 2 has `tags`, and stores `age` as text
+//! migrations.from_version(1)
+//!     .fill_default("tags".parse()?, Value::Array(vec![]))
+//!     .convert("age".parse()?, |age| Ok(Value::Text(age.to_string())));
+//!
+//! let old: Value = r#"{"name": "Ada", "age": 36}"#.parse()?;
+//! let new = migrations.migrate(old)?;
+//! assert_eq!(new.to_string(), r#"{"age": "36", "full_name": "Ada", "tags": [], "version": 2}"#);
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+use crate::path::{Path, Segment};
+use crate::{Error, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+type ConvertHook = dyn Fn(Value) -> Result<Value, Error> + Send + Sync;
+type UpdateHook = dyn Fn(&mut Value) -> Result<(), Error> + Send + Sync;
+
+#[derive(Clone)]
+enum Change {
+    Rename(Path, String),
+    Convert(Path, Arc<ConvertHook>),
+    FillDefault(Path, Value),
+    Remove(Path),
+    Update(Arc<UpdateHook>),
+}
+
+/// Changes that upgrade a document from one version to the next
+#[derive(Clone, Default)]
+pub struct Migration {
+    changes: Vec<Change>,
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migration").field("changes", &self.changes.len()).finish()
+    }
+}
+
+/// Why a document couldn't be upgraded
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MigrationError {
+    /// The version being upgraded from
+    pub version: u32,
+    pub path: Path,
+    pub error: Error,
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "upgrading from version {}, at {}: {}", self.version, self.path, self.error)
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl Migration {
+    /// Renames a record field. The path is to the old field.
+    pub fn rename(&mut self, path: Path, to: &str) -> &mut Self {
+        self.changes.push(Change::Rename(path, to.into()));
+        self
+    }
+
+    /// Replaces the value at the path with the result of `convert`, if there is a value
+    pub fn convert(&mut self, path: Path, convert: impl Fn(Value) -> Result<Value, Error> + Send + Sync + 'static) -> &mut Self {
+        self.changes.push(Change::Convert(path, Arc::new(convert)));
+        self
+    }
+
+    /// Adds a record field that is missing. Nothing is added if the record itself doesn't exist.
+    pub fn fill_default(&mut self, path: Path, value: Value) -> &mut Self {
+        self.changes.push(Change::FillDefault(path, value));
+        self
+    }
+
+    /// Removes a record field
+    pub fn remove(&mut self, path: Path) -> &mut Self {
+        self.changes.push(Change::Remove(path));
+        self
+    }
+
+    /// Changes the whole document, for anything the other methods can't do
+    pub fn update(&mut self, update: impl Fn(&mut Value) -> Result<(), Error> + Send + Sync + 'static) -> &mut Self {
+        self.changes.push(Change::Update(Arc::new(update)));
+        self
+    }
+
+    fn apply(&self, document: &mut Value) -> Result<(), (Path, Error)> {
+        for change in &self.changes {
+            match change {
+                Change::Rename(path, to) => {
+                    if let Some((fields, key)) = parent_record(document, path) {
+                        if let Some(value) = fields.remove(key) {
+                            fields.insert(to.clone(), value);
+                        }
+                    }
+                },
+                Change::Convert(path, convert) => {
+                    if let Some(value) = path.get_mut(document) {
+                        *value = convert(value.take()).map_err(|e| (path.clone(), e))?;
+                    }
+                },
+                Change::FillDefault(path, default) => {
+                    if let Some((fields, key)) = parent_record(document, path) {
+                        fields.entry(key.clone()).or_insert_with(|| default.clone());
+                    }
+                },
+                Change::Remove(path) => {
+                    if let Some((fields, key)) = parent_record(document, path) {
+                        fields.remove(key);
+                    }
+                },
+                Change::Update(update) => update(document).map_err(|e| (Path::default(), e))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The record containing the field at the path, and the field's key
+fn parent_record<'a, 'p>(document: &'a mut Value, path: &'p Path) -> Option<(&'a mut HashMap<String, Value>, &'p String)> {
+    let (Segment::Key(key), parent) = path.segments().split_last()? else {
+        return None;
+    };
+    match Path::from(parent.to_vec()).get_mut(document)? {
+        Value::Record(fields) => Some((fields, key)),
+        _ => None,
+    }
+}
+
+/// Upgrades of documents to the current version
+#[derive(Debug, Clone)]
+pub struct Migrations {
+    key: String,
+    current: u32,
+    steps: BTreeMap<u32, Migration>,
+}
+
+impl Migrations {
+    /// Documents will be upgraded to the `current` version
+    #[must_use]
+    pub fn new(current: u32) -> Self {
+        Self { key: "version".into(), current, steps: BTreeMap::new() }
+    }
+
+    /// Name of the top-level field with the version number, instead of `"version"`
+    pub fn version_key(&mut self, key: &str) -> &mut Self {
+        self.key = key.into();
+        self
+    }
+
+    /// Changes that upgrade documents from `version` to `version + 1`. Versions without changes are skipped.
+    ///
+    /// # Panics
+    ///
+    /// If the version isn't older than the current one
+    pub fn from_version(&mut self, version: u32) -> &mut Migration {
+        assert!(version < self.current, "version {version} isn't older than the current version {}", self.current);
+        self.steps.entry(version).or_default()
+    }
+
+    /// Version of the document, 0 if it doesn't have the version field
+    pub fn version_of(&self, document: &Value) -> Result<u32, Error> {
+        let Value::Record(fields) = document else {
+            return Err(Error::UnexpectedValue { expected: "a record" });
+        };
+        match fields.get(&self.key) {
+            None => Ok(0),
+            Some(&Value::Integer(version)) => version.try_into().map_err(|_| Error::UnexpectedValue { expected: "a version number" }),
+            Some(_) => Err(Error::UnexpectedValue { expected: "a version number" }),
+        }
+    }
+
+    /// Applies all migrations from the document's version, and sets its version to the current one.
+    ///
+    /// Documents from newer versions are an error.
+    pub fn migrate(&self, mut document: Value) -> Result<Value, MigrationError> {
+        let version = self.version_of(&document).map_err(|error| MigrationError { version: 0, path: Path::default(), error })?;
+        if version > self.current {
+            return Err(MigrationError {
+                version,
+                path: Path::from(vec![Segment::Key(self.key.clone())]),
+                error: Error::UnexpectedValue { expected: "a version that isn't newer than the current one" },
+            });
+        }
+        for (&from, migration) in self.steps.range(version..self.current) {
+            migration.apply(&mut document).map_err(|(path, error)| MigrationError { version: from, path, error })?;
+        }
+        if let Value::Record(fields) = &mut document {
+            fields.insert(self.key.clone(), Value::Integer(self.current.into()));
+        }
+        Ok(document)
+    }
+}
+
+#[test]
+fn migrations() {
+    let mut migrations = Migrations::new(3);
+    migrations.version_key("v");
+    migrations.from_version(0).rename("user.name".parse().unwrap(), "login").remove("debug".parse().unwrap());
+    migrations.from_version(2).convert("user.id".parse().unwrap(), |id| match id {
+        Value::Integer(n) => Ok(Value::Text(format!("u{n}"))),
+        _ => Err(Error::UnexpectedValue { expected: "an integer" }),
+    }).fill_default("user.admin".parse().unwrap(), Value::Bool(false)).fill_default("missing.field".parse().unwrap(), Value::Null).update(|doc| {
+        if let Value::Record(fields) = doc {
+            fields.insert("migrated".into(), Value::Bool(true));
+        }
+        Ok(())
+    });
+
+    let v0: Value = r#"{"debug": true, "user": {"id": 7, "name": "ada"}}"#.parse().unwrap();
+    assert_eq!(migrations.migrate(v0).unwrap().to_string(), r#"{"migrated": true, "user": {"admin": false, "id": "u7", "login": "ada"}, "v": 3}"#);
+
+    let v1: Value = r#"{"debug": true, "user": {"id": 7, "name": "ada"}, "v": 1}"#.parse().unwrap();
+    assert_eq!(migrations.migrate(v1).unwrap().to_string(), r#"{"debug": true, "migrated": true, "user": {"admin": false, "id": "u7", "name": "ada"}, "v": 3}"#);
+
+    let current: Value = r#"{"user": {"id": 7}, "v": 3}"#.parse().unwrap();
+    assert_eq!(migrations.migrate(current.clone()).unwrap(), current);
+
+    let bad: Value = r#"{"user": {"id": "7"}, "v": 2}"#.parse().unwrap();
+    let err = migrations.migrate(bad).unwrap_err();
+    assert_eq!((err.version, err.path.to_string()), (2, "user.id".into()));
+
+    let newer: Value = r#"{"v": 4}"#.parse().unwrap();
+    assert_eq!(migrations.migrate(newer).unwrap_err().path.to_string(), "v");
+    assert_eq!(migrations.version_of(&Value::Null), Err(Error::UnexpectedValue { expected: "a record" }));
+}
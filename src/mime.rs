@@ -0,0 +1,9 @@
+/// MIME type of Nota messages
+pub const CONTENT_TYPE: &str = "application/nota";
+
+/// Accepts `application/nota` and `application/*+nota`, with any parameters
+#[cfg(any(feature = "actix-web", feature = "axum"))]
+pub(crate) fn is_nota_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    mime == CONTENT_TYPE || (mime.starts_with("application/") && mime.ends_with("+nota"))
+}
@@ -0,0 +1,93 @@
+//! Parsing files via [`memmap2`], without reading them into memory first
+//!
+//! [`Value::parse_file`] parses the whole file with the slice parser. For multi-GB documents where only a part is needed,
+//! [`MappedFile::pull`] returns a [pull parser](crate::pull) over the mapped bytes, which pages in only what it reads.
+//!
+//! The file must not be modified while it's mapped. If it is, parsing may fail or return garbage, but won't be unsound.
+use crate::pull::PullParser;
+use crate::{ParseOptions, Value};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+
+/// Read-only memory map of a whole file
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let file = File::open(path)?;
+        // Safety: the map is read-only, and all parsers treat the bytes as untrusted,
+        // so they only give wrong results if another process changes the file.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self { map })
+    }
+
+    /// Mapped bytes of the file
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Returns events without parsing the rest of the file
+    #[must_use]
+    pub fn pull<const DEPTH: usize>(&self) -> PullParser<'_, DEPTH> {
+        PullParser::new(&self.map)
+    }
+
+    /// Parses one value that takes the whole file
+    pub fn parse_with(&self, options: &ParseOptions) -> Result<Value, io::Error> {
+        let mut data = self.as_bytes();
+        let value = Value::parse_slice_with(&mut data, options)?;
+        if !data.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "trailing bytes after the value"));
+        }
+        Ok(value)
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+impl Value {
+    /// Maps the file into memory and parses it with [`parse_slice`](Self::parse_slice). The file must contain exactly one value.
+    pub fn parse_file(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        Self::parse_file_with(path, &ParseOptions::default())
+    }
+
+    pub fn parse_file_with(path: impl AsRef<Path>, options: &ParseOptions) -> Result<Self, io::Error> {
+        MappedFile::open(path)?.parse_with(options)
+    }
+}
+
+#[test]
+fn parse_file() {
+    use crate::pull::Event;
+
+    let value = Value::Array(vec![Value::Text("x".repeat(5000)), Value::Integer(-7)]);
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+    let path = std::env::temp_dir().join(format!("nota-mmap-{}.nota", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+    assert_eq!(Value::parse_file(&path).unwrap(), value);
+
+    let file = MappedFile::open(&path).unwrap();
+    let mut events = file.pull::<4>();
+    assert_eq!(events.next(), Some(Ok(Event::Array(2))));
+    assert!(matches!(events.next(), Some(Ok(Event::Text(t))) if t.len() == 5000));
+    assert_eq!(events.next(), Some(Ok(Event::Integer(-7))));
+    drop(file);
+
+    bytes.push(0xC2);
+    std::fs::write(&path, &bytes).unwrap();
+    assert_eq!(Value::parse_file(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    std::fs::remove_file(&path).unwrap();
+}
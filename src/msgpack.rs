@@ -0,0 +1,115 @@
+//! Conversions to and from [`rmpv::Value`]
+//!
+//! MessagePack floats (`F32` and `F64`) can't be converted, since Nota has no binary floating-point numbers.
+//!
+//! Lossy cases:
+//!
+//! * Ext types other than [`BIT_BLOB_EXT`] become blobs with their payload, and their type number is lost.
+//! * Nota integers outside of the 64-bit range can't be converted to MessagePack.
+//!
+//! Blobs that are a whole number of bytes become `Binary`. Blobs with a partial byte use the [`BIT_BLOB_EXT`] ext type,
+//! with the number of unused bits in the first byte of the payload.
+use crate::{Error, Value};
+use bitvec::vec::BitVec;
+
+/// Ext type number for blobs that aren't whole bytes
+pub const BIT_BLOB_EXT: i8 = 78;
+
+impl TryFrom<Value> for rmpv::Value {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Ok(match value {
+            Value::Blob(bits) => {
+                let unused = (8 - bits.len() % 8) % 8;
+                if unused == 0 {
+                    Self::Binary(bits.into_vec())
+                } else {
+                    let mut payload = vec![unused as u8];
+                    payload.extend_from_slice(bits.as_raw_slice());
+                    // BitVec doesn't clear bits past its length
+                    if let Some(last) = payload.last_mut() {
+                        *last &= !(0xFF >> (bits.len() % 8));
+                    }
+                    Self::Ext(BIT_BLOB_EXT, payload)
+                }
+            },
+            Value::Text(s) => Self::String(s.into()),
+            Value::Array(a) => Self::Array(a.into_iter().map(Self::try_from).collect::<Result<_, _>>()?),
+            Value::Record(r) => Self::Map(r.into_iter().map(|(k, v)| Ok((Self::String(k.into()), Self::try_from(v)?))).collect::<Result<_, Error>>()?),
+            Value::Integer(i) => {
+                if let Ok(i) = i64::try_from(i) {
+                    Self::from(i)
+                } else if let Ok(u) = u64::try_from(i) {
+                    Self::from(u)
+                } else {
+                    return Err(Error::Unrepresentable("integer larger than 64 bits"));
+                }
+            },
+            Value::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float")),
+            Value::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag")),
+            Value::Bool(b) => Self::Boolean(b),
+            Value::Null => Self::Nil,
+        })
+    }
+}
+
+impl TryFrom<rmpv::Value> for Value {
+    type Error = Error;
+
+    fn try_from(msgpack: rmpv::Value) -> Result<Self, Error> {
+        use rmpv::Value as M;
+        Ok(match msgpack {
+            M::Nil => Self::Null,
+            M::Boolean(b) => Self::Bool(b),
+            M::Integer(i) => match (i.as_i64(), i.as_u64()) {
+                (Some(i), _) => Self::Integer(i.into()),
+                (_, Some(u)) => Self::Integer(u.into()),
+                _ => return Err(Error::Unrepresentable("integer")),
+            },
+            M::F32(_) | M::F64(_) => return Err(Error::Unrepresentable("float")),
+            M::String(s) => Self::Text(s.into_str().ok_or(Error::Unrepresentable("string that isn't UTF-8"))?),
+            M::Binary(b) => Self::Blob(BitVec::from_vec(b)),
+            M::Array(a) => Self::Array(a.into_iter().map(Self::try_from).collect::<Result<_, _>>()?),
+            M::Map(m) => Self::Record(Box::new(m.into_iter().map(|(k, v)| match k {
+                M::String(k) => Ok((k.into_str().ok_or(Error::Unrepresentable("string that isn't UTF-8"))?, Self::try_from(v)?)),
+                _ => Err(Error::Unrepresentable("map key that isn't a string")),
+            }).collect::<Result<_, _>>()?)),
+            M::Ext(BIT_BLOB_EXT, payload) => match payload.split_first() {
+                Some((&unused @ 1..=7, bytes)) if !bytes.is_empty() => {
+                    let mut bits = BitVec::from_vec(bytes.to_vec());
+                    bits.truncate(bytes.len() * 8 - unused as usize);
+                    Self::Blob(bits)
+                },
+                _ => return Err(Error::Unrepresentable("malformed bit blob")),
+            },
+            M::Ext(_, payload) => Self::Blob(BitVec::from_vec(payload)),
+        })
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let mut bits = BitVec::from_vec(vec![0xAA]);
+    bits.push(true);
+    let value = Value::Array(vec![
+        Value::Blob(bits),
+        Value::Blob(BitVec::from_vec(vec![1, 2])),
+        Value::Integer(u64::MAX.into()),
+        Value::Integer(i64::MIN.into()),
+        Value::Record(Box::new([("k".to_string(), Value::Null)].into_iter().collect())),
+        Value::Text("☃".into()),
+        Value::Bool(true),
+    ]);
+    let msgpack = rmpv::Value::try_from(value.clone()).unwrap();
+    let rmpv::Value::Array(items) = &msgpack else { panic!() };
+    assert_eq!(items[0], rmpv::Value::Ext(BIT_BLOB_EXT, vec![7, 0xAA, 0x80]));
+    assert_eq!(Value::try_from(msgpack).unwrap(), value);
+
+    assert_eq!(Value::try_from(rmpv::Value::F64(0.25)), Err(Error::Unrepresentable("float")));
+    assert!(rmpv::Value::try_from(Value::Integer(i128::MAX)).is_err());
+
+    let mut bits = BitVec::from_vec(vec![0xFF]);
+    bits.truncate(3);
+    assert_eq!(rmpv::Value::try_from(Value::Blob(bits)).unwrap(), rmpv::Value::Ext(BIT_BLOB_EXT, vec![5, 0xE0]));
+}
@@ -0,0 +1,293 @@
+//! IP and socket addresses
+//!
+//! An [`IpAddr`] is stored as a [`Blob`](Value::Blob) of its octets, 32 bits for IPv4 and 128 bits for IPv6.
+//! A [`SocketAddr`](std::net::SocketAddr) is a record `{"ip": x"7f000001", "port": 8080}`. IPv6 flow info and scope ID aren't kept.
+//!
+//! The [tagged](crate::tagged) forms, `{"$ip": "::1"}` and `{"$socket": "127.0.0.1:8080"}`, have the usual text instead.
+//! Conversions from values accept all of these forms, and text of the addresses.
+//!
+//! For serde, use `#[serde(with = "nota::net::blob")]` on `IpAddr` fields and `#[serde(with = "nota::net::record")]`
+//! on `SocketAddr` fields, since their own serde implementations write text.
+//!
+//! ```rust
+//! use nota::Value;
+//! use std::net::{IpAddr, Ipv4Addr};
+//!
+//! let ip = IpAddr::from(Ipv4Addr::LOCALHOST);
+//! let value = Value::from(ip);
+//! assert_eq!(value.to_string(), r#"x"7f000001""#);
+//! assert_eq!(IpAddr::try_from(&value)?, ip);
+//! # Ok::<_, nota::Error>(())
+//! ```
+use crate::{Error, Value};
+use bitvec::prelude::*;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+#[cfg(feature = "records")]
+use std::net::SocketAddr;
+
+const EXPECTED_IP: Error = Error::UnexpectedValue { expected: "an IP address" };
+#[cfg(feature = "records")]
+const EXPECTED_SOCKET: Error = Error::UnexpectedValue { expected: "a socket address" };
+
+fn ip_from_octets(octets: &[u8]) -> Option<IpAddr> {
+    match octets.len() {
+        4 => <[u8; 4]>::try_from(octets).ok().map(IpAddr::from),
+        16 => <[u8; 16]>::try_from(octets).ok().map(IpAddr::from),
+        _ => None,
+    }
+}
+
+impl From<Ipv4Addr> for Value {
+    fn from(ip: Ipv4Addr) -> Self {
+        Self::Blob(BitVec::from_slice(&ip.octets()))
+    }
+}
+
+impl From<Ipv6Addr> for Value {
+    fn from(ip: Ipv6Addr) -> Self {
+        Self::Blob(BitVec::from_slice(&ip.octets()))
+    }
+}
+
+impl From<IpAddr> for Value {
+    fn from(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ip) => ip.into(),
+            IpAddr::V6(ip) => ip.into(),
+        }
+    }
+}
+
+impl TryFrom<&Value> for IpAddr {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        match value {
+            Value::Blob(bits) if bits.len() % 8 == 0 => ip_from_octets(bits.as_raw_slice()).ok_or(EXPECTED_IP),
+            Value::Text(text) => text.parse().map_err(|_| EXPECTED_IP),
+            #[cfg(feature = "records")]
+            value => match value.as_tagged() {
+                Some(("ip", inner)) => Self::try_from(inner),
+                _ => Err(EXPECTED_IP),
+            },
+            #[cfg(not(feature = "records"))]
+            _ => Err(EXPECTED_IP),
+        }
+    }
+}
+
+impl TryFrom<Value> for IpAddr {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Self::try_from(&value)
+    }
+}
+
+#[cfg(feature = "records")]
+impl From<SocketAddr> for Value {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Record(Box::new([
+            ("ip".into(), addr.ip().into()),
+            ("port".into(), Self::Integer(addr.port().into())),
+        ].into_iter().collect()))
+    }
+}
+
+#[cfg(feature = "records")]
+impl TryFrom<&Value> for SocketAddr {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        if let Some(("socket", inner)) = value.as_tagged() {
+            return Self::try_from(inner);
+        }
+        match value {
+            Value::Text(text) => text.parse().map_err(|_| EXPECTED_SOCKET),
+            Value::Record(fields) if fields.len() == 2 => {
+                let ip = IpAddr::try_from(fields.get("ip").ok_or(EXPECTED_SOCKET)?)?;
+                match fields.get("port") {
+                    Some(&Value::Integer(port)) => Ok(Self::new(ip, port.try_into().map_err(|_| EXPECTED_SOCKET)?)),
+                    _ => Err(EXPECTED_SOCKET),
+                }
+            },
+            _ => Err(EXPECTED_SOCKET),
+        }
+    }
+}
+
+#[cfg(feature = "records")]
+impl TryFrom<Value> for SocketAddr {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Self::try_from(&value)
+    }
+}
+
+#[cfg(feature = "records")]
+impl crate::tagged::Extension for IpAddr {
+    const TAG: &'static str = "ip";
+
+    fn to_value(&self) -> Value {
+        Value::Text(self.to_string())
+    }
+
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        Self::try_from(value)
+    }
+}
+
+#[cfg(feature = "records")]
+impl crate::tagged::Extension for SocketAddr {
+    const TAG: &'static str = "socket";
+
+    fn to_value(&self) -> Value {
+        Value::Text(self.to_string())
+    }
+
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        Self::try_from(value)
+    }
+}
+
+/// Serde `with` module that writes `IpAddr` as a blob of its octets, and reads blobs or text
+#[cfg(feature = "serde")]
+pub mod blob {
+    use serde::de::{Error, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+    use std::net::IpAddr;
+
+    pub fn serialize<S: Serializer>(ip: &IpAddr, serializer: S) -> Result<S::Ok, S::Error> {
+        match ip {
+            IpAddr::V4(ip) => serializer.serialize_bytes(&ip.octets()),
+            IpAddr::V6(ip) => serializer.serialize_bytes(&ip.octets()),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<IpAddr, D::Error> {
+        deserializer.deserialize_any(IpVisitor)
+    }
+
+    struct IpVisitor;
+
+    impl Visitor<'_> for IpVisitor {
+        type Value = IpAddr;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("4 or 16 bytes of an IP address")
+        }
+
+        fn visit_bytes<E: Error>(self, octets: &[u8]) -> Result<IpAddr, E> {
+            super::ip_from_octets(octets).ok_or_else(|| E::invalid_length(octets.len(), &self))
+        }
+
+        fn visit_str<E: Error>(self, text: &str) -> Result<IpAddr, E> {
+            text.parse().map_err(E::custom)
+        }
+    }
+}
+
+/// Serde `with` module that writes `SocketAddr` as an `{"ip", "port"}` record, and reads records or text
+#[cfg(feature = "serde")]
+pub mod record {
+    use serde::de::{Error, MapAccess, Visitor};
+    use serde::ser::SerializeStruct;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::fmt;
+    use std::net::{IpAddr, SocketAddr};
+
+    struct Ip(IpAddr);
+
+    impl Serialize for Ip {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::blob::serialize(&self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Ip {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            super::blob::deserialize(deserializer).map(Ip)
+        }
+    }
+
+    pub fn serialize<S: Serializer>(addr: &SocketAddr, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut record = serializer.serialize_struct("SocketAddr", 2)?;
+        record.serialize_field("ip", &Ip(addr.ip()))?;
+        record.serialize_field("port", &addr.port())?;
+        record.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SocketAddr, D::Error> {
+        deserializer.deserialize_any(SocketVisitor)
+    }
+
+    struct SocketVisitor;
+
+    impl<'de> Visitor<'de> for SocketVisitor {
+        type Value = SocketAddr;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a record with ip and port")
+        }
+
+        fn visit_str<E: Error>(self, text: &str) -> Result<SocketAddr, E> {
+            text.parse().map_err(E::custom)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<SocketAddr, A::Error> {
+            let (mut ip, mut port) = (None, None);
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "ip" => ip = Some(map.next_value::<Ip>()?.0),
+                    "port" => port = Some(map.next_value::<u16>()?),
+                    _ => return Err(A::Error::unknown_field(&key, &["ip", "port"])),
+                }
+            }
+            Ok(SocketAddr::new(ip.ok_or_else(|| A::Error::missing_field("ip"))?, port.ok_or_else(|| A::Error::missing_field("port"))?))
+        }
+    }
+}
+
+#[test]
+fn conversions() {
+    let v6: IpAddr = "2001:db8::1".parse().unwrap();
+    assert_eq!(Value::from(v6).to_string(), r#"x"20010db8000000000000000000000001""#);
+    assert_eq!(IpAddr::try_from(Value::from(v6)), Ok(v6));
+    assert_eq!(IpAddr::try_from(Value::Text("10.0.0.1".into())), Ok(IpAddr::from([10, 0, 0, 1])));
+    assert_eq!(IpAddr::try_from(Value::Blob(BitVec::from_slice(&[1, 2, 3]))), Err(EXPECTED_IP));
+
+    #[cfg(feature = "records")]
+    {
+        use crate::tagged::{decode, encode};
+        let addr = SocketAddr::new(IpAddr::from([192, 168, 0, 1]), 8080);
+        let value = Value::from(addr);
+        assert_eq!(value.to_string(), r#"{"ip": x"c0a80001", "port": 8080}"#);
+        assert_eq!(SocketAddr::try_from(&value), Ok(addr));
+        assert_eq!(SocketAddr::try_from(Value::Text("[::1]:80".into())), Ok(SocketAddr::new(IpAddr::from(Ipv6Addr::LOCALHOST), 80)));
+        assert_eq!(SocketAddr::try_from(r#"{"ip": x"c0a80001", "port": 65536}"#.parse::<Value>().unwrap()), Err(EXPECTED_SOCKET));
+
+        let tagged = encode(&addr);
+        assert_eq!(tagged.to_string(), r#"{"$socket": "192.168.0.1:8080"}"#);
+        assert_eq!(decode::<SocketAddr>(&tagged), Ok(addr));
+        assert_eq!(IpAddr::try_from(&encode(&v6)), Ok(v6));
+    }
+
+    #[cfg(feature = "serde")]
+    {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Peer {
+            #[serde(with = "blob")]
+            ip: IpAddr,
+            #[serde(with = "record")]
+            listen: SocketAddr,
+        }
+        let peer = Peer { ip: v6, listen: "127.0.0.1:53".parse().unwrap() };
+        let value = crate::serde::to_value(&peer).unwrap();
+        assert_eq!(value.to_string(), r#"{"ip": x"20010db8000000000000000000000001", "listen": {"ip": x"7f000001", "port": 53}}"#);
+        assert_eq!(crate::serde::from_value::<Peer>(value).unwrap(), peer);
+        let text: Peer = crate::serde::from_value(r#"{"ip": "2001:db8::1", "listen": "127.0.0.1:53"}"#.parse().unwrap()).unwrap();
+        assert_eq!(text, peer);
+    }
+}
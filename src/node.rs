@@ -0,0 +1,106 @@
+//! Node.js bindings via napi-rs: `Value` ↔ JS value conversions, and exported `encode` and `decode` functions
+//!
+//! | Nota | JavaScript |
+//! |------|------------|
+//! | Integer | `number` when it's a safe integer, `bigint` otherwise |
+//! | Text | `string` |
+//! | Blob (whole bytes only) | `Buffer` (and `Uint8Array` converts to it) |
+//! | Array | `Array` |
+//! | Record | plain `Object` |
+//! | Bool | `boolean` |
+//! | Null | `null` (and `undefined` converts to it) |
+//!
+//! A `number` is accepted only if `Number.isSafeInteger()` would be true for it. Fractions, `NaN`, `Infinity`,
+//! and integers that may have been rounded throw an error with the `InvalidArg` code. Pass a `bigint` or a `string` instead.
+//!
+//! To build the addon, build the `cdylib` with `napi build --features napi`.
+use crate::{Error, ParseOptions, Value};
+use bitvec::vec::BitVec;
+use napi::bindgen_prelude::*;
+use napi::{JsValue, ValueType};
+use napi_derive::napi;
+use std::collections::HashMap;
+
+const MAX_SAFE_INTEGER: i128 = (1 << 53) - 1;
+
+impl From<Error> for napi::Error {
+    fn from(err: Error) -> Self {
+        Self::new(Status::InvalidArg, err.to_string())
+    }
+}
+
+impl FromNapiValue for Value {
+    unsafe fn from_napi_value(env: napi::sys::napi_env, napi_val: napi::sys::napi_value) -> Result<Self> {
+        let unknown = Unknown::from_napi_value(env, napi_val)?;
+        Ok(match unknown.get_type()? {
+            ValueType::Null | ValueType::Undefined => Self::Null,
+            ValueType::Boolean => Self::Bool(unknown.cast()?),
+            ValueType::String => Self::Text(unknown.cast()?),
+            ValueType::Number => {
+                let n: f64 = unknown.cast()?;
+                if n.fract() != 0. || n.abs() > MAX_SAFE_INTEGER as f64 {
+                    return Err(Error::Unrepresentable("number that isn't a safe integer").into());
+                }
+                Self::Integer(n as i128)
+            },
+            ValueType::BigInt => match unknown.cast::<BigInt>()?.get_i128() {
+                (i, true) => Self::Integer(i),
+                _ => return Err(Error::Unrepresentable("bigint larger than 128 bits").into()),
+            },
+            ValueType::Object if unknown.is_typedarray()? => Self::Blob(BitVec::from_vec(unknown.cast::<Uint8Array>()?.to_vec())),
+            ValueType::Object if unknown.is_array()? => Self::Array(unknown.cast()?),
+            ValueType::Object => {
+                let object: Object<'_> = unknown.cast()?;
+                Self::Record(Box::new(Object::keys(&object)?.into_iter().map(|key| {
+                    let value = object.get(&key)?.unwrap_or(Self::Null);
+                    Ok((key, value))
+                }).collect::<Result<HashMap<_, _>>>()?))
+            },
+            _ => return Err(Error::Unrepresentable("JS value without a Nota equivalent").into()),
+        })
+    }
+}
+
+impl ToNapiValue for &Value {
+    unsafe fn to_napi_value(env: napi::sys::napi_env, val: Self) -> Result<napi::sys::napi_value> {
+        match val {
+            Value::Null => Null::to_napi_value(env, Null),
+            Value::Bool(b) => bool::to_napi_value(env, *b),
+            Value::Text(s) => <&String>::to_napi_value(env, s),
+            Value::Integer(i) if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(i) => f64::to_napi_value(env, *i as f64),
+            Value::Integer(i) => i128::to_napi_value(env, *i),
+            Value::Blob(bits) if bits.len() % 8 == 0 => Buffer::to_napi_value(env, bits.as_raw_slice().into()),
+            Value::Blob(_) => Err(Error::Unrepresentable("blob with a partial byte").into()),
+            Value::Array(a) => Vec::to_napi_value(env, a.iter().collect::<Vec<_>>()),
+            Value::Record(r) => {
+                let mut object = Object::new(&Env::from(env))?;
+                for (k, v) in r.iter() {
+                    object.set(k, v)?;
+                }
+                Object::to_napi_value(env, object)
+            },
+            Value::DecimalFloat(_) => Err(Error::Unrepresentable("decimal float").into()),
+            Value::Unknown { .. } => Err(Error::Unrepresentable("unknown tag").into()),
+        }
+    }
+}
+
+impl ToNapiValue for Value {
+    unsafe fn to_napi_value(env: napi::sys::napi_env, val: Self) -> Result<napi::sys::napi_value> {
+        <&Self>::to_napi_value(env, &val)
+    }
+}
+
+/// Serializes a JS value to Nota bytes
+#[napi]
+pub fn encode(value: Value) -> Result<Buffer> {
+    let mut out = Vec::new();
+    value.try_serialize_into(&mut out).map_err(|e| napi::Error::new(Status::InvalidArg, e.to_string()))?;
+    Ok(out.into())
+}
+
+/// Parses Nota bytes into a JS value, with [`ParseOptions::untrusted`] limits, so that deeply nested data can't overflow the stack
+#[napi]
+pub fn decode(bytes: Buffer) -> Result<Value> {
+    Value::parse_slice_with(&mut &bytes[..], &ParseOptions::untrusted()).map_err(|e| napi::Error::new(Status::InvalidArg, e.to_string()))
+}
@@ -0,0 +1,104 @@
+//! Ordering of any two values, and sorting of arrays
+use crate::path::Path;
+use crate::Value;
+use std::cmp::Ordering;
+
+impl Value {
+    /// Total ordering of values, consistent with `==`, for sorting and deduplicating documents deterministically.
+    ///
+    /// Values of different kinds are ordered `Null < Bool < Integer < DecimalFloat < Text < Blob < Array < Record < Unknown`.
+    /// Texts are compared by their UTF-8 bytes, and arrays element by element. Records are compared as lists of their fields
+    /// sorted by key. DEC64 values are compared by their exponent and then coefficient, not numerically.
+    #[must_use]
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Integer(a), Self::Integer(b)) => a.cmp(b),
+            #[cfg(feature = "dec64")]
+            #[allow(deprecated)]
+            (Self::DecimalFloat(a), Self::DecimalFloat(b)) => (a.exponent, a.coefficient).cmp(&(b.exponent, b.coefficient)),
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            #[cfg(feature = "blobs")]
+            (Self::Blob(a), Self::Blob(b)) => a.cmp(b),
+            (Self::Array(a), Self::Array(b)) => lexicographic(a, b, Self::total_cmp),
+            #[cfg(feature = "records")]
+            (Self::Record(a), Self::Record(b)) => {
+                let mut a: Vec<_> = a.iter().collect();
+                let mut b: Vec<_> = b.iter().collect();
+                a.sort_unstable_by_key(|(key, _)| *key);
+                b.sort_unstable_by_key(|(key, _)| *key);
+                lexicographic(&a, &b, |(a_key, a), (b_key, b)| a_key.cmp(b_key).then_with(|| a.total_cmp(b)))
+            },
+            (Self::Unknown { tag: a_tag, raw_bytes: a }, Self::Unknown { tag: b_tag, raw_bytes: b }) => (a_tag, a).cmp(&(b_tag, b)),
+            _ => self.kind_rank().cmp(&other.kind_rank()),
+        }
+    }
+
+    fn kind_rank(&self) -> u8 {
+        match self {
+            Self::Null => 0,
+            Self::Bool(_) => 1,
+            Self::Integer(_) => 2,
+            #[cfg(feature = "dec64")]
+            Self::DecimalFloat(_) => 3,
+            Self::Text(_) => 4,
+            #[cfg(feature = "blobs")]
+            Self::Blob(_) => 5,
+            Self::Array(_) => 6,
+            #[cfg(feature = "records")]
+            Self::Record(_) => 7,
+            Self::Unknown { .. } => 8,
+        }
+    }
+
+    /// Sorts elements of the array with the comparison function. The sort is stable. Does nothing if this isn't an array.
+    ///
+    /// ```rust
+    /// use nota::Value;
+    ///
+    /// let mut doc: Value = r#"[3, "b", null, 1, "a"]"#.parse().unwrap();
+    /// doc.sort_array_by(Value::total_cmp);
+    /// assert_eq!(doc.to_string(), r#"[null, 1, 3, "a", "b"]"#);
+    /// ```
+    pub fn sort_array_by(&mut self, compare: impl FnMut(&Self, &Self) -> Ordering) {
+        if let Self::Array(items) = self {
+            items.sort_by(compare);
+        }
+    }
+
+    /// Sorts elements of the array by the values at the path in each of them, in the [total order](Self::total_cmp).
+    ///
+    /// Elements that don't have a value at the path go first. The sort is stable. Does nothing if this isn't an array.
+    pub fn sort_array_by_key(&mut self, path: &Path) {
+        self.sort_array_by(|a, b| match (path.get(a), path.get(b)) {
+            (Some(a), Some(b)) => a.total_cmp(b),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        });
+    }
+}
+
+/// Like `Ord` for slices, with a custom comparison of elements
+fn lexicographic<T>(a: &[T], b: &[T], mut compare: impl FnMut(&T, &T) -> Ordering) -> Ordering {
+    a.iter().zip(b).map(|(a, b)| compare(a, b)).find(|&o| o != Ordering::Equal).unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn sorting() {
+    let mut doc: Value = r#"[{"n": 2, "id": "b"}, {"id": "x"}, {"n": 1}, {"n": [2]}, {"n": "1"}, {"n": 1, "id": "a"}, {"n": null}]"#.parse().unwrap();
+    doc.sort_array_by_key(&"n".parse().unwrap());
+    assert_eq!(doc.to_string(), r#"[{"id": "x"}, {"n": null}, {"n": 1}, {"id": "a", "n": 1}, {"id": "b", "n": 2}, {"n": "1"}, {"n": [2]}]"#);
+
+    let Value::Array(items) = &doc else { unreachable!() };
+    for (i, a) in items.iter().enumerate() {
+        for (j, b) in items.iter().enumerate() {
+            assert_eq!(a.total_cmp(b) == Ordering::Equal, a == b);
+            assert_eq!(a.total_cmp(b), b.total_cmp(a).reverse(), "{i} {j}");
+        }
+    }
+
+    let a: Value = r#"{"a": 1, "b": 2}"#.parse().unwrap();
+    let b: Value = r#"{"a": 1, "c": 0}"#.parse().unwrap();
+    assert_eq!(a.total_cmp(&b), Ordering::Less);
+    assert_eq!(Value::Array(vec![Value::Integer(1)]).total_cmp(&Value::Array(vec![])), Ordering::Greater);
+}
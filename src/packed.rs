@@ -0,0 +1,118 @@
+//! Arrays of fixed-size integers packed into a blob
+//!
+//! Every element of a Nota array has its own tag, so a column of a million numbers takes a few megabytes and a million values to decode.
+//! [`Packed`] stores the numbers big-endian in a single [`Value::Blob`] instead, which is encoded and decoded in bulk.
+//!
+//! This is a convention, not a different encoding: other decoders see a regular blob, and both sides need to agree on the element type.
+//! There are no floats, because Nota doesn't have IEEE754.
+//!
+//! ```rust
+//! use nota::packed::Packed;
+//!
+//! let samples = Packed(vec![-1i16, 300, 7]);
+//! let mut message = Vec::new();
+//! samples.serialize_into(&mut message);
+//! assert_eq!(message.len(), 8);
+//!
+//! let value = nota::Value::parse_slice(&mut &message[..])?;
+//! assert_eq!(Packed::<i16>::try_from(&value)?, samples);
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+use crate::{serialize_unsigned_preamble, Error, Value};
+use bitvec::vec::BitVec;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Integer types that can be [`Packed`]
+pub trait Element: Copy + sealed::Sealed {
+    /// Bytes per element
+    const SIZE: usize;
+    #[doc(hidden)]
+    fn write_be(self, into: &mut Vec<u8>);
+    #[doc(hidden)]
+    fn read_be(bytes: &[u8]) -> Self;
+}
+
+macro_rules! element {
+    ($($t:ty),*) => {$(
+        impl sealed::Sealed for $t {}
+        impl Element for $t {
+            const SIZE: usize = std::mem::size_of::<$t>();
+
+            #[inline]
+            fn write_be(self, into: &mut Vec<u8>) {
+                into.extend_from_slice(&self.to_be_bytes());
+            }
+
+            #[inline]
+            fn read_be(bytes: &[u8]) -> Self {
+                let mut be = [0; std::mem::size_of::<$t>()];
+                be.copy_from_slice(bytes);
+                Self::from_be_bytes(be)
+            }
+        }
+    )*};
+}
+element!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
+/// Integers stored as one blob. Converts to and from [`Value::Blob`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Packed<T>(pub Vec<T>);
+
+impl<T: Element> Packed<T> {
+    /// Appends the blob to `into`, without making a [`Value`] first
+    pub fn serialize_into(&self, into: &mut Vec<u8>) {
+        let len = self.0.len() * T::SIZE;
+        serialize_unsigned_preamble(0, len as u128 * 8, into);
+        into.reserve(len);
+        for &n in &self.0 {
+            n.write_be(into);
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if !bytes.len().is_multiple_of(T::SIZE) {
+            return Err(Error::Unrepresentable("blob that isn't a whole number of elements"));
+        }
+        Ok(Self(bytes.chunks_exact(T::SIZE).map(T::read_be).collect()))
+    }
+}
+
+impl<T: Element> From<Packed<T>> for Value {
+    fn from(packed: Packed<T>) -> Self {
+        let mut bytes = Vec::with_capacity(packed.0.len() * T::SIZE);
+        for n in packed.0 {
+            n.write_be(&mut bytes);
+        }
+        Self::Blob(BitVec::from_vec(bytes))
+    }
+}
+
+impl<T: Element> TryFrom<&Value> for Packed<T> {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        match value {
+            Value::Blob(bits) if bits.len().is_multiple_of(8) => Self::from_bytes(bits.as_raw_slice()),
+            _ => Err(Error::Unrepresentable("value that isn't a blob of bytes")),
+        }
+    }
+}
+
+#[test]
+fn round_trip() {
+    let ints = Packed(vec![i64::MIN, -1, 0, 1, i64::MAX]);
+    let mut bytes = Vec::new();
+    ints.serialize_into(&mut bytes);
+    let value = Value::from(ints.clone());
+    assert_eq!(value.serialized_size(), bytes.len());
+    assert_eq!(Value::parse_slice(&mut &bytes[..]).unwrap(), value);
+    assert_eq!(Packed::try_from(&value), Ok(ints));
+
+    assert_eq!(Packed::<u32>::try_from(&value).unwrap().0.len(), 10);
+    assert!(Packed::<u16>::try_from(&Value::Blob(BitVec::from_vec(vec![1, 2, 3]))).is_err());
+    assert!(Packed::<u8>::try_from(&Value::Null).is_err());
+    assert_eq!(Packed::<u8>::try_from(&Value::Blob(BitVec::new())), Ok(Packed(vec![])));
+}
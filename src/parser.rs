@@ -0,0 +1,738 @@
+use crate::progress::Tracker;
+use crate::path::Segment;
+use crate::{ascii_prefix, kim_char, kim_group, varint, Constants, Dictionary, Error, ProgressCallback, Stats, Value};
+#[cfg(feature = "records")]
+use crate::FieldIds;
+#[cfg(feature = "blobs")]
+use bitvec::vec::BitVec;
+#[cfg(feature = "records")]
+use std::collections::HashMap;
+use std::io::{BufRead, Read};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Settings for [`Value::parse_with`]
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// When set to `true` (from any thread), parsing stops with [`Error::Cancelled`].
+    ///
+    /// It's checked before every value.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Called periodically with the number of bytes read so far
+    pub progress: Option<ProgressCallback>,
+    /// Deepest allowed nesting of arrays and records. A scalar at the top level has depth 1.
+    pub max_depth: Option<usize>,
+    /// Largest allowed number of elements, fields, characters, or bits in a single value
+    pub max_len: Option<usize>,
+    /// Largest allowed size of the whole document in bytes.
+    ///
+    /// It's checked at the start of every value, and before reading a text or a blob, so it can be exceeded only by the few bytes of a number or a length.
+    pub max_size: Option<usize>,
+    /// Largest number of values that [`Decoder::push`](crate::Decoder::push) decodes in one call, counting elements of arrays and records too.
+    ///
+    /// This bounds the time spent in a single call, e.g. in an event loop. Texts and blobs count as one value each, and are copied in bulk.
+    /// Other parsers can't be paused, so they don't use it.
+    pub max_values_per_push: Option<usize>,
+    /// Reject encodings that aren't the shortest possible: numbers, lengths, and kim chars with leading zero groups,
+    /// negative zero, and blobs with padding bits set. The parsed values are the same either way.
+    ///
+    /// Such documents are valid, but rejecting them ensures that numbers, lengths, chars, and blobs have only one encoding.
+    /// Record fields are still accepted in any order, and a repeated key replaces the earlier field,
+    /// so this alone doesn't make the bytes canonical. [`SerializeOptions::canonical`](crate::SerializeOptions::canonical) writes documents that are accepted.
+    pub strict: bool,
+    /// Accept texts written as references with [`SerializeOptions::backrefs`](crate::SerializeOptions::backrefs).
+    ///
+    /// Off by default, so that only standard Nota is accepted. Beware that references can expand a small message into many copies of a long text.
+    pub backrefs: bool,
+    /// Texts that back-references can point to, in addition to texts earlier in the document. Must be the same as in [`SerializeOptions::dictionary`](crate::SerializeOptions::dictionary).
+    pub dictionary: Option<Dictionary>,
+    /// Values of constants other than `false`, `true`, and `null`
+    pub constants: Option<Constants>,
+    /// Names of record keys written as numbers. Must be the same as in [`SerializeOptions::field_ids`](crate::SerializeOptions::field_ids).
+    #[cfg(feature = "records")]
+    pub field_ids: Option<FieldIds>,
+    /// Parse the reserved tag `111` and undefined constants as [`Value::Unknown`], instead of failing, so that documents using future extensions can still be read.
+    ///
+    /// A reserved tag is assumed to be followed by a number, like a length, and nothing else.
+    /// With [`backrefs`](Self::backrefs), the tag `111` is a back-reference instead.
+    pub unknown: bool,
+}
+
+impl ParseOptions {
+    /// Conservative limits for data from untrusted sources, which guard against running out of memory or stack.
+    ///
+    /// Documents are limited to 16MB, nesting to 64 levels, and a single value to a million elements, chars, or bits.
+    /// Encodings must be [`strict`](Self::strict), and back-references aren't accepted.
+    ///
+    /// These limits are not stable, and may become stricter. Set the fields yourself if you depend on specific values.
+    #[must_use]
+    pub fn untrusted() -> Self {
+        Self {
+            max_depth: Some(64),
+            max_len: Some(1 << 20),
+            max_size: Some(16 << 20),
+            strict: true,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<(), io::Error> {
+        match &self.cancel {
+            Some(cancel) if cancel.load(Ordering::Relaxed) => Err(Error::Cancelled.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_depth(&self, depth: usize) -> Result<(), io::Error> {
+        match self.max_depth {
+            Some(max) if depth > max => Err(Error::TooDeep { max }.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Finds the text in the dictionary, or returns the index among the document's own texts
+    pub(crate) fn backref(&self, index: usize) -> Result<&str, usize> {
+        let texts = self.dictionary.as_ref().map_or(&[][..], Dictionary::texts);
+        texts.get(index).map(String::as_str).ok_or_else(|| index - texts.len())
+    }
+
+    pub(crate) fn check_len(&self, len: usize) -> Result<(), io::Error> {
+        match self.max_len {
+            Some(max) if len > max => Err(Error::TooLong { len, max }.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// `size` is the number of bytes that have been read, or are about to be read
+    pub(crate) fn check_size(&self, size: u64) -> Result<(), io::Error> {
+        match self.max_size {
+            Some(max) if size > max as u64 => Err(Error::TooLarge { max }.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails in [`strict`](Self::strict) mode if the encoding could have been shorter
+    #[inline]
+    pub(crate) fn check_minimal(&self, is_minimal: bool) -> Result<(), io::Error> {
+        if self.strict && !is_minimal {
+            return Err(Error::NotMinimal.into());
+        }
+        Ok(())
+    }
+
+    /// A constant other than `false`, `true`, and `null`, which is only known to [`constants`](Self::constants), or [`unknown`](Self::unknown)
+    pub(crate) fn constant(&self, preamble: u8) -> Result<Value, io::Error> {
+        if let Some(value) = self.constants.as_ref().and_then(|c| c.get(preamble & 0b0001_1111)) {
+            return Ok(value);
+        }
+        if self.unknown {
+            return Ok(Value::Unknown { tag: 0b110, raw_bytes: Box::new([preamble]) });
+        }
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// The field name for a key, which may be a number from [`field_ids`](Self::field_ids)
+    #[cfg(feature = "records")]
+    pub(crate) fn field_name(&self, key: String) -> String {
+        match &self.field_ids {
+            Some(ids) => ids.name_of_key(key),
+            None => key,
+        }
+    }
+
+    /// Bits of the last byte past the end of the blob must be zero in [`strict`](Self::strict) mode
+    #[cfg(feature = "blobs")]
+    pub(crate) fn check_padding(&self, bytes: &[u8], bits: usize) -> Result<(), io::Error> {
+        self.check_minimal(bits.is_multiple_of(8) || bytes.last().is_none_or(|&last| last & (0xFF >> (bits % 8)) == 0))
+    }
+}
+
+/// Where [`Parser`] gets its bytes from
+pub(crate) trait Input: Read {
+    fn byte(&mut self) -> Result<u8, io::Error>;
+
+    /// Appends up to `max` ASCII chars that are available without reading. Returns how many.
+    fn ascii(&mut self, _max: usize, _out: &mut String) -> Result<usize, io::Error> {
+        Ok(0)
+    }
+
+    /// Reads up to `len` bytes, fewer only at the end of the input.
+    ///
+    /// Allocates up front at most 1MB, so that a corrupted length can't allocate much more than the data that is actually there.
+    #[cfg(feature = "blobs")]
+    fn blob_bytes(&mut self, len: usize) -> Result<Vec<u8>, io::Error> {
+        // exact capacity lets read_to_end read straight into it, without growing and probing
+        let mut out = Vec::with_capacity(len.min(1 << 20));
+        (&mut *self).take(len as u64).read_to_end(&mut out)?;
+        Ok(out)
+    }
+}
+
+/// Reads a byte at a time with `read_exact`
+pub(crate) struct Unbuffered<R>(pub R);
+
+impl<R: Read> Read for Unbuffered<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<R: Read> Input for Unbuffered<R> {
+    #[inline]
+    fn byte(&mut self) -> Result<u8, io::Error> {
+        let mut next = 0;
+        self.0.read_exact(std::slice::from_mut(&mut next))?;
+        Ok(next)
+    }
+}
+
+/// Takes bytes directly from the reader's buffer
+pub(crate) struct Buffered<R>(pub R);
+
+impl<R: BufRead> Buffered<R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        loop {
+            match self.0.fill_buf() {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+                Err(e) => return Err(e),
+                // returning the buffer from here would keep self borrowed in the loop
+                Ok(_) => return self.0.fill_buf(),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Read for Buffered<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<R: BufRead> Input for Buffered<R> {
+    #[inline]
+    fn byte(&mut self) -> Result<u8, io::Error> {
+        let &next = self.fill_buf()?.first().ok_or(io::ErrorKind::UnexpectedEof)?;
+        self.0.consume(1);
+        Ok(next)
+    }
+
+    fn ascii(&mut self, max: usize, out: &mut String) -> Result<usize, io::Error> {
+        let buf = self.fill_buf()?;
+        let ascii = ascii_prefix(buf, max);
+        let len = ascii.len();
+        out.push_str(ascii);
+        self.0.consume(len);
+        Ok(len)
+    }
+
+    /// Copies from the reader's buffer, without zero-filling the `Vec` first
+    #[cfg(feature = "blobs")]
+    fn blob_bytes(&mut self, len: usize) -> Result<Vec<u8>, io::Error> {
+        let mut out = Vec::with_capacity(len.min(1 << 20));
+        while out.len() < len {
+            let buf = self.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let chunk = buf.len().min(len - out.len());
+            out.extend_from_slice(&buf[..chunk]);
+            self.0.consume(chunk);
+        }
+        Ok(out)
+    }
+}
+
+pub(crate) struct Parser<'a, I> {
+    input: I,
+    options: &'a ParseOptions,
+    offset: u64,
+    depth: usize,
+    progress: Tracker<'a>,
+    stats: Option<Stats>,
+    /// Position in the document for error reporting. Tracked only when validating.
+    pub(crate) path: Option<Vec<Segment>>,
+    /// Where the most recently started value begins
+    pub(crate) value_offset: u64,
+    /// Texts that back-references point to
+    strings: Vec<String>,
+}
+
+impl<'a, I: Input> Parser<'a, I> {
+    pub fn new(input: I, options: &'a ParseOptions) -> Self {
+        Self {
+            input,
+            options,
+            offset: 0,
+            depth: 0,
+            progress: Tracker::new(options.progress.as_ref()),
+            stats: None,
+            path: None,
+            value_offset: 0,
+            strings: Vec::new(),
+        }
+    }
+
+    pub fn parse(mut self) -> Result<Value, io::Error> {
+        let value = self.value()?;
+        self.options.check_size(self.offset)?;
+        self.progress.finish(self.offset);
+        Ok(value)
+    }
+
+    pub fn parse_with_stats(mut self) -> Result<(Value, Stats), io::Error> {
+        self.parse_with_stats_mut()
+    }
+
+    /// Leaves the parser available for checking where it has stopped
+    pub(crate) fn parse_with_stats_mut(&mut self) -> Result<(Value, Stats), io::Error> {
+        self.stats = Some(Stats::default());
+        let value = self.value()?;
+        self.options.check_size(self.offset)?;
+        self.progress.finish(self.offset);
+        let mut stats = self.stats.take().unwrap_or_default();
+        stats.total_bytes = self.offset;
+        Ok((value, stats))
+    }
+
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn enter(&mut self, segment: impl FnOnce() -> Segment) {
+        if let Some(path) = &mut self.path {
+            path.push(segment());
+        }
+    }
+
+    fn leave(&mut self) {
+        if let Some(path) = &mut self.path {
+            path.pop();
+        }
+    }
+
+    #[inline]
+    fn stat(&mut self, f: impl FnOnce(&mut Stats)) {
+        if let Some(stats) = &mut self.stats {
+            f(stats);
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, io::Error> {
+        let next = self.input.byte()?;
+        self.offset += 1;
+        Ok(next)
+    }
+
+    fn value(&mut self) -> Result<Value, io::Error> {
+        self.depth += 1;
+        let res = self.value_inner();
+        self.depth -= 1;
+        res
+    }
+
+    fn value_inner(&mut self) -> Result<Value, io::Error> {
+        self.options.check_cancelled()?;
+        self.value_offset = self.offset;
+        self.options.check_depth(self.depth)?;
+        self.options.check_size(self.offset)?;
+        self.progress.value(self.offset);
+        let depth = self.depth;
+        self.stat(|s| s.max_depth = s.max_depth.max(depth));
+        let preamble = self.byte()?;
+        let kind = preamble & 0b1110_0000;
+        Ok(match kind {
+            #[cfg(feature = "blobs")]
+            0b0000_0000 => {
+                let len = self.len(preamble)?;
+                let len_bytes = len.div_ceil(8);
+                self.options.check_size(self.offset.saturating_add(len_bytes as u64))?;
+                let out = self.input.blob_bytes(len_bytes)?;
+                self.offset += out.len() as u64;
+                if out.len() != len_bytes {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                self.options.check_padding(&out, len)?;
+                let mut out = BitVec::from_vec(out);
+                if len & 7 != 0 {
+                    out.truncate(len);
+                }
+                self.stat(|s| { s.blobs += 1; s.largest_blob = s.largest_blob.max(len); });
+                Value::Blob(out)
+            },
+            0b0010_0000 => {
+                let len = self.len(preamble)?;
+                // every char takes at least a byte
+                self.options.check_size(self.offset.saturating_add(len as u64))?;
+                let mut out = String::with_capacity(len.min(1 << 20));
+                let start = self.offset;
+                let mut remaining = len;
+                while remaining > 0 {
+                    let ascii = self.input.ascii(remaining, &mut out)?;
+                    self.offset += ascii as u64;
+                    remaining -= ascii;
+                    if ascii == 0 {
+                        out.push(self.kim_char()?);
+                        remaining -= 1;
+                    }
+                }
+                let text_bytes = self.offset - start;
+                self.stat(|s| { s.texts += 1; s.text_bytes += text_bytes; s.longest_text = s.longest_text.max(len); });
+                if self.options.backrefs {
+                    self.strings.push(out.clone());
+                }
+                Value::Text(out)
+            },
+            0b0100_0000 => {
+                let len = self.len(preamble)?;
+                self.stat(|s| { s.arrays += 1; s.largest_array = s.largest_array.max(len); });
+                let mut out = Vec::with_capacity(len.min(1 << 18));
+                for i in 0..len {
+                    self.enter(|| Segment::Index(i));
+                    out.push(self.value()?);
+                    self.leave();
+                }
+                Value::Array(out)
+            },
+            #[cfg(feature = "records")]
+            0b0110_0000 => {
+                let len = self.len(preamble)?;
+                self.stat(|s| { s.records += 1; s.largest_record = s.largest_record.max(len); });
+                let mut out = HashMap::with_capacity(len.min(1 << 16));
+                for i in 0..len {
+                    self.enter(|| Segment::Index(i));
+                    let Value::Text(k) = self.value()? else {
+                        return Err(io::ErrorKind::InvalidData.into());
+                    };
+                    let k = self.options.field_name(k);
+                    self.leave();
+                    self.enter(|| Segment::Key(k.clone()));
+                    let v = self.value()?;
+                    self.leave();
+                    out.insert(k, v);
+                }
+                Value::Record(Box::new(out))
+            },
+            0b1000_0000 => {
+                let sign = preamble & 0b000_1000;
+                let mut val = (preamble & 0b000_0111) as u128;
+                if preamble & 0b0001_0000 != 0 {
+                    loop {
+                        let next = self.byte()?;
+                        val = varint::push_int(val, next)?;
+                        self.options.check_minimal(val >= 8)?;
+                        if next & 0b1000_0000 == 0 {
+                            break;
+                        }
+                    }
+                }
+                self.options.check_minimal(sign == 0 || val != 0)?;
+                self.stat(|s| s.integers += 1);
+                Value::Integer(varint::signed(sign != 0, val)?)
+            },
+            #[cfg(feature = "dec64")]
+            0b1010_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            0b1100_0000 => {
+                let val = preamble & 0b0001_1111;
+                match val {
+                    0 | 1 => {
+                        self.stat(|s| s.bools += 1);
+                        Value::Bool(val != 0)
+                    },
+                    2 => {
+                        self.stat(|s| s.nulls += 1);
+                        Value::Null
+                    },
+                    _ => self.options.constant(preamble)?,
+                }
+            },
+            0b1110_0000 if self.options.backrefs => {
+                let index = self.unsigned(preamble)?;
+                let text = match self.options.backref(index) {
+                    Ok(text) => text.to_owned(),
+                    Err(index) => self.strings.get(index).ok_or(io::ErrorKind::InvalidData)?.clone(),
+                };
+                self.stat(|s| s.texts += 1);
+                Value::Text(text)
+            },
+            0b1110_0000 if self.options.unknown => {
+                let mut raw_bytes = vec![preamble];
+                if preamble & 0b0001_0000 != 0 {
+                    let mut len = usize::from(preamble & 0b000_1111);
+                    loop {
+                        let next = self.byte()?;
+                        // the number is skipped, but it must fit like a length
+                        len = varint::push_len(len, next)?;
+                        raw_bytes.push(next);
+                        if next & 0b1000_0000 == 0 {
+                            break;
+                        }
+                    }
+                }
+                Value::Unknown { tag: 0b111, raw_bytes: raw_bytes.into() }
+            },
+            #[cfg(not(feature = "blobs"))]
+            0b0000_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            #[cfg(not(feature = "records"))]
+            0b0110_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            #[cfg(not(feature = "dec64"))]
+            0b1010_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            _ => return Err(io::ErrorKind::InvalidData.into()),
+        })
+    }
+
+    fn kim_char(&mut self) -> Result<char, io::Error> {
+        let start = self.offset;
+        let mut val = 0;
+        loop {
+            let next = self.byte()?;
+            self.options.check_minimal(val != 0 || next != 0b1000_0000)?;
+            val = kim_group(val, next, start)?;
+            if next & 0b1000_0000 == 0 {
+                return Ok(kim_char(val, start)?);
+            }
+        }
+    }
+
+    #[inline(never)]
+    fn len(&mut self, preamble: u8) -> Result<usize, io::Error> {
+        let len = self.unsigned(preamble)?;
+        self.options.check_len(len)?;
+        Ok(len)
+    }
+
+    fn unsigned(&mut self, preamble: u8) -> Result<usize, io::Error> {
+        let mut len = preamble as usize & 0b000_1111;
+        if preamble & 0b0001_0000 != 0 {
+            loop {
+                let next = self.byte()?;
+                len = varint::push_len(len, next)?;
+                self.options.check_minimal(len >= 16)?;
+                if next & 0b1000_0000 == 0 {
+                    break;
+                }
+            }
+        }
+        Ok(len)
+    }
+}
+
+#[test]
+fn cancel() {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let options = ParseOptions { cancel: Some(cancel.clone()), ..Default::default() };
+    let data = [0x42, 0xC0, 0xC1];
+    assert_eq!(Value::parse_with(&mut &data[..], &options).unwrap(), Value::Array(vec![Value::Bool(false), Value::Bool(true)]));
+
+    cancel.store(true, Ordering::Relaxed);
+    let err = Value::parse_with(&mut &data[..], &options).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::Cancelled));
+}
+
+#[test]
+fn progress() {
+    use std::sync::Mutex;
+
+    let value = Value::Array(vec![Value::Text("x".repeat(1000)); 200]);
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let r = reports.clone();
+    let callback = crate::ProgressCallback::new(move |p| r.lock().unwrap().push(p));
+
+    let mut out = Vec::new();
+    value.serialize_with(&mut out, &crate::SerializeOptions { progress: Some(callback.clone()), ..Default::default() });
+    let written = std::mem::take(&mut *reports.lock().unwrap());
+    assert_eq!(written.len(), 4);
+    assert_eq!(written.last().unwrap().bytes, out.len() as u64);
+    assert_eq!(written.last().unwrap().values, 201);
+
+    let options = ParseOptions { progress: Some(callback), ..Default::default() };
+    assert_eq!(Value::parse_with(&mut &out[..], &options).unwrap(), value);
+    assert_eq!(*reports.lock().unwrap(), written);
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn stats() {
+    let mut record = HashMap::new();
+    record.insert("ab".into(), Value::Array(vec![Value::Integer(1), Value::Blob(BitVec::repeat(true, 11))]));
+    record.insert("☃".into(), Value::Bool(true));
+    let value = Value::Array(vec![Value::Record(Box::new(record)), Value::Text("cat".into()), Value::Null]);
+    let mut out = Vec::new();
+    value.serialize_into(&mut out);
+
+    let (parsed, stats) = Value::parse_with_stats(&mut &out[..], &ParseOptions::default()).unwrap();
+    assert_eq!(parsed, value);
+    assert_eq!(stats, Stats {
+        blobs: 1, texts: 3, arrays: 2, records: 1, integers: 1, bools: 1, nulls: 1,
+        max_depth: 4,
+        total_bytes: out.len() as u64,
+        text_bytes: 2 + 2 + 3,
+        longest_text: 3,
+        largest_blob: 11,
+        largest_array: 3,
+        largest_record: 2,
+    });
+    assert_eq!(stats.values(), 10);
+}
+
+#[cfg(not(any(feature = "blobs", feature = "records")))]
+#[test]
+fn disabled_types() {
+    assert_eq!(Value::parse_from(&mut &[0x61, 0x21, 0x61, 0x80][..]).unwrap_err().kind(), io::ErrorKind::Unsupported);
+    assert_eq!(Value::parse_from(&mut &[0x41, 0x08, 0xFF][..]).unwrap_err().kind(), io::ErrorKind::Unsupported);
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn buffered() {
+    use std::io::BufReader;
+
+    let value = Value::Array(vec![
+        Value::Record(Box::new([
+            ("ascii".to_string(), Value::Text("hello world".into())),
+            ("mixed".to_string(), Value::Text("snow ☃ and 𓂀 too".into())),
+        ].into_iter().collect())),
+        Value::Blob(BitVec::from_vec(vec![1, 2, 3, 4, 5])),
+        Value::Integer(-1234567890123),
+    ]);
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+    bytes.push(0xC2);
+
+    for capacity in 1..6 {
+        let mut reader = BufReader::with_capacity(capacity, &bytes[..]);
+        assert_eq!(Value::parse_buf(&mut reader).unwrap(), value);
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [0xC2]);
+    }
+    let err = Value::parse_buf(&mut &bytes[..bytes.len() - 3]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[cfg(feature = "blobs")]
+#[test]
+fn large_blob() {
+    use std::io::BufReader;
+
+    let value = Value::Blob(BitVec::from_vec((0..3 << 20).map(|n| n as u8).collect()));
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+    assert_eq!(Value::parse_from(&mut &bytes[..]).unwrap(), value);
+    assert_eq!(Value::parse_buf(&mut BufReader::new(&bytes[..])).unwrap(), value);
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert_eq!(Value::parse_from(&mut &truncated[..]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(Value::parse_buf(&mut BufReader::new(truncated)).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[cfg(feature = "blobs")]
+#[test]
+fn untrusted() {
+    use crate::Decoder;
+
+    let parse_all = |data: &[u8], options: &ParseOptions| {
+        let values = [
+            Value::parse_with(&mut &data[..], options),
+            Value::parse_buf_with(&mut &data[..], options),
+            Value::parse_slice_with(&mut &data[..], options),
+            Decoder::with_options(options.clone()).push(data).and_then(|(_, value)| value.ok_or(io::ErrorKind::UnexpectedEof.into())),
+        ];
+        values.map(|res| res.map_err(|e| Error::from_io(&e).cloned()))
+    };
+
+    let longer = [
+        (&[0x30, 0x03, b'a', b'b', b'c'][..], Value::Text("abc".into())),
+        (&[0x21, 0x80, b'a'], Value::Text("a".into())),
+        (&[0x90, 0x05], Value::Integer(5)),
+        (&[0x98, 0x80, 0x01], Value::Integer(-1)),
+        (&[0x88], Value::Integer(0)),
+        (&[0x03, 0b1010_0001], Value::Blob([true, false, true].into_iter().collect())),
+    ];
+    for (data, value) in longer {
+        for res in parse_all(data, &ParseOptions::default()) {
+            assert_eq!(res, Ok(value.clone()));
+        }
+        for res in parse_all(data, &ParseOptions::untrusted()) {
+            assert_eq!(res, Err(Some(Error::NotMinimal)));
+        }
+    }
+
+    let value = Value::Array(vec![
+        Value::Text("snow ☃ 𓂀".repeat(10)),
+        Value::Integer(i128::MIN + 1),
+        Value::Integer(i128::MIN),
+        Value::Integer(-8),
+        Value::Integer(1 << 40),
+        Value::Array(vec![Value::Null; 16]),
+        Value::Blob([true, false, true].into_iter().collect()),
+    ]);
+    let mut data = Vec::new();
+    value.serialize_with(&mut data, &crate::SerializeOptions { canonical: true, ..Default::default() });
+    for res in parse_all(&data, &ParseOptions::untrusted()) {
+        assert_eq!(res, Ok(value.clone()));
+    }
+
+    let options = ParseOptions { max_size: Some(50), ..Default::default() };
+    let mut nulls = vec![0x50, 0x64];
+    nulls.extend([0xC2; 100]);
+    // the text is cut off, but it's rejected based on its length
+    let text = [0x3F, 0xFF, 0x7F, b'a'];
+    for data in [&nulls[..], &text] {
+        for res in parse_all(data, &options) {
+            assert_eq!(res, Err(Some(Error::TooLarge { max: 50 })));
+        }
+    }
+    assert!(parse_all(&nulls[..50], &options).iter().all(|res| res != &Err(Some(Error::TooLarge { max: 50 }))));
+}
+
+#[test]
+fn unknown_tags() {
+    let data = [0x43, 0xF2, 0x85, 0x01, 0xC5, 0xC2];
+    let value = Value::Array(vec![
+        Value::Unknown { tag: 0b111, raw_bytes: Box::new([0xF2, 0x85, 0x01]) },
+        Value::Unknown { tag: 0b110, raw_bytes: Box::new([0xC5]) },
+        Value::Null,
+    ]);
+    let options = ParseOptions { unknown: true, ..Default::default() };
+    assert_eq!(Value::parse_with(&mut &data[..], &options).unwrap(), value);
+    assert_eq!(Value::parse_buf_with(&mut &data[..], &options).unwrap(), value);
+    assert_eq!(Value::parse_slice_with(&mut &data[..], &options).unwrap(), value);
+    let mut decoder = crate::Decoder::with_options(options.clone());
+    assert_eq!(data.iter().find_map(|b| decoder.push(std::slice::from_ref(b)).unwrap().1), Some(value.clone()));
+    #[cfg(feature = "bumpalo")]
+    assert_eq!(crate::arena::ArenaValue::parse_in_with(&mut &data[..], &bumpalo::Bump::new(), &options).unwrap().to_value(), value);
+
+    let mut out = Vec::new();
+    value.serialize_into(&mut out);
+    assert_eq!(out, data);
+    assert_eq!(value.serialized_size(), data.len());
+    assert_eq!(value.to_string(), r#"[unknown(x"f28501"), unknown(x"c5"), null]"#);
+    assert_eq!(value.to_string().parse::<Value>().unwrap(), value);
+
+    assert!(Value::parse_slice(&mut &data[..]).is_err());
+    let backrefs = ParseOptions { backrefs: true, ..options };
+    assert!(Value::parse_slice_with(&mut &data[..], &backrefs).is_err());
+}
+
+#[test]
+fn constants() {
+    let data = [0x43, 0xC3, 0xC4, 0xC5];
+    let constants = Some(Constants::new(|c| match c {
+        3 => Some(Value::Null),
+        4 => Some(Value::Array(vec![Value::Text("four".into())])),
+        _ => None,
+    }));
+    let options = ParseOptions { constants: constants.clone(), unknown: true, ..Default::default() };
+    let value = Value::Array(vec![Value::Null, Value::Array(vec![Value::Text("four".into())]), Value::Unknown { tag: 0b110, raw_bytes: Box::new([0xC5]) }]);
+    assert_eq!(Value::parse_with(&mut &data[..], &options).unwrap(), value);
+    assert_eq!(Value::parse_slice_with(&mut &data[..], &options).unwrap(), value);
+    assert_eq!(crate::Decoder::with_options(options.clone()).push(&data).unwrap(), (data.len(), Some(value.clone())));
+    #[cfg(feature = "bumpalo")]
+    assert_eq!(crate::arena::ArenaValue::parse_in_with(&mut &data[..], &bumpalo::Bump::new(), &options).unwrap().to_value(), value);
+
+    let options = ParseOptions { constants, ..Default::default() };
+    assert_eq!(Value::parse_slice_with(&mut &data[..3], &options).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(Value::parse_slice_with(&mut &data[..], &options).unwrap_err().kind(), io::ErrorKind::Unsupported);
+}
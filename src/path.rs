@@ -0,0 +1,308 @@
+//! Paths to values inside a document, like `users[3].name` or `["first name"]`
+use crate::Value;
+use std::borrow::Cow;
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+/// One step of a [`Path`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Segment {
+    /// Element of an array
+    Index(usize),
+    /// Field of a record
+    Key(String),
+}
+
+/// Location of a value, from the top-level value down.
+///
+/// The text form is `.` for the top-level value, `name` or `["any key"]` for record fields (joined with `.`), and `[3]` for array elements.
+/// Quoted keys use Rust's string escapes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Path(Vec<Segment>);
+
+/// Why a [`Path`] couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PathError {
+    /// Byte offset in the path text
+    pub offset: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path at {}: {}", self.offset, self.reason)
+    }
+}
+
+impl std::error::Error for PathError {}
+
+impl Path {
+    #[must_use]
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+
+    pub fn push(&mut self, segment: Segment) {
+        self.0.push(segment);
+    }
+
+    pub fn pop(&mut self) -> Option<Segment> {
+        self.0.pop()
+    }
+
+    /// `None` if there's no such field or element
+    #[must_use]
+    pub fn get<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.0.iter().try_fold(value, |value, segment| match (segment, value) {
+            (Segment::Index(i), Value::Array(items)) => items.get(*i),
+            #[cfg(feature = "records")]
+            (Segment::Key(k), Value::Record(fields)) => fields.get(k),
+            _ => None,
+        })
+    }
+
+    #[must_use]
+    pub fn get_mut<'a>(&self, value: &'a mut Value) -> Option<&'a mut Value> {
+        self.0.iter().try_fold(value, |value, segment| match (segment, value) {
+            (Segment::Index(i), Value::Array(items)) => items.get_mut(*i),
+            #[cfg(feature = "records")]
+            (Segment::Key(k), Value::Record(fields)) => fields.get_mut(k),
+            _ => None,
+        })
+    }
+
+    /// The path as a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901), like `/users/3/name`
+    #[must_use]
+    pub fn to_pointer(&self) -> String {
+        let mut out = String::new();
+        for segment in &self.0 {
+            out.push('/');
+            match segment {
+                Segment::Index(i) => write!(out, "{i}").unwrap(),
+                Segment::Key(k) => out.push_str(&k.replace('~', "~0").replace('/', "~1")),
+            }
+        }
+        out
+    }
+}
+
+impl Value {
+    /// Looks up a value by a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901), like `/users/3/name`.
+    ///
+    /// The empty pointer is the top-level value. In keys, `~1` stands for `/` and `~0` for `~`.
+    /// Returns `None` if there's no such field or element, or the pointer is invalid.
+    ///
+    /// ```rust
+    /// use nota::Value;
+    ///
+    /// let doc: Value = r#"[["a", "b"], "c"]"#.parse().unwrap();
+    /// assert_eq!(doc.pointer("/0/1"), Some(&Value::Text("b".into())));
+    /// assert_eq!(doc.pointer("/2"), None);
+    /// ```
+    #[must_use]
+    pub fn pointer(&self, pointer: &str) -> Option<&Self> {
+        pointer_tokens(pointer)?.try_fold(self, |value, token| match value {
+            Self::Array(items) => items.get(array_index(&token?)?),
+            #[cfg(feature = "records")]
+            Self::Record(fields) => fields.get(&*token?),
+            _ => None,
+        })
+    }
+
+    /// Like [`pointer`](Self::pointer), for changing the value
+    #[must_use]
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Self> {
+        pointer_tokens(pointer)?.try_fold(self, |value, token| match value {
+            Self::Array(items) => items.get_mut(array_index(&token?)?),
+            #[cfg(feature = "records")]
+            Self::Record(fields) => fields.get_mut(&*token?),
+            _ => None,
+        })
+    }
+}
+
+/// Unescaped reference tokens, `None` for tokens with invalid escapes
+fn pointer_tokens(pointer: &str) -> Option<impl Iterator<Item = Option<Cow<'_, str>>>> {
+    let tokens = match pointer {
+        "" => None,
+        _ => Some(pointer.strip_prefix('/')?.split('/')),
+    };
+    Some(tokens.into_iter().flatten().map(|token| {
+        if !token.contains('~') {
+            return Some(Cow::Borrowed(token));
+        }
+        let mut out = String::with_capacity(token.len());
+        let mut parts = token.split('~');
+        out.push_str(parts.next()?);
+        for part in parts {
+            out.push(match part.as_bytes().first() {
+                Some(b'0') => '~',
+                Some(b'1') => '/',
+                _ => return None,
+            });
+            out.push_str(&part[1..]);
+        }
+        Some(Cow::Owned(out))
+    }))
+}
+
+/// RFC 6901 doesn't allow leading zeros
+fn array_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl From<Vec<Segment>> for Path {
+    fn from(segments: Vec<Segment>) -> Self {
+        Self(segments)
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c == '_' || c.is_alphanumeric())
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_char('.');
+        }
+        for (n, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Index(i) => write!(f, "[{i}]")?,
+                Segment::Key(k) if is_identifier(k) => {
+                    if n > 0 {
+                        f.write_char('.')?;
+                    }
+                    f.write_str(k)?;
+                },
+                Segment::Key(k) => write!(f, "[{k:?}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Path {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, PathError> {
+        let err = |offset, reason| PathError { offset, reason };
+        let mut segments = Vec::new();
+        let mut rest = s.strip_prefix('.').unwrap_or(s);
+        let at = |rest: &str| s.len() - rest.len();
+        let mut first = true;
+        while !rest.is_empty() {
+            if let Some(inner) = rest.strip_prefix('[') {
+                if let Some(quoted) = inner.strip_prefix('"') {
+                    let (key, after) = unquote(quoted).map_err(|reason| err(at(quoted), reason))?;
+                    rest = after.strip_prefix(']').ok_or_else(|| err(at(after), "expected `]`"))?;
+                    segments.push(Segment::Key(key));
+                } else {
+                    let end = inner.find(']').ok_or_else(|| err(at(inner), "expected `]`"))?;
+                    let index = inner[..end].parse().map_err(|_| err(at(inner), "expected an array index or a quoted key"))?;
+                    rest = &inner[end + 1..];
+                    segments.push(Segment::Index(index));
+                }
+            } else {
+                if !first {
+                    rest = rest.strip_prefix('.').ok_or_else(|| err(at(rest), "expected `.` or `[`"))?;
+                }
+                let end = rest.find(|c: char| c != '_' && !c.is_alphanumeric()).unwrap_or(rest.len());
+                if end == 0 {
+                    return Err(err(at(rest), "expected a key"));
+                }
+                segments.push(Segment::Key(rest[..end].into()));
+                rest = &rest[end..];
+            }
+            first = false;
+        }
+        Ok(Self(segments))
+    }
+}
+
+/// Reads a string after the opening `"`, and returns the rest after the closing quote
+pub(crate) fn unquote(mut s: &str) -> Result<(String, &str), &'static str> {
+    let mut out = String::new();
+    loop {
+        let mut chars = s.chars();
+        let c = chars.next().ok_or("unterminated string")?;
+        s = chars.as_str();
+        match c {
+            '"' => return Ok((out, s)),
+            '\\' => {
+                let mut chars = s.chars();
+                out.push(match chars.next().ok_or("unterminated string")? {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    '0' => '\0',
+                    c @ ('"' | '\\' | '\'') => c,
+                    'u' => {
+                        let (hex, after) = chars.as_str().strip_prefix('{').and_then(|h| h.split_once('}')).ok_or("expected `\\u{…}`")?;
+                        chars = after.chars();
+                        u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).ok_or("invalid `\\u{…}` escape")?
+                    },
+                    _ => return Err("unknown escape"),
+                });
+                s = chars.as_str();
+            },
+            c => out.push(c),
+        }
+    }
+}
+
+#[test]
+fn parse_and_display() {
+    for text in [".", "a", "users[3].name", r#"[0]["first name"].x_1"#, r#"["a\"b\n"]"#, r#"["caf\u{e9}!"]"#] {
+        let path: Path = text.parse().unwrap();
+        let again: Path = path.to_string().parse().unwrap();
+        assert_eq!(path, again, "{text}");
+    }
+    assert_eq!("".parse::<Path>().unwrap(), Path::default());
+    assert_eq!(".a.b".parse::<Path>().unwrap().to_string(), "a.b");
+    assert_eq!(r#"["caf\u{e9}!"]"#.parse::<Path>().unwrap().segments(), [Segment::Key("café!".into())]);
+    assert_eq!("a[x]".parse::<Path>().unwrap_err().offset, 2);
+    assert_eq!("a b".parse::<Path>().unwrap_err().offset, 1);
+    assert!(r#"["a"#.parse::<Path>().is_err());
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn lookup() {
+    let mut value = Value::Record(Box::new([("users".to_string(), Value::Array(vec![
+        Value::Null,
+        Value::Record(Box::new([("name".to_string(), Value::Text("x".into()))].into_iter().collect())),
+    ]))].into_iter().collect()));
+
+    let path: Path = "users[1].name".parse().unwrap();
+    assert_eq!(path.get(&value), Some(&Value::Text("x".into())));
+    *path.get_mut(&mut value).unwrap() = Value::Bool(true);
+    assert_eq!(path.get(&value), Some(&Value::Bool(true)));
+    assert_eq!("users[2]".parse::<Path>().unwrap().get(&value), None);
+    assert_eq!("users.name".parse::<Path>().unwrap().get(&value), None);
+    assert_eq!(Path::default().get(&value), Some(&value));
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn pointer() {
+    let mut value: Value = r#"{"users": [null, {"name": "x"}], "a/b": {"~": 1}, "": [true]}"#.parse().unwrap();
+    assert_eq!(value.pointer(""), Some(&value.clone()));
+    assert_eq!(value.pointer("/users/1/name"), Some(&Value::Text("x".into())));
+    assert_eq!(value.pointer("/a~1b/~0"), Some(&Value::Integer(1)));
+    assert_eq!(value.pointer("//0"), Some(&Value::Bool(true)));
+    for invalid in ["users", "/users/01", "/users/-", "/users/+1", "/a~2b", "/a~", "/users/1/name/x"] {
+        assert_eq!(value.pointer(invalid), None, "{invalid}");
+    }
+    *value.pointer_mut("/users/0").unwrap() = Value::Integer(2);
+    assert_eq!(value.pointer("/users/0"), Some(&Value::Integer(2)));
+
+    let path: Path = r#"["a/b"]["~"]"#.parse().unwrap();
+    assert_eq!(path.to_pointer(), "/a~1b/~0");
+    assert_eq!(value.pointer(&path.to_pointer()), path.get(&value));
+    assert_eq!(Path::default().to_pointer(), "");
+}
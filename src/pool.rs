@@ -0,0 +1,138 @@
+use crate::{Error, Value};
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+impl Value {
+    /// Clears `buf` and serializes into it, keeping its allocation. Returns the serialized bytes.
+    ///
+    /// Fails like [`try_serialize_into`](Self::try_serialize_into).
+    pub fn serialize_reusing<'b>(&self, buf: &'b mut Vec<u8>) -> Result<&'b [u8], Error> {
+        buf.clear();
+        self.try_serialize_into(buf)?;
+        Ok(buf)
+    }
+}
+
+/// Keeps serialization buffers for reuse, so that each message doesn't need a new allocation.
+///
+/// ```rust
+/// let pool = nota::BufferPool::new(8);
+/// let mut buf = pool.get();
+/// nota::Value::Null.serialize_into(&mut buf);
+/// assert_eq!(&buf[..], [0xC2]);
+/// // buf goes back to the pool when dropped
+/// ```
+#[derive(Debug)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    max_buffers: usize,
+    max_capacity: usize,
+}
+
+impl BufferPool {
+    /// Keeps up to `max_buffers` idle buffers. Buffers that grew over 1MB aren't kept.
+    #[must_use]
+    pub fn new(max_buffers: usize) -> Self {
+        Self::with_max_capacity(max_buffers, 1 << 20)
+    }
+
+    /// Buffers with capacity over `max_capacity` are freed instead of kept, so one huge message doesn't pin its memory.
+    #[must_use]
+    pub fn with_max_capacity(max_buffers: usize, max_capacity: usize) -> Self {
+        Self { buffers: Mutex::new(Vec::new()), max_buffers, max_capacity }
+    }
+
+    /// Returns an empty buffer, which goes back to the pool when dropped
+    pub fn get(&self) -> PooledBuffer<'_> {
+        let buf = self.buffers.lock().ok().and_then(|mut b| b.pop()).unwrap_or_default();
+        PooledBuffer { buf, pool: self }
+    }
+
+    /// Number of idle buffers
+    pub fn idle(&self) -> usize {
+        self.buffers.lock().map_or(0, |b| b.len())
+    }
+
+    fn put(&self, mut buf: Vec<u8>) {
+        if buf.capacity() == 0 || buf.capacity() > self.max_capacity {
+            return;
+        }
+        buf.clear();
+        if let Ok(mut buffers) = self.buffers.lock() {
+            if buffers.len() < self.max_buffers {
+                buffers.push(buf);
+            }
+        }
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+/// Buffer from a [`BufferPool`]. Derefs to `Vec<u8>`.
+#[derive(Debug)]
+pub struct PooledBuffer<'p> {
+    buf: Vec<u8>,
+    pool: &'p BufferPool,
+}
+
+impl PooledBuffer<'_> {
+    /// Takes the buffer out of the pool's management
+    #[must_use]
+    pub fn into_inner(mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buf)
+    }
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buf
+    }
+}
+
+impl DerefMut for PooledBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        self.pool.put(std::mem::take(&mut self.buf));
+    }
+}
+
+#[test]
+fn reuse() {
+    let mut buf = Vec::new();
+    assert_eq!(Value::Text("hello".into()).serialize_reusing(&mut buf).unwrap(), [0x25, b'h', b'e', b'l', b'l', b'o']);
+    let ptr = buf.as_ptr();
+    assert_eq!(Value::Null.serialize_reusing(&mut buf).unwrap(), [0xC2]);
+    assert_eq!(buf.as_ptr(), ptr);
+
+    let pool = BufferPool::with_max_capacity(1, 100);
+    let mut a = pool.get();
+    Value::Integer(1).serialize_into(&mut a);
+    let ptr = a.as_ptr();
+    let b = pool.get();
+    drop(a);
+    drop(b);
+    assert_eq!(pool.idle(), 1);
+    let a = pool.get();
+    assert!(a.is_empty());
+    assert_eq!(a.as_ptr(), ptr);
+
+    let mut big = pool.get();
+    big.reserve(1000);
+    drop(big);
+    drop(a);
+    assert_eq!(pool.idle(), 1);
+    let owned = pool.get().into_inner();
+    assert_eq!(owned.as_ptr(), ptr);
+    assert_eq!(pool.idle(), 0);
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// How far parsing or serialization got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Progress {
+    /// Bytes read or written so far
+    pub bytes: u64,
+    /// Values (including all nested values) so far
+    pub values: u64,
+}
+
+/// Function called every 64KB of data, and once at the end.
+///
+/// Set it in [`ParseOptions::progress`](crate::ParseOptions::progress) or [`SerializeOptions::progress`](crate::SerializeOptions::progress).
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+const REPORT_INTERVAL: u64 = 1 << 16;
+
+pub(crate) struct Tracker<'a> {
+    callback: Option<&'a ProgressCallback>,
+    values: u64,
+    next_report: u64,
+}
+
+impl<'a> Tracker<'a> {
+    pub fn new(callback: Option<&'a ProgressCallback>) -> Self {
+        Self {
+            next_report: if callback.is_some() { REPORT_INTERVAL } else { u64::MAX },
+            callback,
+            values: 0,
+        }
+    }
+
+    #[inline]
+    pub fn value(&mut self, bytes: u64) {
+        self.values += 1;
+        if bytes >= self.next_report {
+            self.report(bytes);
+        }
+    }
+
+    #[cold]
+    fn report(&mut self, bytes: u64) {
+        if let Some(callback) = self.callback {
+            (callback.0)(Progress { bytes, values: self.values });
+            self.next_report = bytes + REPORT_INTERVAL;
+        }
+    }
+
+    pub fn finish(&mut self, bytes: u64) {
+        self.report(bytes);
+    }
+}
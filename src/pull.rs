@@ -0,0 +1,390 @@
+//! Event-based parsing of a slice, without allocating
+//!
+//! [`PullParser`] returns one [`Event`] per value, borrowing texts and blobs from the input.
+//! Arrays and records are returned as headers with their length, followed by events for their elements,
+//! so the parser only needs to remember how many elements are left at each level of nesting.
+//! The deepest nesting it can track is set at compile time.
+//!
+//! The parser doesn't need an allocator, but the rest of the crate does, and it requires `std`, so it can't be built for `no_std` targets.
+//!
+//! ```rust
+//! use nota::pull::{Event, PullParser};
+//!
+//! # let mut buf = [0; 8];
+//! # let mut w = nota::SliceWriter::new(&mut buf);
+//! # w.record(1).unwrap(); w.key("led").unwrap(); w.bool(true).unwrap();
+//! # let message = w.written();
+//! let mut parser = PullParser::<8>::new(message);
+//! while let Some(event) = parser.next() {
+//!     match event? {
+//!         Event::Text(key) if key == "led" => {
+//!             let on = parser.next() == Some(Ok(Event::Bool(true)));
+//!         },
+//!         _ => {},
+//!     }
+//! }
+//! # Ok::<_, nota::pull::PullError>(())
+//! ```
+use crate::varint;
+use core::fmt;
+
+/// One value, or a header of an array or record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<'a> {
+    Blob(Bits<'a>),
+    Text(KimStr<'a>),
+    /// The next `len` values are the elements
+    Array(usize),
+    /// The next `len` pairs of [`Event::Text`] and a value are the fields
+    Record(usize),
+    Integer(i128),
+    Bool(bool),
+    Null,
+}
+
+/// Blob borrowed from the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bits<'a> {
+    /// The last byte may have unused padding bits
+    pub bytes: &'a [u8],
+    pub bit_len: usize,
+}
+
+/// Text borrowed from the input, in Nota's kim encoding. Already validated.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct KimStr<'a> {
+    bytes: &'a [u8],
+    chars: usize,
+}
+
+impl<'a> KimStr<'a> {
+    /// Encoded bytes
+    #[must_use]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Number of characters
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.chars
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.chars == 0
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        let mut bytes = self.bytes;
+        core::iter::from_fn(move || {
+            let (c, rest) = kim_char(bytes).ok()?;
+            bytes = rest;
+            Some(c)
+        })
+    }
+}
+
+impl PartialEq<str> for KimStr<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.chars().eq(other.chars())
+    }
+}
+
+impl PartialEq<&str> for KimStr<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl fmt::Display for KimStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chars().try_for_each(|c| fmt::Write::write_char(f, c))
+    }
+}
+
+impl fmt::Debug for KimStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        self.chars().try_for_each(|c| c.escape_debug().try_for_each(|e| fmt::Write::write_char(f, e)))?;
+        f.write_str("\"")
+    }
+}
+
+/// Why [`PullParser`] has stopped. See [`PullParser::offset`] for where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PullError {
+    /// The input ends in the middle of a value
+    UnexpectedEnd,
+    /// Nesting is deeper than the parser's `DEPTH`
+    TooDeep,
+    /// A record key isn't text
+    KeyNotText,
+    /// A length or integer doesn't fit in `usize` or `i128`
+    Overflow,
+    /// Text has a code point that isn't a valid `char`
+    InvalidChar,
+    /// A decimal float or an unknown constant
+    Unsupported,
+}
+
+impl PullError {
+    pub(crate) fn reason(self) -> &'static str {
+        match self {
+            Self::UnexpectedEnd => "unexpected end of data",
+            Self::TooDeep => "nesting is too deep",
+            Self::KeyNotText => "record key isn't text",
+            Self::Overflow => "number is too large",
+            Self::InvalidChar => "invalid character",
+            Self::Unsupported => "unsupported value type",
+        }
+    }
+}
+
+impl fmt::Display for PullError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.reason())
+    }
+}
+
+impl std::error::Error for PullError {}
+
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    /// Elements left. For records it counts keys and values separately.
+    remaining: usize,
+    is_record: bool,
+}
+
+/// Parses one document from a slice, up to `DEPTH` levels deep, as an iterator of [`Event`]s.
+///
+/// After an error or the end of the document, it returns `None`.
+#[derive(Debug, Clone)]
+pub struct PullParser<'a, const DEPTH: usize> {
+    data: &'a [u8],
+    pos: usize,
+    stack: [Level; DEPTH],
+    depth: usize,
+    done: bool,
+}
+
+fn kim_char(bytes: &[u8]) -> Result<(char, &[u8]), PullError> {
+    let mut code = 0u32;
+    for (i, &byte) in bytes.iter().enumerate() {
+        code = code.checked_mul(128).ok_or(PullError::InvalidChar)? | u32::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            let c = char::from_u32(code).ok_or(PullError::InvalidChar)?;
+            return Ok((c, &bytes[i + 1..]));
+        }
+    }
+    Err(PullError::UnexpectedEnd)
+}
+
+impl<'a, const DEPTH: usize> PullParser<'a, DEPTH> {
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0, stack: [Level { remaining: 0, is_record: false }; DEPTH], depth: 0, done: false }
+    }
+
+    /// Where the next event starts, or where the error was
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// Number of arrays and records that the next event is in
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Whole document has been parsed
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.done && self.depth == 0
+    }
+
+    /// Data after the end of the document
+    #[must_use]
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+
+    fn byte(&mut self) -> Result<u8, PullError> {
+        let &byte = self.data.get(self.pos).ok_or(PullError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Continues the number from the `more` bit of the first byte
+    fn continuation(&mut self, mut n: u128, more: bool) -> Result<u128, PullError> {
+        if !more {
+            return Ok(n);
+        }
+        if let Some((groups, len)) = varint::decode8(&self.data[self.pos..]) {
+            if (n.leading_zeros() as usize) < 7 * len {
+                return Err(PullError::Overflow);
+            }
+            self.pos += len;
+            return Ok(n << (7 * len) | u128::from(groups));
+        }
+        loop {
+            let byte = self.byte()?;
+            if n.leading_zeros() < 7 {
+                return Err(PullError::Overflow);
+            }
+            n = n << 7 | u128::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+    }
+
+    fn len(&mut self, preamble: u8) -> Result<usize, PullError> {
+        let len = self.continuation(u128::from(preamble & 0x0F), preamble & 0x10 != 0)?;
+        usize::try_from(len).map_err(|_| PullError::Overflow)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PullError> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.data.len()).ok_or(PullError::UnexpectedEnd)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn event(&mut self) -> Result<Event<'a>, PullError> {
+        let start = self.pos;
+        let preamble = self.byte()?;
+        let expects_key = self.depth > 0 && {
+            let level = self.stack[self.depth - 1];
+            level.is_record && level.remaining.is_multiple_of(2)
+        };
+        if expects_key && preamble & 0b1110_0000 != 0b0010_0000 {
+            self.pos = start;
+            return Err(PullError::KeyNotText);
+        }
+        Ok(match preamble & 0b1110_0000 {
+            0b0000_0000 => {
+                let bit_len = self.len(preamble)?;
+                Event::Blob(Bits { bytes: self.take(bit_len.div_ceil(8))?, bit_len })
+            },
+            0b0010_0000 => {
+                let chars = self.len(preamble)?;
+                let text_start = self.pos;
+                let mut rest = &self.data[text_start..];
+                for _ in 0..chars {
+                    rest = kim_char(rest)?.1;
+                }
+                let bytes = self.take(self.data.len() - text_start - rest.len())?;
+                Event::Text(KimStr { bytes, chars })
+            },
+            header @ (0b0100_0000 | 0b0110_0000) => {
+                let len = self.len(preamble)?;
+                let is_record = header == 0b0110_0000;
+                let remaining = if is_record { len.checked_mul(2).ok_or(PullError::Overflow)? } else { len };
+                if remaining > 0 {
+                    let level = self.stack.get_mut(self.depth).ok_or(PullError::TooDeep)?;
+                    *level = Level { remaining, is_record };
+                    self.depth += 1;
+                    // the header itself doesn't count as an element of the new level
+                    return Ok(if is_record { Event::Record(len) } else { Event::Array(len) });
+                }
+                if is_record { Event::Record(0) } else { Event::Array(0) }
+            },
+            0b1000_0000 => {
+                let n = self.continuation(u128::from(preamble & 0b111), preamble & 0x10 != 0)?;
+                Event::Integer(varint::signed(preamble & 0b1000 != 0, n).map_err(|_| PullError::Overflow)?)
+            },
+            0b1100_0000 => match preamble & 0b1_1111 {
+                0 => Event::Bool(false),
+                1 => Event::Bool(true),
+                2 => Event::Null,
+                _ => return Err(PullError::Unsupported),
+            },
+            _ => return Err(PullError::Unsupported),
+        })
+    }
+
+    /// Counts the value as an element of its parent, and closes finished levels
+    fn finish_value(&mut self) {
+        while self.depth > 0 {
+            let level = &mut self.stack[self.depth - 1];
+            level.remaining -= 1;
+            if level.remaining > 0 {
+                return;
+            }
+            self.depth -= 1;
+        }
+        self.done = true;
+    }
+}
+
+impl<'a, const DEPTH: usize> Iterator for PullParser<'a, DEPTH> {
+    type Item = Result<Event<'a>, PullError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let depth = self.depth;
+        let res = self.event();
+        match &res {
+            Err(_) => self.done = true,
+            // a non-empty array or record has just opened a new level, and is counted when it's finished
+            Ok(Event::Array(_) | Event::Record(_)) if self.depth > depth => {},
+            Ok(_) => self.finish_value(),
+        }
+        Some(res)
+    }
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn events() {
+    use crate::Value;
+
+    let value = Value::Array(vec![
+        Value::Record(Box::new([("ключ".to_string(), Value::Array(vec![]))].into_iter().collect())),
+        Value::Blob([true, false, true].into_iter().collect()),
+        Value::Integer(-1 << 100),
+        Value::Array(vec![Value::Array(vec![Value::Null])]),
+        Value::Bool(true),
+    ]);
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+    bytes.push(0xFF);
+
+    let mut parser = PullParser::<3>::new(&bytes);
+    let events: Vec<_> = parser.by_ref().collect::<Result<_, _>>().unwrap();
+    assert_eq!(events.len(), 10);
+    assert_eq!(events[0], Event::Array(5));
+    assert_eq!(events[1], Event::Record(1));
+    let Event::Text(key) = events[2] else { panic!() };
+    assert_eq!(key, "ключ");
+    assert_eq!(format!("{key:?}"), "\"ключ\"");
+    assert_eq!(events[3], Event::Array(0));
+    assert_eq!(events[4], Event::Blob(Bits { bytes: &[0b1010_0000], bit_len: 3 }));
+    assert_eq!(events[5], Event::Integer(-1 << 100));
+    assert_eq!(events[8], Event::Null);
+    assert_eq!(events[9], Event::Bool(true));
+    assert!(parser.is_finished());
+    assert_eq!(parser.remaining(), [0xFF]);
+
+    let mut parser = PullParser::<2>::new(&bytes);
+    assert_eq!(parser.nth(7), Some(Err(PullError::TooDeep)));
+    assert_eq!(parser.next(), None);
+
+    let mut parser = PullParser::<3>::new(&bytes[..bytes.len() - 3]);
+    assert_eq!(parser.find_map(Result::err), Some(PullError::UnexpectedEnd));
+
+    for n in [i128::MIN, i128::MAX] {
+        let mut bytes = Vec::new();
+        Value::Integer(n).serialize_into(&mut bytes);
+        assert_eq!(PullParser::<1>::new(&bytes).next(), Some(Ok(Event::Integer(n))));
+    }
+
+    let mut parser = PullParser::<1>::new(&[0x61, 0x80, 0xC0]);
+    assert_eq!(parser.nth(1), Some(Err(PullError::KeyNotText)));
+    assert_eq!(parser.offset(), 1);
+}
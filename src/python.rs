@@ -0,0 +1,145 @@
+//! Python bindings: `Value` ↔ Python object conversions, and a `nota` module with `encode` and `decode` functions
+//!
+//! | Nota | Python |
+//! |------|--------|
+//! | Integer | `int` |
+//! | Text | `str` |
+//! | Blob (whole bytes only) | `bytes` |
+//! | Array | `list` (and `tuple` converts to it) |
+//! | Record | `dict` with `str` keys |
+//! | Bool | `bool` |
+//! | Null | `None` |
+//!
+//! `float` and `decimal.Decimal` raise `TypeError`, like other types that Nota doesn't have.
+//! Convert them to `int` or `str` explicitly, depending on the precision that the data needs.
+//!
+//! To build the extension module, add `cdylib` to the crate types and build with `maturin build --features python,pyo3/extension-module`.
+use crate::{Error, ErrorCode, ParseOptions, Value};
+use bitvec::vec::BitVec;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyInt, PyList, PyString, PyTuple};
+use std::collections::HashMap;
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        PyValueError::new_err((err.to_string(), err.code() as u32))
+    }
+}
+
+impl<'py> IntoPyObject<'py> for &Value {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(match self {
+            Value::Null => py.None().into_bound(py),
+            Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+            Value::Integer(i) => i.into_pyobject(py)?.into_any(),
+            Value::Text(s) => PyString::new(py, s).into_any(),
+            Value::Blob(bits) if bits.len() % 8 == 0 => PyBytes::new(py, bits.as_raw_slice()).into_any(),
+            Value::Blob(_) => return Err(Error::Unrepresentable("blob with a partial byte").into()),
+            Value::Array(a) => PyList::new(py, a.iter().map(|v| v.into_pyobject(py)).collect::<PyResult<Vec<_>>>()?)?.into_any(),
+            Value::Record(r) => {
+                let dict = PyDict::new(py);
+                for (k, v) in r.iter() {
+                    dict.set_item(k, v)?;
+                }
+                dict.into_any()
+            },
+            Value::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float").into()),
+            Value::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag").into()),
+        })
+    }
+}
+
+impl<'py> IntoPyObject<'py> for Value {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        (&self).into_pyobject(py)
+    }
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for Value {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        Ok(if obj.is_none() {
+            Self::Null
+        } else if let Ok(b) = obj.cast::<PyBool>() {
+            Self::Bool(b.is_true())
+        } else if obj.is_instance_of::<PyInt>() {
+            Self::Integer(obj.extract()?)
+        } else if let Ok(s) = obj.cast::<PyString>() {
+            Self::Text(s.to_str()?.into())
+        } else if let Ok(b) = obj.cast::<PyBytes>() {
+            Self::Blob(BitVec::from_vec(b.as_bytes().to_vec()))
+        } else if obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>() {
+            Self::Array(obj.try_iter()?.map(|item| item?.extract()).collect::<PyResult<_>>()?)
+        } else if let Ok(dict) = obj.cast::<PyDict>() {
+            Self::Record(Box::new(dict.iter().map(|(k, v)| {
+                let k = k.cast::<PyString>().map_err(|_| PyTypeError::new_err("dict keys must be str"))?.to_str()?.to_owned();
+                Ok((k, v.extract()?))
+            }).collect::<PyResult<HashMap<_, _>>>()?))
+        } else {
+            return Err(PyTypeError::new_err(format!("{} can't be converted to nota", obj.get_type().name()?)));
+        })
+    }
+}
+
+/// Serializes a Python object to Nota bytes
+///
+/// Raises `ValueError(message, code)` like `decode`.
+#[pyfunction]
+fn encode(py: Python<'_>, value: Value) -> PyResult<Bound<'_, PyBytes>> {
+    let mut out = Vec::new();
+    value.try_serialize_into(&mut out).map_err(|e| PyValueError::new_err((e.to_string(), e.code() as u32)))?;
+    Ok(PyBytes::new(py, &out))
+}
+
+/// Parses Nota bytes into a Python object
+///
+/// The data is parsed with [`ParseOptions::untrusted`] limits, so that deeply nested data can't overflow the stack.
+///
+/// Raises `ValueError(message, code)`, where `code` is a number from [`ErrorCode`].
+#[pyfunction]
+fn decode(data: &[u8]) -> PyResult<Value> {
+    Value::parse_slice_with(&mut &data[..], &ParseOptions::untrusted()).map_err(|e| PyValueError::new_err((e.to_string(), ErrorCode::from_io(&e) as u32)))
+}
+
+#[pymodule]
+fn nota(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(encode, m)?)?;
+    m.add_function(wrap_pyfunction!(decode, m)?)?;
+    Ok(())
+}
+
+#[test]
+fn roundtrip() {
+    Python::initialize();
+    Python::attach(|py| {
+        let obj = py.eval(pyo3::ffi::c_str!("{'a': [1, -2**100, True, None, b'\\x01', 'x', (2,)], 'f': '0.5'}"), None, None).unwrap();
+        let value: Value = obj.extract().unwrap();
+        let Value::Record(r) = &value else { panic!() };
+        assert_eq!(r["f"], Value::Text("0.5".into()));
+        let float = py.eval(pyo3::ffi::c_str!("[0.5]"), None, None).unwrap();
+        assert!(float.extract::<Value>().unwrap_err().is_instance_of::<PyTypeError>(py));
+        let Value::Array(a) = &r["a"] else { panic!() };
+        assert_eq!(a[1], Value::Integer(-(1 << 100)));
+        assert_eq!(a[2], Value::Bool(true));
+        assert_eq!(a[6], Value::Array(vec![Value::Integer(2)]));
+
+        let bytes = encode(py, value.clone()).unwrap();
+        let back = decode(bytes.as_bytes()).unwrap();
+        assert_eq!(back, value);
+        let mut nested = vec![0x41; 100_000];
+        nested.push(0xC2);
+        assert!(decode(&nested).unwrap_err().is_instance_of::<PyValueError>(py));
+        let obj2 = back.into_pyobject(py).unwrap();
+        assert_eq!(obj2.extract::<Value>().unwrap(), value);
+    });
+}
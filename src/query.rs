@@ -0,0 +1,378 @@
+//! Queries in a small subset of [jq](https://jqlang.org), compiled once and run on any number of documents
+//!
+//! * `.` is the input itself
+//! * `.name`, `."any key"` or `.["any key"]` is a record field
+//! * `.[3]` is an array element, and `.[-1]` counts from the end
+//! * `.[1:3]` or `.[2:]` is a slice of an array, as a new array
+//! * `.[]` is every element of an array, or every field of a record, in order of keys
+//! * `..` is the input and everything in it
+//! * `select(.path)` keeps inputs where the path has a value that isn't `null` or `false`, and
+//!   `select(.path == literal)` where it compares as asked. The operators are `==`, `!=`, `<`, `<=`, `>`, `>=`,
+//!   using [`Value::total_cmp`]. Literals are integers, `true`, `false`, `null`, or texts quoted with Rust's escapes.
+//! * `a | b` runs `b` on every result of `a`
+//!
+//! Steps can be chained, like `.users[].name`. The leading `.` is optional, so every [path](crate::path) is a query too.
+//! Unlike jq, lookups that don't match produce nothing, rather than `null` or an error.
+//!
+//! ```rust
+//! use nota::query::Query;
+//! use nota::Value;
+//!
+//! let query: Query = ".[] | select(.[0] >= 2) | .[1]".parse()?;
+//! let doc: Value = r#"[[1, "a"], [2, "b"], [3, "c"]]"#.parse()?;
+//! let found: Vec<_> = query.run(&doc).iter().map(|v| v.to_string()).collect();
+//! assert_eq!(found, [r#""b""#, r#""c""#]);
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+use crate::path::unquote;
+use crate::Value;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// A compiled query. Parse it from text.
+#[derive(Debug, Clone)]
+pub struct Query {
+    source: String,
+    steps: Vec<Step>,
+}
+
+/// Why a [`Query`] couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QueryError {
+    /// Byte offset in the query text
+    pub offset: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query at {}: {}", self.offset, self.reason)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone)]
+enum Step {
+    #[cfg_attr(not(feature = "records"), allow(dead_code))]
+    Key(String),
+    Index(isize),
+    Slice(Option<isize>, Option<isize>),
+    Each,
+    Recurse,
+    Select(Box<Condition>),
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    steps: Vec<Step>,
+    compare: Option<(Compare, Value)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Query {
+    /// All results, in order. Results that are parts of the input are borrowed, and new values (slices) are owned.
+    #[must_use]
+    pub fn run<'a>(&self, value: &'a Value) -> Vec<Cow<'a, Value>> {
+        run_steps(&self.steps, value)
+    }
+
+    /// The first result, if there is any
+    #[must_use]
+    pub fn first<'a>(&self, value: &'a Value) -> Option<Cow<'a, Value>> {
+        self.run(value).into_iter().next()
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn run_steps<'a>(steps: &[Step], value: &'a Value) -> Vec<Cow<'a, Value>> {
+    let mut values = vec![Cow::Borrowed(value)];
+    for step in steps {
+        let mut next = Vec::new();
+        for value in values {
+            match value {
+                Cow::Borrowed(value) => step.apply(value, &mut next),
+                Cow::Owned(value) => {
+                    let mut out = Vec::new();
+                    step.apply(&value, &mut out);
+                    next.extend(out.into_iter().map(|v| Cow::Owned(v.into_owned())));
+                },
+            }
+        }
+        values = next;
+    }
+    values
+}
+
+impl Step {
+    fn apply<'a>(&self, value: &'a Value, out: &mut Vec<Cow<'a, Value>>) {
+        match (self, value) {
+            #[cfg(feature = "records")]
+            (Self::Key(key), Value::Record(fields)) => out.extend(fields.get(key).map(Cow::Borrowed)),
+            (&Self::Index(i), Value::Array(items)) => {
+                let i = if i < 0 { items.len().checked_sub(i.unsigned_abs()) } else { Some(i.unsigned_abs()) };
+                out.extend(i.and_then(|i| items.get(i)).map(Cow::Borrowed));
+            },
+            (&Self::Slice(start, end), Value::Array(items)) => {
+                let clamp = |i: isize| if i < 0 { items.len().saturating_sub(i.unsigned_abs()) } else { i.unsigned_abs().min(items.len()) };
+                let start = start.map_or(0, clamp);
+                let end = end.map_or(items.len(), clamp).max(start);
+                out.push(Cow::Owned(Value::Array(items[start..end].to_vec())));
+            },
+            (Self::Each, Value::Array(items)) => out.extend(items.iter().map(Cow::Borrowed)),
+            #[cfg(feature = "records")]
+            (Self::Each, Value::Record(fields)) => {
+                let mut sorted: Vec<_> = fields.iter().collect();
+                sorted.sort_unstable_by_key(|(key, _)| *key);
+                out.extend(sorted.into_iter().map(|(_, field)| Cow::Borrowed(field)));
+            },
+            (Self::Recurse, _) => {
+                out.push(Cow::Borrowed(value));
+                let mut children = Vec::new();
+                Self::Each.apply(value, &mut children);
+                for child in children {
+                    if let Cow::Borrowed(child) = child {
+                        Self::Recurse.apply(child, out);
+                    }
+                }
+            },
+            (Self::Select(condition), _) if condition.matches(value) => out.push(Cow::Borrowed(value)),
+            _ => {},
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, value: &Value) -> bool {
+        run_steps(&self.steps, value).iter().any(|found| match &self.compare {
+            None => !matches!(**found, Value::Null | Value::Bool(false)),
+            Some((compare, literal)) => {
+                let ordering = found.total_cmp(literal);
+                match compare {
+                    Compare::Eq => ordering.is_eq(),
+                    Compare::Ne => ordering.is_ne(),
+                    Compare::Lt => ordering.is_lt(),
+                    Compare::Le => ordering.is_le(),
+                    Compare::Gt => ordering.is_gt(),
+                    Compare::Ge => ordering.is_ge(),
+                }
+            },
+        })
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, QueryError> {
+        let mut parser = QueryParser { s, rest: s };
+        let steps = parser.pipeline()?;
+        if !parser.rest.is_empty() {
+            return Err(parser.err("expected `|`"));
+        }
+        Ok(Self { source: s.into(), steps })
+    }
+}
+
+struct QueryParser<'s> {
+    s: &'s str,
+    rest: &'s str,
+}
+
+impl<'s> QueryParser<'s> {
+    fn err(&self, reason: &'static str) -> QueryError {
+        QueryError { offset: self.s.len() - self.rest.len(), reason }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str, reason: &'static str) -> Result<(), QueryError> {
+        if self.eat(token) { Ok(()) } else { Err(self.err(reason)) }
+    }
+
+    fn pipeline(&mut self) -> Result<Vec<Step>, QueryError> {
+        let mut steps = Vec::new();
+        loop {
+            self.term(&mut steps)?;
+            if !self.eat("|") {
+                self.skip_whitespace();
+                return Ok(steps);
+            }
+        }
+    }
+
+    fn term(&mut self, steps: &mut Vec<Step>) -> Result<(), QueryError> {
+        if self.eat("select(") {
+            let mut condition = Condition { steps: Vec::new(), compare: None };
+            self.term(&mut condition.steps)?;
+            if let Some(compare) = self.compare() {
+                condition.compare = Some((compare, self.literal()?));
+            }
+            self.expect(")", "expected `)`")?;
+            steps.push(Step::Select(Box::new(condition)));
+        } else if self.eat("..") {
+            steps.push(Step::Recurse);
+        } else if self.eat(".") {
+            self.step(steps)?;
+        } else if !self.step(steps)? {
+            return Err(self.err("expected `.`"));
+        }
+        loop {
+            if self.rest.starts_with('[') {
+                self.step(steps)?;
+            } else if let Some(after) = self.rest.strip_prefix('.').filter(|after| after.starts_with(['"', '[']) || starts_with_key(after)) {
+                self.rest = after;
+                self.step(steps)?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// A key or brackets right at the start, `false` if there's none
+    fn step(&mut self, steps: &mut Vec<Step>) -> Result<bool, QueryError> {
+        if let Some(quoted) = self.rest.strip_prefix('"') {
+            steps.push(Step::Key(self.quoted(quoted)?));
+        } else if let Some(inner) = self.rest.strip_prefix('[') {
+            self.rest = inner;
+            steps.push(self.brackets()?);
+        } else if starts_with_key(self.rest) {
+            let end = self.rest.find(|c: char| c != '_' && !c.is_alphanumeric()).unwrap_or(self.rest.len());
+            steps.push(Step::Key(self.rest[..end].into()));
+            self.rest = &self.rest[end..];
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// After the `[`
+    fn brackets(&mut self) -> Result<Step, QueryError> {
+        if self.eat("]") {
+            return Ok(Step::Each);
+        }
+        if let Some(quoted) = self.rest.strip_prefix('"') {
+            let key = self.quoted(quoted)?;
+            self.expect("]", "expected `]`")?;
+            return Ok(Step::Key(key));
+        }
+        let start = self.index()?;
+        let step = if self.eat(":") {
+            Step::Slice(start, self.index()?)
+        } else {
+            Step::Index(start.ok_or_else(|| self.err("expected an array index, a slice or a quoted key"))?)
+        };
+        self.expect("]", "expected `]`")?;
+        Ok(step)
+    }
+
+    fn index(&mut self) -> Result<Option<isize>, QueryError> {
+        self.skip_whitespace();
+        let digits = self.rest.strip_prefix('-').unwrap_or(self.rest);
+        let end = self.rest.len() - digits.len() + digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+        if end == self.rest.len() - digits.len() {
+            return Ok(None);
+        }
+        let index = self.rest[..end].parse().map_err(|_| self.err("array index is too large"))?;
+        self.rest = &self.rest[end..];
+        Ok(Some(index))
+    }
+
+    /// After the opening `"`
+    fn quoted(&mut self, quoted: &'s str) -> Result<String, QueryError> {
+        self.rest = quoted;
+        let (key, after) = unquote(quoted).map_err(|reason| self.err(reason))?;
+        self.rest = after;
+        Ok(key)
+    }
+
+    fn compare(&mut self) -> Option<Compare> {
+        [("==", Compare::Eq), ("!=", Compare::Ne), ("<=", Compare::Le), (">=", Compare::Ge), ("<", Compare::Lt), (">", Compare::Gt)]
+            .into_iter()
+            .find(|&(token, _)| self.eat(token))
+            .map(|(_, compare)| compare)
+    }
+
+    fn literal(&mut self) -> Result<Value, QueryError> {
+        self.skip_whitespace();
+        if let Some(quoted) = self.rest.strip_prefix('"') {
+            return self.quoted(quoted).map(Value::Text);
+        }
+        for (token, value) in [("null", Value::Null), ("true", Value::Bool(true)), ("false", Value::Bool(false))] {
+            if self.eat(token) {
+                return Ok(value);
+            }
+        }
+        let digits = self.rest.strip_prefix('-').unwrap_or(self.rest);
+        let end = self.rest.len() - digits.len() + digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+        let n = self.rest[..end].parse().map_err(|_| self.err("expected an integer, a quoted text, `true`, `false` or `null`"))?;
+        self.rest = &self.rest[end..];
+        Ok(Value::Integer(n))
+    }
+}
+
+fn starts_with_key(s: &str) -> bool {
+    s.starts_with(|c: char| c == '_' || c.is_alphanumeric())
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn queries() {
+    let doc: Value = r#"{"users": [{"name": "a", "age": 30, "tags": ["x"]}, {"name": "b", "age": 41}, {"name": "c", "admin": true}], "first name": "d"}"#.parse().unwrap();
+    let run = |query: &str| {
+        let query: Query = query.parse().unwrap();
+        query.run(&doc).iter().map(ToString::to_string).collect::<Vec<_>>().join(" ")
+    };
+    assert_eq!(run("."), doc.to_string());
+    assert_eq!(run(".users[1].name"), r#""b""#);
+    assert_eq!(run("users[1].name"), r#""b""#);
+    assert_eq!(run(r#"."first name""#), r#""d""#);
+    assert_eq!(run(r#".["first name"]"#), r#""d""#);
+    assert_eq!(run(".users[-1].name"), r#""c""#);
+    assert_eq!(run(".users[-4]"), "");
+    assert_eq!(run(".users[].name"), r#""a" "b" "c""#);
+    assert_eq!(run(".users | .[] | .name"), r#""a" "b" "c""#);
+    assert_eq!(run(".users[1:][].name"), r#""b" "c""#);
+    assert_eq!(run(".users[:-2] | .[].name"), r#""a""#);
+    assert_eq!(run(".users[5:1]"), "[]");
+    assert_eq!(run(".users[] | select(.age > 30) | .name"), r#""b""#);
+    assert_eq!(run(r#".users[] | select(.name != "a").name"#), r#""b" "c""#);
+    assert_eq!(run(".users[] | select(.admin) | .name"), r#""c""#);
+    assert_eq!(run(".users[] | select(.tags[]) | .name"), r#""a""#);
+    assert_eq!(run(r#".. | select(. == "x")"#), r#""x""#);
+    assert_eq!("..".parse::<Query>().unwrap().run(&doc).len(), 14);
+    assert_eq!(run(".nope.deeper"), "");
+
+    for (invalid, offset) in [("", 0), (".a b", 3), (".[x]", 2), (".a[1", 4), ("select(.a == x)", 13), (".a |", 4)] {
+        assert_eq!(invalid.parse::<Query>().unwrap_err().offset, offset, "{invalid}");
+    }
+}
@@ -0,0 +1,184 @@
+//! Using all CPU cores via [`rayon`] for documents that are one huge array
+//!
+//! Elements of an array are encoded independently, so chunks of them can be serialized into separate buffers and concatenated.
+//! When parsing, a quick pass finds where each element starts, and then the elements are decoded in parallel.
+use crate::slice_parser::SliceParser;
+use crate::{serialize_unsigned_preamble, unsigned_preamble_size, varint, Error, ParseOptions, SerializeOptions, Value};
+use rayon::prelude::*;
+use std::io;
+use std::ops::Range;
+
+/// Arrays shorter than this aren't worth splitting
+const MIN_CHUNK: usize = 256;
+
+impl Value {
+    /// Same output as [`serialize_into`](Self::serialize_into), but elements of a top-level array are serialized in parallel.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`serialize_into`](Self::serialize_into)
+    pub fn par_serialize_into(&self, into: &mut Vec<u8>) {
+        self.par_serialize_with(into, &SerializeOptions::default());
+    }
+
+    /// # Panics
+    ///
+    /// Same as [`serialize_into`](Self::serialize_into)
+    pub fn par_serialize_with(&self, into: &mut Vec<u8>, options: &SerializeOptions) {
+        if let Err(err) = self.try_par_serialize_with(into, options) {
+            panic!("{err}");
+        }
+    }
+
+    /// Fails like [`try_serialize_into`](Self::try_serialize_into). Nothing is appended then.
+    pub fn try_par_serialize_into(&self, into: &mut Vec<u8>) -> Result<(), Error> {
+        self.try_par_serialize_with(into, &SerializeOptions::default())
+    }
+
+    /// The `progress` callback isn't called, because chunks don't finish in order
+    pub fn try_par_serialize_with(&self, into: &mut Vec<u8>, options: &SerializeOptions) -> Result<(), Error> {
+        let Value::Array(items) = self else {
+            return self.try_serialize_with(into, options);
+        };
+        if items.len() < MIN_CHUNK * 2 || options.backrefs {
+            return self.try_serialize_with(into, options);
+        }
+        let options = SerializeOptions { progress: None, ..options.clone() };
+        let chunk = items.len().div_ceil(rayon::current_num_threads() * 4).max(MIN_CHUNK);
+        let chunks = items.par_chunks(chunk).map(|items| {
+            let mut out = Vec::new();
+            for item in items {
+                item.try_serialize_with(&mut out, &options)?;
+            }
+            Ok(out)
+        }).collect::<Result<Vec<_>, Error>>()?;
+        serialize_unsigned_preamble(0b0100_0000, items.len() as u128, into);
+        into.reserve(chunks.iter().map(Vec::len).sum());
+        for chunk in chunks {
+            into.extend_from_slice(&chunk);
+        }
+        Ok(())
+    }
+
+    /// Same as [`parse_slice`](Self::parse_slice), but elements of a top-level array are parsed in parallel
+    pub fn par_parse_slice(data: &mut &[u8]) -> Result<Self, io::Error> {
+        Self::par_parse_slice_with(data, &ParseOptions::default())
+    }
+
+    /// The `progress` callback isn't called, because elements don't finish in order
+    pub fn par_parse_slice_with(data: &mut &[u8], options: &ParseOptions) -> Result<Self, io::Error> {
+        // back-references can point to texts in earlier elements
+        let Some(&preamble) = data.first().filter(|&&p| p & 0b1110_0000 == 0b0100_0000 && !options.backrefs) else {
+            return Self::parse_slice_with(data, options);
+        };
+        options.check_depth(1)?;
+        let mut pos = 1;
+        let len = read_len(data, &mut pos, preamble)?;
+        options.check_len(len)?;
+        if len < MIN_CHUNK * 2 {
+            return Self::parse_slice_with(data, options);
+        }
+        options.check_minimal(pos == unsigned_preamble_size(len as u128, 4))?;
+        let ranges = element_ranges(data, &mut pos, len)?;
+        // the elements are parsed separately, and only know their own sizes
+        options.check_size(pos as u64)?;
+        let options = ParseOptions { progress: None, ..options.clone() };
+        let items = ranges.into_par_iter().map(|range| {
+            let (value, _) = SliceParser::new(&data[range], &options).at_depth(1).parse()?;
+            Ok(value)
+        }).collect::<Result<Vec<_>, io::Error>>()?;
+        *data = &data[pos..];
+        Ok(Value::Array(items))
+    }
+}
+
+/// Finds `len` values starting at `pos`, without decoding them, and moves `pos` past them.
+/// The values are only checked enough to find their ends.
+fn element_ranges(data: &[u8], pos: &mut usize, len: usize) -> Result<Vec<Range<usize>>, io::Error> {
+    let mut ranges = Vec::with_capacity(len.min(data.len()));
+    for _ in 0..len {
+        let start = *pos;
+        skip_value(data, pos)?;
+        ranges.push(start..*pos);
+    }
+    Ok(ranges)
+}
+
+fn skip_value(data: &[u8], pos: &mut usize) -> Result<(), io::Error> {
+    // values in nested arrays and records don't need a stack, only a count
+    let mut pending = 1usize;
+    while pending > 0 {
+        pending -= 1;
+        let preamble = byte(data, pos)?;
+        match preamble & 0b1110_0000 {
+            0b0000_0000 => {
+                let bits = read_len(data, pos, preamble)?;
+                *pos = pos.checked_add(bits.div_ceil(8)).filter(|&end| end <= data.len()).ok_or(io::ErrorKind::UnexpectedEof)?;
+            },
+            0b0010_0000 => {
+                // every char ends with a byte without the top bit
+                for _ in 0..read_len(data, pos, preamble)? {
+                    while byte(data, pos)? & 0b1000_0000 != 0 {}
+                }
+            },
+            0b0100_0000 => pending = pending.checked_add(read_len(data, pos, preamble)?).ok_or(io::ErrorKind::InvalidData)?,
+            0b0110_0000 => {
+                let fields = read_len(data, pos, preamble)?.checked_mul(2).ok_or(io::ErrorKind::InvalidData)?;
+                pending = pending.checked_add(fields).ok_or(io::ErrorKind::InvalidData)?;
+            },
+            0b1000_0000 if preamble & 0b0001_0000 != 0 => {
+                while byte(data, pos)? & 0b1000_0000 != 0 {}
+            },
+            // the rest is one byte, or is invalid and will fail when parsed properly
+            _ => {},
+        }
+    }
+    Ok(())
+}
+
+#[inline]
+fn byte(data: &[u8], pos: &mut usize) -> Result<u8, io::Error> {
+    let &byte = data.get(*pos).ok_or(io::ErrorKind::UnexpectedEof)?;
+    *pos += 1;
+    Ok(byte)
+}
+
+fn read_len(data: &[u8], pos: &mut usize, preamble: u8) -> Result<usize, io::Error> {
+    let mut len = usize::from(preamble & 0b000_1111);
+    if preamble & 0b0001_0000 != 0 {
+        if let Some((groups, bytes)) = varint::decode8(&data[*pos..]) {
+            *pos += bytes;
+            len = varint::join_len(len, groups, bytes)?;
+        } else {
+            loop {
+                let next = byte(data, pos)?;
+                len = varint::push_len(len, next)?;
+                if next & 0b1000_0000 == 0 {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(len)
+}
+
+#[test]
+fn same_as_sequential() {
+    for len in [0, 10, 1000, 5000] {
+        let value = Value::Array((0..len).map(|n| if n % 3 == 0 { Value::Text(n.to_string()) } else { Value::Integer(n * 1000) }).collect());
+        let mut expected = Vec::new();
+        value.serialize_into(&mut expected);
+        let mut out = vec![0xC2];
+        value.par_serialize_into(&mut out);
+        assert_eq!(out[1..], expected);
+
+        expected.push(0xC2);
+        let mut rest = &expected[..];
+        assert_eq!(Value::par_parse_slice(&mut rest).unwrap(), value);
+        assert_eq!(rest, [0xC2]);
+        for cut in [1, 100, expected.len() / 2, expected.len() - 2] {
+            let err = Value::par_parse_slice(&mut &expected[..cut.min(expected.len() - 2)]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Reading logs of values that may have damaged parts
+//!
+//! A log that is only appended to can end with a truncated value after a crash, or have a few corrupted bytes in the middle.
+//! [`Recover`] reads all the values it can, reports each damaged part once, and continues from the next place where
+//! values can be read again, instead of giving up on the rest of the log.
+//!
+//! Without framing, the next value is found by trying every byte after the damage. A random byte is often a valid value,
+//! so it's required that the value after it can be read too (this can't find a single good value between
+//! damage and a truncated end of the log). [`Frames`](Recover::frames) with a [checksum](crate::framing::Checksum)
+//! recover much more reliably.
+//!
+//! ```rust
+//! use nota::recover::Recover;
+//! # let mut log = Vec::new();
+//! # for n in 0..3 { nota::Value::Integer(n).serialize_into(&mut log); }
+//!
+//! for entry in Recover::values(&log, nota::ParseOptions::default()) {
+//!     match entry {
+//!         Ok((offset, value)) => println!("{offset}: {value}"),
+//!         Err(damaged) => eprintln!("skipped {} bytes at {}: {}", damaged.len, damaged.offset, damaged.error),
+//!     }
+//! }
+//! ```
+use crate::framing::{read_frame_bytes, Checksum, FrameOptions};
+use crate::{ParseOptions, Value};
+use std::fmt;
+use std::io;
+
+/// A part of the log that couldn't be read, and has been skipped
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Damaged {
+    /// Byte offset where reading has failed
+    pub offset: usize,
+    /// Number of bytes skipped
+    pub len: usize,
+    /// Why reading at `offset` has failed
+    pub error: io::Error,
+}
+
+impl fmt::Display for Damaged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} damaged bytes at {}: {}", self.len, self.offset, self.error)
+    }
+}
+
+impl std::error::Error for Damaged {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Iterator of values in a log, with their byte offsets, and the damaged parts between them
+#[derive(Debug)]
+pub struct Recover<'a> {
+    data: &'a [u8],
+    pos: usize,
+    format: Format,
+}
+
+#[derive(Debug)]
+enum Format {
+    Values(ParseOptions),
+    Frames(FrameOptions),
+}
+
+impl<'a> Recover<'a> {
+    /// For values written one after another, without framing
+    #[must_use]
+    pub fn values(data: &'a [u8], options: ParseOptions) -> Self {
+        Self { data, pos: 0, format: Format::Values(options) }
+    }
+
+    /// For values written with [`write_framed_with`](crate::framing::write_framed_with)
+    #[must_use]
+    pub fn frames(data: &'a [u8], options: FrameOptions) -> Self {
+        Self { data, pos: 0, format: Format::Frames(options) }
+    }
+
+    /// Reads one entry at `pos`, and returns how many bytes it took
+    fn read_at(&self, pos: usize) -> Result<(Value, usize), io::Error> {
+        let mut rest = &self.data[pos..];
+        match &self.format {
+            Format::Values(options) => {
+                let value = Value::parse_slice_with(&mut rest, options)?;
+                Ok((value, self.data.len() - pos - rest.len()))
+            },
+            Format::Frames(options) => {
+                let frame = read_frame_bytes(&mut rest, options.max_frame_size)?.ok_or(io::ErrorKind::UnexpectedEof)?;
+                let mut body = options.checksum.verify(&frame)?;
+                let value = Value::parse_slice_with(&mut body, &options.parse)?;
+                if !body.is_empty() {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "trailing bytes in frame"));
+                }
+                Ok((value, self.data.len() - pos - rest.len()))
+            },
+        }
+    }
+
+    /// An entry can be read at `pos`, and it's either checksummed, the last one, or followed by another readable entry
+    fn plausible(&self, pos: usize) -> bool {
+        let checksummed = matches!(&self.format, Format::Frames(o) if o.checksum != Checksum::None);
+        self.read_at(pos).is_ok_and(|(_, len)| checksummed || pos + len == self.data.len() || self.read_at(pos + len).is_ok())
+    }
+}
+
+impl Iterator for Recover<'_> {
+    type Item = Result<(usize, Value), Damaged>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let offset = self.pos;
+        match self.read_at(offset) {
+            Ok((value, len)) => {
+                self.pos += len;
+                Some(Ok((offset, value)))
+            },
+            Err(error) => {
+                self.pos = (offset + 1..self.data.len()).find(|&pos| self.plausible(pos)).unwrap_or(self.data.len());
+                Some(Err(Damaged { offset, len: self.pos - offset, error }))
+            },
+        }
+    }
+}
+
+#[test]
+fn skips_damage() {
+    use crate::framing::write_framed_with;
+
+    let values: Vec<_> = (0..5).map(|n| Value::Text(format!("entry number {n}"))).collect();
+    let mut log = Vec::new();
+    for value in &values {
+        value.serialize_into(&mut log);
+    }
+    let entry_len = log.len() / 5;
+    log[entry_len * 2] = 0xFF;
+    let entries: Vec<_> = Recover::values(&log, ParseOptions::default()).collect();
+    assert_eq!(entries.len(), 5);
+    assert_eq!(entries[1].as_ref().unwrap(), &(entry_len, values[1].clone()));
+    assert_eq!(entries[2].as_ref().unwrap_err().offset, entry_len * 2);
+    assert_eq!(entries[2].as_ref().unwrap_err().len, entry_len);
+    assert_eq!(entries[3].as_ref().unwrap(), &(entry_len * 3, values[3].clone()));
+    assert_eq!(entries[4].as_ref().unwrap(), &(entry_len * 4, values[4].clone()));
+
+    // the last entry cut short
+    log[entry_len * 2] = 0x2E;
+    log.truncate(log.len() - 3);
+    let entries: Vec<_> = Recover::values(&log, ParseOptions::default()).collect();
+    assert_eq!(entries.len(), 5);
+    assert!(entries[..4].iter().all(Result::is_ok));
+    assert_eq!(entries[4].as_ref().unwrap_err().len, entry_len - 3);
+
+    let options = FrameOptions { checksum: Checksum::Crc32, ..FrameOptions::default() };
+    let mut log = Vec::new();
+    for value in &values {
+        write_framed_with(&mut log, value, &options).unwrap();
+    }
+    let entry_len = log.len() / 5;
+    log[entry_len + 5] ^= 1;
+    let entries: Vec<_> = Recover::frames(&log, options).collect();
+    assert_eq!(entries.len(), 5);
+    assert!(entries[1].is_err());
+    assert_eq!(entries.iter().filter_map(|e| e.as_ref().ok()).map(|(_, v)| v).collect::<Vec<_>>(), [&values[0], &values[2], &values[3], &values[4]]);
+}
@@ -0,0 +1,121 @@
+use crate::slice_parser::SliceParser;
+use crate::{ParseOptions, Value};
+#[cfg(feature = "records")]
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+/// Keeps the allocations of parsed values for parsing the next ones.
+///
+/// When decoding many messages of a similar shape, give each value back with [`recycle`](Self::recycle) when done with it,
+/// and its `String`s, `Vec`s, and `HashMap`s will be reused by [`parse_slice`](Self::parse_slice) instead of allocating new ones.
+///
+/// ```rust
+/// # let messages = [&[0x41, 0x23, b'c', b'a', b't'][..], &[0x41, 0x23, b'd', b'o', b'g']];
+/// let mut recycler = nota::Recycler::new();
+/// for mut message in messages {
+///     let value = recycler.parse_slice(&mut message)?;
+///     // use the value
+///     recycler.recycle(value);
+/// }
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Default)]
+pub struct Recycler {
+    pub(crate) strings: Vec<String>,
+    pub(crate) arrays: Vec<Vec<Value>>,
+    /// Boxed like in `Value`, to reuse the box too
+    #[cfg(feature = "records")]
+    #[allow(clippy::vec_box)]
+    pub(crate) records: Vec<Box<HashMap<String, Value>>>,
+    #[cfg(feature = "blobs")]
+    pub(crate) bytes: Vec<Vec<u8>>,
+}
+
+impl Recycler {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Same as [`Value::parse_slice`], but reuses allocations of recycled values
+    pub fn parse_slice(&mut self, data: &mut &[u8]) -> Result<Value, io::Error> {
+        self.parse_slice_with(data, &ParseOptions::default())
+    }
+
+    pub fn parse_slice_with(&mut self, data: &mut &[u8], options: &ParseOptions) -> Result<Value, io::Error> {
+        let (value, len) = SliceParser::new(data, options).with_recycler(self).parse()?;
+        *data = &data[len..];
+        Ok(value)
+    }
+
+    /// Takes the value apart, and keeps its allocations
+    pub fn recycle(&mut self, value: Value) {
+        match value {
+            #[cfg(feature = "blobs")]
+            Value::Blob(bits) => {
+                let mut bytes = bits.into_vec();
+                bytes.clear();
+                self.bytes.push(bytes);
+            },
+            Value::Text(mut text) => {
+                text.clear();
+                self.strings.push(text);
+            },
+            Value::Array(mut items) => {
+                for item in items.drain(..) {
+                    self.recycle(item);
+                }
+                self.arrays.push(items);
+            },
+            #[cfg(feature = "records")]
+            Value::Record(mut fields) => {
+                for (mut k, v) in fields.drain() {
+                    k.clear();
+                    self.strings.push(k);
+                    self.recycle(v);
+                }
+                self.records.push(fields);
+            },
+            _ => {},
+        }
+    }
+}
+
+impl fmt::Debug for Recycler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recycler")
+            .field("strings", &self.strings.len())
+            .field("arrays", &self.arrays.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn reuses_allocations() {
+    let row = |name: &str, tags: &[&str]| Value::Record(Box::new([
+        ("name".to_string(), Value::Text(name.into())),
+        ("tags".to_string(), Value::Array(tags.iter().map(|&t| Value::Text(t.into())).collect())),
+    ].into_iter().collect()));
+    let mut first = Vec::new();
+    Value::Array(vec![row("first", &["a", "b"])]).serialize_into(&mut first);
+    let mut second = Vec::new();
+    let expected = Value::Array(vec![row("second", &["c"]), row("third", &[])]);
+    expected.serialize_into(&mut second);
+
+    let mut recycler = Recycler::new();
+    let value = recycler.parse_slice(&mut &first[..]).unwrap();
+    let Value::Array(rows) = &value else { panic!() };
+    let outer = rows.as_ptr();
+    recycler.recycle(value);
+    assert_eq!(recycler.strings.len(), 5);
+    assert_eq!(recycler.arrays.len(), 2);
+    assert_eq!(recycler.records.len(), 1);
+
+    let value = recycler.parse_slice(&mut &second[..]).unwrap();
+    assert_eq!(value, expected);
+    let Value::Array(rows) = &value else { panic!() };
+    assert_eq!(rows.as_ptr(), outer);
+    assert!(recycler.strings.is_empty() && recycler.arrays.is_empty() && recycler.records.is_empty());
+}
@@ -0,0 +1,53 @@
+//! Using values as Redis arguments and replies with [`redis`]
+//!
+//! Values are sent as Nota bytes in bulk strings, and parsed with [`ParseOptions::untrusted`] limits when read back.
+//! Arguments can't fail in `redis`, so values are serialized up front with [`Encoded::try_from`].
+//!
+//! ```rust,ignore
+//! con.set("key", Encoded::try_from(&value)?)?;
+//! let value: Value = con.get("key")?;
+//! ```
+use crate::{Error, ParseOptions, Value};
+use redis::{FromRedisValue, ParsingError, RedisWrite, ToRedisArgs, ToSingleRedisArg};
+
+/// A serialized value, to be sent as an argument
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encoded(pub Vec<u8>);
+
+impl TryFrom<&Value> for Encoded {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        let mut bytes = Vec::new();
+        value.try_serialize_into(&mut bytes)?;
+        Ok(Self(bytes))
+    }
+}
+
+impl ToRedisArgs for Encoded {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        out.write_arg(&self.0);
+    }
+}
+
+impl ToSingleRedisArg for Encoded {}
+
+impl FromRedisValue for Value {
+    fn from_redis_value(v: redis::Value) -> Result<Self, ParsingError> {
+        let bytes = Vec::<u8>::from_redis_value(v)?;
+        Self::parse_slice_with(&mut &bytes[..], &ParseOptions::untrusted()).map_err(|e| e.to_string().into())
+    }
+}
+
+#[test]
+fn args_and_replies() {
+    let value = Value::Array(vec![Value::Text("k".into()), Value::Integer(1 << 70)]);
+    let args = Encoded::try_from(&value).unwrap().to_redis_args();
+    assert_eq!(args.len(), 1);
+    let back = Value::from_redis_value(redis::Value::BulkString(args[0].clone())).unwrap();
+    assert_eq!(back, value);
+    assert!(Value::from_redis_value(redis::Value::Nil).is_err());
+    let mut nested = vec![0x41; 100_000];
+    nested.push(0xC2);
+    assert!(Value::from_redis_value(redis::Value::BulkString(nested)).is_err());
+}
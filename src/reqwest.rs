@@ -0,0 +1,59 @@
+//! `.nota()` helpers for reqwest, like its `.json()`
+//!
+//! ```rust,no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use nota::reqwest::{RequestBuilderExt, ResponseExt};
+//! let reply: Vec<String> = reqwest::Client::new().post("http://localhost/")
+//!     .nota(&["hello"])?
+//!     .send().await?
+//!     .nota().await?;
+//! # Ok(()) }
+//! ```
+use crate::mime::CONTENT_TYPE;
+use crate::{Error, ParseOptions};
+use reqwest::header;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::io;
+
+pub trait RequestBuilderExt: Sized {
+    /// Sets the body to the value serialized as Nota, and sets the content type.
+    ///
+    /// Unlike `.json()`, this fails immediately if the value can't be serialized.
+    fn nota<T: Serialize + ?Sized>(self, value: &T) -> Result<Self, Error>;
+}
+
+impl RequestBuilderExt for reqwest::RequestBuilder {
+    fn nota<T: Serialize + ?Sized>(self, value: &T) -> Result<Self, Error> {
+        let body = crate::serde::to_vec(value)?;
+        Ok(self.header(header::CONTENT_TYPE, CONTENT_TYPE).body(body))
+    }
+}
+
+pub trait ResponseExt {
+    /// Reads the whole body and parses it as Nota, with [`ParseOptions::untrusted`] limits. HTTP errors are wrapped in `io::Error`.
+    fn nota<T: DeserializeOwned>(self) -> impl Future<Output = Result<T, io::Error>> + Send;
+}
+
+impl ResponseExt for reqwest::Response {
+    async fn nota<T: DeserializeOwned>(self) -> Result<T, io::Error> {
+        let body = self.bytes().await.map_err(io::Error::other)?;
+        crate::serde::from_slice_with(&body, &ParseOptions::untrusted())
+    }
+}
+
+#[test]
+fn request_and_response() {
+    let request = reqwest::Client::new().post("http://localhost/").nota(&[1u8, 2]).unwrap().build().unwrap();
+    assert_eq!(request.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+    let body = request.body().unwrap().as_bytes().unwrap().to_vec();
+
+    let response = reqwest::Response::from(http::Response::new(body));
+    assert_eq!(crate::block_on(response.nota::<Vec<u8>>()).unwrap(), [1, 2]);
+
+    let mut nested = vec![0x41; 100_000];
+    nested.push(0xC2);
+    let response = reqwest::Response::from(http::Response::new(nested));
+    assert!(crate::block_on(response.nota::<crate::Value>()).is_err());
+}
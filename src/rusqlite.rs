@@ -0,0 +1,37 @@
+//! Storing values in SQLite `BLOB` columns with [`rusqlite`]
+//!
+//! Values are encoded as Nota bytes, and parsed with [`ParseOptions::untrusted`] limits when read back. Columns of other types aren't accepted.
+use crate::{ParseOptions, Value};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+impl ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let mut out = Vec::new();
+        self.try_serialize_into(&mut out).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+        Ok(ToSqlOutput::from(out))
+    }
+}
+
+impl FromSql for Value {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let mut bytes = value.as_blob()?;
+        Self::parse_slice_with(&mut bytes, &ParseOptions::untrusted()).map_err(|e| FromSqlError::Other(e.into()))
+    }
+}
+
+#[test]
+fn blob_column() {
+    let db = rusqlite::Connection::open_in_memory().unwrap();
+    db.execute("CREATE TABLE t (v BLOB)", ()).unwrap();
+    let value = Value::Record(Box::new([("a".to_string(), Value::Array(vec![Value::Integer(-1), Value::Null]))].into_iter().collect()));
+    db.execute("INSERT INTO t VALUES (?1)", [&value]).unwrap();
+    let back: Value = db.query_row("SELECT v FROM t", (), |row| row.get(0)).unwrap();
+    assert_eq!(back, value);
+
+    let res = db.query_row("SELECT 'text'", (), |row| row.get::<_, Value>(0));
+    assert!(res.is_err());
+    let mut nested = vec![0x41; 100_000];
+    nested.push(0xC2);
+    let res = db.query_row("SELECT ?1", [nested], |row| row.get::<_, Value>(0));
+    assert!(res.is_err());
+}
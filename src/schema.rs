@@ -0,0 +1,531 @@
+//! Expected shapes of documents, which can be shared between services as Nota files
+//!
+//! A schema is itself a Nota document, usually written in the [diagnostic notation](crate::diag).
+//! Types are either names (`"any"`, `"null"`, `"bool"`, `"integer"`, `"text"`, `"blob"`, or a name from `types`),
+//! or records with a `"type"` and its options:
+//!
+//! | `"type"` | Options |
+//! |----------|---------|
+//! | `"integer"` | `"min"`, `"max"` |
+//! | `"text"` | `"min_len"`, `"max_len"` in chars |
+//! | `"blob"` | `"min_len"`, `"max_len"` in bits |
+//! | `"array"` | `"items"` (any if not set), `"min_len"`, `"max_len"` |
+//! | `"record"` | `"fields"`, `"extra"` (type of fields not in `"fields"`, which aren't allowed if not set), `"doc"` |
+//! | `"enum"` | `"values"`, an array of the allowed values |
+//! | `"one_of"` | `"types"`, an array of types |
+//!
+//! Fields are types, and can also have `"optional": true` and a `"doc"`.
+//!
+//! ```rust
+//! use nota::schema::Schema;
+//!
+//! let schema: Schema = r#"{"root": "User", "types": {"User": {"type": "record", "fields": {
+//!     "name": {"type": "text", "max_len": 100},
+//!     "age": {"type": "integer", "min": 0},
+//!     "tags": {"type": "array", "items": "text", "optional": true}
+//! }}}}"#.parse()?;
+//!
+//! let user = r#"{"name": "Ann", "age": -1}"#.parse()?;
+//! let err = schema.validate(&user).unwrap_err();
+//! assert_eq!(err.to_string(), "at age: -1 is less than 0");
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+use crate::path::{Path, Segment};
+use crate::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Root type, and named types it can refer to
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Schema {
+    pub root: Type,
+    pub types: BTreeMap<String, Type>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Type {
+    Any,
+    Null,
+    Bool,
+    Integer { min: Option<i128>, max: Option<i128> },
+    /// Lengths in chars
+    Text { min_len: Option<usize>, max_len: Option<usize> },
+    /// Lengths in bits
+    #[cfg(feature = "blobs")]
+    Blob { min_len: Option<usize>, max_len: Option<usize> },
+    Array { items: Box<Type>, min_len: Option<usize>, max_len: Option<usize> },
+    Record(Record),
+    /// One of these values
+    Enum(Vec<Value>),
+    /// Matches any of these types
+    OneOf(Vec<Type>),
+    /// Name of a type in [`Schema::types`]
+    Ref(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Record {
+    /// Sorted by name
+    pub fields: Vec<Field>,
+    /// Type of fields that aren't in `fields`. They're not allowed if it's `None`.
+    pub extra: Option<Box<Type>>,
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Field {
+    pub name: String,
+    pub ty: Type,
+    /// Can be missing
+    pub optional: bool,
+    pub doc: Option<String>,
+}
+
+/// Why a value doesn't match the schema, or why a schema definition is invalid
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SchemaError {
+    /// Location in the value, or in the schema definition
+    pub path: Path,
+    pub reason: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {}: {}", self.path, self.reason)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl Field {
+    #[must_use]
+    pub fn new(name: impl Into<String>, ty: Type) -> Self {
+        Self { name: name.into(), ty, optional: false, doc: None }
+    }
+}
+
+impl Record {
+    /// Finds a field by name
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.binary_search_by(|f| f.name.as_str().cmp(name)).ok().map(|i| &self.fields[i])
+    }
+}
+
+impl Schema {
+    #[must_use]
+    pub fn new(root: Type) -> Self {
+        Self { root, types: BTreeMap::new() }
+    }
+
+    /// Reads a schema definition, in the format described in the [module docs](self)
+    pub fn from_value(value: &Value) -> Result<Self, SchemaError> {
+        let mut path = Path::default();
+        let (root, mut types) = (value_of(value, "root"), BTreeMap::new());
+        if let Some(defs) = value_of(value, "types") {
+            path.push(Segment::Key("types".into()));
+            let mut defs: Vec<_> = record(defs, &path)?.iter().collect();
+            defs.sort_unstable_by_key(|&(k, _)| k);
+            for (name, def) in defs {
+                path.push(Segment::Key(name.clone()));
+                types.insert(name.clone(), read_type(def, &mut path)?);
+                path.pop();
+            }
+            path.pop();
+        }
+        path.push(Segment::Key("root".into()));
+        let root = read_type(root.ok_or_else(|| err(&Path::default(), "missing `root`"))?, &mut path)?;
+        let schema = Self { root, types };
+        schema.check_refs(&Path::from(vec![Segment::Key("root".into())]), "types")?;
+        Ok(schema)
+    }
+
+    /// The schema definition, which [`from_value`](Self::from_value) reads back
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        let mut definition = HashMap::from([("root".to_string(), write_type(&self.root))]);
+        if !self.types.is_empty() {
+            let types = self.types.iter().map(|(name, ty)| (name.clone(), write_type(ty))).collect();
+            definition.insert("types".into(), Value::Record(Box::new(types)));
+        }
+        Value::Record(Box::new(definition))
+    }
+
+    /// Checks that the value has this schema's root type
+    pub fn validate(&self, value: &Value) -> Result<(), SchemaError> {
+        self.validate_type(&self.root, value, &mut Path::default())
+    }
+
+    /// Checks that the value has the given type, which can refer to this schema's named types
+    pub fn validate_type(&self, ty: &Type, value: &Value, path: &mut Path) -> Result<(), SchemaError> {
+        let mismatch = |expected: &str| Err(err(path, format!("expected {expected}, found {}", kind(value))));
+        match (ty, value) {
+            (Type::Any, _) | (Type::Null, Value::Null) | (Type::Bool, Value::Bool(_)) => Ok(()),
+            (Type::Integer { min, max }, &Value::Integer(n)) => {
+                match (min, max) {
+                    (Some(min), _) if n < *min => return Err(err(path, format!("{n} is less than {min}"))),
+                    (_, Some(max)) if n > *max => return Err(err(path, format!("{n} is more than {max}"))),
+                    _ => {},
+                }
+                Ok(())
+            },
+            (Type::Text { min_len, max_len }, Value::Text(text)) => check_len(text.chars().count(), *min_len, *max_len, path),
+            #[cfg(feature = "blobs")]
+            (Type::Blob { min_len, max_len }, Value::Blob(bits)) => check_len(bits.len(), *min_len, *max_len, path),
+            (Type::Array { items, min_len, max_len }, Value::Array(values)) => {
+                check_len(values.len(), *min_len, *max_len, path)?;
+                for (i, item) in values.iter().enumerate() {
+                    path.push(Segment::Index(i));
+                    self.validate_type(items, item, path)?;
+                    path.pop();
+                }
+                Ok(())
+            },
+            (Type::Record(record), Value::Record(fields)) => {
+                if let Some(missing) = record.fields.iter().find(|f| !f.optional && !fields.contains_key(&f.name)) {
+                    return Err(err(path, format!("missing field `{}`", missing.name)));
+                }
+                let mut keys: Vec<_> = fields.keys().collect();
+                keys.sort_unstable();
+                for key in keys {
+                    let ty = match (record.field(key), &record.extra) {
+                        (Some(field), _) => &field.ty,
+                        (None, Some(extra)) => extra,
+                        (None, None) => return Err(err(path, format!("unexpected field `{key}`"))),
+                    };
+                    path.push(Segment::Key(key.clone()));
+                    self.validate_type(ty, &fields[key], path)?;
+                    path.pop();
+                }
+                Ok(())
+            },
+            (Type::Enum(values), _) if values.contains(value) => Ok(()),
+            (Type::Enum(values), _) => Err(err(path, format!("{value} is not one of {}", Value::Array(values.clone())))),
+            (Type::OneOf(types), _) => {
+                if types.iter().any(|ty| self.validate_type(ty, value, &mut path.clone()).is_ok()) {
+                    return Ok(());
+                }
+                Err(err(path, format!("{} doesn't match any of {} types", kind(value), types.len())))
+            },
+            (Type::Ref(name), _) => match self.types.get(name) {
+                Some(ty) => self.validate_type(ty, value, path),
+                None => Err(err(path, format!("undefined type `{name}`"))),
+            },
+            (Type::Null, _) => mismatch("null"),
+            (Type::Bool, _) => mismatch("bool"),
+            (Type::Integer { .. }, _) => mismatch("integer"),
+            (Type::Text { .. }, _) => mismatch("text"),
+            #[cfg(feature = "blobs")]
+            (Type::Blob { .. }, _) => mismatch("blob"),
+            (Type::Array { .. }, _) => mismatch("array"),
+            (Type::Record(_), _) => mismatch("record"),
+        }
+    }
+
+    /// Follows [`Type::Ref`]s to the type they name
+    #[must_use]
+    pub fn resolve<'a>(&'a self, mut ty: &'a Type) -> Option<&'a Type> {
+        for _ in 0..=self.types.len() {
+            match ty {
+                Type::Ref(name) => ty = self.types.get(name)?,
+                ty => return Some(ty),
+            }
+        }
+        None
+    }
+
+    /// All refs are defined, and types can't contain themselves, other than inside arrays or records.
+    /// Errors are at `root`, or in the `types` record of the definition.
+    pub(crate) fn check_refs(&self, root: &Path, types: &str) -> Result<(), SchemaError> {
+        let named = self.types.iter().map(|(name, ty)| (Path::from(vec![Segment::Key(types.into()), Segment::Key(name.clone())]), Some(name), ty));
+        for (path, name, ty) in named.chain([(root.clone(), None, &self.root)]) {
+            let mut seen = HashSet::new();
+            seen.extend(name);
+            self.check_ref(ty, &path, &mut seen, true)?;
+        }
+        Ok(())
+    }
+
+    fn check_ref<'a>(&'a self, ty: &'a Type, path: &Path, seen: &mut HashSet<&'a String>, direct: bool) -> Result<(), SchemaError> {
+        match ty {
+            Type::Ref(name) => {
+                let Some(target) = self.types.get(name) else {
+                    return Err(err(path, format!("undefined type `{name}`")));
+                };
+                if direct {
+                    if !seen.insert(name) {
+                        return Err(err(path, format!("type `{name}` contains itself")));
+                    }
+                    self.check_ref(target, path, seen, true)?;
+                    seen.remove(name);
+                }
+                Ok(())
+            },
+            Type::OneOf(types) => types.iter().try_for_each(|ty| self.check_ref(ty, path, seen, direct)),
+            Type::Array { items, .. } => self.check_ref(items, path, seen, false),
+            Type::Record(record) => {
+                record.fields.iter().map(|f| &f.ty).chain(record.extra.as_deref())
+                    .try_for_each(|ty| self.check_ref(ty, path, seen, false))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for Schema {
+    type Err = SchemaError;
+
+    /// Parses a schema definition in the [diagnostic notation](crate::diag)
+    fn from_str(s: &str) -> Result<Self, SchemaError> {
+        let value: Value = s.parse().map_err(|e: crate::diag::DiagError| err(&Path::default(), e.to_string()))?;
+        Self::from_value(&value)
+    }
+}
+
+fn err(path: &Path, reason: impl Into<String>) -> SchemaError {
+    SchemaError { path: path.clone(), reason: reason.into() }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        #[cfg(feature = "blobs")]
+        Value::Blob(_) => "blob",
+        Value::Text(_) => "text",
+        Value::Array(_) => "array",
+        Value::Record(_) => "record",
+        Value::Integer(_) => "integer",
+        #[cfg(feature = "dec64")]
+        Value::DecimalFloat(_) => "decimal float",
+        Value::Bool(_) => "bool",
+        Value::Null => "null",
+        Value::Unknown { .. } => "unknown value",
+    }
+}
+
+fn check_len(len: usize, min: Option<usize>, max: Option<usize>, path: &Path) -> Result<(), SchemaError> {
+    match (min, max) {
+        (Some(min), _) if len < min => Err(err(path, format!("length {len} is less than {min}"))),
+        (_, Some(max)) if len > max => Err(err(path, format!("length {len} is more than {max}"))),
+        _ => Ok(()),
+    }
+}
+
+fn value_of<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Record(fields) => fields.get(key),
+        _ => None,
+    }
+}
+
+fn record<'a>(value: &'a Value, path: &Path) -> Result<&'a HashMap<String, Value>, SchemaError> {
+    match value {
+        Value::Record(fields) => Ok(fields),
+        _ => Err(err(path, "expected a record")),
+    }
+}
+
+/// Reads an optional option of the type at `path`
+fn option<'a, T>(value: &'a Value, key: &str, path: &Path, read: impl FnOnce(&'a Value) -> Option<T>) -> Result<Option<T>, SchemaError> {
+    value_of(value, key).map(|v| read(v).ok_or_else(|| err(path, format!("invalid `{key}`")))).transpose()
+}
+
+fn integer(value: &Value) -> Option<i128> {
+    match value {
+        &Value::Integer(n) => Some(n),
+        _ => None,
+    }
+}
+
+fn len(value: &Value) -> Option<usize> {
+    integer(value).and_then(|n| n.try_into().ok())
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::Text(text) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+fn read_type(value: &Value, path: &mut Path) -> Result<Type, SchemaError> {
+    let name = match value {
+        Value::Text(name) => return Ok(match name.as_str() {
+            "any" => Type::Any,
+            "null" => Type::Null,
+            "bool" => Type::Bool,
+            "integer" => Type::Integer { min: None, max: None },
+            "text" => Type::Text { min_len: None, max_len: None },
+            #[cfg(feature = "blobs")]
+            "blob" => Type::Blob { min_len: None, max_len: None },
+            "array" => Type::Array { items: Box::new(Type::Any), min_len: None, max_len: None },
+            "record" => Type::Record(Record { extra: Some(Box::new(Type::Any)), ..Record::default() }),
+            _ => Type::Ref(name.clone()),
+        }),
+        Value::Record(fields) => match fields.get("type") {
+            Some(Value::Text(name)) => name.as_str(),
+            _ => return Err(err(path, "expected a `type`")),
+        },
+        _ => return Err(err(path, "expected a type name or a record")),
+    };
+    let min_len = option(value, "min_len", path, len)?;
+    let max_len = option(value, "max_len", path, len)?;
+    Ok(match name {
+        "integer" => Type::Integer { min: option(value, "min", path, integer)?, max: option(value, "max", path, integer)? },
+        "text" => Type::Text { min_len, max_len },
+        #[cfg(feature = "blobs")]
+        "blob" => Type::Blob { min_len, max_len },
+        "array" => {
+            let items = match value_of(value, "items") {
+                Some(items) => {
+                    path.push(Segment::Key("items".into()));
+                    let items = read_type(items, path)?;
+                    path.pop();
+                    items
+                },
+                None => Type::Any,
+            };
+            Type::Array { items: Box::new(items), min_len, max_len }
+        },
+        "record" => {
+            let mut record = Record { doc: option(value, "doc", path, text)?, ..Record::default() };
+            if let Some(extra) = value_of(value, "extra") {
+                path.push(Segment::Key("extra".into()));
+                record.extra = Some(Box::new(read_type(extra, path)?));
+                path.pop();
+            }
+            if let Some(fields) = value_of(value, "fields") {
+                path.push(Segment::Key("fields".into()));
+                for (name, field) in record_fields(fields, path)? {
+                    path.push(Segment::Key(name.clone()));
+                    record.fields.push(Field {
+                        name: name.clone(),
+                        ty: read_type(field, path)?,
+                        optional: option(field, "optional", path, |v| match v { Value::Bool(b) => Some(*b), _ => None })?.unwrap_or(false),
+                        doc: option(field, "doc", path, text)?,
+                    });
+                    path.pop();
+                }
+                path.pop();
+            }
+            Type::Record(record)
+        },
+        "enum" => match value_of(value, "values") {
+            Some(Value::Array(values)) => Type::Enum(values.clone()),
+            _ => return Err(err(path, "expected an array of `values`")),
+        },
+        "one_of" => match value_of(value, "types") {
+            Some(Value::Array(types)) => {
+                path.push(Segment::Key("types".into()));
+                let types = types.iter().enumerate().map(|(i, ty)| {
+                    path.push(Segment::Index(i));
+                    let ty = read_type(ty, path);
+                    path.pop();
+                    ty
+                }).collect::<Result<_, _>>()?;
+                path.pop();
+                Type::OneOf(types)
+            },
+            _ => return Err(err(path, "expected an array of `types`")),
+        },
+        name => read_type(&Value::Text(name.into()), path)?,
+    })
+}
+
+/// The shortest definition: a name if the type has no options
+fn write_type(ty: &Type) -> Value {
+    let (name, mut options) = match ty {
+        Type::Any => ("any", vec![]),
+        Type::Null => ("null", vec![]),
+        Type::Bool => ("bool", vec![]),
+        Type::Integer { min, max } => ("integer", [("min", *min), ("max", *max)].into_iter().filter_map(|(k, v)| Some((k, Value::Integer(v?)))).collect()),
+        Type::Text { min_len, max_len } => ("text", len_options(*min_len, *max_len)),
+        #[cfg(feature = "blobs")]
+        Type::Blob { min_len, max_len } => ("blob", len_options(*min_len, *max_len)),
+        Type::Array { items, min_len, max_len } => {
+            let mut options = len_options(*min_len, *max_len);
+            if **items != Type::Any {
+                options.push(("items", write_type(items)));
+            }
+            ("array", options)
+        },
+        Type::Record(Record { fields, extra: Some(extra), doc: None }) if fields.is_empty() && **extra == Type::Any => ("record", vec![]),
+        Type::Record(record) => {
+            let mut options = vec![("fields", Value::Record(Box::new(record.fields.iter().map(|field| {
+                let mut ty = write_type(&field.ty);
+                if field.optional || field.doc.is_some() {
+                    if let Value::Text(name) = ty {
+                        ty = Value::Record(Box::new(HashMap::from([("type".into(), Value::Text(name))])));
+                    }
+                    if let Value::Record(options) = &mut ty {
+                        if field.optional {
+                            options.insert("optional".into(), Value::Bool(true));
+                        }
+                        options.extend(field.doc.clone().map(|doc| ("doc".into(), Value::Text(doc))));
+                    }
+                }
+                (field.name.clone(), ty)
+            }).collect())))];
+            options.extend(record.extra.as_deref().map(|extra| ("extra", write_type(extra))));
+            options.extend(record.doc.clone().map(|doc| ("doc", Value::Text(doc))));
+            ("record", options)
+        },
+        Type::Enum(values) => ("enum", vec![("values", Value::Array(values.clone()))]),
+        Type::OneOf(types) => ("one_of", vec![("types", Value::Array(types.iter().map(write_type).collect()))]),
+        Type::Ref(name) => return Value::Text(name.clone()),
+    };
+    if options.is_empty() {
+        return Value::Text(name.into());
+    }
+    options.push(("type", Value::Text(name.into())));
+    Value::Record(Box::new(options.into_iter().map(|(k, v)| (k.to_string(), v)).collect()))
+}
+
+fn len_options(min_len: Option<usize>, max_len: Option<usize>) -> Vec<(&'static str, Value)> {
+    [("min_len", min_len), ("max_len", max_len)].into_iter().filter_map(|(k, v)| Some((k, Value::Integer(v? as i128)))).collect()
+}
+
+/// Fields sorted by name
+fn record_fields<'a>(value: &'a Value, path: &Path) -> Result<Vec<(&'a String, &'a Value)>, SchemaError> {
+    let mut fields: Vec<_> = record(value, path)?.iter().collect();
+    fields.sort_unstable_by_key(|&(k, _)| k);
+    Ok(fields)
+}
+
+#[test]
+fn validation() {
+    let schema: Schema = r#"{"root": "Tree", "types": {
+        "Tree": {"type": "record", "doc": "A node", "fields": {
+            "name": {"type": "text", "min_len": 1, "max_len": 3},
+            "size": {"type": "integer", "min": 0, "max": 9, "optional": true},
+            "kind": {"type": "enum", "values": ["a", "b"]},
+            "children": {"type": "array", "items": "Tree", "optional": true},
+            "parent": {"type": "one_of", "types": ["null", "text"], "optional": true}
+        }}
+    }}"#.parse().unwrap();
+    let Some(Type::Record(tree)) = schema.types.get("Tree") else { panic!() };
+    assert_eq!(tree.doc.as_deref(), Some("A node"));
+    assert_eq!(tree.fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["children", "kind", "name", "parent", "size"]);
+    assert!(tree.field("size").unwrap().optional);
+
+    let check = |doc: &str| schema.validate(&doc.parse().unwrap()).map_err(|e| e.to_string());
+    assert_eq!(check(r#"{"name": "a", "kind": "a", "children": [{"name": "b", "kind": "b", "size": 9, "parent": null}]}"#), Ok(()));
+    assert_eq!(check(r#"{"name": "", "kind": "a"}"#).unwrap_err(), "at name: length 0 is less than 1");
+    assert_eq!(check(r#"{"name": "a", "kind": "a", "children": [{"name": "b", "kind": "c"}]}"#).unwrap_err(), r#"at children[0].kind: "c" is not one of ["a", "b"]"#);
+    assert_eq!(check(r#"{"name": "a", "kind": "a", "size": 10}"#).unwrap_err(), "at size: 10 is more than 9");
+    assert_eq!(check(r#"{"name": "a", "kind": "a", "parent": 1}"#).unwrap_err(), "at parent: integer doesn't match any of 2 types");
+    assert_eq!(check(r#"{"name": "a"}"#).unwrap_err(), "at .: missing field `kind`");
+    assert_eq!(check(r#"{"name": "a", "kind": "a", "x": 1}"#).unwrap_err(), "at .: unexpected field `x`");
+    assert_eq!(check(r#"[]"#).unwrap_err(), "at .: expected record, found array");
+
+    let invalid = |def: &str| def.parse::<Schema>().unwrap_err().to_string();
+    assert_eq!(invalid(r#"{"root": "Nope"}"#), "at root: undefined type `Nope`");
+    assert_eq!(invalid(r#"{"root": "A", "types": {"A": {"type": "one_of", "types": ["null", "A"]}}}"#), "at types.A: type `A` contains itself");
+    assert_eq!(invalid(r#"{"root": {"type": "integer", "min": "0"}}"#), "at root: invalid `min`");
+    assert_eq!(invalid(r#"{"root": {"type": "array", "items": 1}}"#), "at root.items: expected a type name or a record");
+}
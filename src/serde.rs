@@ -0,0 +1,589 @@
+//! Serde support: `Serialize`/`Deserialize` for [`Value`], and conversion of any serde type to and from Nota
+//!
+//! | Rust | Nota |
+//! |------|------|
+//! | integers | Integer |
+//! | `bool` | Bool |
+//! | `String`, `char` | Text |
+//! | bytes (e.g. with `serde_bytes`) | Blob |
+//! | `None`, `()`, unit structs | Null |
+//! | sequences, tuples | Array |
+//! | maps, structs | Record |
+//! | unit enum variants | Text with the variant name |
+//! | other enum variants | Record with one field named after the variant |
+//!
+//! `f32` and `f64` can't be serialized. Fields can opt in to being stored as decimal text with
+//! `#[serde(with = "nota::serde::float_as_text")]`. Deserializing a float accepts such text, and integers.
+//! Map keys must be strings, chars, or integers. Integer keys are written as decimal text.
+use crate::{Error, ParseOptions, Value};
+use bitvec::vec::BitVec;
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Serde(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Serde(msg.to_string())
+    }
+}
+
+/// Converts any serializable type to a [`Value`]
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value.serialize(ValueSerializer)
+}
+
+/// Converts a [`Value`] to any deserializable type
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    T::deserialize(value)
+}
+
+/// Serializes any serializable type to Nota bytes
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    to_value(value)?.try_serialize_into(&mut out)?;
+    Ok(out)
+}
+
+/// Serializes any serializable type as Nota
+pub fn to_writer<T: Serialize + ?Sized, W: Write>(value: &T, mut writer: W) -> Result<(), io::Error> {
+    writer.write_all(&to_vec(value)?)
+}
+
+/// Parses one Nota value into any deserializable type
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, io::Error> {
+    from_slice_with(bytes, &ParseOptions::default())
+}
+
+/// Same as [`from_slice`], with limits on the parsed value, e.g. [`ParseOptions::untrusted`] for data from the network
+pub fn from_slice_with<T: DeserializeOwned>(mut bytes: &[u8], options: &ParseOptions) -> Result<T, io::Error> {
+    Ok(from_value(Value::parse_slice_with(&mut bytes, options)?)?)
+}
+
+/// Parses one Nota value into any deserializable type
+pub fn from_reader<T: DeserializeOwned, R: Read>(reader: &mut R) -> Result<T, io::Error> {
+    Ok(from_value(Value::parse_from(reader)?)?)
+}
+
+/// For `#[serde(with = "nota::serde::float_as_text")]` on `f64` fields, which stores them as the shortest decimal text
+/// that parses back to the same number
+pub mod float_as_text {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(f: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(f)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        f64::deserialize(deserializer)
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Integer(i) => match (i64::try_from(*i), u64::try_from(*i)) {
+                (Ok(i), _) => serializer.serialize_i64(i),
+                (_, Ok(u)) => serializer.serialize_u64(u),
+                _ => serializer.serialize_i128(*i),
+            },
+            Self::Text(s) => serializer.serialize_str(s),
+            Self::Blob(bits) if bits.len() % 8 == 0 => serializer.serialize_bytes(bits.as_raw_slice()),
+            Self::Blob(_) => Err(ser::Error::custom("blob with a partial byte can't be serialized")),
+            Self::Array(a) => serializer.collect_seq(a),
+            Self::Record(r) => serializer.collect_map(r.iter()),
+            Self::DecimalFloat(_) => Err(ser::Error::custom("decimal float can't be serialized")),
+            Self::Unknown { .. } => Err(ser::Error::custom("unknown tag can't be serialized")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> { Ok(Value::Bool(b)) }
+    fn visit_i64<E>(self, i: i64) -> Result<Value, E> { Ok(Value::Integer(i.into())) }
+    fn visit_u64<E>(self, u: u64) -> Result<Value, E> { Ok(Value::Integer(u.into())) }
+    fn visit_i128<E>(self, i: i128) -> Result<Value, E> { Ok(Value::Integer(i)) }
+    fn visit_u128<E: de::Error>(self, u: u128) -> Result<Value, E> {
+        Ok(Value::Integer(u.try_into().map_err(|_| E::custom("integer larger than 128 bits"))?))
+    }
+    fn visit_f64<E: de::Error>(self, f: f64) -> Result<Value, E> { Err(E::invalid_type(de::Unexpected::Float(f), &self)) }
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> { Ok(Value::Text(s.into())) }
+    fn visit_string<E>(self, s: String) -> Result<Value, E> { Ok(Value::Text(s)) }
+    fn visit_bytes<E>(self, b: &[u8]) -> Result<Value, E> { Ok(Value::Blob(BitVec::from_slice(b))) }
+    fn visit_byte_buf<E>(self, b: Vec<u8>) -> Result<Value, E> { Ok(Value::Blob(BitVec::from_vec(b))) }
+    fn visit_none<E>(self) -> Result<Value, E> { Ok(Value::Null) }
+    fn visit_unit<E>(self) -> Result<Value, E> { Ok(Value::Null) }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Value, D::Error> {
+        Value::deserialize(d)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, d: D) -> Result<Value, D::Error> {
+        Value::deserialize(d)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut a = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(v) = seq.next_element()? {
+            a.push(v);
+        }
+        Ok(Value::Array(a))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut r = HashMap::with_capacity(map.size_hint().unwrap_or(0).min(4096));
+        while let Some((k, v)) = map.next_entry()? {
+            r.insert(k, v);
+        }
+        Ok(Value::Record(Box::new(r)))
+    }
+}
+
+struct ValueSerializer;
+
+fn integer<T: TryInto<i128>>(i: T) -> Result<Value, Error> {
+    Ok(Value::Integer(i.try_into().map_err(|_| Error::Unrepresentable("integer larger than 128 bits"))?))
+}
+
+fn variant_record(variant: &str, value: Value) -> Value {
+    Value::Record(Box::new([(variant.to_string(), value)].into_iter().collect()))
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, b: bool) -> Result<Value, Error> { Ok(Value::Bool(b)) }
+    fn serialize_i8(self, i: i8) -> Result<Value, Error> { integer(i) }
+    fn serialize_i16(self, i: i16) -> Result<Value, Error> { integer(i) }
+    fn serialize_i32(self, i: i32) -> Result<Value, Error> { integer(i) }
+    fn serialize_i64(self, i: i64) -> Result<Value, Error> { integer(i) }
+    fn serialize_i128(self, i: i128) -> Result<Value, Error> { integer(i) }
+    fn serialize_u8(self, i: u8) -> Result<Value, Error> { integer(i) }
+    fn serialize_u16(self, i: u16) -> Result<Value, Error> { integer(i) }
+    fn serialize_u32(self, i: u32) -> Result<Value, Error> { integer(i) }
+    fn serialize_u64(self, i: u64) -> Result<Value, Error> { integer(i) }
+    fn serialize_u128(self, i: u128) -> Result<Value, Error> { integer(i) }
+    fn serialize_f32(self, _: f32) -> Result<Value, Error> { Err(Error::Unrepresentable("float")) }
+    fn serialize_f64(self, _: f64) -> Result<Value, Error> { Err(Error::Unrepresentable("float")) }
+    fn serialize_char(self, c: char) -> Result<Value, Error> { Ok(Value::Text(c.into())) }
+    fn serialize_str(self, s: &str) -> Result<Value, Error> { Ok(Value::Text(s.into())) }
+    fn serialize_bytes(self, b: &[u8]) -> Result<Value, Error> { Ok(Value::Blob(BitVec::from_slice(b))) }
+    fn serialize_none(self) -> Result<Value, Error> { Ok(Value::Null) }
+    fn serialize_unit(self) -> Result<Value, Error> { Ok(Value::Null) }
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Value, Error> { Ok(Value::Null) }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<Value, Error> {
+        Ok(Value::Text(variant.into()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _: &'static str, _: u32, variant: &'static str, value: &T) -> Result<Value, Error> {
+        Ok(variant_record(variant, to_value(value)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer { variant: None, items: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _: &'static str, _: u32, variant: &'static str, len: usize) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer { variant: Some(variant), items: Vec::with_capacity(len) })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer { variant: None, fields: HashMap::with_capacity(len.unwrap_or(0)), key: None })
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<MapSerializer, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _: &'static str, _: u32, variant: &'static str, len: usize) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer { variant: Some(variant), fields: HashMap::with_capacity(len), key: None })
+    }
+}
+
+struct SeqSerializer {
+    variant: Option<&'static str>,
+    items: Vec<Value>,
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        let array = Value::Array(self.items);
+        Ok(match self.variant {
+            Some(variant) => variant_record(variant, array),
+            None => array,
+        })
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct MapSerializer {
+    variant: Option<&'static str>,
+    fields: HashMap<String, Value>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(match to_value(key)? {
+            Value::Text(s) => s,
+            Value::Integer(i) => i.to_string(),
+            _ => return Err(Error::Unrepresentable("map key that isn't a string or an integer")),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().ok_or_else(|| Error::Serde("map value without a key".into()))?;
+        self.fields.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        let record = Value::Record(Box::new(self.fields));
+        Ok(match self.variant {
+            Some(variant) => variant_record(variant, record),
+            None => record,
+        })
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.fields.insert(key.into(), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Self::Null => visitor.visit_unit(),
+            Self::Bool(b) => visitor.visit_bool(b),
+            Self::Integer(i) => match (i64::try_from(i), u64::try_from(i)) {
+                (Ok(i), _) => visitor.visit_i64(i),
+                (_, Ok(u)) => visitor.visit_u64(u),
+                _ => visitor.visit_i128(i),
+            },
+            Self::Text(s) => visitor.visit_string(s),
+            Self::Blob(bits) if bits.len() % 8 == 0 => visitor.visit_byte_buf(bits.into_vec()),
+            Self::Blob(_) => Err(Error::Unrepresentable("blob with a partial byte")),
+            Self::Array(a) => {
+                let mut seq = SeqDeserializer::new(a.into_iter());
+                let res = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(res)
+            },
+            Self::Record(r) => {
+                let mut map = MapDeserializer::new(r.into_iter().map(|(k, v)| (Key(k), v)));
+                let res = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(res)
+            },
+            Self::DecimalFloat(_) => Err(Error::Unrepresentable("decimal float")),
+            Self::Unknown { .. } => Err(Error::Unrepresentable("unknown tag")),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Self::Null => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Self::Text(s) => visitor.visit_f64(s.parse().map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&s), &"a decimal number"))?),
+            Self::Integer(i) => visitor.visit_f64(i as f64),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _: &'static str, _: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Self::Text(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Self::Record(r) if r.len() == 1 => {
+                let (variant, value) = r.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer { variant, value })
+            },
+            _ => Err(de::Error::custom("expected text or a record with one field for an enum")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Value,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = Value;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Value), Error> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Value {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+}
+
+/// Record keys, which can be parsed as integers if the map needs them
+struct Key(String);
+
+impl<'de> IntoDeserializer<'de, Error> for Key {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_integer_key {
+    ($($method:ident => $visit:ident),*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0.parse() {
+                Ok(i) => visitor.$visit(i),
+                Err(_) => visitor.visit_string(self.0),
+            }
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for Key {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _: &'static str, _: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    deserialize_integer_key! {
+        deserialize_i8 => visit_i8, deserialize_i16 => visit_i16, deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64, deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8, deserialize_u16 => visit_u16, deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64, deserialize_u128 => visit_u128
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf option unit unit_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[test]
+fn roundtrip() {
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    enum Shape {
+        Dot,
+        Circle(u32),
+        Rect { w: i64, h: i64 },
+        Pair(i8, i8),
+    }
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Doc {
+        name: String,
+        big: u128,
+        #[serde(with = "float_as_text")]
+        scale: f64,
+        missing: Option<bool>,
+        shapes: Vec<Shape>,
+        by_id: HashMap<u16, char>,
+        unit: (),
+    }
+
+    let doc = Doc {
+        name: "x".into(),
+        big: 1 << 100,
+        scale: 0.25,
+        missing: None,
+        shapes: vec![Shape::Dot, Shape::Circle(3), Shape::Rect { w: -1, h: 2 }, Shape::Pair(1, 2)],
+        by_id: [(7, 'z')].into_iter().collect(),
+        unit: (),
+    };
+    let value = to_value(&doc).unwrap();
+    let Value::Record(r) = &value else { panic!() };
+    assert_eq!(r["scale"], Value::Text("0.25".into()));
+    assert_eq!(r["missing"], Value::Null);
+    let Value::Array(shapes) = &r["shapes"] else { panic!() };
+    assert_eq!(shapes[0], Value::Text("Dot".into()));
+    assert_eq!(shapes[1], variant_record("Circle", Value::Integer(3)));
+
+    let bytes = to_vec(&doc).unwrap();
+    assert_eq!(from_slice::<Doc>(&bytes).unwrap(), doc);
+    assert_eq!(from_slice::<Value>(&bytes).unwrap(), value);
+
+    let err = from_slice::<Doc>(&to_vec(&1).unwrap()).unwrap_err();
+    assert!(matches!(Error::from_io(&err), Some(Error::Serde(_))));
+    assert!(to_value(&u128::MAX).is_err());
+    assert_eq!(to_value(&0.5), Err(Error::Unrepresentable("float")));
+    assert_eq!(from_value::<f64>(Value::Integer(2)).unwrap(), 2.);
+}
@@ -0,0 +1,179 @@
+use crate::progress::Tracker;
+use crate::{serialize_signed_preamble, serialize_string, serialize_unsigned_preamble, string_size, unsigned_preamble_size, Dictionary, Error, ProgressCallback, Value};
+#[cfg(feature = "records")]
+use crate::FieldIds;
+use std::collections::HashMap;
+
+/// Settings for [`Value::serialize_with`]
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SerializeOptions {
+    /// Called periodically with the number of bytes written so far
+    pub progress: Option<ProgressCallback>,
+    /// Write record fields sorted by key, so that equal values always serialize to the same bytes.
+    ///
+    /// Integers and lengths always use their shortest encoding, and padding bits of blobs are always zero, so with this option the output is canonical.
+    pub canonical: bool,
+    /// Write repeated texts and keys as references to their first occurrence, when that's shorter.
+    ///
+    /// This is an extension of Nota. Other decoders can't read it, and this crate reads it only with [`ParseOptions::backrefs`](crate::ParseOptions::backrefs).
+    pub backrefs: bool,
+    /// Texts known in advance to the reader, which `backrefs` can point to. Must be the same as in [`ParseOptions::dictionary`](crate::ParseOptions::dictionary).
+    pub dictionary: Option<Dictionary>,
+    /// Write record keys as numbers from this table. Must be the same as in [`ParseOptions::field_ids`](crate::ParseOptions::field_ids).
+    #[cfg(feature = "records")]
+    pub field_ids: Option<FieldIds>,
+}
+
+pub(crate) struct Serializer<'a> {
+    into: &'a mut Vec<u8>,
+    start: usize,
+    progress: Tracker<'a>,
+    #[cfg_attr(not(feature = "records"), allow(dead_code))]
+    canonical: bool,
+    /// Index of each text among the texts written in full, when using back-references
+    strings: Option<HashMap<String, usize>>,
+    /// Written texts are numbered after the dictionary
+    written_strings: usize,
+    dictionary: Option<&'a Dictionary>,
+    #[cfg(feature = "records")]
+    field_ids: Option<&'a FieldIds>,
+}
+
+impl<'a> Serializer<'a> {
+    pub fn new(into: &'a mut Vec<u8>, options: &'a SerializeOptions) -> Self {
+        Self {
+            start: into.len(),
+            into,
+            progress: Tracker::new(options.progress.as_ref()),
+            canonical: options.canonical,
+            strings: options.backrefs.then(HashMap::new),
+            written_strings: options.dictionary.as_ref().map_or(0, Dictionary::len),
+            dictionary: options.dictionary.as_ref(),
+            #[cfg(feature = "records")]
+            field_ids: options.field_ids.as_ref(),
+        }
+    }
+
+    fn written(&self) -> u64 {
+        (self.into.len() - self.start) as u64
+    }
+
+    pub fn serialize(mut self, value: &Value) -> Result<(), Error> {
+        self.value(value)?;
+        self.progress.finish(self.written());
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some(strings) = &mut self.strings {
+            match self.dictionary.and_then(|d| d.find(text)).or_else(|| strings.get(text).copied()) {
+                Some(index) if unsigned_preamble_size(index as u128, 4) < string_size(text) => {
+                    serialize_unsigned_preamble(0b1110_0000, index as u128, self.into);
+                    return;
+                },
+                Some(_) => {},
+                None => { strings.insert(text.to_owned(), self.written_strings); },
+            }
+            // the parser remembers every text written in full
+            self.written_strings += 1;
+        }
+        serialize_string(text, self.into);
+    }
+
+    #[cfg(feature = "records")]
+    fn key(&mut self, key: &str) -> Result<(), Error> {
+        match self.field_ids {
+            Some(ids) => self.text(ids.key(key)?),
+            None => self.text(key),
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, value: &Value) -> Result<(), Error> {
+        self.progress.value(self.written());
+        let into = &mut *self.into;
+        match value {
+            #[cfg(feature = "blobs")]
+            Value::Blob(val) => {
+                serialize_unsigned_preamble(0, val.len() as u128, into);
+                debug_assert_eq!(val.len().div_ceil(8), val.as_raw_slice().len());
+                into.extend_from_slice(val.as_raw_slice());
+                // BitVec doesn't clear bits past its length
+                if let Some(last) = into.last_mut().filter(|_| val.len() % 8 != 0) {
+                    *last &= !(0xFF >> (val.len() % 8));
+                }
+            },
+            Value::Text(val) => {
+                self.text(val);
+            },
+            Value::Array(val) => {
+                serialize_unsigned_preamble(0b0100_0000, val.len() as u128, into);
+                for v in val {
+                    self.value(v)?;
+                }
+            },
+            #[cfg(feature = "records")]
+            Value::Record(val) => {
+                serialize_unsigned_preamble(0b0110_0000, val.len() as u128, into);
+                if self.canonical {
+                    let mut fields: Vec<_> = val.iter().collect();
+                    fields.sort_unstable_by_key(|&(k, _)| k);
+                    for (k, v) in fields {
+                        self.key(k)?;
+                        self.value(v)?;
+                    }
+                } else {
+                    for (k, v) in val.iter() {
+                        self.key(k)?;
+                        self.value(v)?;
+                    }
+                }
+            },
+            Value::Integer(val) => {
+                serialize_signed_preamble(0b1000_0000, *val, into);
+            },
+            #[cfg(feature = "dec64")]
+            Value::DecimalFloat(_) => {
+                return Err(Error::Unrepresentable("decimal float"));
+            },
+            Value::Bool(val) => {
+                into.push(0b1100_0000 | u8::from(*val));
+            },
+            Value::Null => {
+                into.push(0b1100_0010);
+            },
+            Value::Unknown { raw_bytes, .. } => {
+                into.extend_from_slice(raw_bytes);
+            },
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn backrefs() {
+    use crate::ParseOptions;
+
+    let row = |name: &str| Value::Record(Box::new([
+        ("name".to_string(), Value::Text(name.into())),
+        ("status".to_string(), Value::Text("active".into())),
+    ].into_iter().collect()));
+    let value = Value::Array(vec![row("x"), row("a long name"), row("a long name"), row("x"), Value::Text("status".into())]);
+    let mut standard = Vec::new();
+    value.serialize_into(&mut standard);
+    let mut compact = Vec::new();
+    value.serialize_with(&mut compact, &SerializeOptions { backrefs: true, ..Default::default() });
+    assert!(compact.len() < standard.len() * 2 / 3);
+
+    let options = ParseOptions { backrefs: true, ..Default::default() };
+    assert_eq!(Value::parse_with(&mut &compact[..], &options).unwrap(), value);
+    assert_eq!(Value::parse_slice_with(&mut &compact[..], &options).unwrap(), value);
+    let mut decoder = crate::Decoder::with_options(options.clone());
+    let decoded = compact.iter().find_map(|b| decoder.push(std::slice::from_ref(b)).unwrap().1);
+    assert_eq!(decoded.as_ref(), Some(&value));
+    assert_eq!(Value::parse_from(&mut &compact[..]).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(Value::parse_slice(&mut &compact[..]).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(Value::parse_with(&mut &[0x41, 0xE0][..], &options).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,96 @@
+//! Signed values, with Ed25519
+//!
+//! [`sign`] wraps a value in a record with its signature, and [`verify`] checks it and returns the value:
+//!
+//! ```text
+//! {"payload": <the value>, "signature": x"<64 bytes>", "key": "<key id>"}
+//! ```
+//!
+//! The signature is of the [canonical](crate::SerializeOptions::canonical) encoding of the payload,
+//! which is encoded again when verifying. The envelope can be stored and parsed in any form, and records
+//! in the payload can be in any order, without breaking the signature.
+//!
+//! The key id is only a hint for picking the key to verify with. It's not signed.
+//!
+//! ```rust
+//! use nota::signing::{self, SigningKey};
+//! use nota::Value;
+//!
+//! let key = SigningKey::from_bytes(&[7; 32]);
+//! let envelope = signing::sign(Value::Text("hello".into()), &key, "key-1")?;
+//!
+//! assert_eq!(signing::key_id(&envelope), Some("key-1"));
+//! assert_eq!(signing::verify(&envelope, &key.verifying_key())?, &Value::Text("hello".into()));
+//! # Ok::<_, nota::Error>(())
+//! ```
+use crate::{Error, SerializeOptions, Value};
+use bitvec::vec::BitVec;
+pub use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+use ed25519_dalek::Signer;
+use std::collections::HashMap;
+
+/// Signs the payload, and wraps it in an envelope record. Fails if the payload can't be serialized.
+pub fn sign(payload: Value, key: &SigningKey, key_id: &str) -> Result<Value, Error> {
+    let signature = key.sign(&canonical(&payload)?);
+    Ok(Value::Record(Box::new(HashMap::from([
+        ("payload".to_string(), payload),
+        ("signature".to_string(), Value::Blob(BitVec::from_slice(&signature.to_bytes()))),
+        ("key".to_string(), Value::Text(key_id.to_string())),
+    ]))))
+}
+
+/// The key id of the envelope, if it has one. It isn't checked until [`verify`].
+#[must_use]
+pub fn key_id(envelope: &Value) -> Option<&str> {
+    match field(envelope, "key")? {
+        Value::Text(id) => Some(id),
+        _ => None,
+    }
+}
+
+/// Checks the signature of the envelope, and returns its payload.
+///
+/// Fails with [`Error::InvalidSignature`] if the envelope is malformed or the signature doesn't match.
+pub fn verify<'a>(envelope: &'a Value, key: &VerifyingKey) -> Result<&'a Value, Error> {
+    let payload = field(envelope, "payload").ok_or(Error::InvalidSignature)?;
+    let signature = match field(envelope, "signature") {
+        Some(Value::Blob(bits)) if bits.len() == 8 * Signature::BYTE_SIZE => Signature::from_slice(bits.as_raw_slice()).map_err(|_| Error::InvalidSignature)?,
+        _ => return Err(Error::InvalidSignature),
+    };
+    key.verify_strict(&canonical(payload)?, &signature).map_err(|_| Error::InvalidSignature)?;
+    Ok(payload)
+}
+
+fn field<'a>(envelope: &'a Value, name: &str) -> Option<&'a Value> {
+    match envelope {
+        Value::Record(fields) => fields.get(name),
+        _ => None,
+    }
+}
+
+fn canonical(value: &Value) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    value.try_serialize_with(&mut data, &SerializeOptions { canonical: true, ..SerializeOptions::default() })?;
+    Ok(data)
+}
+
+#[test]
+fn sign_and_verify() {
+    let key = SigningKey::from_bytes(&[1; 32]);
+    let payload = Value::Record(Box::new((0..20).map(|n| (n.to_string(), Value::Integer(n))).collect()));
+    let envelope = sign(payload.clone(), &key, "k").unwrap();
+
+    let mut data = Vec::new();
+    envelope.serialize_into(&mut data);
+    let parsed = Value::parse_slice(&mut &data[..]).unwrap();
+    assert_eq!(verify(&parsed, &key.verifying_key()), Ok(&payload));
+    assert_eq!(verify(&parsed, &SigningKey::from_bytes(&[2; 32]).verifying_key()), Err(Error::InvalidSignature));
+
+    let mut tampered = parsed.clone();
+    let Value::Record(fields) = &mut tampered else { panic!() };
+    let Some(Value::Record(payload)) = fields.get_mut("payload") else { panic!() };
+    payload.insert("0".into(), Value::Integer(-1));
+    assert_eq!(verify(&tampered, &key.verifying_key()), Err(Error::InvalidSignature));
+    assert_eq!(verify(&Value::Null, &key.verifying_key()), Err(Error::InvalidSignature));
+    assert_eq!(key_id(&parsed), Some("k"));
+}
@@ -0,0 +1,287 @@
+use crate::progress::Tracker;
+use crate::{ascii_prefix, kim_char, kim_group, varint, ParseOptions, Recycler, Value};
+#[cfg(feature = "blobs")]
+use bitvec::vec::BitVec;
+use std::io;
+
+/// Same as `Parser`, but reads directly from memory instead of one byte at a time via `Read`
+pub(crate) struct SliceParser<'a> {
+    data: &'a [u8],
+    pos: usize,
+    options: &'a ParseOptions,
+    depth: usize,
+    progress: Tracker<'a>,
+    /// Texts that back-references point to
+    strings: Vec<String>,
+    recycler: Option<&'a mut Recycler>,
+}
+
+impl<'a> SliceParser<'a> {
+    pub fn new(data: &'a [u8], options: &'a ParseOptions) -> Self {
+        Self { data, pos: 0, options, depth: 0, progress: Tracker::new(options.progress.as_ref()), strings: Vec::new(), recycler: None }
+    }
+
+    /// Takes allocations from the recycler instead of making new ones
+    pub fn with_recycler(mut self, recycler: &'a mut Recycler) -> Self {
+        self.recycler = Some(recycler);
+        self
+    }
+
+    fn string(&mut self, capacity: usize) -> String {
+        let mut out = self.recycler.as_deref_mut().and_then(|r| r.strings.pop()).unwrap_or_default();
+        out.reserve(capacity);
+        out
+    }
+
+    /// For parsing elements of an array that has been parsed separately
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    pub fn at_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Returns the value and the number of bytes it took
+    pub fn parse(mut self) -> Result<(Value, usize), io::Error> {
+        let value = self.value()?;
+        self.options.check_size(self.pos as u64)?;
+        self.progress.finish(self.pos as u64);
+        Ok((value, self.pos))
+    }
+
+    #[inline]
+    fn byte(&mut self) -> Result<u8, io::Error> {
+        let &byte = self.data.get(self.pos).ok_or(io::ErrorKind::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    #[cfg(feature = "blobs")]
+    #[inline]
+    fn take(&mut self, len: usize) -> Result<&'a [u8], io::Error> {
+        let bytes = self.data.get(self.pos..).and_then(|rest| rest.get(..len)).ok_or(io::ErrorKind::UnexpectedEof)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn value(&mut self) -> Result<Value, io::Error> {
+        self.depth += 1;
+        let res = self.value_inner();
+        self.depth -= 1;
+        res
+    }
+
+    fn value_inner(&mut self) -> Result<Value, io::Error> {
+        self.options.check_cancelled()?;
+        self.options.check_depth(self.depth)?;
+        self.options.check_size(self.pos as u64)?;
+        self.progress.value(self.pos as u64);
+        let preamble = self.byte()?;
+        Ok(match preamble & 0b1110_0000 {
+            #[cfg(feature = "blobs")]
+            0b0000_0000 => {
+                let len = self.len(preamble)?;
+                self.options.check_size(self.pos.saturating_add(len.div_ceil(8)) as u64)?;
+                let bytes = self.take(len.div_ceil(8))?;
+                self.options.check_padding(bytes, len)?;
+                let mut out = self.recycler.as_deref_mut().and_then(|r| r.bytes.pop()).unwrap_or_default();
+                out.extend_from_slice(bytes);
+                let mut out = BitVec::from_vec(out);
+                out.truncate(len);
+                Value::Blob(out)
+            },
+            0b0010_0000 => {
+                let len = self.len(preamble)?;
+                // every char takes at least a byte
+                self.options.check_size(self.pos.saturating_add(len) as u64)?;
+                let text = self.text(len)?;
+                if self.options.backrefs {
+                    self.strings.push(text.clone());
+                }
+                Value::Text(text)
+            },
+            0b0100_0000 => {
+                let len = self.len(preamble)?;
+                // every element takes at least a byte
+                let mut out = self.recycler.as_deref_mut().and_then(|r| r.arrays.pop()).unwrap_or_default();
+                out.reserve(len.min(self.data.len() - self.pos));
+                for _ in 0..len {
+                    out.push(self.value()?);
+                }
+                Value::Array(out)
+            },
+            #[cfg(feature = "records")]
+            0b0110_0000 => {
+                let len = self.len(preamble)?;
+                let mut out = self.recycler.as_deref_mut().and_then(|r| r.records.pop()).unwrap_or_default();
+                out.reserve(len.min((self.data.len() - self.pos) / 2));
+                for _ in 0..len {
+                    let Value::Text(k) = self.value()? else {
+                        return Err(io::ErrorKind::InvalidData.into());
+                    };
+                    let k = self.options.field_name(k);
+                    let v = self.value()?;
+                    out.insert(k, v);
+                }
+                Value::Record(out)
+            },
+            0b1000_0000 => {
+                let mut val = u128::from(preamble & 0b000_0111);
+                if preamble & 0b0001_0000 != 0 {
+                    if let Some((groups, bytes)) = varint::decode8(&self.data[self.pos..]) {
+                        self.pos += bytes;
+                        val = val << (7 * bytes) | u128::from(groups);
+                        self.options.check_minimal(val >> (7 * (bytes - 1)) >= 8)?;
+                    } else {
+                        loop {
+                            let next = self.byte()?;
+                            val = varint::push_int(val, next)?;
+                            self.options.check_minimal(val >= 8)?;
+                            if next & 0b1000_0000 == 0 {
+                                break;
+                            }
+                        }
+                    }
+                }
+                self.options.check_minimal(preamble & 0b000_1000 == 0 || val != 0)?;
+                Value::Integer(varint::signed(preamble & 0b000_1000 != 0, val)?)
+            },
+            #[cfg(feature = "dec64")]
+            0b1010_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            0b1100_0000 => match preamble & 0b0001_1111 {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                2 => Value::Null,
+                _ => self.options.constant(preamble)?,
+            },
+            0b1110_0000 if self.options.backrefs => {
+                let index = self.unsigned(preamble)?;
+                Value::Text(match self.options.backref(index) {
+                    Ok(text) => text.to_owned(),
+                    Err(index) => self.strings.get(index).ok_or(io::ErrorKind::InvalidData)?.clone(),
+                })
+            },
+            0b1110_0000 if self.options.unknown => {
+                let start = self.pos - 1;
+                self.unsigned(preamble)?;
+                Value::Unknown { tag: 0b111, raw_bytes: self.data[start..self.pos].into() }
+            },
+            #[cfg(not(feature = "blobs"))]
+            0b0000_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            #[cfg(not(feature = "records"))]
+            0b0110_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            #[cfg(not(feature = "dec64"))]
+            0b1010_0000 => return Err(io::ErrorKind::Unsupported.into()),
+            _ => return Err(io::ErrorKind::InvalidData.into()),
+        })
+    }
+
+    /// `len` is in chars, which take at least a byte each
+    fn text(&mut self, len: usize) -> Result<String, io::Error> {
+        let mut out = self.string(len.min(self.data.len() - self.pos));
+        let mut remaining = len;
+        while remaining > 0 {
+            let ascii = ascii_prefix(&self.data[self.pos..], remaining);
+            if !ascii.is_empty() {
+                out.push_str(ascii);
+                self.pos += ascii.len();
+                remaining -= ascii.len();
+                continue;
+            }
+            let start = self.pos as u64;
+            let mut val = 0u32;
+            loop {
+                let next = self.byte()?;
+                self.options.check_minimal(val != 0 || next != 0b1000_0000)?;
+                val = kim_group(val, next, start)?;
+                if next & 0b1000_0000 == 0 {
+                    break;
+                }
+            }
+            out.push(kim_char(val, start)?);
+            remaining -= 1;
+        }
+        Ok(out)
+    }
+
+    fn len(&mut self, preamble: u8) -> Result<usize, io::Error> {
+        let len = self.unsigned(preamble)?;
+        self.options.check_len(len)?;
+        Ok(len)
+    }
+
+    fn unsigned(&mut self, preamble: u8) -> Result<usize, io::Error> {
+        let mut len = usize::from(preamble & 0b000_1111);
+        if preamble & 0b0001_0000 != 0 {
+            if let Some((groups, bytes)) = varint::decode8(&self.data[self.pos..]) {
+                self.pos += bytes;
+                len = varint::join_len(len, groups, bytes)?;
+                self.options.check_minimal(len >> (7 * (bytes - 1)) >= 16)?;
+            } else {
+                loop {
+                    let next = self.byte()?;
+                    len = varint::push_len(len, next)?;
+                    self.options.check_minimal(len >= 16)?;
+                    if next & 0b1000_0000 == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(len)
+    }
+}
+
+impl Value {
+    /// Parses directly from memory, without a `Read` call for every byte like [`parse_from`](Self::parse_from) with a slice.
+    /// That makes it about 20% faster for documents of integers, and 5% for documents of records (see `benches/value.rs`).
+    ///
+    /// On success, `data` is advanced past the value.
+    pub fn parse_slice(data: &mut &[u8]) -> Result<Self, io::Error> {
+        Self::parse_slice_with(data, &ParseOptions::default())
+    }
+
+    pub fn parse_slice_with(data: &mut &[u8], options: &ParseOptions) -> Result<Self, io::Error> {
+        let (value, len) = SliceParser::new(data, options).parse()?;
+        *data = &data[len..];
+        Ok(value)
+    }
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn same_as_reader() {
+    use crate::Error;
+
+    let value = Value::Array(vec![
+        Value::Record(Box::new([
+            ("ascii".to_string(), Value::Text("hello".into())),
+            ("☃".to_string(), Value::Text("snow ☃ 𓂀".into())),
+            ("bits".to_string(), Value::Blob([true, false, true].into_iter().collect())),
+            ("bytes".to_string(), Value::Blob(BitVec::from_vec(vec![1, 2, 3]))),
+        ].into_iter().collect())),
+        Value::Integer(-1234567890123),
+        Value::Integer(i128::MAX),
+        Value::Array(vec![Value::Null, Value::Bool(true), Value::Text(String::new())]),
+    ]);
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+    bytes.push(0xC2);
+
+    let mut rest = &bytes[..];
+    assert_eq!(Value::parse_slice(&mut rest).unwrap(), value);
+    assert_eq!(rest, [0xC2]);
+
+    for len in 0..bytes.len() - 1 {
+        let mut data = &bytes[..len];
+        let expected = Value::parse_from(&mut &bytes[..len]).unwrap_err();
+        assert_eq!(Value::parse_slice(&mut data).unwrap_err().kind(), expected.kind());
+        assert_eq!(data.len(), len);
+    }
+
+    let options = ParseOptions { max_depth: Some(2), ..Default::default() };
+    let err = Value::parse_slice_with(&mut &bytes[..], &options).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::TooDeep { max: 2 }));
+    let options = ParseOptions { max_len: Some(10), ..Default::default() };
+    let err = Value::parse_slice_with(&mut &bytes[..], &options).unwrap_err();
+    assert_eq!(Error::from_io(&err), Some(&Error::TooLong { len: 24, max: 10 }));
+}
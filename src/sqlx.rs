@@ -0,0 +1,52 @@
+//! Storing values in Postgres `bytea` columns with [`sqlx`]
+//!
+//! Values are encoded as Nota bytes, and parsed with [`ParseOptions::untrusted`] limits when read back. Columns of other types aren't accepted.
+use crate::{ParseOptions, Value};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type};
+
+impl Type<Postgres> for Value {
+    fn type_info() -> PgTypeInfo {
+        <[u8] as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <[u8] as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl PgHasArrayType for Value {
+    fn array_type_info() -> PgTypeInfo {
+        <&[u8] as PgHasArrayType>::array_type_info()
+    }
+}
+
+impl Encode<'_, Postgres> for Value {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        let mut out = Vec::new();
+        self.try_serialize_into(&mut out)?;
+        <&[u8] as Encode<Postgres>>::encode(&out, buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for Value {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let mut bytes = <&[u8] as Decode<Postgres>>::decode(value)?;
+        Ok(Self::parse_slice_with(&mut bytes, &ParseOptions::untrusted())?)
+    }
+}
+
+#[test]
+fn bytea() {
+    use sqlx::TypeInfo;
+
+    assert_eq!(<Value as Type<Postgres>>::type_info().name(), "BYTEA");
+    let mut buf = PgArgumentBuffer::default();
+    let value = Value::Array(vec![Value::Integer(1), Value::Text("x".into())]);
+    assert!(matches!(value.encode_by_ref(&mut buf), Ok(IsNull::No)));
+    let mut expected = Vec::new();
+    value.serialize_into(&mut expected);
+    assert_eq!(&buf[..], &expected[..]);
+}
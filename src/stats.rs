@@ -0,0 +1,34 @@
+/// Summary of a parsed document, from [`Value::parse_with_stats`](crate::Value::parse_with_stats)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    pub blobs: u64,
+    pub texts: u64,
+    pub arrays: u64,
+    pub records: u64,
+    pub integers: u64,
+    pub bools: u64,
+    pub nulls: u64,
+    /// Nesting level of the deepest value. A scalar at the top level has depth 1.
+    pub max_depth: usize,
+    /// Size of the whole document in bytes
+    pub total_bytes: u64,
+    /// Encoded size of all texts and record keys (excluding their length prefixes)
+    pub text_bytes: u64,
+    /// Number of characters in the longest text or key
+    pub longest_text: usize,
+    /// Size in *bits*
+    pub largest_blob: usize,
+    /// Number of elements
+    pub largest_array: usize,
+    /// Number of fields
+    pub largest_record: usize,
+}
+
+impl Stats {
+    /// Number of all values, including nested ones and record keys
+    #[must_use]
+    pub fn values(&self) -> u64 {
+        self.blobs + self.texts + self.arrays + self.records + self.integers + self.bools + self.nulls
+    }
+}
@@ -0,0 +1,264 @@
+//! Values of custom types, like datetimes and UUIDs, tagged with the name of their type
+//!
+//! A tagged value is a record with a single field, whose key is the tag name prefixed with `$`, such as `{"$point": [1, 2]}`.
+//! Keys starting with `$` are reserved for tags, so records with a single such field shouldn't be used for anything else.
+//!
+//! Types implement [`Extension`] to be converted with [`encode`] and [`decode`]. A [`Registry`] has conversions
+//! for many types, including types from other crates, and can decode tagged values without knowing their type in advance.
+//!
+//! ```rust
+//! use nota::tagged::{Extension, Registry};
+//! use nota::{Error, Value};
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Point(i128, i128);
+//!
+//! impl Extension for Point {
+//!     const TAG: &'static str = "point";
+//!
+//!     fn to_value(&self) -> Value {
+//!         Value::Array(vec![Value::Integer(self.0), Value::Integer(self.1)])
+//!     }
+//!
+//!     fn from_value(value: &Value) -> Result<Self, Error> {
+//!         match value {
+//!             Value::Array(xy) => match xy[..] {
+//!                 [Value::Integer(x), Value::Integer(y)] => Ok(Point(x, y)),
+//!                 _ => Err(Error::UnexpectedValue { expected: "two integers" }),
+//!             },
+//!             _ => Err(Error::UnexpectedValue { expected: "an array" }),
+//!         }
+//!     }
+//! }
+//!
+//! let value = nota::tagged::encode(&Point(1, 2));
+//! assert_eq!(value.to_string(), r#"{"$point": [1, 2]}"#);
+//!
+//! let mut registry = Registry::new();
+//! registry.register_extension::<Point>();
+//! let (tag, point) = registry.decode_any(&value).unwrap()?;
+//! assert_eq!(tag, "point");
+//! assert_eq!(point.downcast_ref(), Some(&Point(1, 2)));
+//! # Ok::<_, Error>(())
+//! ```
+use crate::path::{Path, Segment};
+use crate::{Error, Value};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Prefix of tag keys
+pub const TAG_PREFIX: char = '$';
+
+/// A type that is stored as a tagged value
+pub trait Extension: Sized {
+    /// Name of the tag, without the `$` prefix
+    const TAG: &'static str;
+
+    /// The value inside the tag
+    fn to_value(&self) -> Value;
+
+    /// From the value inside the tag
+    fn from_value(value: &Value) -> Result<Self, Error>;
+}
+
+impl Value {
+    /// A record with a single `$tag` field
+    #[must_use]
+    pub fn tagged(tag: &str, value: Self) -> Self {
+        Self::Record(Box::new([(format!("{TAG_PREFIX}{tag}"), value)].into_iter().collect()))
+    }
+
+    /// The tag (without `$`) and the value inside, if this is a tagged value
+    #[must_use]
+    pub fn as_tagged(&self) -> Option<(&str, &Self)> {
+        match self {
+            Self::Record(fields) if fields.len() == 1 => {
+                let (key, value) = fields.iter().next()?;
+                Some((key.strip_prefix(TAG_PREFIX)?, value))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Tagged value of `T`
+#[must_use]
+pub fn encode<T: Extension>(value: &T) -> Value {
+    Value::tagged(T::TAG, value.to_value())
+}
+
+/// From a value tagged with `T`'s tag
+pub fn decode<T: Extension>(value: &Value) -> Result<T, Error> {
+    match value.as_tagged() {
+        Some((tag, inner)) if tag == T::TAG => T::from_value(inner),
+        _ => Err(Error::UnexpectedValue { expected: T::TAG }),
+    }
+}
+
+type Decoded = Box<dyn Any + Send + Sync>;
+type EncodeHook = dyn Fn(&dyn Any) -> Option<Value> + Send + Sync;
+type DecodeHook = dyn Fn(&Value) -> Result<Decoded, Error> + Send + Sync;
+
+struct Hooks {
+    encode: Box<EncodeHook>,
+    decode: Box<DecodeHook>,
+}
+
+/// Conversions of tagged values, by tag name and by Rust type
+#[derive(Clone, Default)]
+pub struct Registry {
+    tags: HashMap<String, Arc<Hooks>>,
+    types: HashMap<TypeId, String>,
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tags: Vec<_> = self.tags.keys().collect();
+        tags.sort_unstable();
+        f.debug_struct("Registry").field("tags", &tags).finish()
+    }
+}
+
+impl Registry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds conversions of `T` to and from the value inside `tag`. They replace any previous conversions of the same tag or type.
+    ///
+    /// This works for types from other crates, which can't implement [`Extension`].
+    pub fn register<T: Any + Send + Sync>(
+        &mut self,
+        tag: &str,
+        encode: impl Fn(&T) -> Value + Send + Sync + 'static,
+        decode: impl Fn(&Value) -> Result<T, Error> + Send + Sync + 'static,
+    ) -> &mut Self {
+        let hooks = Hooks {
+            encode: Box::new(move |value| value.downcast_ref().map(&encode)),
+            decode: Box::new(move |value| Ok(Box::new(decode(value)?))),
+        };
+        self.types.retain(|_, old| old != tag);
+        if let Some(old) = self.types.insert(TypeId::of::<T>(), tag.into()) {
+            self.tags.remove(&old);
+        }
+        self.tags.insert(tag.into(), Arc::new(hooks));
+        self
+    }
+
+    pub fn register_extension<T: Extension + Any + Send + Sync>(&mut self) -> &mut Self {
+        self.register(T::TAG, T::to_value, T::from_value)
+    }
+
+    /// Tag of a registered type
+    #[must_use]
+    pub fn tag_of<T: Any>(&self) -> Option<&str> {
+        self.types.get(&TypeId::of::<T>()).map(String::as_str)
+    }
+
+    /// Tagged value of a registered type
+    pub fn encode<T: Any>(&self, value: &T) -> Result<Value, Error> {
+        let tag = self.tag_of::<T>().ok_or(Error::Unrepresentable("unregistered type"))?;
+        let inner = (self.tags[tag].encode)(value).ok_or(Error::Unrepresentable("unregistered type"))?;
+        Ok(Value::tagged(tag, inner))
+    }
+
+    /// From a value tagged with the tag registered for `T`
+    pub fn decode<T: Any>(&self, value: &Value) -> Result<T, Error> {
+        let tag = self.tag_of::<T>().ok_or(Error::Unrepresentable("unregistered type"))?;
+        match value.as_tagged() {
+            Some((found, inner)) if found == tag => {
+                let decoded = (self.tags[tag].decode)(inner)?;
+                decoded.downcast().map(|d| *d).map_err(|_| Error::Unrepresentable("unregistered type"))
+            },
+            _ => Err(Error::UnexpectedValue { expected: "a value with the registered tag" }),
+        }
+    }
+
+    /// Decodes a tagged value with whichever conversion is registered for its tag.
+    ///
+    /// `None` if the value isn't tagged, or its tag isn't registered.
+    #[must_use]
+    pub fn decode_any<'a>(&self, value: &'a Value) -> Option<Result<(&'a str, Decoded), Error>> {
+        let (tag, inner) = value.as_tagged()?;
+        let hooks = self.tags.get(tag)?;
+        Some((hooks.decode)(inner).map(|decoded| (tag, decoded)))
+    }
+
+    /// Checks that all tagged values in the document have registered tags, and can be decoded
+    pub fn check(&self, value: &Value) -> Result<(), (Path, Error)> {
+        self.check_at(value, &mut Path::default())
+    }
+
+    fn check_at(&self, value: &Value, path: &mut Path) -> Result<(), (Path, Error)> {
+        if value.as_tagged().is_some() {
+            return match self.decode_any(value) {
+                Some(Ok(_)) => Ok(()),
+                Some(Err(err)) => Err((path.clone(), err)),
+                None => Err((path.clone(), Error::Unrepresentable("unregistered tag"))),
+            };
+        }
+        match value {
+            Value::Array(items) => items.iter().enumerate().try_for_each(|(i, item)| {
+                path.push(Segment::Index(i));
+                self.check_at(item, path)?;
+                path.pop();
+                Ok(())
+            }),
+            Value::Record(fields) => fields.iter().try_for_each(|(key, field)| {
+                path.push(Segment::Key(key.clone()));
+                self.check_at(field, path)?;
+                path.pop();
+                Ok(())
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn registry() {
+    use std::ops::Range;
+
+    struct Celsius(i128);
+    impl Extension for Celsius {
+        const TAG: &'static str = "celsius";
+
+        fn to_value(&self) -> Value {
+            Value::Integer(self.0)
+        }
+
+        fn from_value(value: &Value) -> Result<Self, Error> {
+            match value {
+                &Value::Integer(n) => Ok(Self(n)),
+                _ => Err(Error::UnexpectedValue { expected: "an integer" }),
+            }
+        }
+    }
+
+    let mut registry = Registry::new();
+    registry.register_extension::<Celsius>().register::<Range<i128>>("range", |r| Value::Array(vec![Value::Integer(r.start), Value::Integer(r.end)]), |v| match v {
+        Value::Array(a) => match a[..] {
+            [Value::Integer(start), Value::Integer(end)] => Ok(start..end),
+            _ => Err(Error::UnexpectedValue { expected: "start and end" }),
+        },
+        _ => Err(Error::UnexpectedValue { expected: "an array" }),
+    });
+    assert_eq!(registry.tag_of::<Range<i128>>(), Some("range"));
+    assert_eq!(format!("{registry:?}"), r#"Registry { tags: ["celsius", "range"] }"#);
+
+    let range = registry.encode(&(1i128..5)).unwrap();
+    assert_eq!(range.to_string(), r#"{"$range": [1, 5]}"#);
+    assert_eq!(registry.decode::<Range<i128>>(&range), Ok(1..5));
+    assert_eq!(registry.decode::<Celsius>(&range).err(), Some(Error::UnexpectedValue { expected: "a value with the registered tag" }));
+    assert_eq!(registry.encode(&1u8), Err(Error::Unrepresentable("unregistered type")));
+    assert_eq!(decode::<Celsius>(&encode(&Celsius(-4))).unwrap().0, -4);
+
+    let doc = Value::Array(vec![range, encode(&Celsius(20)), Value::tagged("celsius", Value::Null)]);
+    assert_eq!(registry.check(&doc).unwrap_err().0.to_string(), "[2]");
+    assert_eq!(registry.check(&Value::tagged("kelvin", Value::Null)).unwrap_err().1, Error::Unrepresentable("unregistered tag"));
+    assert!(registry.decode_any(&Value::Null).is_none());
+    assert!(registry.check(&Value::Array(vec![encode(&Celsius(1))])).is_ok());
+}
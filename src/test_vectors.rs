@@ -0,0 +1,81 @@
+//! Values with their expected encodings, for testing other implementations of Nota
+//!
+//! Every vector has the value in [diagnostic notation](crate::diag), and its canonical encoding,
+//! i.e. with the shortest lengths and numbers, and record fields sorted by key.
+//! Parsers should decode the bytes to the value, and canonical encoders should write exactly these bytes.
+//!
+//! [`VECTORS`] is plain data, so it can be exported to any format, e.g. with the bytes in hex:
+//!
+//! ```rust
+//! for v in nota::test_vectors::VECTORS {
+//!     let hex: String = v.bytes.iter().map(|b| format!("{b:02x}")).collect();
+//!     println!("{:?}: {hex} = {}", v.description, v.notation);
+//! }
+//! ```
+use crate::Value;
+
+/// A value and its encoding
+#[derive(Debug, Clone, Copy)]
+pub struct TestVector {
+    pub description: &'static str,
+    /// The value in [diagnostic notation](crate::diag)
+    pub notation: &'static str,
+    /// Canonical Nota encoding of the value
+    pub bytes: &'static [u8],
+}
+
+impl TestVector {
+    /// Parses the [`notation`](Self::notation)
+    #[must_use]
+    pub fn value(&self) -> Value {
+        self.notation.parse().expect("test vectors are valid")
+    }
+}
+
+const fn v(description: &'static str, notation: &'static str, bytes: &'static [u8]) -> TestVector {
+    TestVector { description, notation, bytes }
+}
+
+pub const VECTORS: &[TestVector] = &[
+    v("zero", "0", &[0x80]),
+    v("largest integer without a continuation", "7", &[0x87]),
+    v("smallest integer with a continuation", "8", &[0x90, 0x08]),
+    v("negative one, with the sign bit", "-1", &[0x89]),
+    v("integer with two continuation bytes", "2023", &[0x90, 0x8F, 0x67]),
+    v("largest i128", "170141183460469231731687303715884105727", &[0x91, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]),
+    v("negative largest i128", "-170141183460469231731687303715884105727", &[0x99, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]),
+    v("smallest i128, which has a magnitude one larger than the largest", "-170141183460469231731687303715884105728", &[0x9A, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]),
+    v("false", "false", &[0xC0]),
+    v("true", "true", &[0xC1]),
+    v("null", "null", &[0xC2]),
+    v("empty text", "\"\"", &[0x20]),
+    v("ASCII text", "\"cat\"", &[0x23, b'c', b'a', b't']),
+    v("NUL char", "\"\\0\"", &[0x21, 0x00]),
+    v("text with 16 chars, which needs a length continuation", "\"abcdefghijklmnop\"", &[0x30, 0x10, b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h', b'i', b'j', b'k', b'l', b'm', b'n', b'o', b'p']),
+    v("chars from the BMP take two bytes in kim", "\"☃★♲\"", &[0x23, 0xCC, 0x03, 0xCC, 0x05, 0xCC, 0x72]),
+    v("chars outside the BMP take three bytes in kim", "\"𓂀\"", &[0x21, 0x84, 0xE1, 0x00]),
+    v("empty blob", "x\"\"", &[0x00]),
+    v("blob of 3 bits, padded with zeros", "b\"101\"", &[0x03, 0b1010_0000]),
+    v("blob of whole bytes", "x\"010203\"", &[0x10, 0x18, 0x01, 0x02, 0x03]),
+    v("blob of 11 bits", "b\"01010101110\"", &[0x0B, 0x55, 0b1100_0000]),
+    v("empty array", "[]", &[0x40]),
+    v("nested empty array", "[[]]", &[0x41, 0x40]),
+    v("array of mixed values", "[1, \"a\", null]", &[0x43, 0x81, 0x21, b'a', 0xC2]),
+    v("empty record", "{}", &[0x60]),
+    v("record with keys in byte order", "{\"B\": 2, \"a\": [true]}", &[0x62, 0x21, b'B', 0x82, 0x21, b'a', 0x41, 0xC1]),
+    v("record with an empty key", "{\"\": null}", &[0x61, 0x20, 0xC2]),
+];
+
+#[test]
+fn vectors() {
+    use crate::{testing, SerializeOptions};
+
+    for vector in VECTORS {
+        let value = vector.value();
+        assert_eq!(value.to_string(), vector.notation, "{}", vector.description);
+        let mut bytes = Vec::new();
+        value.serialize_with(&mut bytes, &SerializeOptions { canonical: true, ..Default::default() });
+        assert_eq!(bytes, vector.bytes, "{}", vector.description);
+        assert_eq!(testing::assert_bytes_roundtrip(vector.bytes), value, "{}", vector.description);
+    }
+}
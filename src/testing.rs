@@ -0,0 +1,91 @@
+//! Assertions for tests of code that produces or consumes Nota
+//!
+//! They check the data with every parser in this crate (from a reader, buffered, from a slice, and incremental),
+//! so a custom encoder can be tested against all of them at once.
+//!
+//! ```rust
+//! use nota::{testing, Value};
+//!
+//! let bytes = testing::assert_roundtrip(&Value::Text("☃".into()));
+//! assert_eq!(testing::assert_bytes_roundtrip(&bytes), Value::Text("☃".into()));
+//! ```
+use crate::{Decoder, ParseOptions, SerializeOptions, Value};
+use std::io::BufReader;
+
+/// Serializes the value, and checks that it's parsed back unchanged, in both regular and canonical form.
+///
+/// Returns the serialized bytes. Panics with a description of the first difference.
+#[track_caller]
+pub fn assert_roundtrip(value: &Value) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+    assert_eq!(value.serialized_size(), bytes.len(), "serialized_size() is not the size of the serialized value");
+    assert_eq!(&assert_bytes_roundtrip(&bytes), value, "the parsed value is different from the serialized one");
+
+    let mut canonical = Vec::new();
+    value.serialize_with(&mut canonical, &SerializeOptions { canonical: true, ..SerializeOptions::default() });
+    assert_eq!(canonical.len(), bytes.len(), "the canonical form has a different length");
+    let strict = ParseOptions { strict: true, ..ParseOptions::default() };
+    match Value::parse_slice_with(&mut &canonical[..], &strict) {
+        Ok(parsed) => assert_eq!(&parsed, value, "the canonical form is parsed as a different value"),
+        Err(err) => panic!("the canonical form is not accepted by the strict parser: {err}\n{}", hex(&canonical)),
+    }
+    bytes
+}
+
+/// Parses exactly one value from `bytes`, checks that all parsers agree on it,
+/// and that it's parsed back unchanged after serializing it again.
+///
+/// The bytes don't need to be in the form this crate would write, e.g. records can be in any order.
+/// Returns the parsed value. Panics with a description of the first difference.
+#[track_caller]
+pub fn assert_bytes_roundtrip(bytes: &[u8]) -> Value {
+    let mut rest = bytes;
+    let value = match Value::parse_slice(&mut rest) {
+        Ok(value) => value,
+        Err(err) => panic!("can't parse: {err}\n{}", hex(bytes)),
+    };
+    assert!(rest.is_empty(), "{} trailing bytes after the value\n{}", rest.len(), hex(bytes));
+
+    let from_reader = Value::parse_from(&mut &bytes[..]).expect("parse_from fails");
+    assert_eq!(from_reader, value, "parse_from disagrees with parse_slice");
+    let buffered = Value::parse_buf(&mut BufReader::with_capacity(1, bytes)).expect("parse_buf fails");
+    assert_eq!(buffered, value, "parse_buf disagrees with parse_slice");
+
+    let mut decoder = Decoder::new();
+    for (i, byte) in bytes.iter().enumerate() {
+        match decoder.push(std::slice::from_ref(byte)) {
+            Ok((_, None)) if i + 1 < bytes.len() => {},
+            Ok((_, Some(decoded))) if i + 1 == bytes.len() => assert_eq!(decoded, value, "Decoder disagrees with parse_slice"),
+            Ok((_, decoded)) => panic!("Decoder ended at byte {} of {}: {decoded:?}", i + 1, bytes.len()),
+            Err(err) => panic!("Decoder fails at byte {i}: {err}"),
+        }
+    }
+
+    let mut again = Vec::new();
+    value.serialize_into(&mut again);
+    assert_eq!(Value::parse_slice(&mut &again[..]).ok().as_ref(), Some(&value), "the value changes after serializing it again\n{}", hex(&again));
+    value
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x} ")).collect()
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn accepts_valid() {
+    let value = Value::Record(Box::new([
+        ("a".to_string(), Value::Integer(-1)),
+        ("b".to_string(), Value::Array(vec![Value::Text("x".into()), Value::Null])),
+    ].into_iter().collect()));
+    let bytes = assert_roundtrip(&value);
+    assert_eq!(bytes.len(), 10);
+
+    // the fields in the other order
+    let swapped = [0x62, 0x21, b'b', 0x42, 0x21, b'x', 0xC2, 0x21, b'a', 0x89];
+    assert_eq!(assert_bytes_roundtrip(&swapped), value);
+
+    assert!(std::panic::catch_unwind(|| assert_bytes_roundtrip(&[0x89, 0xC2])).is_err());
+    assert!(std::panic::catch_unwind(|| assert_bytes_roundtrip(&[0x42, 0xC2])).is_err());
+}
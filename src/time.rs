@@ -0,0 +1,116 @@
+//! Datetimes from the [`time`] crate
+//!
+//! [`OffsetDateTime`] is stored the same way as [`chrono`'s datetimes](crate::chrono): as an [`Integer`](Value::Integer)
+//! of nanoseconds since 1970-01-01T00:00:00Z, which loses the offset, or tagged as `{"$datetime": "<RFC 3339 text>"}`,
+//! which keeps it. Conversions from values accept the integers, RFC 3339 text, and the tagged form.
+//! For serde, use `#[serde(with = "nota::time::epoch_nanos")]` to store them as integers.
+//!
+//! ```rust
+//! use nota::Value;
+//! use time::OffsetDateTime;
+//!
+//! let time = OffsetDateTime::from_unix_timestamp(1_700_000_000)?;
+//! let value = Value::from(time);
+//! assert_eq!(value, Value::Integer(1_700_000_000_000_000_000));
+//! assert_eq!(OffsetDateTime::try_from(&value)?, time);
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+use crate::{Error, Value};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+const EXPECTED: Error = Error::UnexpectedValue { expected: "a datetime" };
+
+impl From<OffsetDateTime> for Value {
+    fn from(time: OffsetDateTime) -> Self {
+        Self::Integer(time.unix_timestamp_nanos())
+    }
+}
+
+impl TryFrom<&Value> for OffsetDateTime {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        match value {
+            &Value::Integer(nanos) => Self::from_unix_timestamp_nanos(nanos).map_err(|_| EXPECTED),
+            Value::Text(text) => Self::parse(text, &Rfc3339).map_err(|_| EXPECTED),
+            #[cfg(feature = "records")]
+            value => match value.as_tagged() {
+                Some(("datetime", inner)) => Self::try_from(inner),
+                _ => Err(EXPECTED),
+            },
+            #[cfg(not(feature = "records"))]
+            _ => Err(EXPECTED),
+        }
+    }
+}
+
+impl TryFrom<Value> for OffsetDateTime {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Self::try_from(&value)
+    }
+}
+
+#[cfg(feature = "records")]
+impl crate::tagged::Extension for OffsetDateTime {
+    const TAG: &'static str = "datetime";
+
+    /// # Panics
+    ///
+    /// If the year is outside of 0–9999, which RFC 3339 can't represent
+    fn to_value(&self) -> Value {
+        Value::Text(self.format(&Rfc3339).expect("the year is within RFC 3339's range"))
+    }
+
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        Self::try_from(value)
+    }
+}
+
+/// Serde `with` module that writes `OffsetDateTime` as integer nanoseconds since the epoch
+#[cfg(feature = "serde")]
+pub mod epoch_nanos {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(time: &OffsetDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i128(time.unix_timestamp_nanos())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OffsetDateTime, D::Error> {
+        OffsetDateTime::from_unix_timestamp_nanos(i128::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[test]
+fn conversions() {
+    let time = OffsetDateTime::parse("1969-12-31T23:59:59.5-02:00", &Rfc3339).unwrap();
+    let value = Value::from(time);
+    assert_eq!(value, Value::Integer(7_199_500_000_000));
+    assert_eq!(OffsetDateTime::try_from(&value).unwrap(), time);
+    assert_eq!(OffsetDateTime::try_from(Value::Integer(i128::MAX)), Err(EXPECTED));
+    assert_eq!(OffsetDateTime::try_from(Value::Text("yesterday".into())), Err(EXPECTED));
+
+    #[cfg(feature = "records")]
+    {
+        use crate::tagged::{decode, encode};
+        let tagged = encode(&time);
+        assert_eq!(tagged.to_string(), r#"{"$datetime": "1969-12-31T23:59:59.5-02:00"}"#);
+        assert_eq!(decode::<OffsetDateTime>(&tagged).unwrap().offset(), time.offset());
+    }
+
+    #[cfg(feature = "serde")]
+    {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Event {
+            #[serde(with = "epoch_nanos")]
+            at: OffsetDateTime,
+        }
+        let event = Event { at: time };
+        let value = crate::serde::to_value(&event).unwrap();
+        assert_eq!(value.to_string(), r#"{"at": 7199500000000}"#);
+        assert_eq!(crate::serde::from_value::<Event>(value).unwrap(), event);
+    }
+}
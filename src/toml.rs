@@ -0,0 +1,114 @@
+//! Conversions to and from [`toml::Value`] and [`toml::Table`]
+//!
+//! TOML floats, including `inf` and `nan`, have no equivalent in Nota. They're an error, unless [`TomlOptions::floats`]
+//! allows converting them to text. Nota's nulls, blobs, and integers outside of the 64-bit range can't be converted to TOML.
+use crate::{Error, Value};
+use std::collections::HashMap;
+
+/// Record key used by [`TomlDatetimes::Tagged`]
+pub const DATETIME_KEY: &str = "$datetime";
+
+/// Settings for converting TOML datetimes and floats
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct TomlOptions {
+    pub datetimes: TomlDatetimes,
+    pub floats: TomlFloats,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TomlDatetimes {
+    /// RFC 3339 text. Converting it back gives a TOML string, not a datetime.
+    #[default]
+    Text,
+    /// Record with a single [`DATETIME_KEY`] field containing RFC 3339 text. It converts back to a TOML datetime.
+    Tagged,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TomlFloats {
+    /// Fail the conversion
+    #[default]
+    Error,
+    /// Text in TOML's float syntax, e.g. `2.5` or `-inf`. Converting it back gives a TOML string, not a float.
+    Text,
+}
+
+impl Value {
+    pub fn from_toml(toml: toml::Value, options: &TomlOptions) -> Result<Self, Error> {
+        Ok(match toml {
+            toml::Value::String(s) => Self::Text(s),
+            toml::Value::Integer(i) => Self::Integer(i.into()),
+            toml::Value::Float(f) => match options.floats {
+                TomlFloats::Error => return Err(Error::Unrepresentable("float")),
+                TomlFloats::Text => Self::Text(toml::Value::Float(f).to_string()),
+            },
+            toml::Value::Boolean(b) => Self::Bool(b),
+            toml::Value::Datetime(d) => match options.datetimes {
+                TomlDatetimes::Text => Self::Text(d.to_string()),
+                TomlDatetimes::Tagged => Self::Record(Box::new([(DATETIME_KEY.into(), Self::Text(d.to_string()))].into_iter().collect())),
+            },
+            toml::Value::Array(a) => Self::Array(a.into_iter().map(|v| Self::from_toml(v, options)).collect::<Result<_, _>>()?),
+            toml::Value::Table(t) => Self::from_toml_table(t, options)?,
+        })
+    }
+
+    pub fn from_toml_table(table: toml::Table, options: &TomlOptions) -> Result<Self, Error> {
+        Ok(Self::Record(Box::new(table.into_iter().map(|(k, v)| Ok((k, Self::from_toml(v, options)?))).collect::<Result<HashMap<_, _>, Error>>()?)))
+    }
+
+    pub fn to_toml(&self) -> Result<toml::Value, Error> {
+        Ok(match self {
+            Self::Text(s) => toml::Value::String(s.clone()),
+            Self::Integer(i) => toml::Value::Integer(i64::try_from(*i).map_err(|_| Error::Unrepresentable("integer larger than 64 bits"))?),
+            Self::Bool(b) => toml::Value::Boolean(*b),
+            Self::Array(a) => toml::Value::Array(a.iter().map(Self::to_toml).collect::<Result<_, _>>()?),
+            Self::Record(r) => match r.get(DATETIME_KEY) {
+                Some(Self::Text(d)) if r.len() == 1 => toml::Value::Datetime(d.parse().map_err(|_| Error::Unrepresentable("invalid datetime"))?),
+                _ => toml::Value::Table(self.to_toml_table()?),
+            },
+            Self::Blob(_) => return Err(Error::Unrepresentable("blob")),
+            Self::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float")),
+            Self::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag")),
+            Self::Null => return Err(Error::Unrepresentable("null")),
+        })
+    }
+
+    /// Fails if this value isn't a record
+    pub fn to_toml_table(&self) -> Result<toml::Table, Error> {
+        match self {
+            Self::Record(r) => r.iter().map(|(k, v)| Ok((k.clone(), v.to_toml()?))).collect(),
+            _ => Err(Error::Unrepresentable("top-level value that isn't a record")),
+        }
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let mut table: toml::Table = r#"
+        name = "nota"
+        version = 3
+        when = 1979-05-27T07:32:00Z
+        [deps]
+        list = [1, 2, 3]
+        float = 2.5
+        limit = -inf
+    "#.parse().unwrap();
+    assert_eq!(Value::from_toml_table(table.clone(), &TomlOptions::default()), Err(Error::Unrepresentable("float")));
+
+    let tagged = Value::from_toml_table(table.clone(), &TomlOptions { datetimes: TomlDatetimes::Tagged, floats: TomlFloats::Text }).unwrap();
+    let Value::Record(r) = &tagged else { panic!() };
+    assert_eq!(r["when"], Value::Record(Box::new([(DATETIME_KEY.into(), Value::Text("1979-05-27T07:32:00Z".into()))].into_iter().collect())));
+
+    let mut expected = table.clone();
+    expected["deps"]["float"] = "2.5".into();
+    expected["deps"]["limit"] = "-inf".into();
+    assert_eq!(tagged.to_toml_table().unwrap(), expected);
+
+    table["deps"].as_table_mut().unwrap().retain(|k, _| k == "list");
+    let text = Value::from_toml_table(table, &TomlOptions::default()).unwrap();
+    assert_eq!(text.to_toml_table().unwrap()["when"], toml::Value::String("1979-05-27T07:32:00Z".into()));
+    assert!(Value::Null.to_toml().is_err());
+}
@@ -0,0 +1,185 @@
+//! Changing values in place, and transforming whole trees
+use crate::path::{Path, Segment};
+use crate::Value;
+
+impl Default for Value {
+    /// `Null`
+    fn default() -> Self {
+        Self::Null
+    }
+}
+
+impl Value {
+    /// Moves the value out, leaving `Null` in its place.
+    ///
+    /// ```rust
+    /// use nota::Value;
+    ///
+    /// let mut doc = Value::Array(vec![Value::Text("long text".into())]);
+    /// if let Value::Array(items) = &mut doc {
+    ///     let text = items[0].take();
+    ///     assert_eq!(text, Value::Text("long text".into()));
+    /// }
+    /// assert_eq!(doc, Value::Array(vec![Value::Null]));
+    /// ```
+    #[must_use = "use `*value = Value::Null` to just clear it"]
+    pub fn take(&mut self) -> Self {
+        std::mem::take(self)
+    }
+
+    /// Puts the new value in place of this one, and returns the old one
+    pub fn replace(&mut self, value: Self) -> Self {
+        std::mem::replace(self, value)
+    }
+
+    /// Transforms every value in the tree, from the bottom up: elements and fields are mapped before the array or record containing them.
+    ///
+    /// ```rust
+    /// use nota::Value;
+    ///
+    /// // clamps all integers to 0..=100
+    /// let doc: Value = "[[-5, 50], 1000]".parse().unwrap();
+    /// let doc = doc.map_values(|v| match v {
+    ///     Value::Integer(n) => Value::Integer(n.clamp(0, 100)),
+    ///     other => other,
+    /// });
+    /// assert_eq!(doc.to_string(), "[[0, 50], 100]");
+    /// ```
+    #[must_use]
+    pub fn map_values(self, mut map: impl FnMut(Self) -> Self) -> Self {
+        self.map_values_dyn(&mut map)
+    }
+
+    fn map_values_dyn(self, map: &mut dyn FnMut(Self) -> Self) -> Self {
+        let mapped = match self {
+            Self::Array(items) => Self::Array(items.into_iter().map(|item| item.map_values_dyn(map)).collect()),
+            #[cfg(feature = "records")]
+            Self::Record(mut fields) => {
+                for value in fields.values_mut() {
+                    *value = value.take().map_values_dyn(map);
+                }
+                Self::Record(fields)
+            },
+            other => other,
+        };
+        map(mapped)
+    }
+
+    /// Transforms every text in the tree, but not record keys
+    #[must_use]
+    pub fn map_strings(self, mut map: impl FnMut(String) -> String) -> Self {
+        self.map_values(|value| match value {
+            Self::Text(text) => Self::Text(map(text)),
+            other => other,
+        })
+    }
+
+    /// Keeps only the array elements for which `keep` returns `true`. Does nothing if this isn't an array.
+    pub fn retain_items(&mut self, mut keep: impl FnMut(usize, &mut Self) -> bool) {
+        if let Self::Array(items) = self {
+            let mut index = 0;
+            items.retain_mut(|item| {
+                index += 1;
+                keep(index - 1, item)
+            });
+        }
+    }
+
+    /// Keeps only the record fields for which `keep` returns `true`. Does nothing if this isn't a record.
+    #[cfg(feature = "records")]
+    pub fn retain_fields(&mut self, mut keep: impl FnMut(&str, &mut Self) -> bool) {
+        if let Self::Record(fields) = self {
+            fields.retain(|key, value| keep(key, value));
+        }
+    }
+
+    /// Removes elements and fields anywhere in the tree, for which `keep` returns `false`.
+    ///
+    /// It goes from the bottom up, so arrays and records are checked after their contents have been pruned.
+    /// Paths are the locations before pruning. The top-level value itself isn't checked.
+    ///
+    /// ```rust
+    /// use nota::Value;
+    ///
+    /// let mut doc: Value = "[1, null, [null], [2, [null]]]".parse().unwrap();
+    /// doc.prune(|_path, value| !matches!(value, Value::Null) && *value != Value::Array(vec![]));
+    /// assert_eq!(doc.to_string(), "[1, [2]]");
+    /// ```
+    pub fn prune(&mut self, mut keep: impl FnMut(&Path, &Self) -> bool) {
+        self.prune_at(&mut Path::default(), &mut keep);
+    }
+
+    fn prune_at(&mut self, path: &mut Path, keep: &mut dyn FnMut(&Path, &Self) -> bool) {
+        let mut keep_child = |path: &mut Path, segment, child: &mut Self| {
+            path.push(segment);
+            child.prune_at(path, keep);
+            let kept = keep(path, child);
+            path.pop();
+            kept
+        };
+        match self {
+            Self::Array(_) => self.retain_items(|i, item| keep_child(path, Segment::Index(i), item)),
+            #[cfg(feature = "records")]
+            Self::Record(_) => self.retain_fields(|key, field| keep_child(path, Segment::Key(key.into()), field)),
+            _ => {},
+        }
+    }
+
+    /// Renames keys of all records in the tree. If two keys of a record get the same name, only one of their fields is kept.
+    #[cfg(feature = "records")]
+    #[must_use]
+    pub fn map_keys(self, mut map: impl FnMut(String) -> String) -> Self {
+        self.map_values(|value| match value {
+            Self::Record(fields) => Self::Record(Box::new(fields.into_iter().map(|(key, value)| (map(key), value)).collect())),
+            other => other,
+        })
+    }
+}
+
+#[test]
+fn take_and_replace() {
+    let mut value = Value::Integer(1);
+    assert_eq!(value.replace(Value::Bool(true)), Value::Integer(1));
+    assert_eq!(value.take(), Value::Bool(true));
+    assert_eq!(value, Value::default());
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn map() {
+    let doc: Value = r#"[" a ", {" b ": [" c "]}, 1]"#.parse().unwrap();
+    let mut seen = Vec::new();
+    let doc = doc.map_values(|v| {
+        seen.push(v.to_string());
+        v
+    });
+    assert_eq!(seen, [r#"" a ""#, r#"" c ""#, r#"[" c "]"#, r#"{" b ": [" c "]}"#, "1", r#"[" a ", {" b ": [" c "]}, 1]"#]);
+
+    let trimmed = doc.clone().map_strings(|s| s.trim().to_string());
+    assert_eq!(trimmed.to_string(), r#"["a", {" b ": ["c"]}, 1]"#);
+    assert_eq!(doc.map_keys(|k| k.trim().to_uppercase()).to_string(), r#"[" a ", {"B": [" c "]}, 1]"#);
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn retain_and_prune() {
+    let mut doc: Value = "[0, 1, 2, 3]".parse().unwrap();
+    doc.retain_items(|i, v| i != 0 && *v != Value::Integer(2));
+    assert_eq!(doc.to_string(), "[1, 3]");
+
+    let mut doc: Value = r#"{"a": 1, "b": 2}"#.parse().unwrap();
+    doc.retain_fields(|k, v| {
+        *v = Value::Integer(10);
+        k == "b"
+    });
+    assert_eq!(doc.to_string(), r#"{"b": 10}"#);
+
+    let mut doc: Value = r#"[[null], {"x": [null, 1, null]}, null]"#.parse().unwrap();
+    let mut seen = Vec::new();
+    doc.prune(|path, v| {
+        seen.push(path.to_string());
+        *v != Value::Null && *v != Value::Array(vec![])
+    });
+    assert_eq!(doc.to_string(), r#"[{"x": [1]}]"#);
+    assert_eq!(seen, ["[0][0]", "[0]", "[1].x[0]", "[1].x[1]", "[1].x[2]", "[1].x", "[1]", "[2]"]);
+}
@@ -0,0 +1,167 @@
+//! [`ufmt::uDisplay`] for values and [pull parser](crate::pull) events, for printing with less code than `core::fmt` needs
+//!
+//! The crate itself requires `std`, so this doesn't make it usable on `no_std` targets.
+//!
+//! The output is the same as the compact [diagnostic notation](crate::diag). Pull events print their scalars the same way,
+//! and arrays and records as their header, e.g. `array(3)`.
+use crate::pull::{Bits, Event, KimStr, PullError};
+use crate::{BufferTooSmall, SliceWriteError, Value};
+use ufmt::{uDisplay, uWrite, uwrite, Formatter};
+
+impl uDisplay for Value {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            #[cfg(feature = "blobs")]
+            Self::Blob(bits) => write_bits(f, bits.as_raw_slice(), bits.len()),
+            Self::Text(text) => write_quoted(f, text.chars()),
+            Self::Array(items) => {
+                f.write_char('[')?;
+                for (n, item) in items.iter().enumerate() {
+                    if n > 0 {
+                        f.write_str(", ")?;
+                    }
+                    item.fmt(f)?;
+                }
+                f.write_char(']')
+            },
+            #[cfg(feature = "records")]
+            Self::Record(fields) => {
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_unstable_by_key(|&(k, _)| k);
+                f.write_char('{')?;
+                for (n, (key, value)) in fields.into_iter().enumerate() {
+                    if n > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_quoted(f, key.chars())?;
+                    f.write_str(": ")?;
+                    value.fmt(f)?;
+                }
+                f.write_char('}')
+            },
+            Self::Integer(n) => uwrite!(f, "{}", n),
+            #[cfg(feature = "dec64")]
+            #[allow(deprecated)]
+            Self::DecimalFloat(d) => uwrite!(f, "{}e{}", d.coefficient, d.exponent),
+            Self::Bool(true) => f.write_str("true"),
+            Self::Bool(false) => f.write_str("false"),
+            Self::Null => f.write_str("null"),
+            Self::Unknown { raw_bytes, .. } => {
+                f.write_str("unknown(")?;
+                write_bits(f, raw_bytes, raw_bytes.len() * 8)?;
+                f.write_char(')')
+            },
+        }
+    }
+}
+
+/// `x"hex"` for whole bytes, `b"bits"` otherwise
+fn write_bits<W: uWrite + ?Sized>(f: &mut Formatter<'_, W>, bytes: &[u8], bit_len: usize) -> Result<(), W::Error> {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    if bit_len.is_multiple_of(8) {
+        f.write_str("x\"")?;
+        for &byte in &bytes[..bit_len / 8] {
+            f.write_char(HEX[usize::from(byte >> 4)].into())?;
+            f.write_char(HEX[usize::from(byte & 15)].into())?;
+        }
+    } else {
+        f.write_str("b\"")?;
+        for i in 0..bit_len {
+            f.write_char(if bytes[i / 8] & (0x80 >> (i % 8)) != 0 { '1' } else { '0' })?;
+        }
+    }
+    f.write_char('"')
+}
+
+/// Same escapes as `{:?}` of a `str`
+fn write_quoted<W: uWrite + ?Sized>(f: &mut Formatter<'_, W>, chars: impl Iterator<Item = char>) -> Result<(), W::Error> {
+    f.write_char('"')?;
+    for c in chars {
+        if c == '\'' {
+            f.write_char(c)?;
+        } else {
+            for e in c.escape_debug() {
+                f.write_char(e)?;
+            }
+        }
+    }
+    f.write_char('"')
+}
+
+impl uDisplay for KimStr<'_> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        write_quoted(f, self.chars())
+    }
+}
+
+impl uDisplay for Bits<'_> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        write_bits(f, self.bytes, self.bit_len)
+    }
+}
+
+impl uDisplay for Event<'_> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            Self::Blob(bits) => bits.fmt(f),
+            Self::Text(text) => text.fmt(f),
+            Self::Array(len) => uwrite!(f, "array({})", len),
+            Self::Record(len) => uwrite!(f, "record({})", len),
+            Self::Integer(n) => uwrite!(f, "{}", n),
+            Self::Bool(true) => f.write_str("true"),
+            Self::Bool(false) => f.write_str("false"),
+            Self::Null => f.write_str("null"),
+        }
+    }
+}
+
+impl uDisplay for PullError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(self.reason())
+    }
+}
+
+impl uDisplay for BufferTooSmall {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str("buffer is too small for the serialized value")
+    }
+}
+
+impl uDisplay for SliceWriteError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            Self::BufferTooSmall => BufferTooSmall.fmt(f),
+            Self::Unrepresentable(what) => {
+                f.write_str(what)?;
+                f.write_str(" can't be serialized")
+            },
+        }
+    }
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn same_as_diag() {
+    struct Out(String);
+    impl uWrite for Out {
+        type Error = core::convert::Infallible;
+        fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+            self.0.push_str(s);
+            Ok(())
+        }
+    }
+
+    let value: Value = r#"{"list": [-170141183460469231731687303715884105727, null, "it's \"☃\"\n"], "bits": b"101", "hex": x"00ff", "ok": true}"#.parse().unwrap();
+    let mut out = Out(String::new());
+    uwrite!(out, "{}", value).unwrap();
+    assert_eq!(out.0, value.to_string());
+
+    let mut buf = [0; 64];
+    let len = value.serialize_to_slice(&mut buf).unwrap();
+    let mut out = Out(String::new());
+    for event in crate::pull::PullParser::<'_, 4>::new(&buf[..len]) {
+        uwrite!(out, "{} ", event.unwrap()).unwrap();
+    }
+    assert!(out.0.starts_with("record(4) \""), "{}", out.0);
+    assert!(out.0.contains(" b\"101\" ") && out.0.contains(" x\"00ff\" ") && out.0.contains(" array(3) -170141183460469231731687303715884105727 null \"it's \\\"☃\\\"\\n\" "));
+}
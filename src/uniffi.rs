@@ -0,0 +1,115 @@
+//! UniFFI bindings for Kotlin and Swift: `encode` and `decode` functions, and [`NotaNode`] for values
+//!
+//! Generate the bindings from the built library with `uniffi-bindgen generate --library`.
+//!
+//! Integers are limited to 64 bits, and blobs to whole bytes.
+//! `decode` parses with [`ParseOptions::untrusted`] limits.
+use crate::{Error, ParseOptions, Value};
+use bitvec::vec::BitVec;
+use std::collections::HashMap;
+use std::{fmt, io};
+
+/// A [`Value`] as seen by Kotlin and Swift. Records are dictionaries.
+#[derive(Debug, Clone, PartialEq, ::uniffi::Enum)]
+pub enum NotaNode {
+    Null,
+    Bool { value: bool },
+    Integer { value: i64 },
+    Text { value: String },
+    Blob { bytes: Vec<u8> },
+    Array { items: Vec<NotaNode> },
+    Record { fields: HashMap<String, NotaNode> },
+}
+
+#[derive(Debug, ::uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum NotaError {
+    /// The input is not valid Nota, or uses an unsupported feature
+    Decode(io::Error),
+    /// The value has no equivalent on the other side
+    Unrepresentable(Error),
+}
+
+impl fmt::Display for NotaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(err) => err.fmt(f),
+            Self::Unrepresentable(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for NotaError {}
+
+impl From<Error> for NotaError {
+    fn from(err: Error) -> Self {
+        Self::Unrepresentable(err)
+    }
+}
+
+impl From<NotaNode> for Value {
+    fn from(node: NotaNode) -> Self {
+        match node {
+            NotaNode::Null => Self::Null,
+            NotaNode::Bool { value } => Self::Bool(value),
+            NotaNode::Integer { value } => Self::Integer(value.into()),
+            NotaNode::Text { value } => Self::Text(value),
+            NotaNode::Blob { bytes } => Self::Blob(BitVec::from_vec(bytes)),
+            NotaNode::Array { items } => Self::Array(items.into_iter().map(Self::from).collect()),
+            NotaNode::Record { fields } => Self::Record(Box::new(fields.into_iter().map(|(k, v)| (k, v.into())).collect())),
+        }
+    }
+}
+
+impl TryFrom<Value> for NotaNode {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Ok(match value {
+            Value::Null => Self::Null,
+            Value::Bool(value) => Self::Bool { value },
+            Value::Integer(i) => Self::Integer { value: i64::try_from(i).map_err(|_| Error::Unrepresentable("integer larger than 64 bits"))? },
+            Value::Text(value) => Self::Text { value },
+            Value::Blob(bits) if bits.len() % 8 == 0 => Self::Blob { bytes: bits.into_vec() },
+            Value::Blob(_) => return Err(Error::Unrepresentable("blob with a partial byte")),
+            Value::Array(a) => Self::Array { items: a.into_iter().map(Self::try_from).collect::<Result<_, _>>()? },
+            Value::Record(r) => Self::Record { fields: r.into_iter().map(|(k, v)| Ok((k, Self::try_from(v)?))).collect::<Result<_, Error>>()? },
+            Value::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float")),
+            Value::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag")),
+        })
+    }
+}
+
+/// Serializes the value to Nota bytes
+#[::uniffi::export]
+#[must_use]
+pub fn encode(node: NotaNode) -> Vec<u8> {
+    let mut out = Vec::new();
+    Value::from(node).serialize_into(&mut out);
+    out
+}
+
+/// Parses Nota bytes
+#[::uniffi::export]
+pub fn decode(bytes: Vec<u8>) -> Result<NotaNode, NotaError> {
+    let value = Value::parse_slice_with(&mut &bytes[..], &ParseOptions::untrusted()).map_err(NotaError::Decode)?;
+    Ok(value.try_into()?)
+}
+
+#[test]
+fn roundtrip() {
+    let node = NotaNode::Record { fields: [
+        ("a".to_string(), NotaNode::Array { items: vec![NotaNode::Integer { value: -5 }, NotaNode::Null] }),
+        ("b".to_string(), NotaNode::Blob { bytes: vec![1, 2, 3] }),
+        ("c".to_string(), NotaNode::Text { value: "x".into() }),
+    ].into_iter().collect() };
+    assert_eq!(decode(encode(node.clone())).unwrap(), node);
+
+    let mut big = Vec::new();
+    Value::Integer(1 << 64).serialize_into(&mut big);
+    assert!(matches!(decode(big), Err(NotaError::Unrepresentable(_))));
+    assert!(matches!(decode(vec![]), Err(NotaError::Decode(_))));
+    let mut nested = vec![0x41; 100_000];
+    nested.push(0xC2);
+    assert!(matches!(decode(nested), Err(NotaError::Decode(_))));
+}
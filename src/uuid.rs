@@ -0,0 +1,132 @@
+//! UUIDs from the [`uuid`] crate
+//!
+//! A [`Uuid`] is stored as a 128-bit [`Blob`](Value::Blob) of its bytes in big-endian (RFC 9562) order.
+//! The [tagged](crate::tagged) form, `{"$uuid": "67e55044-10b1-426f-9742-bb680e5fe0c8"}`, has the canonical hyphenated text,
+//! so that UUIDs are readable in the diagnostic notation.
+//!
+//! Conversions from values accept the blobs, UUID text, and the tagged form.
+//! For serde, use `#[serde(with = "nota::uuid::blob")]` to store them as blobs, since `Uuid`'s own serde implementation writes text.
+//!
+//! ```rust
+//! use nota::Value;
+//! use uuid::Uuid;
+//!
+//! let id = Uuid::from_u128(0x67e55044_10b1_426f_9742_bb680e5fe0c8);
+//! let value = Value::from(id);
+//! assert_eq!(value.to_string(), r#"x"67e5504410b1426f9742bb680e5fe0c8""#);
+//! assert_eq!(Uuid::try_from(&value)?, id);
+//! # Ok::<_, nota::Error>(())
+//! ```
+use crate::{Error, Value};
+use bitvec::prelude::*;
+use uuid::Uuid;
+
+const EXPECTED: Error = Error::UnexpectedValue { expected: "a UUID" };
+
+impl From<Uuid> for Value {
+    fn from(id: Uuid) -> Self {
+        Self::Blob(BitVec::from_slice(id.as_bytes()))
+    }
+}
+
+impl TryFrom<&Value> for Uuid {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Error> {
+        match value {
+            Value::Blob(bits) if bits.len() == 128 => Uuid::from_slice(bits.as_raw_slice()).map_err(|_| EXPECTED),
+            Value::Text(text) => Uuid::try_parse(text).map_err(|_| EXPECTED),
+            #[cfg(feature = "records")]
+            value => match value.as_tagged() {
+                Some(("uuid", inner)) => Self::try_from(inner),
+                _ => Err(EXPECTED),
+            },
+            #[cfg(not(feature = "records"))]
+            _ => Err(EXPECTED),
+        }
+    }
+}
+
+impl TryFrom<Value> for Uuid {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Self::try_from(&value)
+    }
+}
+
+#[cfg(feature = "records")]
+impl crate::tagged::Extension for Uuid {
+    const TAG: &'static str = "uuid";
+
+    fn to_value(&self) -> Value {
+        Value::Text(self.hyphenated().to_string())
+    }
+
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        Self::try_from(value)
+    }
+}
+
+/// Serde `with` module that writes `Uuid` as a 128-bit blob
+#[cfg(feature = "serde")]
+pub mod blob {
+    use serde::de::{Error, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+    use uuid::Uuid;
+
+    pub fn serialize<S: Serializer>(id: &Uuid, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(id.as_bytes())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Uuid, D::Error> {
+        deserializer.deserialize_bytes(UuidVisitor)
+    }
+
+    struct UuidVisitor;
+
+    impl Visitor<'_> for UuidVisitor {
+        type Value = Uuid;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("16 bytes of a UUID")
+        }
+
+        fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<Uuid, E> {
+            Uuid::from_slice(bytes).map_err(E::custom)
+        }
+    }
+}
+
+#[test]
+fn conversions() {
+    let id = Uuid::from_u128(0x67e55044_10b1_426f_9742_bb680e5fe0c8);
+    let value = Value::from(id);
+    assert_eq!(Uuid::try_from(value).unwrap(), id);
+    assert_eq!(Uuid::try_from(Value::Text("67E55044-10B1-426F-9742-BB680E5FE0C8".into())).unwrap(), id);
+    assert_eq!(Uuid::try_from(Value::Blob(BitVec::from_slice(&[0; 15]))), Err(EXPECTED));
+    assert_eq!(Uuid::try_from(Value::Integer(1)), Err(EXPECTED));
+
+    #[cfg(feature = "records")]
+    {
+        use crate::tagged::{decode, encode};
+        let tagged = encode(&id);
+        assert_eq!(tagged.to_string(), r#"{"$uuid": "67e55044-10b1-426f-9742-bb680e5fe0c8"}"#);
+        assert_eq!(decode::<Uuid>(&tagged).unwrap(), id);
+        assert_eq!(Uuid::try_from(&tagged).unwrap(), id);
+    }
+
+    #[cfg(feature = "serde")]
+    {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct User {
+            #[serde(with = "blob")]
+            id: Uuid,
+        }
+        let user = User { id };
+        let value = crate::serde::to_value(&user).unwrap();
+        assert_eq!(value.to_string(), r#"{"id": x"67e5504410b1426f9742bb680e5fe0c8"}"#);
+        assert_eq!(crate::serde::from_value::<User>(value).unwrap(), user);
+    }
+}
@@ -0,0 +1,89 @@
+//! Checking documents without keeping them, with the location of the first error
+use crate::parser::{Parser, Unbuffered};
+use crate::path::{Path, Segment};
+use crate::{ParseOptions, Stats};
+use std::fmt;
+use std::io::{self, Read};
+
+/// Where and why [`validate`] has failed
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Invalid {
+    /// Byte offset of the start of the innermost value that has failed to parse
+    pub offset: u64,
+    /// [Path](Path) to that value, like `users[3].name`. Empty for the top-level value.
+    pub path: String,
+    pub error: io::Error,
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "at byte {}: {}", self.offset, self.error)
+        } else {
+            write!(f, "at byte {} ({}): {}", self.offset, self.path, self.error)
+        }
+    }
+}
+
+impl std::error::Error for Invalid {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Parses exactly one value, enforcing the limits in `options`, and checks there's no data after it
+pub fn validate<R: Read>(reader: &mut R, options: &ParseOptions) -> Result<Stats, Invalid> {
+    let mut parser = Parser::new(Unbuffered(&mut *reader), options);
+    parser.path = Some(Vec::new());
+    let res = parser.parse_with_stats_mut();
+    let (offset, path) = (parser.value_offset, parser.path.take().unwrap_or_default());
+    let end = parser.offset();
+    drop(parser);
+    let (_, stats) = res.map_err(|error| Invalid { offset, path: format_path(path), error })?;
+
+    let mut extra = 0;
+    match reader.read(std::slice::from_mut(&mut extra)) {
+        Ok(0) => Ok(stats),
+        Ok(_) => Err(Invalid { offset: end, path: String::new(), error: io::Error::new(io::ErrorKind::InvalidData, "trailing bytes after the value") }),
+        Err(error) => Err(Invalid { offset: end, path: String::new(), error }),
+    }
+}
+
+fn format_path(path: Vec<Segment>) -> String {
+    if path.is_empty() { String::new() } else { Path::from(path).to_string() }
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn locations() {
+    use crate::{Error, Value};
+
+    let value = Value::Record(Box::new([("users".to_string(), Value::Array(vec![
+        Value::Null,
+        Value::Record(Box::new([("first name".to_string(), Value::Text("x".repeat(20)))].into_iter().collect())),
+    ]))].into_iter().collect()));
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes);
+
+    let stats = validate(&mut &bytes[..], &ParseOptions::default()).unwrap();
+    assert_eq!(stats.max_depth, 4);
+
+    let options = ParseOptions { max_len: Some(10), ..Default::default() };
+    let err = validate(&mut &bytes[..], &options).unwrap_err();
+    assert_eq!(err.path, r#"users[1]["first name"]"#);
+    assert_eq!(err.offset, bytes.len() as u64 - 22);
+    assert_eq!(Error::from_io(&err.error), Some(&Error::TooLong { len: 20, max: 10 }));
+
+    let options = ParseOptions { max_depth: Some(2), ..Default::default() };
+    let err = validate(&mut &bytes[..], &options).unwrap_err();
+    assert_eq!(err.path, "users[0]");
+    assert_eq!(Error::from_io(&err.error), Some(&Error::TooDeep { max: 2 }));
+
+    let err = validate(&mut &bytes[..bytes.len() - 1], &ParseOptions::default()).unwrap_err();
+    assert_eq!(err.error.kind(), io::ErrorKind::UnexpectedEof);
+
+    bytes.push(0xC0);
+    let err = validate(&mut &bytes[..], &ParseOptions::default()).unwrap_err();
+    assert_eq!(err.offset, bytes.len() as u64 - 1);
+}
@@ -0,0 +1,90 @@
+use crate::Error;
+
+/// Appends the 7-bit group of `byte` to a length, unless the length would no longer fit in `usize`
+#[inline]
+pub(crate) fn push_len(len: usize, byte: u8) -> Result<usize, Error> {
+    if len.leading_zeros() < 7 {
+        return Err(Error::LengthTooLarge);
+    }
+    Ok(len << 7 | usize::from(byte & 0x7F))
+}
+
+/// Appends the 7-bit group of `byte` to the magnitude of an integer, unless it would no longer fit in `u128`
+#[inline]
+pub(crate) fn push_int(val: u128, byte: u8) -> Result<u128, Error> {
+    if val.leading_zeros() < 7 {
+        return Err(Error::IntegerTooLarge);
+    }
+    Ok(val << 7 | u128::from(byte & 0x7F))
+}
+
+/// Applies the sign to the magnitude of an integer, unless the result doesn't fit in `i128`
+#[inline]
+pub(crate) fn signed(negative: bool, magnitude: u128) -> Result<i128, Error> {
+    if negative { 0i128.checked_sub_unsigned(magnitude) } else { i128::try_from(magnitude).ok() }.ok_or(Error::IntegerTooLarge)
+}
+
+/// Appends the groups decoded by [`decode8`] to a length, unless the length would no longer fit in `usize`
+#[inline]
+pub(crate) fn join_len(len: usize, groups: u64, bytes: usize) -> Result<usize, Error> {
+    // 4 bits of the preamble and 8 groups fit in u64
+    usize::try_from((len as u64) << (7 * bytes) | groups).map_err(|_| Error::LengthTooLarge)
+}
+
+/// Decodes up to 8 bytes of 7-bit groups at once. The number continues while the top bit of a byte is set.
+///
+/// Returns the value of the groups and how many bytes they took,
+/// or `None` if `data` is shorter than 8 bytes or the number doesn't end within them.
+#[inline]
+pub(crate) fn decode8(data: &[u8]) -> Option<(u64, usize)> {
+    let word = u64::from_be_bytes(*data.first_chunk::<8>()?);
+    let ends = !word & 0x8080_8080_8080_8080;
+    if ends == 0 {
+        return None;
+    }
+    let len = ends.leading_zeros() as usize / 8 + 1;
+    // the last byte is the least significant group
+    let mut groups = (word >> (64 - 8 * len)) & 0x7F7F_7F7F_7F7F_7F7F;
+    groups = (groups & 0x007F_007F_007F_007F) | (groups & 0x7F00_7F00_7F00_7F00) >> 1;
+    groups = (groups & 0x0000_3FFF_0000_3FFF) | (groups & 0x3FFF_0000_3FFF_0000) >> 2;
+    groups = (groups & 0x0000_0000_0FFF_FFFF) | (groups & 0x0FFF_FFFF_0000_0000) >> 4;
+    Some((groups, len))
+}
+
+#[test]
+fn same_as_loop() {
+    for value in [0, 1, 0x7F, 0x80, 0x3FFF, 0x4000, 12345678, (1 << 49) - 1, (1 << 56) - 1] {
+        let mut encoded = Vec::new();
+        let mut shift = (64 - u64::leading_zeros(value)).div_ceil(7).max(1) * 7;
+        while shift > 0 {
+            shift -= 7;
+            encoded.push((value >> shift) as u8 & 0x7F | if shift > 0 { 0x80 } else { 0 });
+        }
+        let len = encoded.len();
+        assert_eq!(decode8(&encoded), if len == 8 { Some((value, len)) } else { None });
+        encoded.extend([0xFF; 8]);
+        assert_eq!(decode8(&encoded), Some((value, len)));
+    }
+    assert_eq!(decode8(&[0xFF; 9]), None);
+    assert_eq!(decode8(&[0x81, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]), Some((1 << 49, 8)));
+}
+
+#[test]
+fn len_overflow() {
+    use crate::{Decoder, Value};
+
+    // a text with a length of 4 + 7 * 20 bits
+    let mut data = vec![0x3F];
+    data.extend([0xFF; 19]);
+    data.push(0x7F);
+    let errors = [
+        Value::parse_from(&mut &data[..]).unwrap_err(),
+        Value::parse_buf(&mut &data[..]).unwrap_err(),
+        Value::parse_slice(&mut &data[..]).unwrap_err(),
+        Decoder::new().push(&data).unwrap_err(),
+    ];
+    for err in errors {
+        assert_eq!(Error::from_io(&err), Some(&Error::LengthTooLarge));
+    }
+    assert_eq!(push_len(usize::MAX >> 7, 0x7F), Ok(usize::MAX));
+}
@@ -0,0 +1,201 @@
+//! Depth-first traversal of documents, with callbacks that can change the values
+//!
+//! Implement the [`Visitor`] methods for the kinds of values you're interested in, and pass the visitor to [`Value::walk`].
+//! Every method gets the [path](Path) to the value, and returns a [`Flow`] that can skip the contents of arrays
+//! and records, or stop the whole walk.
+//!
+//! ```rust
+//! use nota::path::Path;
+//! use nota::visit::{Flow, Visitor};
+//! use nota::Value;
+//!
+//! /// Finds the first negative number
+//! struct FindNegative(Option<String>);
+//!
+//! impl Visitor for FindNegative {
+//!     fn visit_integer(&mut self, path: &Path, n: &mut i128) -> Flow {
+//!         if *n >= 0 {
+//!             return Flow::Continue;
+//!         }
+//!         self.0 = Some(path.to_string());
+//!         Flow::Stop
+//!     }
+//! }
+//!
+//! let mut doc: Value = "[1, [2, -3], -4]".parse().unwrap();
+//! let mut visitor = FindNegative(None);
+//! assert_eq!(doc.walk(&mut visitor), Flow::Stop);
+//! assert_eq!(visitor.0.as_deref(), Some("[1][1]"));
+//! ```
+use crate::path::{Path, Segment};
+use crate::Value;
+#[cfg(feature = "blobs")]
+use bitvec::prelude::*;
+#[cfg(feature = "records")]
+use std::collections::HashMap;
+
+/// What to do after a [`Visitor`] callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flow {
+    #[default]
+    Continue,
+    /// Don't visit the elements or fields of the array or record being entered. It's still left.
+    Skip,
+    /// End the walk, without calling any more callbacks
+    Stop,
+}
+
+/// Callbacks for [`Value::walk`]. All of them do nothing by default.
+///
+/// Arrays and records are entered before their contents are visited, and left after. The contents can be changed in either callback,
+/// and the walk visits what's there after `enter_*` returns. Record fields are visited in order of their keys.
+#[allow(unused_variables)]
+pub trait Visitor {
+    fn enter_array(&mut self, path: &Path, items: &mut Vec<Value>) -> Flow {
+        Flow::Continue
+    }
+
+    fn leave_array(&mut self, path: &Path, items: &mut Vec<Value>) -> Flow {
+        Flow::Continue
+    }
+
+    #[cfg(feature = "records")]
+    fn enter_record(&mut self, path: &Path, fields: &mut HashMap<String, Value>) -> Flow {
+        Flow::Continue
+    }
+
+    #[cfg(feature = "records")]
+    fn leave_record(&mut self, path: &Path, fields: &mut HashMap<String, Value>) -> Flow {
+        Flow::Continue
+    }
+
+    #[cfg(feature = "blobs")]
+    fn visit_blob(&mut self, path: &Path, bits: &mut BitVec<u8, Msb0>) -> Flow {
+        Flow::Continue
+    }
+
+    fn visit_text(&mut self, path: &Path, text: &mut String) -> Flow {
+        Flow::Continue
+    }
+
+    fn visit_integer(&mut self, path: &Path, n: &mut i128) -> Flow {
+        Flow::Continue
+    }
+
+    fn visit_bool(&mut self, path: &Path, b: &mut bool) -> Flow {
+        Flow::Continue
+    }
+
+    fn visit_null(&mut self, path: &Path) -> Flow {
+        Flow::Continue
+    }
+
+    /// DEC64 and [unknown](Value::Unknown) values
+    fn visit_other(&mut self, path: &Path, value: &mut Value) -> Flow {
+        Flow::Continue
+    }
+}
+
+impl Value {
+    /// Visits this value and everything in it, depth-first.
+    ///
+    /// Returns [`Flow::Stop`] if the visitor has stopped the walk, and [`Flow::Continue`] otherwise.
+    pub fn walk(&mut self, visitor: &mut impl Visitor) -> Flow {
+        match walk(self, visitor, &mut Path::default()) {
+            Flow::Stop => Flow::Stop,
+            _ => Flow::Continue,
+        }
+    }
+}
+
+fn walk(value: &mut Value, visitor: &mut impl Visitor, path: &mut Path) -> Flow {
+    match value {
+        Value::Array(items) => {
+            match visitor.enter_array(path, items) {
+                Flow::Stop => return Flow::Stop,
+                Flow::Skip => {},
+                Flow::Continue => for (i, item) in items.iter_mut().enumerate() {
+                    path.push(Segment::Index(i));
+                    let flow = walk(item, visitor, path);
+                    path.pop();
+                    if flow == Flow::Stop {
+                        return Flow::Stop;
+                    }
+                },
+            }
+            visitor.leave_array(path, items)
+        },
+        #[cfg(feature = "records")]
+        Value::Record(fields) => {
+            match visitor.enter_record(path, fields) {
+                Flow::Stop => return Flow::Stop,
+                Flow::Skip => {},
+                Flow::Continue => {
+                    let mut sorted: Vec<_> = fields.iter_mut().collect();
+                    sorted.sort_unstable_by_key(|(key, _)| *key);
+                    for (key, field) in sorted {
+                        path.push(Segment::Key(key.clone()));
+                        let flow = walk(field, visitor, path);
+                        path.pop();
+                        if flow == Flow::Stop {
+                            return Flow::Stop;
+                        }
+                    }
+                },
+            }
+            visitor.leave_record(path, fields)
+        },
+        #[cfg(feature = "blobs")]
+        Value::Blob(bits) => visitor.visit_blob(path, bits),
+        Value::Text(text) => visitor.visit_text(path, text),
+        Value::Integer(n) => visitor.visit_integer(path, n),
+        Value::Bool(b) => visitor.visit_bool(path, b),
+        Value::Null => visitor.visit_null(path),
+        other => visitor.visit_other(path, other),
+    }
+}
+
+#[cfg(feature = "records")]
+#[test]
+fn walk_and_change() {
+    #[derive(Default)]
+    struct Redact {
+        log: Vec<String>,
+    }
+
+    impl Visitor for Redact {
+        fn enter_record(&mut self, path: &Path, fields: &mut HashMap<String, Value>) -> Flow {
+            self.log.push(format!("enter {path}"));
+            if fields.contains_key("skip") {
+                return Flow::Skip;
+            }
+            fields.remove("internal");
+            Flow::Continue
+        }
+
+        fn leave_record(&mut self, path: &Path, fields: &mut HashMap<String, Value>) -> Flow {
+            self.log.push(format!("leave {path}"));
+            fields.insert("checked".into(), Value::Bool(true));
+            Flow::Continue
+        }
+
+        fn visit_text(&mut self, path: &Path, text: &mut String) -> Flow {
+            self.log.push(format!("text {path}"));
+            if path.segments().last() == Some(&Segment::Key("password".into())) {
+                *text = "***".into();
+            }
+            Flow::Continue
+        }
+
+        fn visit_null(&mut self, path: &Path) -> Flow {
+            self.log.push(format!("null {path}"));
+            Flow::Stop
+        }
+    }
+
+    let mut doc: Value = r#"{"users": [{"name": "a", "password": "hunter2", "internal": {}}, {"skip": 1}], "z": [null, "unseen"]}"#.parse().unwrap();
+    let mut redact = Redact::default();
+    assert_eq!(doc.walk(&mut redact), Flow::Stop);
+    assert_eq!(redact.log, ["enter .", "enter users[0]", "text users[0].name", "text users[0].password", "leave users[0]", "enter users[1]", "leave users[1]", "null z[0]"]);
+    assert_eq!(doc.to_string(), r#"{"users": [{"checked": true, "name": "a", "password": "***"}, {"checked": true, "skip": 1}], "z": [null, "unseen"]}"#);
+}
@@ -0,0 +1,112 @@
+//! Conversions to and from JavaScript values, and `encode`/`decode` functions exported to JS
+//!
+//! | Nota | JavaScript |
+//! |------|------------|
+//! | Integer | `number` when it's a safe integer, `bigint` otherwise |
+//! | Text | `string` |
+//! | Blob (whole bytes only) | `Uint8Array` |
+//! | Array | `Array` |
+//! | Record | plain `Object` |
+//! | Bool | `boolean` |
+//! | Null | `null` (and `undefined` converts to it) |
+//!
+//! JS numbers that aren't safe integers, like `0.5`, `NaN`, or `2 ** 60`, can't be converted, because they may have lost
+//! precision already. Use a `bigint` for larger integers, or a `string` for a fraction that needs to be kept.
+use crate::{Error, ErrorCode, ParseOptions, Value};
+use bitvec::vec::BitVec;
+use js_sys::{Array, BigInt, Number, Object, Reflect, Uint8Array};
+use std::io;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+const MAX_SAFE_INTEGER: i128 = (1 << 53) - 1;
+
+impl Value {
+    pub fn from_js(value: &JsValue) -> Result<Self, Error> {
+        Ok(if value.is_null() || value.is_undefined() {
+            Self::Null
+        } else if let Some(b) = value.as_bool() {
+            Self::Bool(b)
+        } else if let Some(s) = value.as_string() {
+            Self::Text(s)
+        } else if let Some(n) = value.as_f64() {
+            if !Number::is_safe_integer(value) {
+                return Err(Error::Unrepresentable("number that isn't a safe integer"));
+            }
+            Self::Integer(n as i128)
+        } else if value.is_bigint() {
+            Self::Integer(i128::try_from(value.clone()).map_err(|_| Error::Unrepresentable("bigint larger than 128 bits"))?)
+        } else if let Some(bytes) = value.dyn_ref::<Uint8Array>() {
+            Self::Blob(BitVec::from_vec(bytes.to_vec()))
+        } else if let Some(array) = value.dyn_ref::<Array>() {
+            Self::Array(array.iter().map(|v| Self::from_js(&v)).collect::<Result<_, _>>()?)
+        } else if let Some(object) = value.dyn_ref::<Object>() {
+            Self::Record(Box::new(Object::entries(object).iter().map(|entry| {
+                let entry: Array = entry.unchecked_into();
+                let key = entry.get(0).as_string().ok_or(Error::Unrepresentable("object key that isn't a string"))?;
+                Ok((key, Self::from_js(&entry.get(1))?))
+            }).collect::<Result<HashMap<_, _>, Error>>()?))
+        } else {
+            return Err(Error::Unrepresentable("JS value without a Nota equivalent"));
+        })
+    }
+
+    pub fn to_js(&self) -> Result<JsValue, Error> {
+        Ok(match self {
+            Self::Null => JsValue::NULL,
+            Self::Bool(b) => JsValue::from_bool(*b),
+            Self::Text(s) => JsValue::from_str(s),
+            Self::Integer(i) if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(i) => JsValue::from_f64(*i as f64),
+            Self::Integer(i) => BigInt::from(*i).into(),
+            Self::Blob(bits) if bits.len() % 8 == 0 => Uint8Array::new_from_slice(bits.as_raw_slice()).into(),
+            Self::Blob(_) => return Err(Error::Unrepresentable("blob with a partial byte")),
+            Self::Array(a) => a.iter().map(Self::to_js).collect::<Result<Array, _>>()?.into(),
+            Self::Record(r) => {
+                let object = Object::new();
+                for (k, v) in r.iter() {
+                    Reflect::set(&object, &JsValue::from_str(k), &v.to_js()?)
+                        .map_err(|_| Error::Unrepresentable("record field"))?;
+                }
+                object.into()
+            },
+            Self::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float")),
+            Self::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag")),
+        })
+    }
+}
+
+/// JS `Error` with a numeric `code` property from [`ErrorCode`]
+fn js_error(message: String, code: ErrorCode) -> JsValue {
+    let error = js_sys::Error::new(&message);
+    let _ = Reflect::set(&error, &JsValue::from_str("code"), &JsValue::from(code as u32));
+    error.into()
+}
+
+impl From<Error> for JsValue {
+    fn from(err: Error) -> Self {
+        js_error(err.to_string(), err.code())
+    }
+}
+
+/// Serializes a JS value to Nota bytes
+///
+/// Throws an `Error` with a numeric `code` property from [`ErrorCode`].
+#[wasm_bindgen]
+pub fn encode(value: &JsValue) -> Result<Uint8Array, JsValue> {
+    let mut out = Vec::new();
+    Value::from_js(value)?.try_serialize_into(&mut out)?;
+    Ok(Uint8Array::new_from_slice(&out))
+}
+
+/// Parses Nota bytes into a JS value
+///
+/// The bytes are parsed with [`ParseOptions::untrusted`] limits, so that deeply nested data can't overflow the stack.
+///
+/// Throws an `Error` with a numeric `code` property from [`ErrorCode`].
+#[wasm_bindgen]
+pub fn decode(bytes: &Uint8Array) -> Result<JsValue, JsValue> {
+    let bytes = bytes.to_vec();
+    let value = Value::parse_slice_with(&mut &bytes[..], &ParseOptions::untrusted()).map_err(|e: io::Error| js_error(e.to_string(), ErrorCode::from_io(&e)))?;
+    Ok(value.to_js()?)
+}
@@ -0,0 +1,255 @@
+use crate::{serialize_signed_preamble, serialize_string, serialize_unsigned_preamble, Value};
+use core::fmt;
+
+/// The buffer given to [`SliceWriter`] doesn't have enough space left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooSmall;
+
+impl fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("buffer is too small for the serialized value")
+    }
+}
+
+impl std::error::Error for BufferTooSmall {}
+
+/// Why [`SliceWriter::value`] or [`Value::serialize_to_slice`] has failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SliceWriteError {
+    /// The value may fit in a larger buffer
+    BufferTooSmall,
+    /// The value can't be serialized into any buffer, e.g. because it contains a DEC64 float
+    Unrepresentable(&'static str),
+}
+
+impl From<BufferTooSmall> for SliceWriteError {
+    fn from(_: BufferTooSmall) -> Self {
+        Self::BufferTooSmall
+    }
+}
+
+impl fmt::Display for SliceWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BufferTooSmall => BufferTooSmall.fmt(f),
+            Self::Unrepresentable(what) => write!(f, "{what} can't be serialized"),
+        }
+    }
+}
+
+impl std::error::Error for SliceWriteError {}
+
+/// Serializes into a fixed-size buffer, without allocating.
+///
+/// Arrays and records are written as a header with the number of elements, followed by that many calls
+/// for the elements (for records, a [`key`](Self::key) before each value).
+///
+/// If a call fails, nothing is written, and a smaller value may still fit. The calls fail only with [`BufferTooSmall`],
+/// except [`value`](Self::value), which also fails with [`SliceWriteError::Unrepresentable`] for values that can't be serialized.
+///
+/// ```rust
+/// # fn main() -> Result<(), nota::BufferTooSmall> {
+/// let mut buf = [0; 32];
+/// let mut w = nota::SliceWriter::new(&mut buf);
+/// w.record(2)?;
+/// w.key("temp")?;
+/// w.integer(-12)?;
+/// w.key("ok")?;
+/// w.bool(true)?;
+/// let message = w.written();
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+/// Fills the rest of the buffer, and remembers if anything didn't fit
+struct Sink<'b> {
+    buf: &'b mut [u8],
+    len: usize,
+    overflow: bool,
+}
+
+impl Extend<u8> for Sink<'_> {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, bytes: I) {
+        for byte in bytes {
+            match self.buf.get_mut(self.len) {
+                Some(slot) => { *slot = byte; self.len += 1; },
+                None => { self.overflow = true; return; },
+            }
+        }
+    }
+}
+
+impl<'a> SliceWriter<'a> {
+    #[must_use]
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    /// Number of bytes written so far
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn written(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// The written part of the buffer, for when the writer has been moved
+    #[must_use]
+    pub fn into_written(self) -> &'a mut [u8] {
+        &mut self.buf[..self.len]
+    }
+
+    fn write(&mut self, f: impl FnOnce(&mut Sink<'_>)) -> Result<(), BufferTooSmall> {
+        let mut sink = Sink { buf: &mut self.buf[..], len: self.len, overflow: false };
+        f(&mut sink);
+        if sink.overflow {
+            return Err(BufferTooSmall);
+        }
+        self.len = sink.len;
+        Ok(())
+    }
+
+    pub fn null(&mut self) -> Result<(), BufferTooSmall> {
+        self.write(|s| s.extend([0b1100_0010]))
+    }
+
+    pub fn bool(&mut self, value: bool) -> Result<(), BufferTooSmall> {
+        self.write(|s| s.extend([0b1100_0000 | u8::from(value)]))
+    }
+
+    pub fn integer(&mut self, value: i128) -> Result<(), BufferTooSmall> {
+        self.write(|s| serialize_signed_preamble(0b1000_0000, value, s))
+    }
+
+    pub fn text(&mut self, value: &str) -> Result<(), BufferTooSmall> {
+        self.write(|s| serialize_string(value, s))
+    }
+
+    /// Same as [`text`](Self::text)
+    pub fn key(&mut self, key: &str) -> Result<(), BufferTooSmall> {
+        self.text(key)
+    }
+
+    /// Blob of whole bytes
+    pub fn bytes(&mut self, bytes: &[u8]) -> Result<(), BufferTooSmall> {
+        self.bits(bytes, bytes.len() * 8)
+    }
+
+    /// Blob of the first `bit_len` bits of `bytes`, most significant bit first
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` has fewer than `bit_len` bits
+    pub fn bits(&mut self, bytes: &[u8], bit_len: usize) -> Result<(), BufferTooSmall> {
+        let bytes = &bytes[..bit_len.div_ceil(8)];
+        self.write(|s| {
+            serialize_unsigned_preamble(0, bit_len as u128, s);
+            if let Some((&last, whole)) = bytes.split_last() {
+                s.extend(whole.iter().copied());
+                let used = match bit_len % 8 {
+                    0 => 0xFF,
+                    rem => !(0xFF >> rem),
+                };
+                s.extend([last & used]);
+            }
+        })
+    }
+
+    /// Header of an array. Write exactly `len` values after it.
+    pub fn array(&mut self, len: usize) -> Result<(), BufferTooSmall> {
+        self.write(|s| serialize_unsigned_preamble(0b0100_0000, len as u128, s))
+    }
+
+    /// Header of a record. Write exactly `len` pairs of [`key`](Self::key) and a value after it.
+    pub fn record(&mut self, len: usize) -> Result<(), BufferTooSmall> {
+        self.write(|s| serialize_unsigned_preamble(0b0110_0000, len as u128, s))
+    }
+
+    /// Writes the whole value. If it doesn't fit, nothing is written.
+    ///
+    /// DEC64 floats can't be serialized, and fail with [`SliceWriteError::Unrepresentable`] regardless of the buffer size.
+    pub fn value(&mut self, value: &Value) -> Result<(), SliceWriteError> {
+        let start = self.len;
+        let res = self.value_inner(value);
+        if res.is_err() {
+            self.len = start;
+        }
+        res
+    }
+
+    fn value_inner(&mut self, value: &Value) -> Result<(), SliceWriteError> {
+        match value {
+            #[cfg(feature = "blobs")]
+            Value::Blob(bits) => self.bits(bits.as_raw_slice(), bits.len())?,
+            Value::Text(text) => self.text(text)?,
+            Value::Array(items) => {
+                self.array(items.len())?;
+                items.iter().try_for_each(|item| self.value_inner(item))?;
+            },
+            #[cfg(feature = "records")]
+            Value::Record(fields) => {
+                self.record(fields.len())?;
+                fields.iter().try_for_each(|(k, v)| {
+                    self.key(k)?;
+                    self.value_inner(v)
+                })?;
+            },
+            Value::Integer(n) => self.integer(*n)?,
+            #[cfg(feature = "dec64")]
+            Value::DecimalFloat(_) => return Err(SliceWriteError::Unrepresentable("decimal float")),
+            Value::Bool(b) => self.bool(*b)?,
+            Value::Null => self.null()?,
+            Value::Unknown { raw_bytes, .. } => self.write(|s| s.extend(raw_bytes.iter().copied()))?,
+        }
+        Ok(())
+    }
+}
+
+impl Value {
+    /// Serializes into a fixed-size buffer, without allocating. Returns the number of bytes written.
+    pub fn serialize_to_slice(&self, buf: &mut [u8]) -> Result<usize, SliceWriteError> {
+        let mut w = SliceWriter::new(buf);
+        w.value(self)?;
+        Ok(w.len())
+    }
+}
+
+#[cfg(all(feature = "blobs", feature = "records"))]
+#[test]
+fn same_as_vec() {
+    let value = Value::Record(Box::new([
+        ("list".to_string(), Value::Array(vec![Value::Integer(-1234567), Value::Null, Value::Text("☃ snow".into())])),
+        ("bits".to_string(), Value::Blob([true, false, true].into_iter().collect())),
+    ].into_iter().collect()));
+    let mut expected = Vec::new();
+    value.serialize_into(&mut expected);
+
+    let mut buf = [0xAA; 64];
+    let len = value.serialize_to_slice(&mut buf).unwrap();
+    assert_eq!(buf[..len], expected);
+
+    for short in 0..expected.len() {
+        assert_eq!(value.serialize_to_slice(&mut buf[..short]), Err(SliceWriteError::BufferTooSmall));
+    }
+
+    let mut buf = [0; 3];
+    let mut w = SliceWriter::new(&mut buf);
+    w.bool(true).unwrap();
+    assert_eq!(w.text("abc"), Err(BufferTooSmall));
+    assert_eq!(w.written(), [0xC1]);
+    w.bits(&[0xFF], 3).unwrap();
+    assert_eq!(w.into_written(), [0xC1, 3, 0b1110_0000]);
+}
@@ -0,0 +1,113 @@
+//! Conversions to and from [`serde_yaml::Value`]
+//!
+//! Rules for YAML features that Nota doesn't have:
+//!
+//! * Merge keys (`<<: *anchor`) are applied first, and keys of the mapping take precedence over merged ones.
+//!   Aliases are already resolved by the YAML parser.
+//! * Keys that are null, booleans, or numbers are converted to text in their YAML syntax (`null`, `true`, `1`).
+//!   Keys that are sequences or mappings can't be converted.
+//! * Tags (`!name value`) are dropped, keeping only the tagged value.
+//! * Float values, like `2.5` or `.inf`, can't be converted. A YAML string (`"2.5"`) can be used instead.
+//!   Float keys are converted to text like other numbers.
+//!
+//! Nota's blobs and integers outside of the 64-bit range can't be converted to YAML.
+use crate::{Error, Value};
+use serde_yaml::Value as Yaml;
+
+impl TryFrom<Yaml> for Value {
+    type Error = Error;
+
+    fn try_from(mut yaml: Yaml) -> Result<Self, Error> {
+        yaml.apply_merge().map_err(|_| Error::Unrepresentable("invalid YAML merge"))?;
+        from_merged(yaml)
+    }
+}
+
+fn from_merged(yaml: Yaml) -> Result<Value, Error> {
+    Ok(match yaml {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(b),
+        Yaml::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::Integer(i.into())
+            } else if let Some(u) = n.as_u64() {
+                Value::Integer(u.into())
+            } else {
+                return Err(Error::Unrepresentable("float"));
+            }
+        },
+        Yaml::String(s) => Value::Text(s),
+        Yaml::Sequence(s) => Value::Array(s.into_iter().map(from_merged).collect::<Result<_, _>>()?),
+        Yaml::Mapping(m) => Value::Record(Box::new(m.into_iter().map(|(k, v)| Ok((key(k)?, from_merged(v)?))).collect::<Result<_, Error>>()?)),
+        Yaml::Tagged(t) => from_merged(t.value)?,
+    })
+}
+
+fn key(yaml: Yaml) -> Result<String, Error> {
+    Ok(match yaml {
+        Yaml::String(s) => s,
+        Yaml::Null => "null".into(),
+        Yaml::Bool(b) => b.to_string(),
+        Yaml::Number(n) => n.to_string(),
+        Yaml::Tagged(t) => key(t.value)?,
+        Yaml::Sequence(_) | Yaml::Mapping(_) => return Err(Error::Unrepresentable("mapping key that isn't a scalar")),
+    })
+}
+
+impl TryFrom<Value> for Yaml {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        Ok(match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Bool(b),
+            Value::Integer(i) => {
+                if let Ok(i) = i64::try_from(i) {
+                    Self::from(i)
+                } else if let Ok(u) = u64::try_from(i) {
+                    Self::from(u)
+                } else {
+                    return Err(Error::Unrepresentable("integer larger than 64 bits"));
+                }
+            },
+            Value::Text(s) => Self::String(s),
+            Value::Array(a) => Self::Sequence(a.into_iter().map(Self::try_from).collect::<Result<_, _>>()?),
+            Value::Record(r) => Self::Mapping(r.into_iter().map(|(k, v)| Ok((Self::String(k), Self::try_from(v)?))).collect::<Result<_, Error>>()?),
+            Value::Blob(_) => return Err(Error::Unrepresentable("blob")),
+            Value::DecimalFloat(_) => return Err(Error::Unrepresentable("decimal float")),
+            Value::Unknown { .. } => return Err(Error::Unrepresentable("unknown tag")),
+        })
+    }
+}
+
+#[test]
+fn merge_and_keys() {
+    let yaml: Yaml = serde_yaml::from_str("
+        base: &base
+          a: 1
+          b: [x, '2.5']
+        derived:
+          <<: *base
+          b: !custom override
+          7: seven
+          true: yes
+          ~: nothing
+    ").unwrap();
+    let value = Value::try_from(yaml).unwrap();
+    let Value::Record(r) = &value else { panic!() };
+    let Value::Record(derived) = &r["derived"] else { panic!() };
+    assert_eq!(derived["a"], Value::Integer(1));
+    assert_eq!(derived["b"], Value::Text("override".into()));
+    assert_eq!(derived["7"], Value::Text("seven".into()));
+    assert_eq!(derived["true"], Value::Text("yes".into()));
+    assert_eq!(derived["null"], Value::Text("nothing".into()));
+    let Value::Record(base) = &r["base"] else { panic!() };
+    assert_eq!(base["b"], Value::Array(vec![Value::Text("x".into()), Value::Text("2.5".into())]));
+
+    let back = Yaml::try_from(value.clone()).unwrap();
+    assert_eq!(Value::try_from(back).unwrap(), value);
+
+    assert!(Value::try_from(serde_yaml::from_str::<Yaml>("? [1]\n: x").unwrap()).is_err());
+    assert_eq!(Value::try_from(serde_yaml::from_str::<Yaml>("[1, .inf]").unwrap()), Err(Error::Unrepresentable("float")));
+    assert!(Value::try_from(serde_yaml::from_str::<Yaml>("2.5: x").unwrap()).is_ok());
+}
@@ -0,0 +1,53 @@
+//! [Zstandard](https://facebook.github.io/zstd/) compression of whole documents
+//!
+//! Nota is already compact for numbers and structure, but texts and repeated keys compress well.
+//! Compress after serializing: the serialized form is what has the redundancy.
+//!
+//! [`read_compressed`] checks for the zstd magic number, so it also reads documents that weren't compressed.
+use crate::{ParseOptions, Value};
+use std::io::{self, BufRead, BufReader, Write};
+
+/// First bytes of every zstd frame
+pub const MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Writes the value as one zstd frame. Level 0 is zstd's default, 1 is the fastest, and 19 or more is the smallest.
+pub fn write_compressed<W: Write>(writer: &mut W, value: &Value, level: i32) -> Result<(), io::Error> {
+    let mut data = Vec::new();
+    value.try_serialize_into(&mut data)?;
+    let mut encoder = zstd::Encoder::new(writer, level)?;
+    encoder.set_pledged_src_size(Some(data.len() as u64))?;
+    encoder.write_all(&data)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Reads one value, compressed with [`write_compressed`] or not
+pub fn read_compressed<R: BufRead>(reader: &mut R) -> Result<Value, io::Error> {
+    read_compressed_with(reader, &ParseOptions::default())
+}
+
+/// Decompresses while parsing, so the limits in `options` also protect from data that decompresses to a huge size
+pub fn read_compressed_with<R: BufRead>(reader: &mut R, options: &ParseOptions) -> Result<Value, io::Error> {
+    if !reader.fill_buf()?.starts_with(&MAGIC) {
+        return Value::parse_buf_with(reader, options);
+    }
+    let mut decoder = BufReader::new(zstd::Decoder::with_buffer(reader)?.single_frame());
+    Value::parse_buf_with(&mut decoder, options)
+}
+
+#[test]
+fn compressed() {
+    let value = Value::Array((0..1000).map(|n| Value::Text(format!("item {}", n % 10))).collect());
+    let mut plain = Vec::new();
+    value.try_serialize_into(&mut plain).unwrap();
+    let mut compressed = Vec::new();
+    write_compressed(&mut compressed, &value, 3).unwrap();
+    assert!(compressed.starts_with(&MAGIC));
+    assert!(compressed.len() < plain.len() / 10);
+
+    assert_eq!(read_compressed(&mut &compressed[..]).unwrap(), value);
+    assert_eq!(read_compressed(&mut &plain[..]).unwrap(), value);
+
+    let options = ParseOptions { max_len: Some(100), ..Default::default() };
+    assert!(read_compressed_with(&mut &compressed[..], &options).is_err());
+}